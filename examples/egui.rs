@@ -0,0 +1,46 @@
+use macroquad::prelude::*;
+
+use macroquad::egui;
+
+#[macroquad::main("egui")]
+async fn main() {
+    let ferris = load_texture("examples/ferris.png").await.unwrap();
+    let mut speed = 1.0;
+    let mut rotation = 0.0f32;
+
+    loop {
+        clear_background(WHITE);
+
+        rotation += speed * get_frame_time();
+        draw_texture_ex(
+            ferris,
+            screen_width() / 2. - ferris.width() / 2.,
+            screen_height() / 2. - ferris.height() / 2.,
+            WHITE,
+            DrawTextureParams {
+                rotation,
+                ..Default::default()
+            },
+        );
+
+        egui::ui(|ctx| {
+            egui::Window::new("egui ❤ macroquad").show(ctx, |ui| {
+                ui.add(egui::Slider::new(&mut speed, 0.0..=10.0).text("speed"));
+                ui.image(egui::texture_id(ferris), egui::vec2(64., 42.));
+
+                let (rect, _) =
+                    ui.allocate_exact_size(egui::vec2(100., 100.), egui::Sense::hover());
+                ui.painter().add(egui::PaintCallback {
+                    rect,
+                    callback: std::sync::Arc::new(egui::CallbackFn::new(move |info| {
+                        let center = info.viewport.center().to_vec2() * info.pixels_per_point;
+                        draw_poly(center.x, center.y, 5, 40., rotation.to_degrees(), RED);
+                    })),
+                });
+            });
+        });
+        egui::draw();
+
+        next_frame().await
+    }
+}
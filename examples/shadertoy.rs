@@ -344,7 +344,7 @@ async fn main() {
                 MaterialParams {
                     pipeline_params,
                     uniforms,
-                    textures: vec![],
+                    ..Default::default()
                 },
             ) {
                 Ok(new_material) => {
@@ -354,7 +354,7 @@ async fn main() {
                     error = None;
                 }
                 Err(err) => {
-                    error = Some(format!("{:#?}", err));
+                    error = Some(format!("{}", err));
                 }
             }
         }
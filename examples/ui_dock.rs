@@ -0,0 +1,42 @@
+use macroquad::prelude::*;
+
+use macroquad::ui::{
+    hash, root_ui,
+    widgets::{DockLayout, DockNode, DockSpace},
+};
+
+#[macroquad::main("UI docking")]
+async fn main() {
+    let layout = DockLayout::new(DockNode::horizontal(
+        0.7,
+        DockNode::vertical(
+            0.7,
+            DockNode::tabs(&["Scene", "Game"]),
+            DockNode::tabs(&["Console"]),
+        ),
+        DockNode::tabs(&["Inspector"]),
+    ))
+    .floating("Tools", Rect::new(100., 100., 200., 150.));
+    let mut dock = DockSpace::new(hash!(), layout);
+    let mut speed = 0.5;
+
+    loop {
+        clear_background(WHITE);
+
+        let rect = Rect::new(0., 0., screen_width(), screen_height());
+        dock.ui(&mut root_ui(), rect, |ui, window| match window {
+            "Inspector" => {
+                ui.label(None, "Speed");
+                ui.slider(hash!(), "[0 .. 1]", 0.0..1.0, &mut speed);
+            }
+            "Tools" => ui.label(None, "Press S to print the layout"),
+            _ => ui.label(None, window),
+        });
+
+        if is_key_pressed(KeyCode::S) {
+            println!("{}", dock.layout().to_ron());
+        }
+
+        next_frame().await
+    }
+}
@@ -0,0 +1,43 @@
+// Canvas size tracking and browser fullscreen for macroquad::window::request_browser_fullscreen.
+// Load after mq_js_bundle.js.
+
+"use strict";
+
+// the bundle only follows the window size, the canvas CSS size may change on its own
+function canvas_follow_css_size() {
+    var update = function () {
+        if (wasm_exports != null) {
+            resize(canvas, wasm_exports.resize);
+        }
+    };
+    if (typeof ResizeObserver !== "undefined") {
+        new ResizeObserver(update).observe(canvas);
+    }
+    document.addEventListener("fullscreenchange", update);
+}
+
+function macroquad_request_browser_fullscreen() {
+    if (canvas.requestFullscreen) {
+        canvas.requestFullscreen();
+    }
+}
+
+function macroquad_exit_browser_fullscreen() {
+    if (document.fullscreenElement != null) {
+        document.exitFullscreen();
+    }
+}
+
+function macroquad_is_browser_fullscreen() {
+    return document.fullscreenElement == canvas;
+}
+
+function canvas_register_plugin(importObject) {
+    importObject.env.macroquad_request_browser_fullscreen = macroquad_request_browser_fullscreen;
+    importObject.env.macroquad_exit_browser_fullscreen = macroquad_exit_browser_fullscreen;
+    importObject.env.macroquad_is_browser_fullscreen = macroquad_is_browser_fullscreen;
+}
+
+canvas_follow_css_size();
+
+miniquad_add_plugin({ register_plugin: canvas_register_plugin, version: "0.1.0", name: "macroquad_canvas" });
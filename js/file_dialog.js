@@ -0,0 +1,68 @@
+// File dialogs for macroquad::file::{open_file_dialog, save_file_dialog}.
+// Load after mq_js_bundle.js.
+
+"use strict";
+
+// -2 while the dialog is open, -1 when cancelled, file size when picked
+var file_dialog_status = -1;
+var file_dialog_name = null;
+var file_dialog_data = null;
+
+function macroquad_open_file_dialog(accept, accept_len) {
+    var input = document.createElement("input");
+    input.type = "file";
+    input.accept = UTF8ToString(accept, accept_len);
+
+    file_dialog_status = -2;
+    input.onchange = function () {
+        var file = input.files[0];
+        if (file == null) {
+            file_dialog_status = -1;
+            return;
+        }
+        file.arrayBuffer().then(function (buffer) {
+            file_dialog_name = new TextEncoder().encode(file.name);
+            file_dialog_data = new Uint8Array(buffer);
+            file_dialog_status = file_dialog_data.length;
+        });
+    };
+    input.oncancel = function () {
+        file_dialog_status = -1;
+    };
+    input.click();
+}
+
+function macroquad_file_dialog_status() {
+    return file_dialog_status;
+}
+
+function macroquad_file_dialog_name_len() {
+    return file_dialog_name.length;
+}
+
+function macroquad_file_dialog_read(name, data) {
+    new Uint8Array(wasm_memory.buffer, name, file_dialog_name.length).set(file_dialog_name);
+    new Uint8Array(wasm_memory.buffer, data, file_dialog_data.length).set(file_dialog_data);
+    file_dialog_name = null;
+    file_dialog_data = null;
+    file_dialog_status = -1;
+}
+
+function macroquad_save_file(name, name_len, data, data_len) {
+    var bytes = new Uint8Array(wasm_memory.buffer, data, data_len).slice();
+    var link = document.createElement("a");
+    link.href = URL.createObjectURL(new Blob([bytes]));
+    link.download = UTF8ToString(name, name_len);
+    link.click();
+    setTimeout(function () { URL.revokeObjectURL(link.href); }, 0);
+}
+
+function file_dialog_register_plugin(importObject) {
+    importObject.env.macroquad_open_file_dialog = macroquad_open_file_dialog;
+    importObject.env.macroquad_file_dialog_status = macroquad_file_dialog_status;
+    importObject.env.macroquad_file_dialog_name_len = macroquad_file_dialog_name_len;
+    importObject.env.macroquad_file_dialog_read = macroquad_file_dialog_read;
+    importObject.env.macroquad_save_file = macroquad_save_file;
+}
+
+miniquad_add_plugin({ register_plugin: file_dialog_register_plugin, version: "0.1.0", name: "macroquad_file_dialog" });
@@ -0,0 +1,60 @@
+// localStorage glue for macroquad::storage.
+// Load after mq_js_bundle.js.
+
+"use strict";
+
+// localStorage only keeps strings, the bytes are stored as base64
+function storage_key(key, key_len) {
+    return "macroquad/" + UTF8ToString(key, key_len);
+}
+
+function macroquad_storage_save(key, key_len, data, data_len) {
+    var bytes = new Uint8Array(wasm_memory.buffer, data, data_len);
+    var binary = "";
+    for (var i = 0; i < bytes.length; i++) {
+        binary += String.fromCharCode(bytes[i]);
+    }
+    try {
+        window.localStorage.setItem(storage_key(key, key_len), btoa(binary));
+        return true;
+    } catch (e) {
+        return false;
+    }
+}
+
+function storage_get(key, key_len) {
+    try {
+        var value = window.localStorage.getItem(storage_key(key, key_len));
+        return value == null ? null : atob(value);
+    } catch (e) {
+        return null;
+    }
+}
+
+function macroquad_storage_len(key, key_len) {
+    var binary = storage_get(key, key_len);
+    return binary == null ? -1 : binary.length;
+}
+
+function macroquad_storage_load(key, key_len, data) {
+    var binary = storage_get(key, key_len);
+    var bytes = new Uint8Array(wasm_memory.buffer, data, binary.length);
+    for (var i = 0; i < binary.length; i++) {
+        bytes[i] = binary.charCodeAt(i);
+    }
+}
+
+function macroquad_storage_remove(key, key_len) {
+    try {
+        window.localStorage.removeItem(storage_key(key, key_len));
+    } catch (e) {}
+}
+
+function storage_register_plugin(importObject) {
+    importObject.env.macroquad_storage_save = macroquad_storage_save;
+    importObject.env.macroquad_storage_len = macroquad_storage_len;
+    importObject.env.macroquad_storage_load = macroquad_storage_load;
+    importObject.env.macroquad_storage_remove = macroquad_storage_remove;
+}
+
+miniquad_add_plugin({ register_plugin: storage_register_plugin, version: "0.1.0", name: "macroquad_storage" });
@@ -216,6 +216,50 @@ pub fn test(_attr: TokenStream, item: TokenStream) -> TokenStream {
     prelude
 }
 
+/// Embed all the files of a directory, relative to the crate root, into the
+/// binary. See `macroquad::file::include_assets`.
+#[proc_macro]
+pub fn include_assets(input: TokenStream) -> TokenStream {
+    let mut source = input.into_iter().peekable();
+    let dir = next_literal(&mut source).expect("include_assets! expects a directory path");
+
+    let root = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set");
+    let root = std::path::Path::new(&root).join(&dir);
+
+    fn visit(dir: &std::path::Path, prefix: &str, files: &mut Vec<(String, std::path::PathBuf)>) {
+        let entries = std::fs::read_dir(dir)
+            .unwrap_or_else(|err| panic!("include_assets!: {}: {}", dir.display(), err));
+        for entry in entries {
+            let path = entry.unwrap().path();
+            let name = format!("{}{}", prefix, path.file_name().unwrap().to_string_lossy());
+            if path.is_dir() {
+                visit(&path, &format!("{}/", name), files);
+            } else {
+                files.push((name, path));
+            }
+        }
+    }
+
+    let mut files = vec![];
+    visit(&root, "", &mut files);
+    files.sort();
+
+    let entries = files
+        .iter()
+        .map(|(name, path)| {
+            format!(
+                "({:?}, include_bytes!({:?}) as &[u8]),",
+                name,
+                path.to_string_lossy()
+            )
+        })
+        .collect::<String>();
+
+    format!("macroquad::file::EmbeddedAssets::new(&[{}])", entries)
+        .parse()
+        .unwrap()
+}
+
 /// Very experimental thing for macroquad::experimantal::scene
 /// Maybe will go away in future versions
 #[doc(hidden)]
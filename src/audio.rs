@@ -1,17 +1,47 @@
 //! Loading and playing sounds.
+//!
+//! Streaming playback is not supported: the audio backend mixes and plays
+//! only fully decoded buffers, so sounds are decoded into memory when
+//! loaded, long music included. A track at 44100Hz stereo costs ~20Mb per
+//! minute, and its decoding is a load hitch on the web.
+//!
+//! Everything macroquad controls on a playing sound is its volume:
+//! buses, positional attenuation and fades are all volume multipliers.
+//! The backend mixer (quad-snd natively, WebAudio buffer sources on the web)
+//! has no per-sample processing hooks, so there are no runtime DSP effects
+//! like reverb, low/high-pass filters or EQ. Bake them into the audio files
+//! instead, e.g. an "underwater" variant of the ambience track.
+//!
+//! This module is output only. There is no microphone capture: the audio
+//! backend opens playback devices only, and capture would need its own
+//! implementation for each platform (ALSA, WASAPI, CoreAudio, OpenSL ES
+//! and getUserMedia on the web).
+//! The output goes to the system default device, opened by the backend at
+//! startup: there is no output device enumeration or selection either.
 
-use crate::{file::load_file, get_context};
+use crate::{file::load_file, get_context, logging::warn, math::Vec3};
 use std::collections::HashMap;
 
+mod analysis;
+mod decode;
+mod info;
+#[cfg(feature = "tracker")]
+pub mod tracker;
+
 #[cfg(all(feature = "audio"))]
-use quad_snd::{AudioContext as QuadSndContext, Sound as QuadSndSound};
+use quad_snd::{
+    AudioContext as QuadSndContext, Playback as QuadSndPlayback, Sound as QuadSndSound,
+};
 
 #[cfg(all(feature = "audio"))]
-pub use quad_snd::PlaySoundParams;
+use quad_snd::PlaySoundParams as QuadSndParams;
 
 #[cfg(not(feature = "audio"))]
 mod dummy_audio {
-    use crate::audio::PlaySoundParams;
+    pub struct PlaySoundParams {
+        pub looped: bool,
+        pub volume: f32,
+    }
 
     pub struct AudioContext {}
 
@@ -27,18 +57,28 @@ mod dummy_audio {
         pub fn resume(&mut self) {}
     }
 
+    pub struct Playback {}
+
+    impl Playback {
+        pub fn stop(self, _ctx: &AudioContext) {}
+
+        pub fn set_volume(&self, _ctx: &AudioContext, _volume: f32) {}
+    }
+
     pub struct Sound {}
 
     impl Sound {
-        pub fn load(_ctx: &mut AudioContext, _data: &[u8]) -> Sound {
+        pub fn load(_ctx: &AudioContext, _data: &[u8]) -> Sound {
             Sound {}
         }
 
-        pub fn play(&mut self, _ctx: &mut AudioContext, _params: PlaySoundParams) {}
+        pub fn play(&self, _ctx: &AudioContext, _params: PlaySoundParams) -> Playback {
+            Playback {}
+        }
 
-        pub fn stop(&mut self, _ctx: &mut AudioContext) {}
+        pub fn stop(&self, _ctx: &AudioContext) {}
 
-        pub fn set_volume(&mut self, _ctx: &mut AudioContext, _volume: f32) {}
+        pub fn set_volume(&self, _ctx: &AudioContext, _volume: f32) {}
 
         pub fn is_loaded(&self) -> bool {
             false
@@ -49,18 +89,323 @@ mod dummy_audio {
 }
 
 #[cfg(not(feature = "audio"))]
-use dummy_audio::{AudioContext as QuadSndContext, Sound as QuadSndSound};
+use dummy_audio::{
+    AudioContext as QuadSndContext, PlaySoundParams as QuadSndParams, Playback as QuadSndPlayback,
+    Sound as QuadSndSound,
+};
 
-#[cfg(not(feature = "audio"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PlaySoundParams {
     pub looped: bool,
     pub volume: f32,
 }
 
+impl Default for PlaySoundParams {
+    fn default() -> PlaySoundParams {
+        PlaySoundParams {
+            looped: false,
+            volume: 1.,
+        }
+    }
+}
+
+/// [play_sound] with the options beyond [PlaySoundParams].
+///
+/// ```ignore
+/// let playback = PlaySound::new(step)
+///     .volume(0.5)
+///     .pitch(rand::gen_range(0.9, 1.1))
+///     .play();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaySound {
+    sound: Sound,
+    params: PlaySoundParams,
+    pitch: f32,
+}
+
+impl PlaySound {
+    pub fn new(sound: Sound) -> PlaySound {
+        PlaySound {
+            sound,
+            params: PlaySoundParams::default(),
+            pitch: 1.,
+        }
+    }
+
+    pub fn params(self, params: PlaySoundParams) -> PlaySound {
+        PlaySound { params, ..self }
+    }
+
+    pub fn looped(mut self, looped: bool) -> PlaySound {
+        self.params.looped = looped;
+        self
+    }
+
+    pub fn volume(mut self, volume: f32) -> PlaySound {
+        self.params.volume = volume;
+        self
+    }
+
+    /// Playback speed, changing the pitch as well: 2.0 is an octave higher.
+    /// Sounds from wav and ogg files only, played at 1.0 otherwise.
+    /// The pitch is fixed for the whole playback.
+    ///
+    /// Each pitch, rounded to 0.01, is a resampled copy of the sound made on
+    /// the first use and kept with the sound. Good for short effects with a
+    /// handful of random pitches, too much memory for long music.
+    /// On the web the copy takes a few frames to load, the first play with
+    /// a new pitch is at the original one.
+    pub fn pitch(mut self, pitch: f32) -> PlaySound {
+        self.pitch = pitch;
+        self
+    }
+
+    pub fn play(self) -> Playback {
+        play_sound_at_pitch(self.sound, self.params, self.pitch)
+    }
+}
+
+/// Only on wasm the sounds are not ready right away
+fn is_loaded(sound: &QuadSndSound) -> bool {
+    #[cfg(target_arch = "wasm32")]
+    return sound.is_loaded();
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = sound;
+        true
+    }
+}
+
+/// Seconds to the end of the intro and the first time through the body
+fn first_pass(sound: &SoundData) -> f32 {
+    let intro = sound.intro.as_ref().map_or(0., |intro| intro.duration);
+
+    intro + sound.duration.unwrap_or(0.)
+}
+
+/// Pitch in hundredths, to keep the number of resampled copies sane
+fn pitch_key(pitch: f32) -> u32 {
+    (pitch.max(0.01) * 100.).round() as u32
+}
+
+/// How the volume of positional sounds falls off with the distance to the listener.
+///
+/// Closer than `reference_distance` the sound plays at full volume,
+/// further than `max_distance` the volume stops decreasing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub reference_distance: f32,
+    pub max_distance: f32,
+    /// How fast the volume falls off, 0 - no attenuation at all.
+    pub rolloff: f32,
+}
+
+impl Default for Attenuation {
+    fn default() -> Attenuation {
+        Attenuation {
+            reference_distance: 1.,
+            max_distance: 100.,
+            rolloff: 1.,
+        }
+    }
+}
+
+impl Attenuation {
+    /// Volume multiplier at the given distance, inverse distance model.
+    pub fn gain(&self, distance: f32) -> f32 {
+        let reference = self.reference_distance.max(f32::EPSILON);
+        let distance = distance.clamp(reference, self.max_distance.max(reference));
+
+        reference / (reference + self.rolloff * (distance - reference))
+    }
+}
+
+/// A named group of sounds sharing a volume, like "music", "sfx" or "voice".
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bus {
+    volume: f32,
+    muted: bool,
+}
+
+impl Default for Bus {
+    fn default() -> Bus {
+        Bus {
+            volume: 1.,
+            muted: false,
+        }
+    }
+}
+
+struct SoundData {
+    native: QuadSndSound,
+    volume: f32,
+    position: Option<Vec3>,
+    bus: Option<String>,
+    /// Multiplier of the ongoing fade, 1 when not fading
+    fade: f32,
+    duration: Option<f32>,
+    /// Part played once before the looped body, see [LoopPoints]
+    intro: Option<Intro>,
+    /// The file, to make the resampled copies from
+    source: Option<Source>,
+    /// Resampled copies, by [pitch_key]
+    pitched: HashMap<u32, QuadSndSound>,
+    analysis: Option<analysis::Analysis>,
+}
+
+enum Source {
+    Bytes(Vec<u8>),
+    /// Read again when needed, not to keep every loaded file in memory
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    File(String),
+}
+
+impl Source {
+    fn decode(&self) -> Option<decode::Samples> {
+        match self {
+            Source::Bytes(bytes) => decode::decode(bytes),
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+            Source::File(path) => {
+                let bytes = match get_context().vfs.read(path) {
+                    Some(bytes) => bytes,
+                    None => std::fs::read(crate::file::assets_path(path)).ok()?,
+                };
+                decode::decode(&crate::file::decompress(bytes).ok()?)
+            }
+        }
+    }
+}
+
+struct Intro {
+    native: QuadSndSound,
+    duration: f32,
+}
+
+struct PlaybackData {
+    sound: usize,
+    /// None while paused
+    native: Option<QuadSndPlayback>,
+    volume: f32,
+    looped: bool,
+    start: f64,
+    /// Playing the intro, the body is not started yet
+    in_intro: bool,
+    pitch: f32,
+    /// Time in the sound the playback is paused at
+    paused: Option<f32>,
+    /// Rest of the first pass after a seek, played before the body.
+    /// With `paused`, waiting to be loaded on the web
+    tail: Option<QuadSndSound>,
+}
+
+impl PlaybackData {
+    /// Seconds since the start of the sound, None once finished
+    fn time(&self, sound: &SoundData, now: f64) -> Option<f32> {
+        if let Some(time) = self.paused {
+            return Some(time);
+        }
+
+        let time = (now - self.start) as f32 * self.pitch;
+        let intro = sound.intro.as_ref().map_or(0., |intro| intro.duration);
+        if time < intro {
+            return Some(time);
+        }
+
+        let body = time - intro;
+        match sound.duration {
+            Some(duration) if self.looped && duration > 0. => Some(intro + body % duration),
+            Some(duration) if body > duration => None,
+            _ => Some(time),
+        }
+    }
+}
+
+struct Fade {
+    sound: usize,
+    from: f32,
+    to: f32,
+    start: f64,
+    duration: f32,
+    /// Stop the sound once faded out
+    stop: bool,
+}
+
+/// Replace the fade of the same sound
+fn push_fade(fades: &mut Vec<Fade>, fade: Fade) {
+    fades.retain(|other| other.sound != fade.sound);
+    fades.push(fade);
+}
+
+/// Fade `current` out and `music` in, both given with their fade volume.
+/// Returns true if `music` has to be played: a music still fading out of
+/// an earlier crossfade is faded back in instead, not played twice.
+fn crossfade_fades(
+    fades: &mut Vec<Fade>,
+    current: Option<(usize, f32)>,
+    music: (usize, f32),
+    now: f64,
+    duration: f32,
+) -> bool {
+    let fading_out = fades.iter().any(|fade| fade.sound == music.0 && fade.stop);
+
+    if let Some((id, from)) = current {
+        push_fade(
+            fades,
+            Fade {
+                sound: id,
+                from,
+                to: 0.,
+                start: now,
+                duration,
+                stop: true,
+            },
+        );
+    }
+    push_fade(
+        fades,
+        Fade {
+            sound: music.0,
+            from: if fading_out { music.1 } else { 0. },
+            to: 1.,
+            start: now,
+            duration,
+            stop: false,
+        },
+    );
+    !fading_out
+}
+
+/// What happens to the audio when the app goes to the background,
+/// see [set_background_audio].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackgroundAudio {
+    /// Keep playing as is
+    Continue,
+    /// Fade everything to `volume` over `duration` seconds, and back
+    /// when the app is back.
+    Duck { volume: f32, duration: f32 },
+    /// Pause the audio output on Android, mute everything on other platforms.
+    Pause,
+}
+
 pub struct AudioContext {
     native_ctx: QuadSndContext,
-    sounds: HashMap<usize, QuadSndSound>,
+    sounds: HashMap<usize, SoundData>,
     id: usize,
+    listener: Vec3,
+    attenuation: Attenuation,
+    buses: HashMap<String, Bus>,
+    fades: Vec<Fade>,
+    music: Option<Sound>,
+    playbacks: HashMap<usize, PlaybackData>,
+    playback_id: usize,
+    background: BackgroundAudio,
+    background_callback: Option<Box<dyn FnMut(bool)>>,
+    /// Multiplier of everything, for [BackgroundAudio::Duck]
+    focus_volume: f32,
+    /// from, to, start, duration
+    focus_fade: Option<(f32, f32, f64, f32)>,
 }
 
 impl AudioContext {
@@ -69,6 +414,23 @@ impl AudioContext {
             native_ctx: QuadSndContext::new(),
             sounds: HashMap::new(),
             id: 0,
+            listener: Vec3::ZERO,
+            attenuation: Default::default(),
+            buses: HashMap::new(),
+            fades: vec![],
+            music: None,
+            playbacks: HashMap::new(),
+            playback_id: 0,
+            // Android always paused the output in the background, the other
+            // platforms keep playing unless the game asks for it
+            background: if cfg!(target_os = "android") {
+                BackgroundAudio::Pause
+            } else {
+                BackgroundAudio::Continue
+            },
+            background_callback: None,
+            focus_volume: 1.,
+            focus_fade: None,
         }
     }
 
@@ -81,6 +443,332 @@ impl AudioContext {
     pub fn resume(&mut self) {
         self.native_ctx.resume()
     }
+
+    /// Volume multiplier from everything but the sound's own volume
+    fn gain(&self, sound: &SoundData) -> f32 {
+        let distance = match sound.position {
+            Some(position) => self.attenuation.gain(position.distance(self.listener)),
+            None => 1.,
+        };
+        let bus = match sound.bus.as_ref().and_then(|bus| self.buses.get(bus)) {
+            Some(bus) if bus.muted => 0.,
+            Some(bus) => bus.volume,
+            None => 1.,
+        };
+
+        distance * bus * sound.fade * self.focus_volume
+    }
+
+    fn update_volume(&self, id: usize) {
+        if let Some(sound) = self.sounds.get(&id) {
+            let gain = self.gain(sound);
+            for playback in self
+                .playbacks
+                .values()
+                .filter(|playback| playback.sound == id)
+            {
+                if let Some(native) = &playback.native {
+                    native.set_volume(&self.native_ctx, playback.volume * gain);
+                }
+            }
+        }
+    }
+
+    fn stop(&mut self, id: usize) {
+        if let Some(sound) = self.sounds.get(&id) {
+            sound.native.stop(&self.native_ctx);
+            if let Some(intro) = &sound.intro {
+                intro.native.stop(&self.native_ctx);
+            }
+            for pitched in sound.pitched.values() {
+                pitched.stop(&self.native_ctx);
+            }
+        }
+        let stopped = self
+            .playbacks
+            .iter()
+            .filter(|(_, playback)| playback.sound == id)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for playback in stopped {
+            let playback = self.playbacks.remove(&playback).unwrap();
+            self.free_tail(playback.tail);
+        }
+    }
+
+    fn free_tail(&self, tail: Option<QuadSndSound>) {
+        if let Some(tail) = tail {
+            tail.stop(&self.native_ctx);
+            tail.delete(&self.native_ctx);
+        }
+    }
+
+    /// Play the playback from `time`, out of a new copy of the rest of the sound
+    fn seek(&mut self, id: usize, time: f32) {
+        let mut playback = match self.playbacks.remove(&id) {
+            Some(playback) => playback,
+            None => return,
+        };
+        let sound = &self.sounds[&playback.sound];
+        let samples = match sound.source.as_ref().and_then(Source::decode) {
+            Some(samples) => samples,
+            None => {
+                warn!("Seeking needs a sound from a wav or ogg file");
+                self.playbacks.insert(id, playback);
+                return;
+            }
+        };
+
+        let end = first_pass(sound);
+        let time = time.clamp(0., end);
+        let rate = samples.sample_rate as f32;
+        let mut tail = samples.slice((time * rate) as usize..(end * rate).round() as usize);
+        if playback.pitch != 1. {
+            tail = tail.resample(playback.pitch);
+        }
+
+        if let Some(native) = playback.native.take() {
+            native.stop(&self.native_ctx);
+        }
+        let tail = QuadSndSound::load(&self.native_ctx, &tail.to_wav());
+        self.free_tail(playback.tail.replace(tail));
+        playback.paused = Some(time);
+        playback.in_intro = false;
+        if is_loaded(playback.tail.as_ref().unwrap()) {
+            self.start_tail(&mut playback, miniquad::date::now());
+        }
+        self.playbacks.insert(id, playback);
+    }
+
+    fn start_tail(&self, playback: &mut PlaybackData, now: f64) {
+        let time = playback.paused.take().unwrap();
+        let gain = self.gain(&self.sounds[&playback.sound]);
+
+        playback.start = now - (time / playback.pitch) as f64;
+        let native = playback.tail.as_ref().unwrap().play(
+            &self.native_ctx,
+            QuadSndParams {
+                looped: false,
+                volume: playback.volume * gain,
+            },
+        );
+        playback.native = Some(native);
+    }
+
+    /// Make the resampled copy of the sound, false if it can't be played yet
+    fn load_pitched(&mut self, id: usize, key: u32) -> bool {
+        let sound = self.sounds.get_mut(&id).unwrap();
+        if sound.intro.is_some() {
+            return false;
+        }
+        if !sound.pitched.contains_key(&key) {
+            let samples = match sound.source.as_ref().and_then(Source::decode) {
+                Some(samples) => samples,
+                None => return false,
+            };
+            let wav = samples.resample(key as f32 / 100.).to_wav();
+            let native = QuadSndSound::load(&self.native_ctx, &wav);
+            sound.pitched.insert(key, native);
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        if !sound.pitched[&key].is_loaded() {
+            return false;
+        }
+
+        true
+    }
+
+    /// Mix of the last samples of the matching playbacks, None if none of them
+    /// has [enable_sound_analysis]
+    fn analysis_window(&self, filter: impl Fn(usize, &PlaybackData) -> bool) -> Option<Vec<f32>> {
+        let now = miniquad::date::now();
+        let mut window = vec![0.; analysis::WINDOW_SIZE];
+        let mut any = false;
+
+        for (_, playback) in self
+            .playbacks
+            .iter()
+            .filter(|(id, data)| filter(**id, data))
+        {
+            let sound = &self.sounds[&playback.sound];
+            if playback.paused.is_some() {
+                continue;
+            }
+            if let (Some(analysis), Some(time)) = (&sound.analysis, playback.time(sound, now)) {
+                analysis.add_window(time, playback.volume * self.gain(sound), &mut window);
+                any = true;
+            }
+        }
+
+        if any {
+            Some(window)
+        } else {
+            None
+        }
+    }
+
+    fn update_spatial_volumes(&self) {
+        for (id, sound) in &self.sounds {
+            if sound.position.is_some() {
+                self.update_volume(*id);
+            }
+        }
+    }
+
+    fn update_bus_volumes(&self, bus: &str) {
+        for (id, sound) in &self.sounds {
+            if sound.bus.as_deref() == Some(bus) {
+                self.update_volume(*id);
+            }
+        }
+    }
+
+    fn start_fade(&mut self, id: usize, to: f32, duration: f32, stop: bool) {
+        if let Some(sound) = self.sounds.get(&id) {
+            push_fade(
+                &mut self.fades,
+                Fade {
+                    sound: id,
+                    from: sound.fade,
+                    to,
+                    start: miniquad::date::now(),
+                    duration,
+                    stop,
+                },
+            );
+        }
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        match self.background {
+            BackgroundAudio::Continue => {}
+            BackgroundAudio::Duck { volume, duration } => {
+                let to = if focused { 1. } else { volume };
+                self.focus_fade = Some((self.focus_volume, to, miniquad::date::now(), duration));
+            }
+            #[cfg(target_os = "android")]
+            BackgroundAudio::Pause if focused => self.resume(),
+            #[cfg(target_os = "android")]
+            BackgroundAudio::Pause => self.pause(),
+            #[cfg(not(target_os = "android"))]
+            BackgroundAudio::Pause => {
+                self.focus_volume = if focused { 1. } else { 0. };
+                self.focus_fade = None;
+                self.update_all_volumes();
+            }
+        }
+    }
+
+    fn update_all_volumes(&self) {
+        for id in self.sounds.keys() {
+            self.update_volume(*id);
+        }
+    }
+
+    /// Advance the fades and forget the finished playbacks, called once a frame.
+    pub(crate) fn update(&mut self) {
+        let now = miniquad::date::now();
+
+        if let Some((from, to, start, duration)) = self.focus_fade {
+            let t = if duration > 0. {
+                ((now - start) as f32 / duration).min(1.)
+            } else {
+                1.
+            };
+            self.focus_volume = from + (to - from) * t;
+            if t >= 1. {
+                self.focus_fade = None;
+            }
+            self.update_all_volumes();
+        }
+
+        let mut playbacks = std::mem::take(&mut self.playbacks);
+        for playback in playbacks.values_mut() {
+            let sound = &self.sounds[&playback.sound];
+            if playback.paused.is_some() {
+                // a seek waiting for the rest of the sound to load
+                if playback.tail.as_ref().is_some_and(is_loaded) {
+                    self.start_tail(playback, now);
+                }
+                continue;
+            }
+
+            let end = match &playback.tail {
+                Some(_) => first_pass(sound),
+                None if playback.in_intro => sound.intro.as_ref().unwrap().duration,
+                None => continue,
+            };
+            if ((now - playback.start) as f32 * playback.pitch) < end {
+                continue;
+            }
+
+            let seeked = playback.tail.is_some();
+            self.free_tail(playback.tail.take());
+            playback.in_intro = false;
+            if seeked && !playback.looped {
+                // finished, forgotten below
+                continue;
+            }
+
+            // the handoff is as precise as the frame time
+            // the body loops seamlessly though
+            let body = match playback.pitch {
+                pitch if pitch != 1. => &sound.pitched[&pitch_key(pitch)],
+                _ => &sound.native,
+            };
+            let gain = self.gain(sound);
+            let native = body.play(
+                &self.native_ctx,
+                QuadSndParams {
+                    looped: playback.looped,
+                    volume: playback.volume * gain,
+                },
+            );
+            playback.native = Some(native);
+        }
+        self.playbacks = playbacks;
+
+        let sounds = &self.sounds;
+        self.playbacks.retain(|_, playback| {
+            sounds
+                .get(&playback.sound)
+                .is_some_and(|sound| playback.time(sound, now).is_some())
+        });
+
+        let mut i = 0;
+        while let Some(fade) = self.fades.get(i) {
+            let t = if fade.duration > 0. {
+                ((now - fade.start) as f32 / fade.duration).min(1.)
+            } else {
+                1.
+            };
+            let (id, finished, stop) = (fade.sound, t >= 1., fade.stop);
+
+            let sound = match self.sounds.get_mut(&id) {
+                Some(sound) => sound,
+                None => {
+                    self.fades.swap_remove(i);
+                    continue;
+                }
+            };
+            sound.fade = fade.from + (fade.to - fade.from) * t;
+
+            if finished && stop {
+                // so the next play is audible
+                sound.fade = 1.;
+                self.stop(id);
+            } else {
+                self.update_volume(id);
+            }
+
+            if finished {
+                self.fades.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -89,19 +777,170 @@ pub struct Sound(usize);
 /// Load audio file.
 ///
 /// Attempts to automatically detect the format of the source of data.
+///
+/// In debug builds the sound is reloaded when the file changes.
 pub async fn load_sound(path: &str) -> Result<Sound, crate::file::FileError> {
     let data = load_file(path).await?;
+    let sound = load_sound_from_bytes(&data).await?;
+    get_context().asset_watcher.watch_sound(path, sound);
+    keep_path_only(sound, path);
 
-    load_sound_from_bytes(&data).await
+    Ok(sound)
+}
+
+/// Forget the bytes of a sound loaded from a file, the file is read again
+/// for the resampled copies.
+#[allow(unused_variables)]
+fn keep_path_only(sound: Sound, path: &str) {
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    {
+        #[cfg(feature = "http")]
+        if crate::file::is_url(path) {
+            return;
+        }
+        let data = get_context()
+            .audio_context
+            .sounds
+            .get_mut(&sound.0)
+            .unwrap();
+        if data.source.is_some() {
+            data.source = Some(Source::File(path.to_string()));
+        }
+    }
+}
+
+/// Decode to a 44100Hz wav on a thread, leaving the backend nothing but a copy to do.
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+async fn decode_in_background(data: &[u8]) -> Option<Vec<u8>> {
+    let data = data.to_vec();
+    let wav = crate::exec::ThreadFuture::spawn(move || {
+        decode::decode(&data).map(|samples| {
+            if samples.sample_rate == 44100 {
+                return samples.to_wav();
+            }
+            let mut samples = samples.resample(samples.sample_rate as f32 / 44100.);
+            samples.sample_rate = 44100;
+            samples.to_wav()
+        })
+    });
+
+    // a decoder panic leaves the file to the backend, like a decoding error
+    wav.await.flatten()
 }
 
 /// Load audio data.
 ///
 /// Attempts to automatically detect the format of the source of data.
+///
+/// Decoding does not block the frame: ogg files are decoded on a background
+/// thread natively, and by the browser on the web, while the future is
+/// pending. To keep drawing a loading screen, or to decode several files
+/// in parallel, load them in coroutines:
+/// ```ignore
+/// let loading = ["music.ogg", "level.ogg"].map(|path| start_coroutine(load_sound(path)));
+/// while !loading.iter().all(|sound| sound.is_done()) {
+///     draw_text("Loading...", 20., 20., 30., WHITE);
+///     next_frame().await;
+/// }
+/// ```
 pub async fn load_sound_from_bytes(data: &[u8]) -> Result<Sound, crate::file::FileError> {
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    if data.starts_with(b"OggS") {
+        if let Some(wav) = decode_in_background(data).await {
+            let sound = load_native_sound(&wav).await;
+            let sound = add_sound(sound, info::duration(&wav), None);
+            let ctx = &mut get_context().audio_context;
+            ctx.sounds.get_mut(&sound.0).unwrap().source = Some(Source::Bytes(data.to_vec()));
+            return Ok(sound);
+        }
+    }
+
+    let sound = load_native_sound(data).await;
+    let duration = info::duration(data);
+
+    let sound = add_sound(sound, duration, None);
+    if duration.is_some() {
+        let ctx = &mut get_context().audio_context;
+        ctx.sounds.get_mut(&sound.0).unwrap().source = Some(Source::Bytes(data.to_vec()));
+    }
+    Ok(sound)
+}
+
+/// Replace the data of `sound` with a new file, for the asset hot-reload.
+///
+/// Playing instances are stopped, the volume, position and bus are kept.
+pub(crate) async fn reload_sound(
+    sound: Sound,
+    bytes: Vec<u8>,
+) -> Result<(), crate::file::FileError> {
+    let reloaded = load_sound_from_bytes(&bytes).await?;
+
+    let ctx = &mut get_context().audio_context;
+    ctx.stop(sound.0);
+    let mut data = ctx.sounds.remove(&reloaded.0).unwrap();
+    match ctx.sounds.remove(&sound.0) {
+        Some(mut old) => {
+            data.volume = old.volume;
+            data.position = old.position;
+            data.bus = old.bus.take();
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+            if let Some(Source::File(path)) = old.source.take() {
+                data.source = Some(Source::File(path));
+            }
+            delete_native_sounds(ctx, old);
+            ctx.sounds.insert(sound.0, data);
+        }
+        // deleted while loading
+        None => delete_native_sounds(ctx, data),
+    }
+    Ok(())
+}
+
+fn delete_native_sounds(ctx: &AudioContext, sound: SoundData) {
+    sound.native.delete(&ctx.native_ctx);
+    if let Some(intro) = sound.intro {
+        intro.native.delete(&ctx.native_ctx);
+    }
+    for pitched in sound.pitched.values() {
+        pitched.delete(&ctx.native_ctx);
+    }
+}
+
+/// Make a sound out of raw samples, for procedurally generated audio.
+///
+/// `samples` are interleaved for stereo sounds, in -1..1 range.
+/// `channels` is 1 or 2. The audio backend mixes at 44100Hz, other sample
+/// rates are resampled.
+///
+/// The audio backend plays only complete buffers, there is no real time
+/// callback or streaming: generate the whole sound first, like a synthesized
+/// effect or a rendered chunk of an emulator output.
+/// ```ignore
+/// let beep: Vec<f32> = (0..44100 / 4)
+///     .map(|i| (i as f32 / 44100. * 440. * std::f32::consts::TAU).sin() * 0.5)
+///     .collect();
+/// let beep = load_sound_from_samples(&beep, 1, 44100).await;
+/// ```
+pub async fn load_sound_from_samples(samples: &[f32], channels: u16, sample_rate: u32) -> Sound {
+    assert!(
+        channels == 1 || channels == 2,
+        "Only mono and stereo sounds are supported"
+    );
+
+    let samples = decode::Samples {
+        data: samples.to_vec(),
+        channels,
+        sample_rate,
+    };
+    let native = load_native_sound(&samples.to_wav()).await;
+
+    add_sound(native, Some(samples.duration()), None)
+}
+
+async fn load_native_sound(data: &[u8]) -> QuadSndSound {
     let sound = {
         let ctx = &mut get_context().audio_context;
-        QuadSndSound::load(&mut ctx.native_ctx, data)
+        QuadSndSound::load(&ctx.native_ctx, data)
     };
 
     // only on wasm the sound is not ready right away
@@ -110,43 +949,550 @@ pub async fn load_sound_from_bytes(data: &[u8]) -> Result<Sound, crate::file::Fi
         crate::window::next_frame().await;
     }
 
+    sound
+}
+
+fn add_sound(native: QuadSndSound, duration: Option<f32>, intro: Option<Intro>) -> Sound {
     let ctx = &mut get_context().audio_context;
 
     let id = ctx.id;
-    ctx.sounds.insert(id, sound);
+    ctx.sounds.insert(
+        id,
+        SoundData {
+            native,
+            volume: 1.,
+            position: None,
+            bus: None,
+            fade: 1.,
+            duration,
+            intro,
+            source: None,
+            pitched: HashMap::new(),
+            analysis: None,
+        },
+    );
     ctx.id += 1;
-    Ok(Sound(id))
+    Sound(id)
+}
+
+/// Loop region of a sound, in sample frames: samples per channel,
+/// at the sample rate of the file.
+///
+/// A looped sound with loop points plays from the start up to `end`, then
+/// keeps looping from `start` to `end`. Good for music with an intro.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoopPoints {
+    pub start: usize,
+    /// None - the end of the sound
+    pub end: Option<usize>,
+}
+
+/// Load audio file with loop points, see [LoopPoints].
+pub async fn load_sound_with_loop(
+    path: &str,
+    loop_points: LoopPoints,
+) -> Result<Sound, crate::file::FileError> {
+    let data = load_file(path).await?;
+    let sound = load_sound_with_loop_from_bytes(&data, loop_points).await?;
+    keep_path_only(sound, path);
+
+    Ok(sound)
+}
+
+/// Load audio data with loop points, see [LoopPoints].
+///
+/// Works with wav and ogg vorbis files only, other formats are loaded as is
+/// and loop as a whole.
+pub async fn load_sound_with_loop_from_bytes(
+    data: &[u8],
+    loop_points: LoopPoints,
+) -> Result<Sound, crate::file::FileError> {
+    let samples = match decode::decode(data) {
+        Some(samples) => samples,
+        None => {
+            warn!("Unsupported format for loop points, loading the sound as is");
+            return load_sound_from_bytes(data).await;
+        }
+    };
+
+    let end = loop_points.end.unwrap_or(usize::MAX);
+    let intro = samples.slice(0..loop_points.start);
+    let body = samples.slice(loop_points.start..end);
+
+    let intro = if intro.frames() != 0 {
+        Some(Intro {
+            native: load_native_sound(&intro.to_wav()).await,
+            duration: intro.duration(),
+        })
+    } else {
+        None
+    };
+    let native = load_native_sound(&body.to_wav()).await;
+
+    let sound = add_sound(native, Some(body.duration()), intro);
+    let ctx = &mut get_context().audio_context;
+    ctx.sounds.get_mut(&sound.0).unwrap().source = Some(Source::Bytes(data.to_vec()));
+    Ok(sound)
 }
 
 pub fn play_sound_once(sound: Sound) {
+    play_sound(sound, Default::default());
+}
+
+/// Playing instance of a sound, returned by [play_sound].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Playback(usize);
+
+/// Play the sound. All the instances of a sound may be controlled
+/// with the [Sound] functions, like [stop_sound], or one by one with the
+/// returned [Playback].
+///
+/// See [PlaySound] for the pitch.
+pub fn play_sound(sound: Sound, params: PlaySoundParams) -> Playback {
+    play_sound_at_pitch(sound, params, 1.)
+}
+
+fn play_sound_at_pitch(sound: Sound, params: PlaySoundParams, pitch: f32) -> Playback {
     let ctx = &mut get_context().audio_context;
-    let sound = &mut ctx.sounds.get_mut(&sound.0).unwrap();
+    let key = pitch_key(pitch);
+    let pitched = key != pitch_key(1.) && ctx.load_pitched(sound.0, key);
+    let gain = ctx.gain(&ctx.sounds[&sound.0]);
+    let data = ctx.sounds.get_mut(&sound.0).unwrap();
 
-    sound.play(
-        &mut ctx.native_ctx,
-        PlaySoundParams {
-            looped: false,
-            volume: 1.0,
+    data.volume = params.volume;
+    let looped = params.looped;
+    let first_part = match &data.intro {
+        _ if pitched => &data.pitched[&key],
+        Some(intro) => &intro.native,
+        None => &data.native,
+    };
+    let native = first_part.play(
+        &ctx.native_ctx,
+        QuadSndParams {
+            volume: params.volume * gain,
+            // the intro plays once
+            looped: looped && data.intro.is_none(),
+        },
+    );
+
+    let id = ctx.playback_id;
+    ctx.playbacks.insert(
+        id,
+        PlaybackData {
+            sound: sound.0,
+            native: Some(native),
+            volume: data.volume,
+            looped,
+            start: miniquad::date::now(),
+            in_intro: data.intro.is_some(),
+            pitch: if pitched { key as f32 / 100. } else { 1. },
+            paused: None,
+            tail: None,
         },
     );
+    ctx.playback_id += 1;
+    Playback(id)
+}
+
+/// Stop all the playing instances of the sound.
+pub fn stop_sound(sound: Sound) {
+    get_context().audio_context.stop(sound.0);
 }
 
-pub fn play_sound(sound: Sound, params: PlaySoundParams) {
+/// Set the volume of all the playing instances of the sound.
+/// For positional sounds the distance attenuation is applied on top of it.
+pub fn set_sound_volume(sound: Sound, volume: f32) {
     let ctx = &mut get_context().audio_context;
-    let sound = &mut ctx.sounds.get_mut(&sound.0).unwrap();
+    ctx.sounds.get_mut(&sound.0).unwrap().volume = volume;
+    for playback in ctx.playbacks.values_mut() {
+        if playback.sound == sound.0 {
+            playback.volume = volume;
+        }
+    }
+    ctx.update_volume(sound.0);
+}
 
-    sound.play(&mut ctx.native_ctx, params);
+/// Sound length in seconds.
+/// Known for wav and ogg files only, None for the other formats.
+pub fn sound_duration(sound: Sound) -> Option<f32> {
+    get_context().audio_context.sounds[&sound.0].duration
 }
 
-pub fn stop_sound(sound: Sound) {
+pub fn stop_playback(playback: Playback) {
+    let ctx = &mut get_context().audio_context;
+    if let Some(playback) = ctx.playbacks.remove(&playback.0) {
+        if let Some(native) = playback.native {
+            native.stop(&ctx.native_ctx);
+        }
+        ctx.free_tail(playback.tail);
+    }
+}
+
+/// Stop the playback, keeping its position for [resume_playback].
+pub fn pause_playback(playback: Playback) {
     let ctx = &mut get_context().audio_context;
-    let sound = &mut ctx.sounds.get_mut(&sound.0).unwrap();
+    let now = miniquad::date::now();
+    let mut data = match ctx.playbacks.remove(&playback.0) {
+        Some(data) if data.paused.is_none() => data,
+        Some(data) => {
+            // paused already, or a seek still loading on the web
+            let tail = ctx.playbacks.entry(playback.0).or_insert(data).tail.take();
+            ctx.free_tail(tail);
+            return;
+        }
+        None => return,
+    };
 
-    sound.stop(&mut ctx.native_ctx);
+    data.paused = data.time(&ctx.sounds[&data.sound], now);
+    if let Some(native) = data.native.take() {
+        native.stop(&ctx.native_ctx);
+    }
+    ctx.free_tail(data.tail.take());
+    if data.paused.is_some() {
+        ctx.playbacks.insert(playback.0, data);
+    }
 }
 
-pub fn set_sound_volume(sound: Sound, volume: f32) {
+/// Play a paused playback from where it was paused, see [seek_playback].
+pub fn resume_playback(playback: Playback) {
+    let ctx = &mut get_context().audio_context;
+    let paused = ctx
+        .playbacks
+        .get(&playback.0)
+        .filter(|data| data.tail.is_none())
+        .and_then(|data| data.paused);
+
+    if let Some(time) = paused {
+        ctx.seek(playback.0, time);
+    }
+}
+
+pub fn is_playback_paused(playback: Playback) -> bool {
+    let ctx = &get_context().audio_context;
+    ctx.playbacks
+        .get(&playback.0)
+        .is_some_and(|data| data.paused.is_some() && data.tail.is_none())
+}
+
+/// Move the playback to `time` seconds into the sound, clamped to the end of
+/// the first time through it. A paused playback stays paused there.
+///
+/// The rest of the sound is decoded again from the file into a new copy,
+/// so seeking is too slow for every frame, and works with wav and ogg files
+/// only. On the web the copy takes a few frames to load, the playback is
+/// silent until then.
+pub fn seek_playback(playback: Playback, time: f32) {
     let ctx = &mut get_context().audio_context;
-    let sound = &mut ctx.sounds.get_mut(&sound.0).unwrap();
-    sound.set_volume(&mut ctx.native_ctx, volume)
+    let data = match ctx.playbacks.get_mut(&playback.0) {
+        Some(data) => data,
+        None => return,
+    };
+
+    if data.paused.is_some() && data.tail.is_none() {
+        let end = first_pass(&ctx.sounds[&data.sound]);
+        data.paused = Some(time.clamp(0., end));
+    } else {
+        ctx.seek(playback.0, time);
+    }
+}
+
+pub fn set_playback_volume(playback: Playback, volume: f32) {
+    let ctx = &mut get_context().audio_context;
+    if let Some(data) = ctx.playbacks.get_mut(&playback.0) {
+        data.volume = volume;
+        let sound = data.sound;
+        ctx.update_volume(sound);
+    }
+}
+
+/// Current position in the sound, in seconds.
+/// None once the playback is stopped or finished.
+///
+/// For formats with an unknown duration, see [sound_duration],
+/// this is the time since the start and won't wrap around for looped sounds.
+pub fn playback_time(playback: Playback) -> Option<f32> {
+    let ctx = &get_context().audio_context;
+    let data = ctx.playbacks.get(&playback.0)?;
+    data.time(ctx.sounds.get(&data.sound)?, miniquad::date::now())
+}
+
+/// Stop the sound and free its memory, like the music of the previous
+/// level: the sounds are kept fully decoded in memory.
+/// The sound can't be played after this.
+///
+/// Sounds from [crate::experimental::assets::load_asset] are deleted with
+/// their last handle.
+pub fn delete_sound(sound: Sound) {
+    get_context().asset_watcher.forget_sound(sound);
+
+    let ctx = &mut get_context().audio_context;
+    ctx.stop(sound.0);
+    if let Some(sound) = ctx.sounds.remove(&sound.0) {
+        delete_native_sounds(ctx, sound);
+    }
+}
+
+/// Position of the sound emitter, None for a non-positional sound.
+/// The volume of positional sounds fades with the distance to the listener,
+/// see [set_listener_position] and [set_attenuation].
+/// For 2D games z may be just 0.
+///
+/// Only the volume changes, the audio backend mixes all sounds with the same
+/// volume for both channels, so there is no stereo panning.
+pub fn set_sound_position(sound: Sound, position: Option<Vec3>) {
+    let ctx = &mut get_context().audio_context;
+    ctx.sounds.get_mut(&sound.0).unwrap().position = position;
+    ctx.update_volume(sound.0);
+}
+
+pub fn set_listener_position(position: Vec3) {
+    let ctx = &mut get_context().audio_context;
+    ctx.listener = position;
+    ctx.update_spatial_volumes();
+}
+
+pub fn listener_position() -> Vec3 {
+    get_context().audio_context.listener
+}
+
+pub fn set_attenuation(attenuation: Attenuation) {
+    let ctx = &mut get_context().audio_context;
+    ctx.attenuation = attenuation;
+    ctx.update_spatial_volumes();
+}
+
+/// Route the sound through a mixer bus, None to play it directly.
+///
+/// Buses are created on the first use, with full volume.
+/// ```ignore
+/// set_sound_bus(theme, Some("music"));
+/// set_sound_bus(jump, Some("sfx"));
+/// // on the settings screen
+/// set_bus_volume("music", music_slider);
+/// ```
+/// Buses only scale the volume, the audio backend has no effect inserts.
+pub fn set_sound_bus(sound: Sound, bus: Option<&str>) {
+    let ctx = &mut get_context().audio_context;
+    if let Some(bus) = bus {
+        ctx.buses.entry(bus.to_string()).or_default();
+    }
+    ctx.sounds.get_mut(&sound.0).unwrap().bus = bus.map(|bus| bus.to_string());
+    ctx.update_volume(sound.0);
+}
+
+pub fn set_bus_volume(bus: &str, volume: f32) {
+    let ctx = &mut get_context().audio_context;
+    ctx.buses.entry(bus.to_string()).or_default().volume = volume;
+    ctx.update_bus_volumes(bus);
+}
+
+pub fn bus_volume(bus: &str) -> f32 {
+    let ctx = &get_context().audio_context;
+    ctx.buses.get(bus).map_or(1., |bus| bus.volume)
+}
+
+/// Silence the bus, keeping its volume for when it is unmuted.
+pub fn set_bus_muted(bus: &str, muted: bool) {
+    let ctx = &mut get_context().audio_context;
+    ctx.buses.entry(bus.to_string()).or_default().muted = muted;
+    ctx.update_bus_volumes(bus);
+}
+
+pub fn is_bus_muted(bus: &str) -> bool {
+    let ctx = &get_context().audio_context;
+    ctx.buses.get(bus).is_some_and(|bus| bus.muted)
+}
+
+/// Play the sound, fading its volume in from silence over `duration` seconds.
+pub fn fade_in_sound(sound: Sound, params: PlaySoundParams, duration: f32) -> Playback {
+    {
+        let ctx = &mut get_context().audio_context;
+        ctx.sounds.get_mut(&sound.0).unwrap().fade = 0.;
+    }
+    let playback = play_sound(sound, params);
+    get_context()
+        .audio_context
+        .start_fade(sound.0, 1., duration, false);
+    playback
+}
+
+/// Fade the sound out over `duration` seconds and stop it.
+pub fn fade_out_sound(sound: Sound, duration: f32) {
+    get_context()
+        .audio_context
+        .start_fade(sound.0, 0., duration, true);
+}
+
+/// Fade the current music out while fading `music` in, looped.
+/// With no music playing just fades `music` in.
+/// Switching to the music that is already playing does nothing.
+/// Switching back to a music still fading out fades it back in.
+///
+/// ```ignore
+/// if entered_cave {
+///     crossfade_to(cave_music, 2.);
+/// }
+/// ```
+pub fn crossfade_to(music: Sound, duration: f32) {
+    let ctx = &mut get_context().audio_context;
+    if ctx.music == Some(music) {
+        return;
+    }
+    let current = ctx
+        .music
+        .replace(music)
+        .and_then(|current| Some((current.0, ctx.sounds.get(&current.0)?.fade)));
+    let sound = &ctx.sounds[&music.0];
+    let (fade, volume) = (sound.fade, sound.volume);
+
+    let now = miniquad::date::now();
+    if crossfade_fades(&mut ctx.fades, current, (music.0, fade), now, duration) {
+        ctx.sounds.get_mut(&music.0).unwrap().fade = 0.;
+        play_sound(
+            music,
+            PlaySoundParams {
+                looped: true,
+                volume,
+            },
+        );
+    }
+}
+
+/// Music started with [crossfade_to]
+pub fn current_music() -> Option<Sound> {
+    get_context().audio_context.music
+}
+
+/// Keep a mono copy of the sound for [playback_amplitude], [playback_spectrum],
+/// [bus_amplitude] and [bus_spectrum].
+///
+/// Works with sounds from wav and ogg files only. The copy is the decoded sound,
+/// ~10Mb per minute of music at 44100Hz.
+pub fn enable_sound_analysis(sound: Sound) {
+    let ctx = &mut get_context().audio_context;
+    let data = ctx.sounds.get_mut(&sound.0).unwrap();
+    if data.analysis.is_some() {
+        return;
+    }
+
+    match data.source.as_ref().and_then(Source::decode) {
+        Some(samples) => data.analysis = Some(analysis::Analysis::new(&samples)),
+        None => warn!("Sound analysis needs a sound from a wav or ogg file"),
+    }
+}
+
+/// Loudness of the playback right now: root mean square of the last ~20ms,
+/// with the playback volume applied.
+/// None for finished playbacks or sounds without [enable_sound_analysis].
+pub fn playback_amplitude(playback: Playback) -> Option<f32> {
+    let ctx = &get_context().audio_context;
+    let window = ctx.analysis_window(|id, _| id == playback.0)?;
+    Some(analysis::amplitude(&window))
+}
+
+/// Spectrum of the last ~20ms of the playback: magnitudes of 512 frequency
+/// bins, bin `i` is at `i * sample_rate / 1024` Hz, ~43Hz wide for 44100Hz sounds.
+/// None for finished playbacks or sounds without [enable_sound_analysis].
+///
+/// ```ignore
+/// if let Some(spectrum) = playback_spectrum(music) {
+///     for (i, magnitude) in spectrum.iter().take(64).enumerate() {
+///         draw_rectangle(i as f32 * 10., 300., 8., -magnitude * 300., GREEN);
+///     }
+/// }
+/// ```
+pub fn playback_spectrum(playback: Playback) -> Option<Vec<f32>> {
+    let ctx = &get_context().audio_context;
+    let window = ctx.analysis_window(|id, _| id == playback.0)?;
+    Some(analysis::spectrum(&window))
+}
+
+/// Like [playback_amplitude], of everything playing on the bus.
+pub fn bus_amplitude(bus: &str) -> Option<f32> {
+    let ctx = &get_context().audio_context;
+    let window =
+        ctx.analysis_window(|_, data| ctx.sounds[&data.sound].bus.as_deref() == Some(bus))?;
+    Some(analysis::amplitude(&window))
+}
+
+/// Like [playback_spectrum], of everything playing on the bus.
+pub fn bus_spectrum(bus: &str) -> Option<Vec<f32>> {
+    let ctx = &get_context().audio_context;
+    let window =
+        ctx.analysis_window(|_, data| ctx.sounds[&data.sound].bus.as_deref() == Some(bus))?;
+    Some(analysis::spectrum(&window))
+}
+
+/// What to do with the audio when the app goes to the background,
+/// [BackgroundAudio::Pause] by default on Android and
+/// [BackgroundAudio::Continue] everywhere else.
+/// Replaces the [set_background_audio_callback] callback.
+///
+/// Right now the windowing backend reports going to the background and back
+/// on Android, and the window getting minimized and restored on Windows.
+/// There are no such events on Linux, macOS and the web, where this does nothing.
+pub fn set_background_audio(behavior: BackgroundAudio) {
+    let ctx = &mut get_context().audio_context;
+    ctx.background = behavior;
+    ctx.background_callback = None;
+}
+
+/// Handle going to the background, `false`, and coming back, `true`, in the
+/// game code instead of a [BackgroundAudio] behavior.
+///
+/// ```ignore
+/// set_background_audio_callback(|focused| {
+///     // keep the music, silence the effects
+///     set_bus_muted("sfx", !focused);
+/// });
+/// ```
+pub fn set_background_audio_callback(callback: impl FnMut(bool) + 'static) {
+    get_context().audio_context.background_callback = Some(Box::new(callback));
+}
+
+pub(crate) fn set_focused(focused: bool) {
+    let ctx = &mut get_context().audio_context;
+    match ctx.background_callback.take() {
+        Some(mut callback) => {
+            // the callback is free to use the audio functions
+            callback(focused);
+            let ctx = &mut get_context().audio_context;
+            if ctx.background_callback.is_none() {
+                ctx.background_callback = Some(callback);
+            }
+        }
+        None => ctx.set_focused(focused),
+    }
+}
+
+#[test]
+fn attenuation_gain() {
+    let attenuation = Attenuation::default();
+    assert_eq!(attenuation.gain(0.), 1.);
+    assert_eq!(attenuation.gain(1.), 1.);
+    assert_eq!(attenuation.gain(2.), 0.5);
+    assert_eq!(attenuation.gain(1000.), attenuation.gain(100.));
+}
+
+#[test]
+fn crossfade_back_and_forth() {
+    let (a, b) = (1, 2);
+    let mut fades = vec![];
+
+    assert!(crossfade_fades(&mut fades, None, (a, 1.), 0., 2.));
+    assert!(crossfade_fades(&mut fades, Some((a, 1.)), (b, 0.), 1., 2.));
+    // `a` is still fading out, it is faded back in from where it is
+    assert!(!crossfade_fades(
+        &mut fades,
+        Some((b, 0.5)),
+        (a, 0.5),
+        2.,
+        2.
+    ));
+
+    assert_eq!(fades.len(), 2);
+    let fade_a = fades.iter().find(|fade| fade.sound == a).unwrap();
+    assert!(!fade_a.stop);
+    assert_eq!((fade_a.from, fade_a.to), (0.5, 1.));
+    assert!(fades.iter().any(|fade| fade.sound == b && fade.stop));
 }
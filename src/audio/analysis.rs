@@ -0,0 +1,106 @@
+//! Amplitude and spectrum of what is playing, for visualizers and rhythm games.
+
+use std::convert::TryFrom;
+use std::f32::consts::PI;
+
+/// Samples analyzed at once, ~23ms at 44100Hz
+pub(crate) const WINDOW_SIZE: usize = 1024;
+
+/// Mono copy of a sound, made by [crate::audio::enable_sound_analysis]
+pub(crate) struct Analysis {
+    pub data: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl Analysis {
+    pub fn new(samples: &super::decode::Samples) -> Analysis {
+        let channels = samples.channels.max(1) as usize;
+        Analysis {
+            data: samples
+                .data
+                .chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                .collect(),
+            sample_rate: samples.sample_rate,
+        }
+    }
+
+    /// Add the last [WINDOW_SIZE] samples before `time` to the window.
+    pub fn add_window(&self, time: f32, volume: f32, window: &mut [f32]) {
+        let end = (time * self.sample_rate as f32) as isize;
+        let start = end - window.len() as isize;
+
+        for (i, w) in (start..end).zip(window.iter_mut()) {
+            if let Some(sample) = usize::try_from(i).ok().and_then(|i| self.data.get(i)) {
+                *w += sample * volume;
+            }
+        }
+    }
+}
+
+/// Root mean square of the window, 0..1 for a not clipping sound
+pub(crate) fn amplitude(window: &[f32]) -> f32 {
+    (window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32).sqrt()
+}
+
+/// Magnitudes of the [WINDOW_SIZE] / 2 frequency bins,
+/// bin `i` is at `i * sample_rate / WINDOW_SIZE` Hz.
+pub(crate) fn spectrum(window: &[f32]) -> Vec<f32> {
+    let n = window.len();
+    debug_assert!(n.is_power_of_two());
+
+    // Hann window against the spectral leakage
+    let mut re: Vec<f32> = window
+        .iter()
+        .enumerate()
+        .map(|(i, s)| s * (0.5 - 0.5 * (2. * PI * i as f32 / n as f32).cos()))
+        .collect();
+    let mut im = vec![0.; n];
+
+    // iterative radix-2 fft
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if j > i {
+            re.swap(i, j);
+        }
+    }
+    let mut size = 2;
+    while size <= n {
+        let angle = -2. * PI / size as f32;
+        for start in (0..n).step_by(size) {
+            for k in 0..size / 2 {
+                let (wi, wr) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + size / 2);
+                let tr = re[b] * wr - im[b] * wi;
+                let ti = re[b] * wi + im[b] * wr;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        size *= 2;
+    }
+
+    // normalized so a full scale sine is ~1 at its bin
+    (0..n / 2)
+        .map(|i| (re[i] * re[i] + im[i] * im[i]).sqrt() * 4. / n as f32)
+        .collect()
+}
+
+#[test]
+fn spectrum_peak() {
+    let window: Vec<f32> = (0..WINDOW_SIZE)
+        .map(|i| (2. * PI * 32. * i as f32 / WINDOW_SIZE as f32).sin())
+        .collect();
+    let spectrum = spectrum(&window);
+
+    let peak = (0..spectrum.len())
+        .max_by(|a, b| spectrum[*a].partial_cmp(&spectrum[*b]).unwrap())
+        .unwrap();
+    assert_eq!(peak, 32);
+    assert!((spectrum[32] - 1.).abs() < 0.01);
+    assert!(spectrum[100] < 0.01);
+    assert!((amplitude(&window) - 0.5f32.sqrt()).abs() < 0.01);
+}
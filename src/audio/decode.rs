@@ -0,0 +1,221 @@
+//! Decoding sounds in macroquad itself, for the things the audio backend
+//! can't do with the encoded files, like splitting a sound at the loop points.
+//!
+//! The decoded samples are re-encoded as 16 bit wav, that the backend loads
+//! without any decoding work.
+
+use std::convert::TryInto;
+use std::ops::Range;
+
+/// Interleaved samples, -1..1
+pub(crate) struct Samples {
+    pub data: Vec<f32>,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+impl Samples {
+    /// Number of samples per channel
+    pub fn frames(&self) -> usize {
+        self.data.len() / self.channels.max(1) as usize
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frames() as f32 / self.sample_rate as f32
+    }
+
+    /// Part of the sound, in frames, clamped to the sound length.
+    pub fn slice(&self, frames: Range<usize>) -> Samples {
+        let channels = self.channels as usize;
+        let end = frames.end.min(self.frames());
+        let start = frames.start.min(end);
+
+        Samples {
+            data: self.data[start * channels..end * channels].to_vec(),
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+        }
+    }
+
+    /// Linear resampling, `speed` 2.0 makes the sound twice shorter
+    /// and an octave higher.
+    pub fn resample(&self, speed: f32) -> Samples {
+        let channels = self.channels as usize;
+        let frames = self.frames();
+        let new_frames = if frames == 0 {
+            0
+        } else {
+            (frames as f32 / speed) as usize
+        };
+
+        let mut data = Vec::with_capacity(new_frames * channels);
+        for i in 0..new_frames {
+            let position = i as f32 * speed;
+            let a = (position as usize).min(frames - 1);
+            let b = (a + 1).min(frames - 1);
+            let t = position - a as f32;
+            for channel in 0..channels {
+                let a = self.data[a * channels + channel];
+                let b = self.data[b * channels + channel];
+                data.push(a + (b - a) * t);
+            }
+        }
+
+        Samples {
+            data,
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+        }
+    }
+
+    pub fn to_wav(&self) -> Vec<u8> {
+        let data_size = self.data.len() as u32 * 2;
+        let block_align = self.channels * 2;
+
+        let mut wav = Vec::with_capacity(44 + data_size as usize);
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        // integer PCM
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&self.channels.to_le_bytes());
+        wav.extend_from_slice(&self.sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(self.sample_rate * block_align as u32).to_le_bytes());
+        wav.extend_from_slice(&block_align.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_size.to_le_bytes());
+        for sample in &self.data {
+            let sample = (sample.clamp(-1., 1.) * i16::MAX as f32) as i16;
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+
+        wav
+    }
+}
+
+/// Decode a wav or ogg vorbis file, None for other formats or broken files.
+pub(crate) fn decode(data: &[u8]) -> Option<Samples> {
+    match data.get(0..4)? {
+        b"RIFF" => decode_wav(data),
+        #[cfg(feature = "audio")]
+        b"OggS" => decode_ogg(data),
+        _ => None,
+    }
+}
+
+fn decode_wav(data: &[u8]) -> Option<Samples> {
+    let u16_at = |offset: usize| -> Option<u16> {
+        Some(u16::from_le_bytes(
+            data.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let u32_at = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    if data.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while let Some(id) = data.get(offset..offset + 4) {
+        let size = u32_at(offset + 4)? as usize;
+        let chunk = offset + 8;
+        match id {
+            b"fmt " => {
+                let mut tag = u16_at(chunk)?;
+                // WAVE_FORMAT_EXTENSIBLE, the real format is in the sub format guid
+                if tag == 0xfffe {
+                    tag = u16_at(chunk + 24)?;
+                }
+                format = Some((
+                    tag,
+                    u16_at(chunk + 2)?,
+                    u32_at(chunk + 4)?,
+                    u16_at(chunk + 14)?,
+                ));
+            }
+            b"data" => {
+                let (tag, channels, sample_rate, bits) = format?;
+                let end = chunk.saturating_add(size).min(data.len());
+                let bytes = &data[chunk..end];
+
+                let data: Vec<f32> = match (tag, bits) {
+                    (1, 8) => bytes.iter().map(|b| (*b as f32 - 128.) / 128.).collect(),
+                    (1, 16) => bytes
+                        .chunks_exact(2)
+                        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.)
+                        .collect(),
+                    (1, 24) => bytes
+                        .chunks_exact(3)
+                        .map(|b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2147483648.)
+                        .collect(),
+                    (1, 32) => bytes
+                        .chunks_exact(4)
+                        .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.)
+                        .collect(),
+                    (3, 32) => bytes
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                    _ => return None,
+                };
+
+                return Some(Samples {
+                    data,
+                    channels,
+                    sample_rate,
+                });
+            }
+            _ => {}
+        }
+        // chunks are padded to even sizes
+        offset = chunk.saturating_add(size + size % 2);
+    }
+    None
+}
+
+#[cfg(feature = "audio")]
+fn decode_ogg(data: &[u8]) -> Option<Samples> {
+    let mut reader = lewton::inside_ogg::OggStreamReader::new(std::io::Cursor::new(data)).ok()?;
+
+    let mut samples = vec![];
+    while let Some(packet) = reader.read_dec_packet_itl().ok()? {
+        samples.extend(packet.iter().map(|sample| *sample as f32 / 32768.));
+    }
+
+    Some(Samples {
+        data: samples,
+        channels: reader.ident_hdr.audio_channels as u16,
+        sample_rate: reader.ident_hdr.audio_sample_rate,
+    })
+}
+
+#[test]
+fn wav_roundtrip() {
+    let samples = Samples {
+        data: vec![0., 0.5, -0.5, 1., 0.25, -1.],
+        channels: 2,
+        sample_rate: 22050,
+    };
+    let decoded = decode(&samples.to_wav()).unwrap();
+
+    assert_eq!((decoded.channels, decoded.sample_rate), (2, 22050));
+    assert_eq!(decoded.frames(), 3);
+    for (a, b) in decoded.data.iter().zip(&samples.data) {
+        assert!((a - b).abs() < 0.001);
+    }
+
+    let body = decoded.slice(1..10);
+    assert_eq!(body.frames(), 2);
+    assert!((body.data[0] + 0.5).abs() < 0.001);
+
+    let slow = body.resample(0.5);
+    assert_eq!(slow.frames(), 4);
+    assert!((slow.data[2] + 0.125).abs() < 0.001);
+}
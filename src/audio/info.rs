@@ -0,0 +1,83 @@
+//! Reading what the audio backend doesn't tell, from the file headers.
+
+use std::convert::TryInto;
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Duration in seconds of a wav or ogg vorbis file, None for other formats.
+pub(crate) fn duration(data: &[u8]) -> Option<f32> {
+    match data.get(0..4)? {
+        b"RIFF" => wav_duration(data),
+        b"OggS" => ogg_duration(data),
+        _ => None,
+    }
+}
+
+fn wav_duration(data: &[u8]) -> Option<f32> {
+    if data.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut bytes_per_second = None;
+    let mut offset = 12;
+    while let Some(id) = data.get(offset..offset + 4) {
+        let size = u32_at(data, offset + 4)? as usize;
+        let chunk = offset + 8;
+        match id {
+            b"fmt " => bytes_per_second = Some(u32_at(data, chunk + 8)?),
+            b"data" => {
+                // streamed wavs may have the size unset
+                let size = size.min(data.len() - chunk);
+                return Some(size as f32 / bytes_per_second? as f32);
+            }
+            _ => {}
+        }
+        // chunks are padded to even sizes
+        offset = chunk.saturating_add(size + size % 2);
+    }
+    None
+}
+
+fn ogg_duration(data: &[u8]) -> Option<f32> {
+    // the first page holds only the vorbis identification header
+    let segments = *data.get(26)? as usize;
+    let packet = &data.get(27 + segments..)?;
+    if packet.get(0..7)? != b"\x01vorbis" {
+        return None;
+    }
+    let sample_rate = u32_at(packet, 12)?;
+
+    // the granule position of the last page is the total number of samples
+    let last_page = data.windows(4).rposition(|window| window == b"OggS")?;
+    let samples =
+        u32_at(data, last_page + 6)? as u64 | (u32_at(data, last_page + 10)? as u64) << 32;
+
+    if sample_rate == 0 {
+        return None;
+    }
+    Some(samples as f32 / sample_rate as f32)
+}
+
+#[test]
+fn wav_duration_from_header() {
+    let wav = |bytes_per_second: u32, data_size: u32| {
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&[1, 0, 2, 0, 0x44, 0xac, 0, 0]);
+        wav.extend_from_slice(&bytes_per_second.to_le_bytes());
+        wav.extend_from_slice(&[4, 0, 16, 0]);
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_size.to_le_bytes());
+        wav.resize(wav.len() + data_size as usize, 0);
+        wav
+    };
+
+    assert_eq!(duration(&wav(176400, 88200)), Some(0.5));
+    // unset size of a streamed wav
+    assert_eq!(duration(&wav(4, 8)[..48]), Some(1.));
+    assert_eq!(duration(b"ID3 not supported"), None);
+}
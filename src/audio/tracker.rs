@@ -0,0 +1,451 @@
+//! Tracker music: ProTracker style MOD files, with 4 to 32 channels.
+//!
+//! The module is rendered into a regular [Sound] when loaded, so it costs the
+//! same memory as any other decoded music while playing, but ships as a tiny
+//! file. The time of each row is kept for syncing the gameplay to the music:
+//!
+//! ```ignore
+//! let song = load_tracker_song("chiptune.mod").await.unwrap();
+//! let playback = play_sound(song.sound, PlaySoundParams { looped: true, ..Default::default() });
+//! loop {
+//!     if let Some(row) = playback_time(playback).and_then(|time| song.row_at(time)) {
+//!         if row.row % 16 == 0 { /* on the beat */ }
+//!     }
+//!     ..
+//! }
+//! ```
+//!
+//! XM, S3M and IT modules are not supported.
+
+use crate::{
+    audio::{load_sound_from_samples, Sound},
+    file::{load_file, FileError},
+};
+
+const SAMPLE_RATE: u32 = 44100;
+/// PAL Amiga clock, divided by 2
+const PAULA_CLOCK: f32 = 3546894.6;
+/// Songs jumping around forever are cut after this
+const MAX_DURATION: f32 = 20. * 60.;
+
+#[derive(Debug)]
+pub enum TrackerError {
+    FileError(FileError),
+    /// Not a MOD file or a broken one
+    UnsupportedFormat,
+}
+
+impl std::fmt::Display for TrackerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TrackerError::FileError(error) => write!(f, "{}", error),
+            TrackerError::UnsupportedFormat => write!(f, "Unsupported tracker module format"),
+        }
+    }
+}
+
+impl std::error::Error for TrackerError {}
+
+impl From<FileError> for TrackerError {
+    fn from(error: FileError) -> TrackerError {
+        TrackerError::FileError(error)
+    }
+}
+
+/// A row of a pattern, as the song got to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackerRow {
+    /// Seconds since the start of the song
+    pub time: f32,
+    /// Position in the song order list
+    pub order: usize,
+    pub pattern: usize,
+    pub row: usize,
+}
+
+pub struct TrackerSong {
+    pub sound: Sound,
+    /// All the played rows, by time
+    pub rows: Vec<TrackerRow>,
+}
+
+impl TrackerSong {
+    /// The row playing at the given time, like [crate::audio::playback_time].
+    pub fn row_at(&self, time: f32) -> Option<&TrackerRow> {
+        let next = self.rows.partition_point(|row| row.time <= time);
+        self.rows.get(next.checked_sub(1)?)
+    }
+}
+
+pub async fn load_tracker_song(path: &str) -> Result<TrackerSong, TrackerError> {
+    let data = load_file(path).await?;
+
+    load_tracker_song_from_bytes(&data).await
+}
+
+pub async fn load_tracker_song_from_bytes(data: &[u8]) -> Result<TrackerSong, TrackerError> {
+    let module = Module::parse(data).ok_or(TrackerError::UnsupportedFormat)?;
+    let (samples, rows) = module.render();
+
+    Ok(TrackerSong {
+        sound: load_sound_from_samples(&samples, 2, SAMPLE_RATE).await,
+        rows,
+    })
+}
+
+struct Instrument {
+    data: Vec<f32>,
+    finetune: i8,
+    volume: u8,
+    loop_start: usize,
+    loop_length: usize,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Cell {
+    instrument: usize,
+    period: u16,
+    effect: u8,
+    param: u8,
+}
+
+struct Module {
+    channels: usize,
+    instruments: Vec<Instrument>,
+    orders: Vec<usize>,
+    /// 64 rows by `channels` cells each
+    patterns: Vec<Vec<Cell>>,
+}
+
+fn channels_from_signature(signature: &[u8]) -> Option<usize> {
+    let digit = |b: u8| (b as char).to_digit(10).map(|d| d as usize);
+    match signature {
+        b"M.K." | b"M!K!" | b"FLT4" | b"4CHN" => Some(4),
+        b"OCTA" | b"CD81" | b"FLT8" => Some(8),
+        [n, b'C', b'H', b'N'] => digit(*n),
+        [a, b, b'C', b'H'] => Some(digit(*a)? * 10 + digit(*b)?),
+        _ => None,
+    }
+    .filter(|channels| (1..=32).contains(channels))
+}
+
+/// Pattern break parameter is the row number in decimal digits
+fn break_row(param: u8) -> usize {
+    ((param >> 4) as usize * 10 + (param & 0x0f) as usize).min(63)
+}
+
+impl Module {
+    fn parse(data: &[u8]) -> Option<Module> {
+        let u16_at = |offset: usize| -> Option<usize> {
+            let bytes = data.get(offset..offset + 2)?;
+            Some((bytes[0] as usize) << 8 | bytes[1] as usize)
+        };
+
+        let channels = channels_from_signature(data.get(1080..1084)?)?;
+        let song_length = (*data.get(950)? as usize).clamp(1, 128);
+        let orders: Vec<usize> = data
+            .get(952..952 + 128)?
+            .iter()
+            .map(|o| *o as usize)
+            .collect();
+        let pattern_count = orders.iter().max()? + 1;
+
+        let pattern_size = 64 * channels * 4;
+        let mut patterns = Vec::with_capacity(pattern_count);
+        for pattern in 0..pattern_count {
+            let offset = 1084 + pattern * pattern_size;
+            let bytes = data.get(offset..offset + pattern_size)?;
+            patterns.push(
+                bytes
+                    .chunks_exact(4)
+                    .map(|b| Cell {
+                        instrument: (b[0] & 0xf0 | b[2] >> 4) as usize,
+                        period: ((b[0] & 0x0f) as u16) << 8 | b[1] as u16,
+                        effect: b[2] & 0x0f,
+                        param: b[3],
+                    })
+                    .collect(),
+            );
+        }
+
+        let mut sample_offset = 1084 + pattern_count * pattern_size;
+        let mut instruments = Vec::with_capacity(31);
+        for i in 0..31 {
+            let header = 20 + i * 30;
+            let length = u16_at(header + 22)? * 2;
+            let finetune = (*data.get(header + 24)? & 0x0f) as i8;
+            let loop_start = u16_at(header + 26)? * 2;
+            let loop_length = u16_at(header + 28)? * 2;
+
+            // some files are cut short, keep what is there
+            let end = (sample_offset + length).min(data.len());
+            let bytes = data.get(sample_offset.min(end)..end)?;
+            sample_offset += length;
+
+            instruments.push(Instrument {
+                data: bytes.iter().map(|b| *b as i8 as f32 / 128.).collect(),
+                // signed nibble
+                finetune: if finetune > 7 {
+                    finetune - 16
+                } else {
+                    finetune
+                },
+                volume: (*data.get(header + 25)?).min(64),
+                loop_start,
+                loop_length: if loop_length > 2 { loop_length } else { 0 },
+            });
+        }
+
+        Some(Module {
+            channels,
+            instruments,
+            orders: orders[..song_length].to_vec(),
+            patterns,
+        })
+    }
+
+    /// Stereo samples at [SAMPLE_RATE] and the time of every row
+    fn render(&self) -> (Vec<f32>, Vec<TrackerRow>) {
+        let mut channels: Vec<Channel> = (0..self.channels).map(|_| Channel::default()).collect();
+        let mut output = vec![];
+        let mut rows = vec![];
+
+        let mut speed = 6;
+        let mut tempo = 125.;
+        let mut order = 0;
+        let mut row = 0;
+        let mut visited = std::collections::HashSet::new();
+        let mut time = 0.;
+
+        while order < self.orders.len() && visited.insert((order, row)) && time < MAX_DURATION {
+            let pattern = self.orders[order];
+            rows.push(TrackerRow {
+                time,
+                order,
+                pattern,
+                row,
+            });
+
+            let mut next = (order, row + 1);
+            let cells = &self.patterns[pattern][row * self.channels..(row + 1) * self.channels];
+            for (channel, cell) in channels.iter_mut().zip(cells) {
+                channel.trigger(cell, &self.instruments);
+                match (cell.effect, cell.param) {
+                    (0xb, position) => next = (position as usize, 0),
+                    (0xd, param) => next = (order + 1, break_row(param)),
+                    (0xf, 0) => {}
+                    (0xf, param) if param < 32 => speed = param as usize,
+                    (0xf, param) => tempo = param as f32,
+                    _ => {}
+                }
+            }
+            // a pattern break after a jump breaks into the jumped-to pattern
+            if let Some(jump) = cells.iter().find(|cell| cell.effect == 0xb) {
+                if let Some(brk) = cells.iter().find(|cell| cell.effect == 0xd) {
+                    next = (jump.param as usize, break_row(brk.param));
+                }
+            }
+
+            let tick_frames = (SAMPLE_RATE as f32 * 2.5 / tempo) as usize;
+            for tick in 0..speed {
+                for channel in channels.iter_mut() {
+                    channel.tick(tick);
+                }
+                mix(&mut channels, &self.instruments, tick_frames, &mut output);
+            }
+            time += speed as f32 * 2.5 / tempo;
+
+            order = next.0;
+            row = next.1;
+            if row >= 64 {
+                order += 1;
+                row = 0;
+            }
+        }
+
+        (output, rows)
+    }
+}
+
+#[derive(Default)]
+struct Channel {
+    instrument: Option<usize>,
+    position: f32,
+    volume: i32,
+    /// Period of the played note
+    base_period: f32,
+    /// Period with the per-tick effects, like vibrato
+    period: f32,
+    target_period: f32,
+    porta_speed: f32,
+    vibrato_position: usize,
+    vibrato_speed: usize,
+    vibrato_depth: f32,
+    effect: u8,
+    param: u8,
+    cut_tick: Option<usize>,
+}
+
+impl Channel {
+    fn trigger(&mut self, cell: &Cell, instruments: &[Instrument]) {
+        self.effect = cell.effect;
+        self.param = cell.param;
+        self.cut_tick = None;
+
+        if cell.instrument != 0 {
+            if let Some(instrument) = instruments.get(cell.instrument - 1) {
+                self.instrument = Some(cell.instrument - 1);
+                self.volume = instrument.volume as i32;
+            }
+        }
+
+        if cell.period != 0 {
+            let finetune = self
+                .instrument
+                .map_or(0, |instrument| instruments[instrument].finetune);
+            let period = cell.period as f32 * 2f32.powf(-(finetune as f32) / 96.);
+
+            if cell.effect == 0x3 || cell.effect == 0x5 {
+                self.target_period = period;
+            } else {
+                self.base_period = period;
+                self.target_period = period;
+                self.position = if cell.effect == 0x9 {
+                    cell.param as f32 * 256.
+                } else {
+                    0.
+                };
+                self.vibrato_position = 0;
+            }
+        }
+
+        let (x, y) = (cell.param >> 4, cell.param & 0x0f);
+        match cell.effect {
+            0x3 if cell.param != 0 => self.porta_speed = cell.param as f32,
+            0x4 => {
+                if x != 0 {
+                    self.vibrato_speed = x as usize;
+                }
+                if y != 0 {
+                    self.vibrato_depth = y as f32;
+                }
+            }
+            0xc => self.volume = cell.param.min(64) as i32,
+            0xe => match x {
+                0x1 => self.base_period -= y as f32,
+                0x2 => self.base_period += y as f32,
+                0xa => self.volume += y as i32,
+                0xb => self.volume -= y as i32,
+                0xc => self.cut_tick = Some(y as usize),
+                _ => {}
+            },
+            _ => {}
+        }
+        self.base_period = self.base_period.clamp(28., 6848.);
+        self.volume = self.volume.clamp(0, 64);
+    }
+
+    fn tick(&mut self, tick: usize) {
+        let (x, y) = (self.param >> 4, self.param & 0x0f);
+        let mut period = self.base_period;
+
+        if tick != 0 {
+            match self.effect {
+                0x1 => self.base_period -= self.param as f32,
+                0x2 => self.base_period += self.param as f32,
+                0x3 | 0x5 => {
+                    let delta = (self.target_period - self.base_period)
+                        .clamp(-self.porta_speed, self.porta_speed);
+                    self.base_period += delta;
+                }
+                0x4 | 0x6 => {
+                    self.vibrato_position = (self.vibrato_position + self.vibrato_speed) % 64
+                }
+                _ => {}
+            }
+            if let 0x5 | 0x6 | 0xa = self.effect {
+                self.volume = (self.volume + x as i32 - y as i32).clamp(0, 64);
+            }
+            self.base_period = self.base_period.clamp(28., 6848.);
+            period = self.base_period;
+        }
+
+        match self.effect {
+            0x0 if self.param != 0 => {
+                let semitones = [0, x, y][tick % 3];
+                period /= 2f32.powf(semitones as f32 / 12.);
+            }
+            0x4 | 0x6 => {
+                let phase = self.vibrato_position as f32 / 64. * std::f32::consts::TAU;
+                period += phase.sin() * self.vibrato_depth * 2.;
+            }
+            _ => {}
+        }
+        self.period = period;
+
+        if self.cut_tick == Some(tick) {
+            self.volume = 0;
+        }
+    }
+}
+
+fn mix(channels: &mut [Channel], instruments: &[Instrument], frames: usize, output: &mut Vec<f32>) {
+    let start = output.len();
+    output.resize(start + frames * 2, 0.);
+    // the amiga hard panning, LRRL, softened for headphones
+    let gain = 2. / channels.len() as f32;
+
+    for (n, channel) in channels.iter_mut().enumerate() {
+        let instrument = match channel.instrument {
+            Some(instrument) if channel.volume != 0 && channel.period > 0. => {
+                &instruments[instrument]
+            }
+            _ => continue,
+        };
+        let left = if n % 4 == 0 || n % 4 == 3 { 0.75 } else { 0.25 };
+        let volume = channel.volume as f32 / 64. * gain;
+        let step = PAULA_CLOCK / channel.period / SAMPLE_RATE as f32;
+        let loop_end = instrument.loop_start + instrument.loop_length;
+
+        for frame in output[start..].chunks_exact_mut(2) {
+            if instrument.loop_length != 0 && channel.position >= loop_end as f32 {
+                channel.position -= instrument.loop_length as f32;
+            }
+            let i = channel.position as usize;
+            let sample = match instrument.data.get(i) {
+                Some(sample) => *sample,
+                None => {
+                    channel.instrument = None;
+                    break;
+                }
+            };
+            frame[0] += sample * volume * left;
+            frame[1] += sample * volume * (1. - left);
+            channel.position += step;
+        }
+    }
+}
+
+#[test]
+fn tracker_rows_timing() {
+    let mut data = vec![0; 1084 + 64 * 4 * 4 + 32];
+    // one looped square wave sample
+    data[20 + 22..20 + 30].copy_from_slice(&[0, 16, 0, 64, 0, 0, 0, 16]);
+    data[950] = 1;
+    data[1080..1084].copy_from_slice(b"M.K.");
+    // C-2 with the sample on the first row, stop the song on the row 32
+    data[1084..1088].copy_from_slice(&[0x01, 0xac, 0x10, 0x00]);
+    data[1084 + 31 * 16..1084 + 31 * 16 + 4].copy_from_slice(&[0, 0, 0x0b, 0x01]);
+    for (i, sample) in data[1084 + 64 * 16..].iter_mut().enumerate() {
+        *sample = if i % 8 < 4 { 100 } else { 156 };
+    }
+
+    let module = Module::parse(&data).unwrap();
+    let (samples, rows) = module.render();
+
+    // 6 ticks of 20ms
+    assert_eq!(rows.len(), 32);
+    assert!((rows[31].time - 31. * 0.12).abs() < 0.001);
+    assert_eq!(samples.len(), 32 * 6 * 882 * 2);
+    assert!(samples.iter().any(|sample| sample.abs() > 0.1));
+}
@@ -1,18 +1,33 @@
 //! 2D and 3D camera.
 
 use crate::{
-    get_context,
+    get_context, get_quad_context,
     math::Rect,
     texture::RenderTarget,
     window::{screen_height, screen_width},
 };
-use glam::{vec2, vec3, Mat4, Vec2, Vec3};
+use glam::{vec2, vec3, vec4, Mat4, Vec2, Vec3, Vec4};
+
+mod shake;
+
+pub use shake::ScreenShake;
 
 pub trait Camera {
     fn matrix(&self) -> Mat4;
     fn depth_enabled(&self) -> bool;
     fn render_pass(&self) -> Option<miniquad::RenderPass>;
     fn viewport(&self) -> Option<(i32, i32, i32, i32)>;
+
+    /// View part of the [Camera::matrix], the rest of it is projection.
+    /// Used for `_View` and `_Projection` material uniforms.
+    fn view_matrix(&self) -> Mat4 {
+        Mat4::IDENTITY
+    }
+
+    /// Camera position in world space, used for `_CameraPos` material uniform.
+    fn position(&self) -> Vec3 {
+        Vec3::ZERO
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -130,27 +145,69 @@ impl Camera for Camera2D {
     fn viewport(&self) -> Option<(i32, i32, i32, i32)> {
         self.viewport
     }
+
+    fn position(&self) -> Vec3 {
+        vec3(self.target.x, self.target.y, 0.)
+    }
 }
 
 impl Camera2D {
+    /// Move the camera target so the visible area stays inside of `bounds`.
+    /// If the visible area is bigger than the bounds, along some axis,
+    /// the camera is centered on the bounds along it.
+    ///
+    /// Zoom, offset and rotation are taken into account. Viewport does not
+    /// affect the visible area, it changes only where on the screen it is drawn.
+    /// A [ScreenShake] applied after the clamp may still show a bit outside of the bounds.
+    pub fn clamp_to(&mut self, bounds: Rect) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        // camera space to world space, undo the scale and then the rotation
+        let to_world = |v: Vec2| {
+            let v = v / self.zoom;
+            vec2(v.x * cos + v.y * sin, -v.x * sin + v.y * cos)
+        };
+
+        let center = self.target + to_world(-self.offset);
+        let (a, b) = (to_world(vec2(1., 1.)), to_world(vec2(1., -1.)));
+        let half = vec2(a.x.abs().max(b.x.abs()), a.y.abs().max(b.y.abs()));
+
+        let clamp_axis = |center: f32, half: f32, min: f32, size: f32| {
+            if half * 2. >= size {
+                min + size / 2.
+            } else {
+                center.clamp(min + half, min + size - half)
+            }
+        };
+        let clamped = vec2(
+            clamp_axis(center.x, half.x, bounds.x, bounds.w),
+            clamp_axis(center.y, half.y, bounds.y, bounds.h),
+        );
+
+        self.target += clamped - center;
+    }
+
     /// Returns the screen space position for a 2d camera world space position
     /// Screen position in window space - from (0, 0) to (screen_width, screen_height())
+    /// With a viewport set - inside of the viewport on the screen
     pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
         let mat = self.matrix();
         let transform = mat.transform_point3(vec3(point.x, point.y, 0.));
+        let rect = viewport_screen_rect(self.viewport);
 
         vec2(
-            (transform.x / 2. + 0.5) * screen_width(),
-            (0.5 - transform.y / 2.) * screen_height(),
+            rect.x + (transform.x / 2. + 0.5) * rect.w,
+            rect.y + (0.5 - transform.y / 2.) * rect.h,
         )
     }
 
     // Returns the world space position for a 2d camera screen space position
     // Point is a screen space position, often mouse x and y
+    // With a viewport set - relative to the viewport on the screen
     pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        let rect = viewport_screen_rect(self.viewport);
         let point = vec2(
-            point.x / screen_width() * 2. - 1.,
-            1. - point.y / screen_height() * 2.,
+            (point.x - rect.x) / rect.w * 2. - 1.,
+            1. - (point.y - rect.y) / rect.h * 2.,
         );
         let inv_mat = self.matrix().inverse();
         let transform = inv_mat.transform_point3(vec3(point.x, point.y, 0.));
@@ -162,7 +219,41 @@ impl Camera2D {
 #[derive(Debug, Clone, Copy)]
 pub enum Projection {
     Perspective,
+    /// No perspective, parallel lines stay parallel: isometric or strategy views.
+    /// [Camera3D::fovy] is the height of the visible area, in world units.
     Orthographics,
+    /// Custom projection matrix, the view still comes from position, target and up.
+    /// [Camera3D::fovy] and [Camera3D::aspect] are not used.
+    Custom(Mat4),
+}
+
+impl Projection {
+    /// Perspective with an off-center, asymmetric, frustum.
+    /// `left`, `right`, `bottom` and `top` are the near plane edges in view space,
+    /// like for multi-projector setups or head-tracked displays.
+    /// Orthographic one is just `Projection::Custom(Mat4::orthographic_rh_gl(..))`.
+    pub fn off_center(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    ) -> Projection {
+        let (width, height, depth) = (right - left, top - bottom, far - near);
+
+        Projection::Custom(Mat4::from_cols(
+            vec4(2. * near / width, 0., 0., 0.),
+            vec4(0., 2. * near / height, 0., 0.),
+            vec4(
+                (right + left) / width,
+                (top + bottom) / height,
+                -(far + near) / depth,
+                -1.,
+            ),
+            vec4(0., 0., -2. * far * near / depth, 0.),
+        ))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -174,7 +265,7 @@ pub struct Camera3D {
     /// Camera up vector (rotation over its axis)
     pub up: Vec3,
     /// Camera field-of-view aperture in Y (degrees)
-    /// in perspective, used as near plane width in orthographic
+    /// in perspective, used as visible area height in orthographic
     pub fovy: f32,
     /// Screen aspect ratio
     /// By default aspect is calculated with screen_width() / screen_height() on each frame
@@ -211,27 +302,103 @@ impl Default for Camera3D {
 impl Camera3D {
     const Z_NEAR: f32 = 0.01;
     const Z_FAR: f32 = 10000.0;
-}
 
-impl Camera for Camera3D {
-    fn matrix(&self) -> Mat4 {
-        let aspect = self.aspect.unwrap_or(screen_width() / screen_height());
+    /// Projection matrix with the near plane replaced by `plane`, so everything
+    /// behind it is clipped, like for planar reflections clipped at the water level.
+    /// Far plane gets skewed, depth precision is the best when the plane is close
+    /// to the camera near plane.
+    ///
+    /// `plane` is in world space, (a, b, c, d) for `ax + by + cz + d = 0`,
+    /// with the normal pointing to the visible side, and should not be behind the camera.
+    /// ```ignore
+    /// // reflection camera, draw only what is above the y = water_level plane
+    /// camera.projection = Projection::Custom(camera.oblique_projection(vec4(0., 1., 0., -water_level)));
+    /// ```
+    /// based on "Oblique View Frustum Depth Projection and Clipping", Eric Lengyel
+    pub fn oblique_projection(&self, plane: Vec4) -> Mat4 {
+        let projection = self.projection_matrix();
+        let plane = self.view_matrix().inverse().transpose() * plane;
+
+        // corner of the frustum opposite to the plane, in clip space
+        let corner = projection.inverse() * vec4(plane.x.signum(), plane.y.signum(), 1., 1.);
+        let plane = plane * (2. / plane.dot(corner));
+
+        // third row, clip space z, becomes the plane minus the fourth row
+        let row_w = projection.row(3);
+        let row_z = plane - row_w;
+        let mut cols = projection.to_cols_array_2d();
+        for (col, z) in cols.iter_mut().zip(row_z.to_array().iter()) {
+            col[2] = *z;
+        }
+
+        Mat4::from_cols_array_2d(&cols)
+    }
+
+    /// Projection part of the camera matrix, without the view.
+    pub fn projection_matrix(&self) -> Mat4 {
+        let aspect = || self.aspect.unwrap_or(screen_width() / screen_height());
 
         match self.projection {
             Projection::Perspective => {
-                Mat4::perspective_rh_gl(self.fovy, aspect, Self::Z_NEAR, Self::Z_FAR)
-                    * Mat4::look_at_rh(self.position, self.target, self.up)
+                Mat4::perspective_rh_gl(self.fovy, aspect(), Self::Z_NEAR, Self::Z_FAR)
             }
             Projection::Orthographics => {
                 let top = self.fovy / 2.0;
-                let right = top * aspect;
+                let right = top * aspect();
 
                 Mat4::orthographic_rh_gl(-right, right, -top, top, Self::Z_NEAR, Self::Z_FAR)
-                    * Mat4::look_at_rh(self.position, self.target, self.up)
             }
+            Projection::Custom(matrix) => matrix,
         }
     }
 
+    /// Returns the screen space position for a 3d world space position,
+    /// or None if the point is behind the camera.
+    /// Screen position in window space - from (0, 0) to (screen_width, screen_height()),
+    /// with a viewport set - inside of the viewport on the screen.
+    pub fn world_to_screen(&self, point: Vec3) -> Option<Vec2> {
+        let clip = self.matrix() * point.extend(1.);
+        if clip.w <= 0. {
+            return None;
+        }
+        let ndc = clip.truncate() / clip.w;
+        let rect = viewport_screen_rect(self.viewport);
+
+        Some(vec2(
+            rect.x + (ndc.x / 2. + 0.5) * rect.w,
+            rect.y + (0.5 - ndc.y / 2.) * rect.h,
+        ))
+    }
+
+    /// Returns the world space point under the screen space position, often mouse x and y,
+    /// `depth` units away from the camera along the ray through it.
+    pub fn screen_to_world(&self, point: Vec2, depth: f32) -> Vec3 {
+        let (origin, direction) = self.screen_ray(point);
+        origin + direction * depth
+    }
+
+    /// Ray from the camera through the screen space position, as (origin, normalized direction).
+    /// Handy for click targeting: intersect it with the scene.
+    pub fn screen_ray(&self, point: Vec2) -> (Vec3, Vec3) {
+        let rect = viewport_screen_rect(self.viewport);
+        let ndc = vec2(
+            (point.x - rect.x) / rect.w * 2. - 1.,
+            1. - (point.y - rect.y) / rect.h * 2.,
+        );
+
+        let inv_mat = self.matrix().inverse();
+        let near = inv_mat.project_point3(vec3(ndc.x, ndc.y, -1.));
+        let far = inv_mat.project_point3(vec3(ndc.x, ndc.y, 1.));
+
+        (near, (far - near).normalize())
+    }
+}
+
+impl Camera for Camera3D {
+    fn matrix(&self) -> Mat4 {
+        self.projection_matrix() * self.view_matrix()
+    }
+
     fn depth_enabled(&self) -> bool {
         true
     }
@@ -243,6 +410,14 @@ impl Camera for Camera3D {
     fn viewport(&self) -> Option<(i32, i32, i32, i32)> {
         self.viewport
     }
+
+    fn view_matrix(&self) -> Mat4 {
+        Mat4::look_at_rh(self.position, self.target, self.up)
+    }
+
+    fn position(&self) -> Vec3 {
+        self.position
+    }
 }
 
 /// Set active 2D or 3D camera
@@ -256,6 +431,62 @@ pub fn set_camera(camera: &dyn Camera) {
     context.gl.viewport(camera.viewport());
     context.gl.depth_test(camera.depth_enabled());
     context.camera_matrix = Some(camera.matrix());
+    context.camera_view = Some((camera.view_matrix(), camera.position()));
+}
+
+/// Set active 2D or 3D camera, drawing only to the `viewport` part of the screen.
+///
+/// The viewport is in screen coordinates, like [crate::input::mouse_position],
+/// and overrides the camera [Camera::viewport], so one camera may be drawn
+/// to several viewports. All the drawing, [crate::window::clear_background] as well,
+/// is clipped to the viewport. Useful for split-screen:
+/// ```ignore
+/// let (w, h) = (screen_width(), screen_height());
+/// for (i, player) in players.iter().enumerate() {
+///     set_camera_with_viewport(&player.camera, Rect::new(i as f32 * w / 2., 0., w / 2., h));
+///     clear_background(SKYBLUE);
+///     draw_scene();
+/// }
+/// set_default_camera();
+/// ```
+/// For input, [Camera2D::screen_to_world] with the camera viewport set to
+/// [screen_rect_to_viewport] of the same rect maps the mouse into this viewport.
+pub fn set_camera_with_viewport(camera: &dyn Camera, viewport: Rect) {
+    set_camera(camera);
+
+    get_context()
+        .gl
+        .viewport(Some(screen_rect_to_viewport(viewport)));
+}
+
+/// Camera viewport, as in [Camera2D::viewport], for a part of the screen in
+/// screen coordinates: logical pixels from the top-left corner.
+pub fn screen_rect_to_viewport(rect: Rect) -> (i32, i32, i32, i32) {
+    let dpi = get_quad_context().dpi_scale();
+    let height = screen_height() * dpi;
+
+    (
+        (rect.x * dpi) as i32,
+        (height - (rect.y + rect.h) * dpi) as i32,
+        (rect.w * dpi) as i32,
+        (rect.h * dpi) as i32,
+    )
+}
+
+/// Part of the screen, in screen coordinates, covered by the camera viewport.
+fn viewport_screen_rect(viewport: Option<(i32, i32, i32, i32)>) -> Rect {
+    match viewport {
+        Some((x, y, w, h)) => {
+            let dpi = get_quad_context().dpi_scale();
+            Rect::new(
+                x as f32 / dpi,
+                screen_height() - (y + h) as f32 / dpi,
+                w as f32 / dpi,
+                h as f32 / dpi,
+            )
+        }
+        None => Rect::new(0., 0., screen_width(), screen_height()),
+    }
 }
 
 /// Reset default 2D camera mode
@@ -268,12 +499,14 @@ pub fn set_default_camera() {
     context.gl.render_pass(None);
     context.gl.depth_test(false);
     context.camera_matrix = None;
+    context.camera_view = None;
 }
 
 pub(crate) struct CameraState {
     render_pass: Option<miniquad::RenderPass>,
     depth_test: bool,
     matrix: Option<Mat4>,
+    view: Option<(Mat4, Vec3)>,
 }
 
 pub fn push_camera_state() {
@@ -283,6 +516,7 @@ pub fn push_camera_state() {
         render_pass: context.gl.get_active_render_pass(),
         depth_test: context.gl.is_depth_test_enabled(),
         matrix: context.camera_matrix,
+        view: context.camera_view,
     };
     context.camera_stack.push(camera_state);
 }
@@ -296,5 +530,48 @@ pub fn pop_camera_state() {
         context.gl.render_pass(camera_state.render_pass);
         context.gl.depth_test(camera_state.depth_test);
         context.camera_matrix = camera_state.matrix;
+        context.camera_view = camera_state.view;
+    }
+}
+
+#[test]
+fn camera2d_clamp_to() {
+    let bounds = Rect::new(0., 0., 1000., 1000.);
+
+    let mut camera = Camera2D::from_display_rect(Rect::new(-50., -50., 100., 100.));
+    camera.clamp_to(bounds);
+    assert!((camera.target - vec2(50., 50.)).length() < 1e-3);
+
+    // 200x100 visible area, rotated: 100 wide along x
+    camera.target = vec2(990., 500.);
+    camera.rotation = 90.;
+    camera.zoom = vec2(1. / 100., -1. / 50.);
+    camera.clamp_to(bounds);
+    assert!((camera.target - vec2(950., 500.)).length() < 1e-3);
+
+    camera.clamp_to(Rect::new(0., 0., 50., 1000.));
+    assert!((camera.target.x - 25.).abs() < 1e-3);
+}
+
+#[test]
+fn camera3d_oblique_projection() {
+    let mut camera = Camera3D {
+        position: vec3(0., 5., 10.),
+        target: vec3(0., 0., 0.),
+        up: vec3(0., 1., 0.),
+        projection: Projection::Custom(Mat4::perspective_rh_gl(1., 1., 0.1, 100.)),
+        ..Default::default()
+    };
+    let plane = vec4(0., 1., 0., -1.);
+    camera.projection = Projection::Custom(camera.oblique_projection(plane));
+
+    // points on the plane end up on the near plane, z = -1 in ndc
+    let matrix = camera.matrix();
+    for point in &[vec3(0., 1., 0.), vec3(2., 1., -3.)] {
+        let ndc = matrix.project_point3(*point);
+        assert!((ndc.z + 1.).abs() < 1e-3, "{:?}", ndc);
     }
+    // above the plane - visible, below - clipped
+    assert!(matrix.project_point3(vec3(0., 2., 0.)).z > -1.);
+    assert!(matrix.project_point3(vec3(0., 0., 0.)).z < -1.);
 }
@@ -0,0 +1,111 @@
+use super::Camera2D;
+use crate::time::get_game_time;
+use glam::{vec2, Vec2};
+
+/// Trauma-based screen shake for a [Camera2D].
+///
+/// Trauma, 0..1, is added with [ScreenShake::add_trauma] and decays over time.
+/// The shake strength is trauma squared, so small hits barely move the camera
+/// while big ones shake it a lot.
+/// Both the decay and the shake itself follow the game clock, [get_game_time],
+/// so they slow down with the time scale and stop with the pause, and there
+/// is nothing to update each frame.
+///
+/// ```ignore
+/// let mut shake = ScreenShake::default();
+/// loop {
+///     if hit {
+///         shake.add_trauma(0.5);
+///     }
+///     set_camera(&shake.apply(&camera));
+///     ...
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ScreenShake {
+    /// Max camera offset at full trauma, in [super::Camera2D::offset] units:
+    /// 1.0 is half of the screen
+    pub amplitude: Vec2,
+    /// Max camera rotation at full trauma, in degrees. 0 - no rotation.
+    pub rotation: f32,
+    /// How fast the camera shakes, in noise periods per second
+    pub frequency: f32,
+    /// Trauma lost per second
+    pub decay: f32,
+
+    trauma: f32,
+    trauma_time: f64,
+}
+
+impl Default for ScreenShake {
+    fn default() -> ScreenShake {
+        ScreenShake {
+            amplitude: vec2(0.05, 0.05),
+            rotation: 0.,
+            frequency: 15.,
+            decay: 1.,
+            trauma: 0.,
+            trauma_time: 0.,
+        }
+    }
+}
+
+impl ScreenShake {
+    /// Add trauma, total trauma is clamped to 1.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma() + amount).clamp(0., 1.);
+        self.trauma_time = get_game_time();
+    }
+
+    /// Current trauma, decayed since the last [ScreenShake::add_trauma].
+    pub fn trauma(&self) -> f32 {
+        // no clock access for a camera that never shook
+        if self.trauma == 0. {
+            return 0.;
+        }
+        (self.trauma - self.decay * (get_game_time() - self.trauma_time) as f32).max(0.)
+    }
+
+    /// Current camera offset and rotation, in degrees.
+    pub fn offset(&self) -> (Vec2, f32) {
+        let trauma = self.trauma();
+        if trauma == 0. {
+            return (vec2(0., 0.), 0.);
+        }
+
+        let shake = trauma * trauma;
+        let t = get_game_time() as f32 * self.frequency;
+        let offset = vec2(noise(t, 0), noise(t, 1)) * self.amplitude * shake;
+
+        (offset, noise(t, 2) * self.rotation * shake)
+    }
+
+    /// Copy of `camera` with the shake added on top of its offset and rotation.
+    pub fn apply(&self, camera: &Camera2D) -> Camera2D {
+        let (offset, rotation) = self.offset();
+
+        Camera2D {
+            offset: camera.offset + offset,
+            rotation: camera.rotation + rotation,
+            ..*camera
+        }
+    }
+}
+
+/// Smooth 1D value noise, -1..1
+fn noise(x: f32, seed: u32) -> f32 {
+    let hash = |n: i32| {
+        let mut h = (n as u32).wrapping_mul(0x27d4_eb2d) ^ seed.wrapping_mul(0x1656_67b1);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x85eb_ca6b);
+        h ^= h >> 13;
+        h as f32 / u32::MAX as f32 * 2. - 1.
+    };
+
+    let i = x.floor();
+    let f = x - i;
+    let f = f * f * (3. - 2. * f);
+    let i = i as i32;
+
+    hash(i) + (hash(i.wrapping_add(1)) - hash(i)) * f
+}
@@ -0,0 +1,571 @@
+//! [egui](https://github.com/emilk/egui) integration, with the `egui` feature.
+//!
+//! The egui frame is built with [ui] and drawn with [draw], on top of what was
+//! drawn before it:
+//! ```ignore
+//! use macroquad::prelude::*;
+//!
+//! #[macroquad::main("egui")]
+//! async fn main() {
+//!     loop {
+//!         clear_background(WHITE);
+//!
+//!         egui::ui(|ctx| {
+//!             egui::Window::new("egui").show(ctx, |ui| {
+//!                 ui.label("Hello from egui");
+//!             });
+//!         });
+//!         egui::draw();
+//!
+//!         next_frame().await
+//!     }
+//! }
+//! ```
+//! All of the egui crate is re-exported in this module.
+
+use crate::{
+    color::Color,
+    get_context, get_quad_context,
+    input::utils::{register_input_subscriber, repeat_all_miniquad_input},
+    material::{gl_use_default_material, gl_use_material, load_material, Material, MaterialParams},
+    quad_gl::{DrawMode, Vertex},
+    texture::{self, FilterMode, Texture2D},
+    time::get_time,
+    window::miniquad::{self, KeyCode, KeyMods, MouseButton},
+};
+
+pub use ::egui::*;
+
+use ::egui::epaint::{ImageDelta, Primitive};
+use std::collections::HashMap;
+
+/// Draw call size limits of the macroquad batcher.
+const MAX_VERTICES: usize = 9000;
+const MAX_INDICES: usize = 4500;
+
+/// Points scrolled by one mouse wheel step.
+const SCROLL_SPEED: f32 = 20.;
+
+/// Paint callback to draw with macroquad inside of egui:
+/// ```ignore
+/// let (rect, _) = ui.allocate_exact_size(egui::vec2(100., 100.), egui::Sense::hover());
+/// ui.painter().add(egui::PaintCallback {
+///     rect,
+///     callback: std::sync::Arc::new(egui::CallbackFn::new(|info| {
+///         let center = info.viewport.center();
+///         draw_circle(center.x, center.y, 40., RED);
+///     })),
+/// });
+/// ```
+/// Macroquad draws with the default camera, in the same points as egui,
+/// clipped to the callback rect.
+pub struct CallbackFn(Box<dyn Fn(PaintCallbackInfo) + Sync + Send>);
+
+impl CallbackFn {
+    pub fn new<F: Fn(PaintCallbackInfo) + Sync + Send + 'static>(f: F) -> CallbackFn {
+        CallbackFn(Box::new(f))
+    }
+}
+
+pub(crate) struct EguiContext {
+    ctx: Context,
+    input_subscriber: usize,
+    input: EguiInput,
+    shapes: Vec<epaint::ClippedShape>,
+    textures_delta: TexturesDelta,
+    textures: HashMap<TextureId, Texture2D>,
+    /// Textures freed by egui, deleted once the frame using them is drawn
+    textures_freed: Vec<TextureId>,
+    cursor_icon: CursorIcon,
+    material: Option<Material>,
+}
+
+fn egui_context() -> &'static mut EguiContext {
+    if get_context().egui_context.is_none() {
+        let input_subscriber = register_input_subscriber();
+        get_context().egui_context = Some(EguiContext {
+            ctx: Context::default(),
+            input_subscriber,
+            input: EguiInput::default(),
+            shapes: vec![],
+            textures_delta: TexturesDelta::default(),
+            textures: HashMap::new(),
+            textures_freed: vec![],
+            cursor_icon: CursorIcon::Default,
+            material: None,
+        });
+    }
+
+    get_context().egui_context.as_mut().unwrap()
+}
+
+/// The egui context, to change the style and fonts, or to check if egui
+/// wants the mouse and the keyboard.
+pub fn context() -> Context {
+    egui_context().ctx.clone()
+}
+
+/// Build the egui frame with all the input since the last call.
+/// Should be called once per frame, before [draw].
+pub fn ui<F: FnOnce(&Context)>(f: F) {
+    let egui = egui_context();
+    repeat_all_miniquad_input(&mut egui.input, egui.input_subscriber);
+
+    let ctx = get_quad_context();
+    let pixels_per_point = ctx.dpi_scale();
+    let (width, height) = ctx.screen_size();
+    let raw_input = RawInput {
+        screen_rect: Some(Rect::from_min_size(
+            Pos2::ZERO,
+            vec2(width, height) / pixels_per_point,
+        )),
+        pixels_per_point: Some(pixels_per_point),
+        time: Some(get_time()),
+        modifiers: egui.input.modifiers,
+        events: std::mem::take(&mut egui.input.events),
+        ..Default::default()
+    };
+    egui.input.pixels_per_point = pixels_per_point;
+
+    // `f` may register textures, no borrow of the egui context is held while it runs
+    let output = egui.ctx.clone().run(raw_input, f);
+
+    let egui = egui_context();
+    let platform_output = output.platform_output;
+    if !platform_output.copied_text.is_empty() {
+        ctx.clipboard_set(&platform_output.copied_text);
+    }
+    if platform_output.cursor_icon != egui.cursor_icon {
+        egui.cursor_icon = platform_output.cursor_icon;
+        ctx.set_mouse_cursor(to_miniquad_cursor(egui.cursor_icon));
+    }
+    egui.shapes = output.shapes;
+    egui.textures_delta.append(output.textures_delta);
+}
+
+/// Draw the last egui frame in screen space, whatever the current camera is.
+pub fn draw() {
+    let egui = egui_context();
+
+    for id in egui.textures_freed.drain(..) {
+        if let Some(texture) = egui.textures.remove(&id) {
+            if let TextureId::Managed(_) = id {
+                texture.delete();
+            }
+        }
+    }
+    let textures_delta = std::mem::take(&mut egui.textures_delta);
+    for (id, delta) in textures_delta.set {
+        update_texture(&mut egui.textures, id, delta);
+    }
+    egui.textures_freed = textures_delta.free;
+
+    let primitives = egui.ctx.tessellate(std::mem::take(&mut egui.shapes));
+    let pixels_per_point = egui.input.pixels_per_point;
+    let pixels_per_point = if pixels_per_point > 0. {
+        pixels_per_point
+    } else {
+        get_quad_context().dpi_scale()
+    };
+
+    // egui colors have their alpha premultiplied
+    let material = *egui.material.get_or_insert_with(|| {
+        load_material(
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            MaterialParams {
+                pipeline_params: miniquad::PipelineParams {
+                    color_blend: Some(miniquad::BlendState::new(
+                        miniquad::Equation::Add,
+                        miniquad::BlendFactor::One,
+                        miniquad::BlendFactor::OneMinusValue(miniquad::BlendValue::SourceAlpha),
+                    )),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    });
+
+    crate::camera::push_camera_state();
+    crate::camera::set_default_camera();
+    gl_use_material(material);
+
+    let (width, height) = get_quad_context().screen_size();
+    for ClippedPrimitive {
+        clip_rect,
+        primitive,
+    } in primitives
+    {
+        // the default camera is in points already, only the scissor is in pixels
+        let gl = &mut get_context().gl;
+        gl.scissor(Some((
+            (clip_rect.min.x * pixels_per_point) as i32,
+            (clip_rect.min.y * pixels_per_point) as i32,
+            (clip_rect.width() * pixels_per_point) as i32,
+            (clip_rect.height() * pixels_per_point) as i32,
+        )));
+
+        match primitive {
+            Primitive::Mesh(mesh) => {
+                let texture = match egui_context().textures.get(&mesh.texture_id) {
+                    Some(texture) => *texture,
+                    None => continue,
+                };
+                gl.texture(Some(texture));
+                gl.draw_mode(DrawMode::Triangles);
+                for (vertices, indices) in split_mesh(&mesh.vertices, &mesh.indices) {
+                    gl.geometry(&vertices, &indices);
+                }
+            }
+            Primitive::Callback(callback) => {
+                let viewport = callback.rect;
+                if let Some(callback) = callback.callback.downcast_ref::<CallbackFn>() {
+                    gl.texture(None);
+                    gl_use_default_material();
+
+                    (callback.0)(PaintCallbackInfo {
+                        viewport,
+                        clip_rect,
+                        pixels_per_point,
+                        screen_size_px: [width as u32, height as u32],
+                    });
+
+                    gl_use_material(material);
+                }
+            }
+        }
+    }
+
+    let gl = &mut get_context().gl;
+    gl.scissor(None);
+    gl.texture(None);
+    gl_use_default_material();
+    crate::camera::pop_camera_state();
+}
+
+/// Egui texture id for a macroquad texture, to draw it with `egui::Image`.
+pub fn texture_id(texture: Texture2D) -> TextureId {
+    let id = TextureId::User(texture.raw_miniquad_texture_handle().gl_internal_id() as u64);
+    egui_context().textures.insert(id, texture);
+    id
+}
+
+fn update_texture(textures: &mut HashMap<TextureId, Texture2D>, id: TextureId, delta: ImageDelta) {
+    let (width, height) = (delta.image.width(), delta.image.height());
+    let bytes = match &delta.image {
+        ImageData::Color(image) => image
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_array())
+            .collect::<Vec<_>>(),
+        ImageData::Font(image) => image
+            .srgba_pixels(1.)
+            .flat_map(|pixel| pixel.to_array())
+            .collect(),
+    };
+
+    match (delta.pos, textures.get(&id)) {
+        (Some([x, y]), Some(texture)) => {
+            let image = texture::Image {
+                bytes,
+                width: width as u16,
+                height: height as u16,
+            };
+            texture.update_part(&image, x as i32, y as i32, width as i32, height as i32);
+        }
+        _ => {
+            let texture = Texture2D::from_rgba8(width as u16, height as u16, &bytes);
+            texture.set_filter(match delta.filter {
+                TextureFilter::Nearest => FilterMode::Nearest,
+                TextureFilter::Linear => FilterMode::Linear,
+            });
+            if let Some(previous) = textures.insert(id, texture) {
+                previous.delete();
+            }
+        }
+    }
+}
+
+/// Egui meshes cut to the macroquad draw call limits, with u16 indices.
+fn split_mesh(vertices: &[epaint::Vertex], indices: &[u32]) -> Vec<(Vec<Vertex>, Vec<u16>)> {
+    let mut meshes = vec![];
+    let mut mesh_vertices = vec![];
+    let mut mesh_indices = vec![];
+    // index of each egui vertex in the current mesh
+    let mut remap: HashMap<u32, u16> = HashMap::new();
+
+    for triangle in indices.chunks_exact(3) {
+        if mesh_vertices.len() + 3 > MAX_VERTICES || mesh_indices.len() + 3 > MAX_INDICES {
+            meshes.push((
+                std::mem::take(&mut mesh_vertices),
+                std::mem::take(&mut mesh_indices),
+            ));
+            remap.clear();
+        }
+
+        for &index in triangle {
+            let index = *remap.entry(index).or_insert_with(|| {
+                let vertex = &vertices[index as usize];
+                let [r, g, b, a] = vertex.color.to_array();
+                mesh_vertices.push(Vertex::new(
+                    vertex.pos.x,
+                    vertex.pos.y,
+                    0.,
+                    vertex.uv.x,
+                    vertex.uv.y,
+                    Color::from_rgba(r, g, b, a),
+                ));
+                (mesh_vertices.len() - 1) as u16
+            });
+            mesh_indices.push(index);
+        }
+    }
+    if !mesh_indices.is_empty() {
+        meshes.push((mesh_vertices, mesh_indices));
+    }
+
+    meshes
+}
+
+fn to_miniquad_cursor(cursor_icon: CursorIcon) -> miniquad::CursorIcon {
+    match cursor_icon {
+        CursorIcon::Help => miniquad::CursorIcon::Help,
+        CursorIcon::PointingHand => miniquad::CursorIcon::Pointer,
+        CursorIcon::Wait | CursorIcon::Progress => miniquad::CursorIcon::Wait,
+        CursorIcon::Crosshair | CursorIcon::Cell => miniquad::CursorIcon::Crosshair,
+        CursorIcon::Text | CursorIcon::VerticalText => miniquad::CursorIcon::Text,
+        CursorIcon::Move | CursorIcon::AllScroll | CursorIcon::Grab | CursorIcon::Grabbing => {
+            miniquad::CursorIcon::Move
+        }
+        CursorIcon::NotAllowed | CursorIcon::NoDrop => miniquad::CursorIcon::NotAllowed,
+        CursorIcon::ResizeHorizontal
+        | CursorIcon::ResizeEast
+        | CursorIcon::ResizeWest
+        | CursorIcon::ResizeColumn => miniquad::CursorIcon::EWResize,
+        CursorIcon::ResizeVertical
+        | CursorIcon::ResizeNorth
+        | CursorIcon::ResizeSouth
+        | CursorIcon::ResizeRow => miniquad::CursorIcon::NSResize,
+        CursorIcon::ResizeNeSw | CursorIcon::ResizeNorthEast | CursorIcon::ResizeSouthWest => {
+            miniquad::CursorIcon::NESWResize
+        }
+        CursorIcon::ResizeNwSe | CursorIcon::ResizeNorthWest | CursorIcon::ResizeSouthEast => {
+            miniquad::CursorIcon::NWSEResize
+        }
+        _ => miniquad::CursorIcon::Default,
+    }
+}
+
+/// Miniquad events collected as egui events, through an input subscriber.
+#[derive(Default)]
+struct EguiInput {
+    events: Vec<Event>,
+    modifiers: Modifiers,
+    pixels_per_point: f32,
+}
+
+impl EguiInput {
+    fn position(&self, x: f32, y: f32) -> Pos2 {
+        let pixels_per_point = if self.pixels_per_point > 0. {
+            self.pixels_per_point
+        } else {
+            1.
+        };
+        pos2(x / pixels_per_point, y / pixels_per_point)
+    }
+
+    fn pointer_button(&mut self, button: MouseButton, x: f32, y: f32, pressed: bool) {
+        let button = match button {
+            MouseButton::Left => PointerButton::Primary,
+            MouseButton::Right => PointerButton::Secondary,
+            MouseButton::Middle => PointerButton::Middle,
+            MouseButton::Unknown => return,
+        };
+        self.events.push(Event::PointerButton {
+            pos: self.position(x, y),
+            button,
+            pressed,
+            modifiers: self.modifiers,
+        });
+    }
+}
+
+fn to_egui_modifiers(modifiers: KeyMods) -> Modifiers {
+    Modifiers {
+        alt: modifiers.alt,
+        ctrl: modifiers.ctrl,
+        shift: modifiers.shift,
+        mac_cmd: cfg!(target_os = "macos") && modifiers.logo,
+        command: if cfg!(target_os = "macos") {
+            modifiers.logo
+        } else {
+            modifiers.ctrl
+        },
+    }
+}
+
+fn to_egui_key(keycode: KeyCode) -> Option<Key> {
+    macro_rules! keys {
+        ($($from:ident => $to:ident),*) => {
+            match keycode {
+                $(KeyCode::$from => Some(Key::$to),)*
+                _ => None,
+            }
+        };
+    }
+
+    keys!(
+        Down => ArrowDown, Left => ArrowLeft, Right => ArrowRight, Up => ArrowUp,
+        Escape => Escape, Tab => Tab, Backspace => Backspace, Enter => Enter, Space => Space,
+        Insert => Insert, Delete => Delete, Home => Home, End => End,
+        PageUp => PageUp, PageDown => PageDown,
+        Key0 => Num0, Key1 => Num1, Key2 => Num2, Key3 => Num3, Key4 => Num4,
+        Key5 => Num5, Key6 => Num6, Key7 => Num7, Key8 => Num8, Key9 => Num9,
+        A => A, B => B, C => C, D => D, E => E, F => F, G => G, H => H, I => I, J => J,
+        K => K, L => L, M => M, N => N, O => O, P => P, Q => Q, R => R, S => S, T => T,
+        U => U, V => V, W => W, X => X, Y => Y, Z => Z,
+        F1 => F1, F2 => F2, F3 => F3, F4 => F4, F5 => F5, F6 => F6,
+        F7 => F7, F8 => F8, F9 => F9, F10 => F10, F11 => F11, F12 => F12
+    )
+}
+
+impl miniquad::EventHandler for EguiInput {
+    fn update(&mut self, _: &mut miniquad::Context) {}
+
+    fn draw(&mut self, _: &mut miniquad::Context) {}
+
+    fn mouse_motion_event(&mut self, _: &mut miniquad::Context, x: f32, y: f32) {
+        let position = self.position(x, y);
+        self.events.push(Event::PointerMoved(position));
+    }
+
+    fn mouse_wheel_event(&mut self, _: &mut miniquad::Context, x: f32, y: f32) {
+        self.events.push(Event::Scroll(vec2(x, y) * SCROLL_SPEED));
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _: &mut miniquad::Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        self.pointer_button(button, x, y, true);
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        _: &mut miniquad::Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        self.pointer_button(button, x, y, false);
+    }
+
+    fn char_event(
+        &mut self,
+        _: &mut miniquad::Context,
+        character: char,
+        modifiers: KeyMods,
+        _repeat: bool,
+    ) {
+        // shortcuts come as key events
+        if !(character.is_control() || modifiers.ctrl || modifiers.logo) {
+            self.events.push(Event::Text(character.to_string()));
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut miniquad::Context,
+        keycode: KeyCode,
+        modifiers: KeyMods,
+        _repeat: bool,
+    ) {
+        self.modifiers = to_egui_modifiers(modifiers);
+
+        if self.modifiers.command {
+            match keycode {
+                KeyCode::C => self.events.push(Event::Copy),
+                KeyCode::X => self.events.push(Event::Cut),
+                KeyCode::V => {
+                    if let Some(text) = ctx.clipboard_get() {
+                        self.events.push(Event::Paste(text));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(key) = to_egui_key(keycode) {
+            self.events.push(Event::Key {
+                key,
+                pressed: true,
+                modifiers: self.modifiers,
+            });
+        }
+    }
+
+    fn key_up_event(&mut self, _: &mut miniquad::Context, keycode: KeyCode, modifiers: KeyMods) {
+        self.modifiers = to_egui_modifiers(modifiers);
+
+        if let Some(key) = to_egui_key(keycode) {
+            self.events.push(Event::Key {
+                key,
+                pressed: false,
+                modifiers: self.modifiers,
+            });
+        }
+    }
+}
+
+const VERTEX_SHADER: &str = "#version 100
+attribute vec3 position;
+attribute vec4 color0;
+attribute vec2 texcoord;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    uv = texcoord;
+    color = color0 / 255.0;
+}
+";
+
+const FRAGMENT_SHADER: &str = "#version 100
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform sampler2D Texture;
+
+void main() {
+    gl_FragColor = texture2D(Texture, uv) * color;
+}
+";
+
+#[test]
+fn egui_split_mesh() {
+    let vertex = epaint::Vertex {
+        pos: Pos2::ZERO,
+        uv: Pos2::ZERO,
+        color: Color32::WHITE,
+    };
+    let vertices = vec![vertex; 4];
+    let triangles = MAX_INDICES / 3 + 1;
+    let indices = (0..triangles)
+        .flat_map(|n| if n % 2 == 0 { [0, 1, 2] } else { [1, 2, 3] })
+        .collect::<Vec<u32>>();
+
+    let meshes = split_mesh(&vertices, &indices);
+    assert_eq!(meshes.len(), 2);
+    assert_eq!(meshes[0].0.len(), 4);
+    assert_eq!(meshes[0].1.len(), MAX_INDICES / 3 * 3);
+    assert_eq!(meshes[1].1, vec![0, 1, 2]);
+}
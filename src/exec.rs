@@ -44,6 +44,42 @@ impl Future for FileLoadingFuture {
     }
 }
 
+/// Result of a background thread, `None` if the thread panicked.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ThreadFuture<T> {
+    receiver: std::sync::mpsc::Receiver<T>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + 'static> ThreadFuture<T> {
+    /// Run `f` on a new thread.
+    pub fn spawn<F: FnOnce() -> T + Send + 'static>(f: F) -> ThreadFuture<T> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            // the future may be dropped already
+            let _ = sender.send(f());
+        });
+
+        ThreadFuture { receiver }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T> Future for ThreadFuture<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<Self::Output> {
+        use std::sync::mpsc::TryRecvError;
+
+        match self.receiver.try_recv() {
+            Ok(result) => Poll::Ready(Some(result)),
+            Err(TryRecvError::Empty) => Poll::Pending,
+            // the thread is gone without sending anything
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+        }
+    }
+}
+
 fn waker() -> Waker {
     unsafe fn clone(data: *const ()) -> RawWaker {
         RawWaker::new(data, &VTABLE)
@@ -3,8 +3,13 @@
 //! some will move into separate crates and some may just disappear.
 
 pub mod animation;
+pub mod assets;
 pub mod camera;
 pub mod collections;
 pub mod coroutines;
+pub mod effects;
+pub mod gpgpu;
 pub mod scene;
 pub mod state_machine;
+pub mod timer;
+pub mod tween;
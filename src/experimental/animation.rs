@@ -61,6 +61,10 @@ use crate::{
     time::get_frame_time,
 };
 
+mod state_machine;
+
+pub use state_machine::AnimationStateMachine;
+
 /// Specification of animation
 #[derive(Clone, Debug)]
 pub struct Animation {
@@ -0,0 +1,413 @@
+use super::{AnimatedSprite, AnimationFrame};
+use crate::{color::Color, texture::DrawTextureParams, texture::Texture2D, time::get_frame_time};
+
+type Condition<C> = Box<dyn Fn(&C) -> bool>;
+type Callback<C> = Box<dyn FnMut(&mut C)>;
+
+struct State {
+    name: String,
+    animation: usize,
+    looping: bool,
+}
+
+enum Trigger<C> {
+    Condition(Condition<C>),
+    End,
+}
+
+struct Transition<C> {
+    /// `None` for the transitions from any other state
+    from: Option<usize>,
+    to: usize,
+    crossfade: f32,
+    trigger: Trigger<C>,
+}
+
+struct FrameEvent<C> {
+    state: usize,
+    frame: u32,
+    callback: Callback<C>,
+}
+
+/// Frame of the previous state, fading out
+struct Crossfade {
+    animation: usize,
+    frame: u32,
+    duration: f32,
+    remaining: f32,
+}
+
+/// States of an [AnimatedSprite], switching between each other on
+/// conditions, with crossfades and callbacks on frames.
+///
+/// `C` is what the conditions look at and the callbacks change, usually
+/// the character state.
+///
+/// ```ignore
+/// struct Body {
+///     speed: f32,
+///     on_ground: bool,
+///     footsteps: u32,
+/// }
+///
+/// let mut animations = AnimationStateMachine::new(sprite)
+///     .state("idle", "idle")
+///     .state("run", "run")
+///     .state_once("land", "land")
+///     .transition("idle", "run", 0.1, |body: &Body| body.speed > 1.)
+///     .transition("run", "idle", 0.2, |body| body.speed <= 1.)
+///     .transition_from_any("land", 0., |body| body.on_ground && body.speed < -5.)
+///     .transition_on_end("land", "idle", 0.1)
+///     .on_frame("run", 3, |body| body.footsteps += 1);
+///
+/// // the machine can not be a part of `body`, but can live next to it
+/// animations.update(&mut player.body);
+/// animations.draw(texture, x, y, WHITE, DrawTextureParams::default());
+/// ```
+pub struct AnimationStateMachine<C = ()> {
+    sprite: AnimatedSprite,
+    states: Vec<State>,
+    transitions: Vec<Transition<C>>,
+    events: Vec<FrameEvent<C>>,
+
+    current: usize,
+    frame: u32,
+    time: f32,
+    /// A non looping state got to its last frame
+    finished: bool,
+    crossfade: Option<Crossfade>,
+}
+
+impl<C> AnimationStateMachine<C> {
+    /// The first added state is the initial one.
+    pub fn new(sprite: AnimatedSprite) -> AnimationStateMachine<C> {
+        AnimationStateMachine {
+            sprite,
+            states: vec![],
+            transitions: vec![],
+            events: vec![],
+            current: 0,
+            frame: 0,
+            time: 0.,
+            finished: false,
+            crossfade: None,
+        }
+    }
+
+    /// State `name` playing the sprite animation `animation` in a loop.
+    pub fn state(self, name: &str, animation: &str) -> AnimationStateMachine<C> {
+        self.add_state(name, animation, true)
+    }
+
+    /// State playing `animation` once and staying on its last frame, see
+    /// [AnimationStateMachine::transition_on_end].
+    pub fn state_once(self, name: &str, animation: &str) -> AnimationStateMachine<C> {
+        self.add_state(name, animation, false)
+    }
+
+    fn add_state(mut self, name: &str, animation: &str, looping: bool) -> AnimationStateMachine<C> {
+        let animation = self
+            .sprite
+            .animations
+            .iter()
+            .position(|sprite_animation| sprite_animation.name == animation)
+            .unwrap_or_else(|| panic!("No animation named {:?} in the sprite", animation));
+
+        self.states.push(State {
+            name: name.to_string(),
+            animation,
+            looping,
+        });
+        self
+    }
+
+    fn state_id(&self, name: &str) -> usize {
+        self.states
+            .iter()
+            .position(|state| state.name == name)
+            .unwrap_or_else(|| panic!("No animation state named {:?}", name))
+    }
+
+    /// Go from `from` to `to` once `condition` holds, fading between them in
+    /// `crossfade` seconds.
+    ///
+    /// Transitions are checked in the order they were added, the first one
+    /// that fits wins.
+    pub fn transition(
+        mut self,
+        from: &str,
+        to: &str,
+        crossfade: f32,
+        condition: impl Fn(&C) -> bool + 'static,
+    ) -> AnimationStateMachine<C> {
+        self.transitions.push(Transition {
+            from: Some(self.state_id(from)),
+            to: self.state_id(to),
+            crossfade,
+            trigger: Trigger::Condition(Box::new(condition)),
+        });
+        self
+    }
+
+    /// [AnimationStateMachine::transition] from every other state.
+    pub fn transition_from_any(
+        mut self,
+        to: &str,
+        crossfade: f32,
+        condition: impl Fn(&C) -> bool + 'static,
+    ) -> AnimationStateMachine<C> {
+        self.transitions.push(Transition {
+            from: None,
+            to: self.state_id(to),
+            crossfade,
+            trigger: Trigger::Condition(Box::new(condition)),
+        });
+        self
+    }
+
+    /// Go from `from` to `to` after the last frame of `from`, for the states
+    /// made with [AnimationStateMachine::state_once].
+    pub fn transition_on_end(
+        mut self,
+        from: &str,
+        to: &str,
+        crossfade: f32,
+    ) -> AnimationStateMachine<C> {
+        self.transitions.push(Transition {
+            from: Some(self.state_id(from)),
+            to: self.state_id(to),
+            crossfade,
+            trigger: Trigger::End,
+        });
+        self
+    }
+
+    /// Call `callback` every time the state `state` gets to the frame
+    /// `frame`, counting from 0.
+    pub fn on_frame(
+        mut self,
+        state: &str,
+        frame: u32,
+        callback: impl FnMut(&mut C) + 'static,
+    ) -> AnimationStateMachine<C> {
+        self.events.push(FrameEvent {
+            state: self.state_id(state),
+            frame,
+            callback: Box::new(callback),
+        });
+        self
+    }
+
+    pub fn current_state(&self) -> &str {
+        &self.states[self.current].name
+    }
+
+    /// Switch to `state` right away, from the first frame. Does nothing if
+    /// it is the current state already.
+    pub fn play(&mut self, state: &str, crossfade: f32, context: &mut C) {
+        let state = self.state_id(state);
+        if state != self.current {
+            self.enter(state, crossfade, context);
+        }
+    }
+
+    /// Advance the animation by the frame time and follow the transitions.
+    pub fn update(&mut self, context: &mut C) {
+        self.advance(get_frame_time(), context);
+    }
+
+    fn advance(&mut self, delta: f32, context: &mut C) {
+        if let Some(crossfade) = &mut self.crossfade {
+            crossfade.remaining -= delta;
+            if crossfade.remaining <= 0. {
+                self.crossfade = None;
+            }
+        }
+
+        let state = &self.states[self.current];
+        let animation = &self.sprite.animations[state.animation];
+        let frame_time = 1. / animation.fps as f32;
+
+        self.time += delta;
+        while self.time >= frame_time && !self.finished {
+            self.time -= frame_time;
+
+            if self.frame + 1 < animation.frames {
+                self.frame += 1;
+            } else if state.looping {
+                self.frame = 0;
+            } else {
+                self.finished = true;
+                break;
+            }
+            fire_events(&mut self.events, self.current, self.frame, context);
+        }
+
+        let current = self.current;
+        let finished = self.finished;
+        let transition = self.transitions.iter().find(|transition| {
+            let from = match transition.from {
+                Some(from) => from == current,
+                None => transition.to != current,
+            };
+            from && match &transition.trigger {
+                Trigger::Condition(condition) => condition(context),
+                Trigger::End => finished,
+            }
+        });
+        if let Some(transition) = transition {
+            let (to, crossfade) = (transition.to, transition.crossfade);
+            self.enter(to, crossfade, context);
+        }
+    }
+
+    fn enter(&mut self, state: usize, crossfade: f32, context: &mut C) {
+        self.crossfade = if crossfade > 0. {
+            Some(Crossfade {
+                animation: self.states[self.current].animation,
+                frame: self.frame,
+                duration: crossfade,
+                remaining: crossfade,
+            })
+        } else {
+            None
+        };
+
+        self.current = state;
+        self.frame = 0;
+        self.time = 0.;
+        self.finished = false;
+        fire_events(&mut self.events, state, 0, context);
+    }
+
+    /// Current frame of the current state.
+    pub fn frame(&self) -> AnimationFrame {
+        self.sprite_frame(self.states[self.current].animation, self.frame)
+    }
+
+    /// Frame of the previous state during a crossfade, with how much it
+    /// is still visible, 1 at the beginning of the crossfade and 0 at the end.
+    pub fn fading_frame(&self) -> Option<(AnimationFrame, f32)> {
+        self.crossfade.as_ref().map(|crossfade| {
+            (
+                self.sprite_frame(crossfade.animation, crossfade.frame),
+                crossfade.remaining / crossfade.duration,
+            )
+        })
+    }
+
+    fn sprite_frame(&self, animation: usize, frame: u32) -> AnimationFrame {
+        let mut sprite = self.sprite.clone();
+        sprite.set_animation(animation);
+        sprite.set_frame(frame);
+        sprite.frame()
+    }
+
+    /// Draw the current frame, over the fading out previous one during the
+    /// crossfades. `params.source` is replaced by the frame and
+    /// `params.dest_size` defaults to the frame size.
+    pub fn draw(
+        &self,
+        texture: Texture2D,
+        x: f32,
+        y: f32,
+        color: Color,
+        params: DrawTextureParams,
+    ) {
+        let draw_frame = |frame: AnimationFrame, color: Color| {
+            crate::texture::draw_texture_ex(
+                texture,
+                x,
+                y,
+                color,
+                DrawTextureParams {
+                    source: Some(frame.source_rect),
+                    dest_size: params.dest_size.or(Some(frame.dest_size)),
+                    ..params.clone()
+                },
+            )
+        };
+
+        if let Some((frame, visible)) = self.fading_frame() {
+            draw_frame(
+                frame,
+                Color {
+                    a: color.a * visible,
+                    ..color
+                },
+            );
+        }
+        draw_frame(self.frame(), color);
+    }
+}
+
+fn fire_events<C>(events: &mut [FrameEvent<C>], state: usize, frame: u32, context: &mut C) {
+    for event in events {
+        if event.state == state && event.frame == frame {
+            (event.callback)(context);
+        }
+    }
+}
+
+#[test]
+fn animation_transitions() {
+    use super::Animation;
+
+    struct Body {
+        speed: f32,
+        steps: u32,
+    }
+
+    let animation = |name: &str, row, frames| Animation {
+        name: name.to_string(),
+        row,
+        frames,
+        fps: 10,
+    };
+    let sprite = AnimatedSprite::new(
+        16,
+        16,
+        &[
+            animation("idle", 0, 2),
+            animation("run", 1, 4),
+            animation("stop", 2, 2),
+        ],
+        true,
+    );
+    let mut machine = AnimationStateMachine::new(sprite)
+        .state("idle", "idle")
+        .state("run", "run")
+        .state_once("stop", "stop")
+        .transition("idle", "run", 0.2, |body: &Body| body.speed > 0.)
+        .transition("run", "stop", 0., |body| body.speed == 0.)
+        .transition_on_end("stop", "idle", 0.)
+        .on_frame("run", 2, |body| body.steps += 1);
+    let mut body = Body {
+        speed: 0.,
+        steps: 0,
+    };
+
+    machine.advance(0.15, &mut body);
+    assert_eq!(machine.current_state(), "idle");
+
+    body.speed = 1.;
+    machine.advance(0.01, &mut body);
+    assert_eq!(machine.current_state(), "run");
+    assert_eq!(machine.frame().source_rect.y, 16.);
+    let (fading, visible) = machine.fading_frame().unwrap();
+    assert_eq!(fading.source_rect.x, 16.);
+    assert_eq!(visible, 1.);
+
+    // 0.85s at 10 fps: frames 1..3, 0..3, 0 with two passes through frame 2
+    machine.advance(0.85, &mut body);
+    assert!(machine.fading_frame().is_none());
+    assert_eq!(body.steps, 2);
+
+    body.speed = 0.;
+    machine.advance(0., &mut body);
+    assert_eq!(machine.current_state(), "stop");
+    machine.advance(0.1, &mut body);
+    assert_eq!(machine.current_state(), "stop");
+    machine.advance(0.1, &mut body);
+    assert_eq!(machine.current_state(), "idle");
+}
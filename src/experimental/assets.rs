@@ -0,0 +1,491 @@
+//! Central asset manager: typed, reference counted handles, each file loaded
+//! only once and decoded off the main thread.
+//!
+//! ```ignore
+//! let player: AssetHandle<Texture2D> = load_asset("player.png");
+//! let music: AssetHandle<Sound> = load_asset("music.ogg");
+//!
+//! while loading_progress() < 1. {
+//!     draw_text(&format!("Loading {:.0}%", loading_progress() * 100.), 20., 20., 30., WHITE);
+//!     next_frame().await;
+//! }
+//!
+//! // the same texture, not loaded again
+//! let enemy = load_asset::<Texture2D>("player.png");
+//! draw_texture(enemy.get().unwrap(), 0., 0., WHITE);
+//! ```
+//!
+//! An asset is freed when its last handle is dropped.
+
+use crate::{
+    audio::{self, Sound},
+    color::{colors::BLACK, colors::WHITE, Color},
+    get_context,
+    logging::error,
+    shapes::{draw_rectangle, draw_rectangle_lines},
+    text::{self, Font},
+    texture::{Image, Texture2D},
+    window::{clear_background, next_frame, screen_height, screen_width},
+};
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+
+pub type AssetFuture<T> = Pin<Box<dyn Future<Output = Result<T, String>>>>;
+
+/// Something [load_asset] can make out of a file.
+///
+/// Implement it to manage custom assets, like levels, the same way:
+/// ```ignore
+/// impl Asset for Level {
+///     type Decoded = Level;
+///
+///     fn decode(bytes: Vec<u8>) -> Result<Level, String> {
+///         Level::parse(&bytes).map_err(|err| err.to_string())
+///     }
+///
+///     fn create(level: Level) -> AssetFuture<Level> {
+///         Box::pin(async move { Ok(level) })
+///     }
+/// }
+/// ```
+pub trait Asset: Sized + 'static {
+    /// What the file decodes to before the asset is made out of it.
+    type Decoded: Send + 'static;
+
+    /// Decode the file. On a background thread natively, keep there all the
+    /// heavy work not needing the GPU or the audio context.
+    fn decode(bytes: Vec<u8>) -> Result<Self::Decoded, String>;
+
+    /// Make the asset from the decoded data, on the main thread.
+    fn create(decoded: Self::Decoded) -> AssetFuture<Self>;
+
+    /// Free the asset, once its last handle is dropped.
+    fn delete(&self) {}
+}
+
+pub(crate) fn decode_image(bytes: &[u8]) -> Result<Image, String> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|err| err.to_string())?
+        .to_rgba8();
+
+    Ok(Image {
+        width: image.width() as u16,
+        height: image.height() as u16,
+        bytes: image.into_raw(),
+    })
+}
+
+impl Asset for Texture2D {
+    type Decoded = Image;
+
+    fn decode(bytes: Vec<u8>) -> Result<Image, String> {
+        decode_image(&bytes)
+    }
+
+    fn create(image: Image) -> AssetFuture<Texture2D> {
+        Box::pin(async move { Ok(Texture2D::from_image(&image)) })
+    }
+
+    fn delete(&self) {
+        Texture2D::delete(self);
+    }
+}
+
+impl Asset for Image {
+    type Decoded = Image;
+
+    fn decode(bytes: Vec<u8>) -> Result<Image, String> {
+        decode_image(&bytes)
+    }
+
+    fn create(image: Image) -> AssetFuture<Image> {
+        Box::pin(async move { Ok(image) })
+    }
+}
+
+impl Asset for Sound {
+    type Decoded = Vec<u8>;
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        Ok(bytes)
+    }
+
+    fn create(bytes: Vec<u8>) -> AssetFuture<Sound> {
+        Box::pin(async move {
+            audio::load_sound_from_bytes(&bytes)
+                .await
+                .map_err(|err| err.to_string())
+        })
+    }
+
+    fn delete(&self) {
+        audio::delete_sound(*self);
+    }
+}
+
+impl Asset for Font {
+    type Decoded = Vec<u8>;
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        Ok(bytes)
+    }
+
+    fn create(bytes: Vec<u8>) -> AssetFuture<Font> {
+        Box::pin(
+            async move { text::load_ttf_font_from_bytes(&bytes).map_err(|err| err.to_string()) },
+        )
+    }
+}
+
+impl Asset for String {
+    type Decoded = String;
+
+    fn decode(bytes: Vec<u8>) -> Result<String, String> {
+        String::from_utf8(bytes).map_err(|err| err.to_string())
+    }
+
+    fn create(string: String) -> AssetFuture<String> {
+        Box::pin(async move { Ok(string) })
+    }
+}
+
+impl Asset for Vec<u8> {
+    type Decoded = Vec<u8>;
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        Ok(bytes)
+    }
+
+    fn create(bytes: Vec<u8>) -> AssetFuture<Vec<u8>> {
+        Box::pin(async move { Ok(bytes) })
+    }
+}
+
+enum State<T> {
+    Loading,
+    Loaded(T),
+    Failed(String),
+}
+
+struct Slot<T: Asset> {
+    path: String,
+    state: RefCell<State<T>>,
+}
+
+impl<T: Asset> Drop for Slot<T> {
+    fn drop(&mut self) {
+        if let State::Loaded(asset) = self.state.get_mut() {
+            asset.delete();
+        }
+    }
+}
+
+/// Shared handle to an asset from [load_asset], cloning it does not load
+/// the asset again.
+pub struct AssetHandle<T: Asset> {
+    slot: Rc<Slot<T>>,
+}
+
+impl<T: Asset> Clone for AssetHandle<T> {
+    fn clone(&self) -> AssetHandle<T> {
+        AssetHandle {
+            slot: self.slot.clone(),
+        }
+    }
+}
+
+impl<T: Asset> AssetHandle<T> {
+    pub fn path(&self) -> &str {
+        &self.slot.path
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        matches!(*self.slot.state.borrow(), State::Loaded(_))
+    }
+
+    /// The asset, `None` while loading or if loading failed.
+    pub fn get(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        match &*self.slot.state.borrow() {
+            State::Loaded(asset) => Some(asset.clone()),
+            _ => None,
+        }
+    }
+
+    /// Call `f` with the asset once it is loaded, for assets that are not `Clone`.
+    pub fn with<R, F: FnOnce(&T) -> R>(&self, f: F) -> Option<R> {
+        match &*self.slot.state.borrow() {
+            State::Loaded(asset) => Some(f(asset)),
+            _ => None,
+        }
+    }
+
+    /// Why loading failed.
+    pub fn error(&self) -> Option<String> {
+        match &*self.slot.state.borrow() {
+            State::Failed(err) => Some(err.clone()),
+            _ => None,
+        }
+    }
+
+    /// Number of handles to the asset.
+    pub fn ref_count(&self) -> usize {
+        Rc::strong_count(&self.slot)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct AssetManager {
+    slots: HashMap<(TypeId, String), Weak<dyn Any>>,
+    /// Loads started and finished since everything was last loaded
+    requested: usize,
+    finished: usize,
+}
+
+/// Counts the load as finished when the loading coroutine ends, or is
+/// stopped before loading it.
+struct LoadFinished<T: Asset> {
+    slot: Weak<Slot<T>>,
+}
+
+impl<T: Asset> Drop for LoadFinished<T> {
+    fn drop(&mut self) {
+        get_context().assets.finished += 1;
+
+        if let Some(slot) = self.slot.upgrade() {
+            let mut state = slot.state.borrow_mut();
+            if let State::Loading = *state {
+                *state = State::Failed("The loading was stopped".to_string());
+            }
+        }
+    }
+}
+
+/// Read the file and decode it, on a thread when the platform has them.
+async fn decode_file<T: Asset>(path: &str) -> Result<T::Decoded, String> {
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    {
+        let mounted = get_context().vfs.read(path);
+        let path = crate::file::assets_path(path);
+        let panicked = format!("Decoding {} panicked", path);
+
+        // a panic in T::decode, user code for the custom assets, fails the
+        // asset instead of leaving it loading forever
+        crate::exec::ThreadFuture::spawn(move || {
+            let bytes = match mounted {
+                Some(bytes) => Ok(bytes),
+                None => std::fs::read(&path)
+                    .map_err(|err| format!("Couldn't load file {}: {}", path, err)),
+            };
+            let bytes = bytes.and_then(|bytes| {
+                crate::file::decompress(bytes).map_err(|err| format!("{}: {}", path, err))
+            });
+            bytes.and_then(T::decode)
+        })
+        .await
+        .unwrap_or(Err(panicked))
+    }
+
+    #[cfg(any(target_arch = "wasm32", target_os = "android"))]
+    {
+        let bytes = crate::file::load_file(path)
+            .await
+            .map_err(|err| err.to_string())?;
+        T::decode(bytes)
+    }
+}
+
+/// Start loading the asset at `path`, or get another handle to it if it is
+/// already loaded or loading.
+///
+/// Returns right away, [AssetHandle::get] gives the asset once it is ready.
+pub fn load_asset<T: Asset>(path: &str) -> AssetHandle<T> {
+    let manager = &mut get_context().assets;
+    let key = (TypeId::of::<T>(), path.to_string());
+    if let Some(slot) = manager.slots.get(&key).and_then(|slot| slot.upgrade()) {
+        return AssetHandle {
+            slot: slot.downcast().unwrap(),
+        };
+    }
+
+    let slot = Rc::new(Slot {
+        path: path.to_string(),
+        state: RefCell::new(State::Loading),
+    });
+    let weak = Rc::downgrade(&slot);
+    manager.slots.retain(|_, slot| slot.strong_count() != 0);
+    manager
+        .slots
+        .insert(key, Rc::downgrade(&(slot.clone() as Rc<dyn Any>)));
+    if manager.requested == manager.finished {
+        manager.requested = 0;
+        manager.finished = 0;
+    }
+    manager.requested += 1;
+
+    let path = path.to_string();
+    // the loads are not stopped with the coroutine or the scope that asked
+    // for them, only by stop_all_coroutines
+    crate::experimental::coroutines::start_unscoped_coroutine(async move {
+        let _finished = LoadFinished { slot: weak.clone() };
+        let result = match decode_file::<T>(&path).await {
+            Ok(decoded) => T::create(decoded).await,
+            Err(err) => Err(err),
+        };
+
+        match (weak.upgrade(), result) {
+            (Some(slot), Ok(asset)) => *slot.state.borrow_mut() = State::Loaded(asset),
+            (Some(slot), Err(err)) => {
+                error!("Failed to load {}: {}", path, err);
+                *slot.state.borrow_mut() = State::Failed(err);
+            }
+            // all the handles were dropped while loading
+            (None, Ok(asset)) => asset.delete(),
+            (None, Err(_)) => {}
+        }
+    });
+
+    AssetHandle { slot }
+}
+
+/// Part of the assets started with [load_asset] done loading, from 0 to 1.
+///
+/// Counts from the moment everything was last loaded, 1 when nothing is loading.
+pub fn loading_progress() -> f32 {
+    let manager = &get_context().assets;
+    if manager.requested == manager.finished {
+        1.
+    } else {
+        manager.finished as f32 / manager.requested as f32
+    }
+}
+
+/// [load_asset] for each path.
+pub fn load_many<T: Asset>(paths: &[&str]) -> Vec<AssetHandle<T>> {
+    paths.iter().map(|path| load_asset(path)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed(String),
+}
+
+trait Progress {
+    fn path(&self) -> &str;
+    fn state(&self) -> LoadState;
+}
+
+impl<T: Asset> Progress for AssetHandle<T> {
+    fn path(&self) -> &str {
+        AssetHandle::path(self)
+    }
+
+    fn state(&self) -> LoadState {
+        match &*self.slot.state.borrow() {
+            State::Loading => LoadState::Loading,
+            State::Loaded(_) => LoadState::Loaded,
+            State::Failed(err) => LoadState::Failed(err.clone()),
+        }
+    }
+}
+
+/// Assets of any type to wait for together, for loading screens.
+/// ```ignore
+/// let levels = load_many::<String>(&["levels/1.ron", "levels/2.ron"]);
+/// let player = load_asset::<Texture2D>("player.png");
+///
+/// let mut batch = LoadBatch::new();
+/// batch.extend(&levels);
+/// batch.add(&player);
+/// // a progress bar in the middle of the screen
+/// batch.wait().await;
+/// ```
+/// For a custom loading screen, draw it until [LoadBatch::is_done] instead,
+/// with [LoadBatch::progress] and [draw_progress_bar].
+#[derive(Default)]
+pub struct LoadBatch {
+    items: Vec<Box<dyn Progress>>,
+}
+
+impl LoadBatch {
+    pub fn new() -> LoadBatch {
+        LoadBatch::default()
+    }
+
+    pub fn add<T: Asset>(&mut self, handle: &AssetHandle<T>) {
+        self.items.push(Box::new(handle.clone()));
+    }
+
+    pub fn extend<T: Asset>(&mut self, handles: &[AssetHandle<T>]) {
+        for handle in handles {
+            self.add(handle);
+        }
+    }
+
+    /// Path and state of each asset, in the order they were added.
+    pub fn items(&self) -> impl Iterator<Item = (&str, LoadState)> {
+        self.items.iter().map(|item| (item.path(), item.state()))
+    }
+
+    /// Part of the assets done loading, or failed to, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.items.is_empty() {
+            return 1.;
+        }
+        let done = self
+            .items
+            .iter()
+            .filter(|item| item.state() != LoadState::Loading)
+            .count();
+        done as f32 / self.items.len() as f32
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.items
+            .iter()
+            .all(|item| item.state() != LoadState::Loading)
+    }
+
+    /// Paths and errors of the assets that failed to load.
+    pub fn failed(&self) -> Vec<(String, String)> {
+        self.items()
+            .filter_map(|(path, state)| match state {
+                LoadState::Failed(err) => Some((path.to_string(), err)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Draw a progress bar on a black screen each frame until everything is loaded.
+    pub async fn wait(&self) {
+        while !self.is_done() {
+            clear_background(BLACK);
+            let (w, h) = (screen_width() / 2., 16.);
+            draw_progress_bar(
+                (screen_width() - w) / 2.,
+                (screen_height() - h) / 2.,
+                w,
+                h,
+                self.progress(),
+                WHITE,
+            );
+            next_frame().await;
+        }
+    }
+}
+
+/// Outlined bar filled up to `progress`, from 0 to 1.
+pub fn draw_progress_bar(x: f32, y: f32, w: f32, h: f32, progress: f32, color: Color) {
+    let progress = progress.clamp(0., 1.);
+    draw_rectangle_lines(x, y, w, h, 2., color);
+    draw_rectangle(x + 4., y + 4., (w - 8.) * progress, h - 8., color);
+}
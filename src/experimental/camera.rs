@@ -1 +1,6 @@
+pub mod first_person;
+pub mod follow;
 pub mod mouse;
+pub mod orbit;
+pub mod transition;
+pub mod virtual_screen;
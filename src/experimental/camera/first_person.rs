@@ -0,0 +1,160 @@
+use crate::prelude::*;
+
+/// Mouse-look and WASD controlled 3D camera.
+///
+/// Mouse look works while the cursor is grabbed, Tab toggles the grab by default.
+/// Movement is time based, the look is by the mouse movement in pixels,
+/// so both feel the same at any frame rate.
+///
+/// ```ignore
+/// let mut camera = FirstPersonCamera::new(vec3(0., 1., 0.));
+/// camera.set_grabbed(true);
+/// loop {
+///     set_camera(&camera.update());
+///     ..
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FirstPersonCamera {
+    pub position: Vec3,
+    /// Rotation around the world up axis, in radians. 0 - looking along +X.
+    pub yaw: f32,
+    /// Rotation up and down, in radians, clamped to [FirstPersonCamera::max_pitch].
+    pub pitch: f32,
+    pub max_pitch: f32,
+    pub world_up: Vec3,
+    /// World units per second
+    pub move_speed: f32,
+    /// Move speed multiplier while Shift is down
+    pub sprint_multiplier: f32,
+    /// Radians per mouse pixel
+    pub look_sensitivity: f32,
+    pub fovy: f32,
+    /// Key toggling the cursor grab, None to manage the grab manually.
+    pub grab_key: Option<KeyCode>,
+
+    grabbed: bool,
+    last_mouse_position: Option<Vec2>,
+}
+
+impl FirstPersonCamera {
+    pub fn new(position: Vec3) -> FirstPersonCamera {
+        FirstPersonCamera {
+            position,
+            yaw: 0.,
+            pitch: 0.,
+            max_pitch: 1.5,
+            world_up: vec3(0., 1., 0.),
+            move_speed: 5.,
+            sprint_multiplier: 2.,
+            look_sensitivity: 0.003,
+            fovy: 45.,
+            grab_key: Some(KeyCode::Tab),
+            grabbed: false,
+            last_mouse_position: None,
+        }
+    }
+
+    /// Grab and hide the cursor, or release it.
+    pub fn set_grabbed(&mut self, grabbed: bool) {
+        self.grabbed = grabbed;
+        set_cursor_grab(grabbed);
+        show_mouse(!grabbed);
+    }
+
+    pub fn is_grabbed(&self) -> bool {
+        self.grabbed
+    }
+
+    /// Unit vector the camera looks along.
+    pub fn front(&self) -> Vec3 {
+        vec3(
+            self.yaw.cos() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.sin() * self.pitch.cos(),
+        )
+        .normalize()
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.front().cross(self.world_up).normalize()
+    }
+
+    /// Read the input, move and rotate the camera by the time from the last frame.
+    pub fn update(&mut self) -> Camera3D {
+        if let Some(key) = self.grab_key {
+            if is_key_pressed(key) {
+                self.set_grabbed(!self.grabbed);
+            }
+        }
+
+        let mouse: Vec2 = mouse_position().into();
+        let mouse_delta = mouse - self.last_mouse_position.unwrap_or(mouse);
+        self.last_mouse_position = Some(mouse);
+        if self.grabbed {
+            self.look(mouse_delta);
+        }
+
+        let mut direction = vec3(0., 0., 0.);
+        let front = self.front();
+        // walking doesn't go up or down with the look
+        let forward = (front - self.world_up * front.dot(self.world_up)).normalize_or_zero();
+        let right = self.right();
+        if is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) {
+            direction += forward;
+        }
+        if is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) {
+            direction -= forward;
+        }
+        if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
+            direction += right;
+        }
+        if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
+            direction -= right;
+        }
+        if is_key_down(KeyCode::Space) {
+            direction += self.world_up;
+        }
+        if is_key_down(KeyCode::LeftControl) {
+            direction -= self.world_up;
+        }
+
+        let mut speed = self.move_speed;
+        if is_key_down(KeyCode::LeftShift) {
+            speed *= self.sprint_multiplier;
+        }
+        // diagonal movement is not faster
+        self.position += direction.normalize_or_zero() * speed * get_frame_time();
+
+        self.camera()
+    }
+
+    /// Rotate by the mouse movement, in pixels.
+    pub fn look(&mut self, mouse_delta: Vec2) {
+        self.yaw += mouse_delta.x * self.look_sensitivity;
+        self.pitch = (self.pitch - mouse_delta.y * self.look_sensitivity)
+            .clamp(-self.max_pitch, self.max_pitch);
+    }
+
+    pub fn camera(&self) -> Camera3D {
+        let front = self.front();
+        Camera3D {
+            position: self.position,
+            target: self.position + front,
+            up: self.right().cross(front).normalize(),
+            fovy: self.fovy,
+            ..Default::default()
+        }
+    }
+}
+
+#[test]
+fn first_person_look_clamped() {
+    let mut camera = FirstPersonCamera::new(vec3(0., 0., 0.));
+    camera.look(vec2(0., -10000.));
+    assert_eq!(camera.pitch, camera.max_pitch);
+
+    let camera = camera.camera();
+    assert!((camera.target - camera.position).y > 0.99);
+    assert!(camera.up.y > 0.);
+}
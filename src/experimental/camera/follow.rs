@@ -0,0 +1,113 @@
+use crate::prelude::*;
+
+/// 2D camera smoothly following a target.
+///
+/// Call [FollowCamera::update] once a frame with the target position and use the
+/// returned [Camera2D]. All the smoothing is time based, so the camera moves
+/// the same way at any frame rate.
+///
+/// ```ignore
+/// let mut follow = FollowCamera::new(player.pos, vec2(320., 180.));
+/// follow.dead_zone = vec2(16., 8.);
+/// loop {
+///     set_camera(&follow.update(player.pos));
+///     ..
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FollowCamera {
+    /// Center of the camera, in world units
+    pub position: Vec2,
+    /// Size of the visible area, in world units
+    pub view_size: Vec2,
+    /// Time, in seconds, for the camera to cover ~63% of the distance to the target.
+    /// 0 - no smoothing, the camera sticks to the target.
+    pub smoothness: f32,
+    /// Half size of the box around the camera center the target can move in
+    /// without moving the camera.
+    pub dead_zone: Vec2,
+    /// Seconds of the target velocity the camera looks ahead by.
+    pub look_ahead: f32,
+    /// Max camera speed, world units per second
+    pub max_speed: f32,
+
+    last_target: Option<Vec2>,
+    lead: Vec2,
+}
+
+impl FollowCamera {
+    pub fn new(position: Vec2, view_size: Vec2) -> FollowCamera {
+        FollowCamera {
+            position,
+            view_size,
+            smoothness: 0.2,
+            dead_zone: vec2(0., 0.),
+            look_ahead: 0.,
+            max_speed: f32::INFINITY,
+            last_target: None,
+            lead: vec2(0., 0.),
+        }
+    }
+
+    /// Move the camera towards the `target`, by the time from the last frame.
+    pub fn update(&mut self, target: Vec2) -> Camera2D {
+        self.step(target, get_frame_time());
+        self.camera()
+    }
+
+    /// Camera with the current position, y axis pointing down, like on the screen.
+    pub fn camera(&self) -> Camera2D {
+        let size = self.view_size;
+        Camera2D::from_display_rect(Rect::new(
+            self.position.x - size.x / 2.,
+            self.position.y - size.y / 2.,
+            size.x,
+            size.y,
+        ))
+    }
+
+    fn step(&mut self, target: Vec2, dt: f32) {
+        let t = if self.smoothness <= 0. {
+            1.
+        } else {
+            1. - (-dt / self.smoothness).exp()
+        };
+
+        if dt > 0. {
+            let velocity = (target - self.last_target.unwrap_or(target)) / dt;
+            self.lead += (velocity * self.look_ahead - self.lead) * t;
+        }
+        self.last_target = Some(target);
+
+        // only the part of the distance out of the dead zone moves the camera
+        let focus = target + self.lead;
+        let diff = focus - self.position;
+        let excess = vec2(
+            diff.x - diff.x.clamp(-self.dead_zone.x, self.dead_zone.x),
+            diff.y - diff.y.clamp(-self.dead_zone.y, self.dead_zone.y),
+        );
+
+        let mut step = excess * t;
+        let max_step = self.max_speed * dt;
+        if step.length() > max_step {
+            step = step.normalize() * max_step;
+        }
+        self.position += step;
+    }
+}
+
+#[test]
+fn follow_frame_rate_independent() {
+    let mut fast = FollowCamera::new(vec2(0., 0.), vec2(100., 100.));
+    let mut slow = fast.clone();
+
+    for _ in 0..60 {
+        fast.step(vec2(100., 50.), 1. / 60.);
+    }
+    for _ in 0..15 {
+        slow.step(vec2(100., 50.), 1. / 15.);
+    }
+
+    assert!((fast.position - slow.position).length() < 0.001);
+    assert!(fast.position.x > 99. && fast.position.x < 100.);
+}
@@ -0,0 +1,132 @@
+use crate::prelude::*;
+
+/// 3D camera rotating around a target point.
+///
+/// Left mouse drag orbits, the wheel zooms, right or middle drag pans the target.
+///
+/// ```ignore
+/// let mut camera = OrbitCamera::new(vec3(0., 0., 0.), 10.);
+/// loop {
+///     set_camera(&camera.update());
+///     ..
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrbitCamera {
+    pub target: Vec3,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Rotation around the world up axis, in radians
+    pub yaw: f32,
+    /// Angle above the target, in radians, clamped to [OrbitCamera::max_pitch].
+    pub pitch: f32,
+    pub max_pitch: f32,
+    pub world_up: Vec3,
+    /// Radians per mouse pixel
+    pub orbit_sensitivity: f32,
+    /// Fraction of the distance zoomed per wheel step
+    pub zoom_sensitivity: f32,
+    /// Target movement per mouse pixel, as a fraction of the distance
+    pub pan_sensitivity: f32,
+    pub fovy: f32,
+
+    last_mouse_position: Option<Vec2>,
+}
+
+impl OrbitCamera {
+    pub fn new(target: Vec3, distance: f32) -> OrbitCamera {
+        OrbitCamera {
+            target,
+            distance,
+            min_distance: 0.1,
+            max_distance: 1000.,
+            yaw: 0.,
+            pitch: 0.5,
+            max_pitch: 1.5,
+            world_up: vec3(0., 1., 0.),
+            orbit_sensitivity: 0.01,
+            zoom_sensitivity: 0.1,
+            pan_sensitivity: 0.002,
+            fovy: 45.,
+            last_mouse_position: None,
+        }
+    }
+
+    /// Camera position, derived from the target, distance and angles.
+    pub fn position(&self) -> Vec3 {
+        let offset = vec3(
+            self.yaw.cos() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.sin() * self.pitch.cos(),
+        );
+        self.target + offset * self.distance
+    }
+
+    /// Read the mouse and return the updated camera.
+    pub fn update(&mut self) -> Camera3D {
+        let mouse: Vec2 = mouse_position().into();
+        let mouse_delta = mouse - self.last_mouse_position.unwrap_or(mouse);
+        self.last_mouse_position = Some(mouse);
+
+        if is_mouse_button_down(MouseButton::Left) {
+            self.orbit(mouse_delta);
+        }
+        if is_mouse_button_down(MouseButton::Right) || is_mouse_button_down(MouseButton::Middle) {
+            self.pan(mouse_delta);
+        }
+
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0. {
+            self.zoom(wheel.signum());
+        }
+
+        self.camera()
+    }
+
+    /// Rotate around the target by the mouse movement, in pixels.
+    pub fn orbit(&mut self, mouse_delta: Vec2) {
+        self.yaw += mouse_delta.x * self.orbit_sensitivity;
+        self.pitch = (self.pitch + mouse_delta.y * self.orbit_sensitivity)
+            .clamp(-self.max_pitch, self.max_pitch);
+    }
+
+    /// Move the target in the view plane by the mouse movement, in pixels.
+    pub fn pan(&mut self, mouse_delta: Vec2) {
+        let front = (self.target - self.position()).normalize();
+        let right = front.cross(self.world_up).normalize();
+        let up = right.cross(front);
+        let scale = self.distance * self.pan_sensitivity;
+
+        self.target += (-right * mouse_delta.x + up * mouse_delta.y) * scale;
+    }
+
+    /// Zoom in by `steps` wheel steps, negative to zoom out.
+    pub fn zoom(&mut self, steps: f32) {
+        self.distance = (self.distance * (1. - self.zoom_sensitivity).powf(steps))
+            .clamp(self.min_distance, self.max_distance);
+    }
+
+    pub fn camera(&self) -> Camera3D {
+        Camera3D {
+            position: self.position(),
+            target: self.target,
+            up: self.world_up,
+            fovy: self.fovy,
+            ..Default::default()
+        }
+    }
+}
+
+#[test]
+fn orbit_keeps_distance() {
+    let mut camera = OrbitCamera::new(vec3(1., 2., 3.), 10.);
+    camera.orbit(vec2(123., 4567.));
+    camera.pan(vec2(30., -20.));
+    assert_eq!(camera.pitch, camera.max_pitch);
+    assert!(((camera.position() - camera.target).length() - 10.).abs() < 0.001);
+
+    camera.zoom(1.);
+    camera.zoom(-1.);
+    assert!((camera.distance - 10.).abs() < 0.001);
+}
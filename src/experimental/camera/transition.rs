@@ -0,0 +1,147 @@
+use crate::prelude::*;
+
+/// Cameras that can be interpolated.
+pub trait CameraLerp: Sized {
+    /// Camera in between `self` (t = 0) and `other` (t = 1).
+    /// Things that can't be interpolated, like render targets or projection kinds,
+    /// are taken from `self` until t reaches 1.
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+pub fn lerp_cameras<C: CameraLerp>(a: &C, b: &C, t: f32) -> C {
+    a.lerp(b, t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Zoom changes geometrically, so the zooming speed looks constant
+fn lerp_zoom(a: f32, b: f32, t: f32) -> f32 {
+    if a == 0. || b == 0. || a.signum() != b.signum() {
+        return lerp(a, b, t);
+    }
+    a.signum() * a.abs().powf(1. - t) * b.abs().powf(t)
+}
+
+/// Shortest way around, in degrees
+fn lerp_angle(a: f32, b: f32, t: f32) -> f32 {
+    let delta = (b - a + 180.).rem_euclid(360.) - 180.;
+    a + delta * t
+}
+
+impl CameraLerp for Camera2D {
+    fn lerp(&self, other: &Camera2D, t: f32) -> Camera2D {
+        let rest = if t >= 1. { other } else { self };
+
+        Camera2D {
+            rotation: lerp_angle(self.rotation, other.rotation, t),
+            zoom: vec2(
+                lerp_zoom(self.zoom.x, other.zoom.x, t),
+                lerp_zoom(self.zoom.y, other.zoom.y, t),
+            ),
+            target: self.target.lerp(other.target, t),
+            offset: self.offset.lerp(other.offset, t),
+            ..*rest
+        }
+    }
+}
+
+impl CameraLerp for Camera3D {
+    fn lerp(&self, other: &Camera3D, t: f32) -> Camera3D {
+        let rest = if t >= 1. { other } else { self };
+
+        let up = self.up.lerp(other.up, t);
+        let aspect = match (self.aspect, other.aspect) {
+            (Some(a), Some(b)) => Some(lerp(a, b, t)),
+            _ => rest.aspect,
+        };
+
+        Camera3D {
+            position: self.position.lerp(other.position, t),
+            target: self.target.lerp(other.target, t),
+            up: if up.length_squared() > 0. {
+                up
+            } else {
+                rest.up
+            },
+            fovy: lerp(self.fovy, other.fovy, t),
+            aspect,
+            ..*rest
+        }
+    }
+}
+
+/// Smooth transition from one camera to another over time,
+/// like a cut from gameplay to a cutscene camera.
+///
+/// ```ignore
+/// let mut transition = CameraTransition::new(gameplay_camera, cutscene_camera, 1.5);
+/// loop {
+///     set_camera(&transition.update());
+///     ..
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CameraTransition<C> {
+    from: C,
+    to: C,
+    duration: f32,
+    elapsed: f32,
+    /// Maps the linear progress, 0..1, to the interpolation factor.
+    /// Smoothstep by default, to start and stop gently.
+    pub easing: fn(f32) -> f32,
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3. - 2. * t)
+}
+
+impl<C: CameraLerp + Clone> CameraTransition<C> {
+    /// `duration` in seconds
+    pub fn new(from: C, to: C, duration: f32) -> CameraTransition<C> {
+        CameraTransition {
+            from,
+            to,
+            duration,
+            elapsed: 0.,
+            easing: smoothstep,
+        }
+    }
+
+    /// Advance the transition by the frame time and return the current camera.
+    pub fn update(&mut self) -> C {
+        self.advance(get_frame_time());
+        self.camera()
+    }
+
+    /// Advance the transition by `dt` seconds.
+    pub fn advance(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    pub fn camera(&self) -> C {
+        self.from.lerp(&self.to, (self.easing)(self.progress()))
+    }
+
+    /// Linear progress, 0..1
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0. {
+            1.
+        } else {
+            self.elapsed / self.duration
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.
+    }
+
+    /// Start a new transition to `to`, from wherever the camera is now.
+    pub fn retarget(&mut self, to: C, duration: f32) {
+        self.from = self.camera();
+        self.to = to;
+        self.duration = duration;
+        self.elapsed = 0.;
+    }
+}
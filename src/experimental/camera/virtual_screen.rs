@@ -0,0 +1,93 @@
+use crate::camera::screen_rect_to_viewport;
+use crate::prelude::*;
+
+/// Fixed logical resolution, scaled to fit the window with letterbox or
+/// pillarbox bars around it.
+///
+/// ```ignore
+/// let screen = VirtualScreen::new(320., 180.);
+/// loop {
+///     screen.set_camera();
+///     // 0..320, 0..180 whatever the window size is
+///     draw_rectangle(0., 0., 32., 32., RED);
+///     let mouse = screen.mouse_position();
+///     next_frame().await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VirtualScreen {
+    pub width: f32,
+    pub height: f32,
+    /// Color of the bars around the virtual screen
+    pub bars_color: Color,
+    /// Scale only by whole numbers, when the window is big enough,
+    /// so pixel art stays crisp. Leaves bigger bars.
+    pub integer_scale: bool,
+}
+
+impl VirtualScreen {
+    pub fn new(width: f32, height: f32) -> VirtualScreen {
+        VirtualScreen {
+            width,
+            height,
+            bars_color: BLACK,
+            integer_scale: false,
+        }
+    }
+
+    /// Virtual pixel size, in screen pixels.
+    pub fn scale(&self) -> f32 {
+        let scale = (screen_width() / self.width).min(screen_height() / self.height);
+        if self.integer_scale && scale >= 1. {
+            scale.floor()
+        } else {
+            scale
+        }
+    }
+
+    /// Part of the screen the virtual screen is drawn to, in screen coordinates.
+    pub fn screen_rect(&self) -> Rect {
+        let scale = self.scale();
+        let (w, h) = (self.width * scale, self.height * scale);
+
+        Rect::new(
+            ((screen_width() - w) / 2.).floor(),
+            ((screen_height() - h) / 2.).floor(),
+            w,
+            h,
+        )
+    }
+
+    /// Camera drawing to the virtual screen, (0, 0) in the top-left corner.
+    pub fn camera(&self) -> Camera2D {
+        Camera2D {
+            viewport: Some(screen_rect_to_viewport(self.screen_rect())),
+            ..Camera2D::from_display_rect(Rect::new(0., 0., self.width, self.height))
+        }
+    }
+
+    /// Fill the whole window with the bars color and set [VirtualScreen::camera].
+    /// Should be called at the beginning of the frame: clearing the screen
+    /// drops everything drawn so far.
+    pub fn set_camera(&self) {
+        set_default_camera();
+        clear_background(self.bars_color);
+        set_camera(&self.camera());
+    }
+
+    /// Screen coordinates, like [mouse_position], to virtual ones.
+    /// Points on the bars are out of the 0..width, 0..height range.
+    pub fn screen_to_virtual(&self, point: Vec2) -> Vec2 {
+        let rect = self.screen_rect();
+        (point - rect.point()) / self.scale()
+    }
+
+    pub fn virtual_to_screen(&self, point: Vec2) -> Vec2 {
+        self.screen_rect().point() + point * self.scale()
+    }
+
+    /// Mouse position in virtual coordinates.
+    pub fn mouse_position(&self) -> Vec2 {
+        self.screen_to_virtual(mouse_position().into())
+    }
+}
@@ -2,6 +2,22 @@
 //! Useful for organizing state machines, animation cutscenes and other stuff that require
 //! some evaluation over time.
 //!
+//! [CoroutineScope] groups coroutines that have to stop together, like the
+//! ones of a level. The coroutines started by the coroutines of a scope
+//! join the scope too, the others run until they end or are stopped.
+//! ```ignore
+//! let level = CoroutineScope::new();
+//! let spawner = level.start(async move {
+//!     loop {
+//!         // this one stops with the level
+//!         start_coroutine(enemy_ai(spawn_enemy()));
+//!         wait_seconds(5.).await;
+//!     }
+//! });
+//! // ..
+//! level.cancel(); // or just drop the scope
+//! ```
+//!
 
 use std::any::{Any, TypeId};
 use std::future::Future;
@@ -24,6 +40,8 @@ struct CoroutineInternal {
     // keep coroutine's memory allocated until the user retrieves the data
     // we can free the memory right away, and just return () on retrieve
     has_value: bool,
+    /// [CoroutineScope] the coroutine belongs to
+    scope: Option<usize>,
 }
 
 enum CoroutineState {
@@ -57,6 +75,12 @@ pub(crate) struct CoroutinesContext {
     coroutines: GenerationalStorage<CoroutineState>,
     active_coroutine_now: Option<f64>,
     active_coroutine_delta: Option<f64>,
+    /// Scope of the coroutine being polled right now, the coroutines it
+    /// starts join it
+    active_scope: Option<usize>,
+    /// (scope, coroutine) for every running coroutine of a scope
+    scoped: Vec<(usize, GenerationalId)>,
+    next_scope: usize,
 }
 
 impl CoroutinesContext {
@@ -65,14 +89,22 @@ impl CoroutinesContext {
             coroutines: GenerationalStorage::new(),
             active_coroutine_now: None,
             active_coroutine_delta: None,
+            active_scope: None,
+            scoped: vec![],
+            next_scope: 0,
         }
     }
 
     pub fn update(&mut self) {
-        self.coroutines.retain(|coroutine| {
+        self.coroutines.retain(|_, coroutine| {
             if let CoroutineState::Running(ref mut f) = coroutine {
                 if f.manual_poll == false {
-                    if let Some(v) = resume(&mut f.future) {
+                    let context = &mut get_context().coroutines_context;
+                    let scope = std::mem::replace(&mut context.active_scope, f.scope);
+                    let value = resume(&mut f.future);
+                    get_context().coroutines_context.active_scope = scope;
+
+                    if let Some(v) = value {
                         if f.has_value {
                             *coroutine = CoroutineState::Value(v);
                         } else {
@@ -84,6 +116,34 @@ impl CoroutinesContext {
 
             true
         });
+
+        self.forget_finished();
+    }
+
+    /// Scoped coroutines that are not running anymore leave their scopes.
+    fn forget_finished(&mut self) {
+        let coroutines = &self.coroutines;
+        self.scoped
+            .retain(|(_, id)| matches!(coroutines.get(*id), Some(CoroutineState::Running(_))));
+    }
+
+    fn stop(&mut self, id: GenerationalId) {
+        self.coroutines.free(id);
+        self.scoped.retain(|(_, coroutine)| *coroutine != id);
+    }
+
+    fn stop_scope(&mut self, scope: usize) {
+        let mut stopped = vec![];
+        self.scoped.retain(|(other, coroutine)| {
+            if *other == scope {
+                stopped.push(*coroutine);
+            }
+            *other != scope
+        });
+
+        for coroutine in stopped {
+            self.coroutines.free(coroutine);
+        }
     }
 
     pub(crate) fn allocated_memory(&self) -> usize {
@@ -185,6 +245,7 @@ impl<T: 'static + Any> Coroutine<T> {
         if let CoroutineState::Running(f) = coroutine {
             context.active_coroutine_now = f.manual_time;
             context.active_coroutine_delta = Some(delta_time);
+            let scope = std::mem::replace(&mut context.active_scope, f.scope);
             *f.manual_time.as_mut().unwrap() += delta_time;
             if let Some(v) = resume(&mut f.future) {
                 if f.has_value {
@@ -192,15 +253,128 @@ impl<T: 'static + Any> Coroutine<T> {
                 } else {
                     context.coroutines.free(self.id);
                 }
+                context.forget_finished();
             }
+            context.active_scope = scope;
             context.active_coroutine_now = None;
             context.active_coroutine_delta = None;
         }
     }
+
+    pub fn stop(&self) {
+        get_context().coroutines_context.stop(self.id);
+    }
+
+    /// Wait for the coroutine to finish and take its value, `None` if it was
+    /// stopped or the value was already retrieved.
+    ///
+    /// Coroutines returning `()` do not remember how they ended, joining a
+    /// stopped one gives `Some(())`.
+    pub async fn join(self) -> Option<T> {
+        while !self.is_done() {
+            crate::window::next_frame().await;
+        }
+
+        self.retrieve()
+    }
+}
+
+/// Wait for all the coroutines, [Coroutine::join] each of them.
+/// ```ignore
+/// let parts = (0..4).map(|n| start_coroutine(generate_chunk(n))).collect();
+/// let chunks = join_all(parts).await;
+/// ```
+pub async fn join_all<T: 'static + Any>(coroutines: Vec<Coroutine<T>>) -> Vec<Option<T>> {
+    let mut values = Vec::with_capacity(coroutines.len());
+    for coroutine in coroutines {
+        values.push(coroutine.join().await);
+    }
+
+    values
+}
+
+/// Owner of a group of coroutines, stops them when cancelled or dropped.
+///
+/// Coroutines started by the coroutines of the scope belong to the scope too,
+/// they stop with it, not with the coroutine that started them.
+pub struct CoroutineScope {
+    id: usize,
+}
+
+impl CoroutineScope {
+    pub fn new() -> CoroutineScope {
+        let context = &mut get_context().coroutines_context;
+        context.next_scope += 1;
+
+        CoroutineScope {
+            id: context.next_scope,
+        }
+    }
+
+    /// [start_coroutine] inside the scope.
+    pub fn start<T: 'static + Any>(
+        &self,
+        future: impl Future<Output = T> + 'static + Send,
+    ) -> Coroutine<T> {
+        start_child(future, Some(self.id))
+    }
+
+    /// Stop all the coroutines of the scope, the scope stays usable.
+    pub fn cancel(&self) {
+        get_context().coroutines_context.stop_scope(self.id);
+    }
+
+    /// Number of coroutines of the scope still running.
+    pub fn active_count(&self) -> usize {
+        let context = &get_context().coroutines_context;
+        context
+            .scoped
+            .iter()
+            .filter(|(scope, _)| *scope == self.id)
+            .count()
+    }
+}
+
+impl Default for CoroutineScope {
+    fn default() -> CoroutineScope {
+        CoroutineScope::new()
+    }
+}
+
+impl Drop for CoroutineScope {
+    fn drop(&mut self) {
+        self.cancel();
+    }
 }
 
 pub fn start_coroutine<T: 'static + Any>(
     future: impl Future<Output = T> + 'static + Send,
+) -> Coroutine<T> {
+    start_local_coroutine(future)
+}
+
+/// [start_coroutine] for the futures holding `Rc`s, everything runs on the
+/// main thread anyway.
+pub(crate) fn start_local_coroutine<T: 'static + Any>(
+    future: impl Future<Output = T> + 'static,
+) -> Coroutine<T> {
+    let scope = get_context().coroutines_context.active_scope;
+
+    start_child(future, scope)
+}
+
+/// [start_local_coroutine] outside of any scope, for macroquad's own
+/// coroutines, like the asset loads, that have to finish whoever started
+/// them.
+pub(crate) fn start_unscoped_coroutine<T: 'static + Any>(
+    future: impl Future<Output = T> + 'static,
+) -> Coroutine<T> {
+    start_child(future, None)
+}
+
+fn start_child<T: 'static + Any>(
+    future: impl Future<Output = T> + 'static,
+    scope: Option<usize>,
 ) -> Coroutine<T> {
     let context = &mut get_context().coroutines_context;
 
@@ -213,7 +387,11 @@ pub fn start_coroutine<T: 'static + Any>(
             has_value,
             manual_poll: false,
             manual_time: None,
+            scope,
         }));
+    if let Some(scope) = scope {
+        context.scoped.push((scope, id));
+    }
 
     Coroutine {
         id,
@@ -225,12 +403,12 @@ pub fn stop_all_coroutines() {
     let context = &mut get_context().coroutines_context;
 
     context.coroutines.clear();
+    context.scoped.clear();
 }
 
-pub fn stop_coroutine(coroutine: Coroutine) {
-    let context = &mut get_context().coroutines_context;
-
-    context.coroutines.free(coroutine.id);
+/// Same as [Coroutine::stop].
+pub fn stop_coroutine<T: 'static + Any>(coroutine: Coroutine<T>) {
+    coroutine.stop();
 }
 
 pub struct TimerDelayFuture {
@@ -279,7 +457,8 @@ pub mod tweens {
         from: T,
         to: T,
         lens: Lens<T>,
-        start_time: f64,
+        /// Seconds of game time since the tween started
+        elapsed: f32,
         time: f32,
     }
     impl<T> Unpin for LinearTweenFuture<T> where
@@ -294,8 +473,9 @@ pub mod tweens {
         type Output = ();
 
         fn poll(self: Pin<&mut Self>, _: &mut Context) -> Poll<Self::Output> {
-            let t = (miniquad::date::now() - self.start_time) / self.time as f64;
             let this = self.get_mut();
+            this.elapsed += crate::time::get_frame_time();
+            let t = this.elapsed / this.time;
             let var = this.lens.get();
 
             // node with value was deleted
@@ -305,7 +485,7 @@ pub mod tweens {
             let var = var.unwrap();
 
             if t <= 1. {
-                *var = this.from + (this.to - this.from) * t as f32;
+                *var = this.from + (this.to - this.from) * t;
 
                 Poll::Pending
             } else {
@@ -333,7 +513,7 @@ pub mod tweens {
             from,
             lens: handle.lens(lens),
             time,
-            start_time: miniquad::date::now(),
+            elapsed: 0.,
         }
     }
 
@@ -1,4 +1,4 @@
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct GenerationalId {
     id: usize,
     generation: usize,
@@ -83,7 +83,7 @@ impl<T> GenerationalStorage<T> {
     /// In other words, remove all elements e such that f(&mut e) returns false. This method operates in place, visiting each element exactly once in the original order, and preserves the order of the retained elements.
     pub fn retain<F>(&mut self, mut f: F)
     where
-        F: FnMut(&mut T) -> bool,
+        F: FnMut(GenerationalId, &mut T) -> bool,
     {
         for (id, cell) in self.vec.iter_mut().enumerate() {
             if cell.is_none() {
@@ -91,7 +91,8 @@ impl<T> GenerationalStorage<T> {
             }
 
             let c = cell.as_mut().unwrap();
-            let pred = f(&mut c.state);
+            let generation = c.generation;
+            let pred = f(GenerationalId { id, generation }, &mut c.state);
             let old_generation = c.generation;
 
             if !pred {
@@ -114,10 +115,11 @@ impl<T> GenerationalStorage<T> {
         // an attempt to free a cell by an outdated ID
         // this is a legit request, no need to panic or anything, just
         // dont ruin the data that lives there now
-        if let Some(cell) = &self.vec[id.id] {
-            if cell.generation != id.generation {
-                return;
-            }
+        // or a second free of the same cell, that would put the index
+        // to the free list twice
+        match &self.vec[id.id] {
+            Some(cell) if cell.generation == id.generation => {}
+            _ => return,
         }
 
         self.free_indices.push((id.id, id.generation));
@@ -0,0 +1,656 @@
+//! Ready-made shader effects.
+//!
+//! Single pass effects, [Outline], [Dissolve], [PaletteSwap], [ChromaticAberration]
+//! and [Crt], are just materials: build one with `material()` and draw
+//! textures with it, call `update()` after changing the params to apply them.
+//!
+//! ```ignore
+//! let dissolve = Dissolve { amount: 0.5, ..Default::default() };
+//! let material = dissolve.material();
+//!
+//! gl_use_material(material);
+//! draw_texture(sprite, 0., 0., WHITE);
+//! gl_use_default_material();
+//! ```
+//!
+//! Multi pass effects, [GaussianBlur] and [Bloom], render into their own
+//! render targets with [PingPong] and give back the resulting texture.
+
+use crate::{
+    color::{Color, BLACK},
+    experimental::gpgpu::PingPong,
+    material::{load_material, Material, MaterialParams},
+    math::vec2,
+    texture::Texture2D,
+};
+use miniquad::{
+    BlendFactor, BlendState, BlendValue, Equation, FilterMode, PipelineParams, UniformType,
+};
+
+const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}
+"#;
+
+fn effect_material(
+    fragment: &str,
+    uniforms: &[(&str, UniformType)],
+    textures: &[&str],
+    blend: bool,
+) -> Material {
+    let color_blend = if blend {
+        Some(BlendState::new(
+            Equation::Add,
+            BlendFactor::Value(BlendValue::SourceAlpha),
+            BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+        ))
+    } else {
+        None
+    };
+
+    load_material(
+        VERTEX,
+        fragment,
+        MaterialParams {
+            pipeline_params: PipelineParams {
+                color_blend,
+                ..Default::default()
+            },
+            uniforms: uniforms
+                .iter()
+                .map(|(name, kind)| (name.to_string(), *kind))
+                .collect(),
+            textures: textures.iter().map(|name| name.to_string()).collect(),
+        },
+    )
+    .unwrap_or_else(|err| panic!("Failed to build effect material: {}", err))
+}
+
+/// Outline around non-transparent pixels of the texture.
+/// Only drawn inside the texture quad, so sprites need some transparent padding.
+#[derive(Debug, Clone)]
+pub struct Outline {
+    pub color: Color,
+    /// Outline width in uv units, `2. / texture.width()` is two pixels wide.
+    pub thickness: f32,
+}
+
+impl Default for Outline {
+    fn default() -> Outline {
+        Outline {
+            color: BLACK,
+            thickness: 0.01,
+        }
+    }
+}
+
+impl Outline {
+    pub fn material(&self) -> Material {
+        let material = effect_material(
+            OUTLINE_FRAGMENT,
+            &[
+                ("OutlineColor", UniformType::Float4),
+                ("Thickness", UniformType::Float1),
+            ],
+            &[],
+            true,
+        );
+        self.update(material);
+        material
+    }
+
+    pub fn update(&self, material: Material) {
+        material.set_uniform("OutlineColor", self.color.to_vec());
+        material.set_uniform("Thickness", self.thickness);
+    }
+}
+
+const OUTLINE_FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+uniform vec4 OutlineColor;
+uniform float Thickness;
+
+void main() {
+    vec4 texel = texture2D(Texture, uv) * color;
+
+    float alpha = 0.0;
+    for (int i = 0; i < 8; i++) {
+        float angle = float(i) * 0.785398;
+        vec2 offset = vec2(cos(angle), sin(angle)) * Thickness;
+        alpha = max(alpha, texture2D(Texture, uv + offset).a);
+    }
+
+    vec4 outline = vec4(OutlineColor.rgb, OutlineColor.a * alpha);
+    gl_FragColor = mix(outline, texel, texel.a);
+}
+"#;
+
+/// Texture burning away along a noise pattern.
+#[derive(Debug, Clone)]
+pub struct Dissolve {
+    /// 0 - fully visible, 1 - fully dissolved
+    pub amount: f32,
+    /// Color of the burning edge
+    pub edge_color: Color,
+    /// Width of the edge, in noise values
+    pub edge_width: f32,
+    /// Noise cells over the whole texture
+    pub noise_scale: f32,
+}
+
+impl Default for Dissolve {
+    fn default() -> Dissolve {
+        Dissolve {
+            amount: 0.,
+            edge_color: Color::new(1., 0.5, 0., 1.),
+            edge_width: 0.05,
+            noise_scale: 16.,
+        }
+    }
+}
+
+impl Dissolve {
+    pub fn material(&self) -> Material {
+        let material = effect_material(
+            DISSOLVE_FRAGMENT,
+            &[
+                ("Amount", UniformType::Float1),
+                ("EdgeColor", UniformType::Float4),
+                ("EdgeWidth", UniformType::Float1),
+                ("NoiseScale", UniformType::Float1),
+            ],
+            &[],
+            true,
+        );
+        self.update(material);
+        material
+    }
+
+    pub fn update(&self, material: Material) {
+        material.set_uniform("Amount", self.amount);
+        material.set_uniform("EdgeColor", self.edge_color.to_vec());
+        material.set_uniform("EdgeWidth", self.edge_width);
+        material.set_uniform("NoiseScale", self.noise_scale);
+    }
+}
+
+const DISSOLVE_FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+uniform float Amount;
+uniform vec4 EdgeColor;
+uniform float EdgeWidth;
+uniform float NoiseScale;
+
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+float noise(vec2 p) {
+    vec2 i = floor(p);
+    vec2 f = fract(p);
+    vec2 u = f * f * (3.0 - 2.0 * f);
+    return mix(
+        mix(hash(i), hash(i + vec2(1.0, 0.0)), u.x),
+        mix(hash(i + vec2(0.0, 1.0)), hash(i + vec2(1.0, 1.0)), u.x),
+        u.y
+    );
+}
+
+void main() {
+    vec4 texel = texture2D(Texture, uv) * color;
+
+    float n = noise(uv * NoiseScale);
+    if (n < Amount) {
+        discard;
+    }
+    float edge = (1.0 - smoothstep(0.0, EdgeWidth, n - Amount)) * step(0.0001, Amount);
+
+    gl_FragColor = vec4(mix(texel.rgb, EdgeColor.rgb, edge * EdgeColor.a), texel.a);
+}
+"#;
+
+/// Replace colors of the texture with other colors.
+#[derive(Debug, Clone)]
+pub struct PaletteSwap {
+    /// (from, to) pairs, up to 16.
+    /// Baked into the material on [PaletteSwap::material], `update` does not change them.
+    pub colors: Vec<(Color, Color)>,
+    /// How far, in rgb, a pixel can be from the "from" color to still be replaced
+    pub tolerance: f32,
+}
+
+impl Default for PaletteSwap {
+    fn default() -> PaletteSwap {
+        PaletteSwap {
+            colors: vec![],
+            tolerance: 0.01,
+        }
+    }
+}
+
+impl PaletteSwap {
+    const MAX_COLORS: usize = 16;
+
+    pub fn material(&self) -> Material {
+        assert!(
+            self.colors.len() <= Self::MAX_COLORS,
+            "PaletteSwap supports up to {} colors",
+            Self::MAX_COLORS
+        );
+
+        // first row - "from" colors, second row - "to" colors
+        let mut bytes = vec![0; Self::MAX_COLORS * 2 * 4];
+        for (i, (from, to)) in self.colors.iter().enumerate() {
+            let from: [u8; 4] = (*from).into();
+            let to: [u8; 4] = (*to).into();
+            bytes[i * 4..i * 4 + 4].copy_from_slice(&from);
+            let ix = (Self::MAX_COLORS + i) * 4;
+            bytes[ix..ix + 4].copy_from_slice(&to);
+        }
+        let palette = Texture2D::from_rgba8(Self::MAX_COLORS as u16, 2, &bytes);
+        palette.set_filter(FilterMode::Nearest);
+
+        let material = effect_material(
+            PALETTE_SWAP_FRAGMENT,
+            &[
+                ("Count", UniformType::Float1),
+                ("Tolerance", UniformType::Float1),
+            ],
+            &["Palette"],
+            true,
+        );
+        material.set_texture("Palette", palette);
+        material.set_uniform("Count", self.colors.len() as f32);
+        self.update(material);
+        material
+    }
+
+    pub fn update(&self, material: Material) {
+        material.set_uniform("Tolerance", self.tolerance);
+    }
+}
+
+const PALETTE_SWAP_FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+uniform sampler2D Palette;
+uniform float Count;
+uniform float Tolerance;
+
+void main() {
+    vec4 texel = texture2D(Texture, uv);
+
+    for (int i = 0; i < 16; i++) {
+        if (float(i) >= Count) {
+            break;
+        }
+        float x = (float(i) + 0.5) / 16.0;
+        vec3 source = texture2D(Palette, vec2(x, 0.25)).rgb;
+        if (distance(texel.rgb, source) <= Tolerance) {
+            texel.rgb = texture2D(Palette, vec2(x, 0.75)).rgb;
+            break;
+        }
+    }
+
+    gl_FragColor = texel * color;
+}
+"#;
+
+/// Red and blue channels shifted apart, more towards the texture edges.
+#[derive(Debug, Clone)]
+pub struct ChromaticAberration {
+    /// Shift at the texture edges, in uv units
+    pub offset: f32,
+}
+
+impl Default for ChromaticAberration {
+    fn default() -> ChromaticAberration {
+        ChromaticAberration { offset: 0.01 }
+    }
+}
+
+impl ChromaticAberration {
+    pub fn material(&self) -> Material {
+        let material = effect_material(
+            CHROMATIC_ABERRATION_FRAGMENT,
+            &[("Offset", UniformType::Float1)],
+            &[],
+            true,
+        );
+        self.update(material);
+        material
+    }
+
+    pub fn update(&self, material: Material) {
+        material.set_uniform("Offset", self.offset);
+    }
+}
+
+const CHROMATIC_ABERRATION_FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+uniform float Offset;
+
+void main() {
+    vec2 shift = (uv - 0.5) * 2.0 * Offset;
+
+    vec4 texel = texture2D(Texture, uv);
+    float r = texture2D(Texture, uv + shift).r;
+    float b = texture2D(Texture, uv - shift).b;
+
+    gl_FragColor = vec4(r, texel.g, b, texel.a) * color;
+}
+"#;
+
+/// Old CRT screen: curved image, scanlines and vignette.
+/// Meant for drawing the whole screen render target.
+#[derive(Debug, Clone)]
+pub struct Crt {
+    /// 0 - flat screen
+    pub curvature: f32,
+    /// Amount of scanlines over the texture height
+    pub scanlines: f32,
+    /// 0 - no vignette, 1 - full
+    pub vignette: f32,
+}
+
+impl Default for Crt {
+    fn default() -> Crt {
+        Crt {
+            curvature: 1.,
+            scanlines: 240.,
+            vignette: 1.,
+        }
+    }
+}
+
+impl Crt {
+    pub fn material(&self) -> Material {
+        let material = effect_material(
+            CRT_FRAGMENT,
+            &[
+                ("Curvature", UniformType::Float1),
+                ("Scanlines", UniformType::Float1),
+                ("Vignette", UniformType::Float1),
+            ],
+            &[],
+            true,
+        );
+        self.update(material);
+        material
+    }
+
+    pub fn update(&self, material: Material) {
+        material.set_uniform("Curvature", self.curvature);
+        material.set_uniform("Scanlines", self.scanlines);
+        material.set_uniform("Vignette", self.vignette);
+    }
+}
+
+// based on https://www.shadertoy.com/view/XtlSD7
+const CRT_FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+uniform float Curvature;
+uniform float Scanlines;
+uniform float Vignette;
+
+vec2 curve(vec2 uv) {
+    uv = uv * 2.0 - 1.0;
+    vec2 offset = abs(uv.yx) / vec2(6.0, 4.0) * Curvature;
+    uv = uv + uv * offset * offset;
+    return uv * 0.5 + 0.5;
+}
+
+void main() {
+    vec2 crt_uv = curve(uv);
+    vec3 res = texture2D(Texture, crt_uv).rgb * color.rgb;
+
+    if (crt_uv.x < 0.0 || crt_uv.x > 1.0 || crt_uv.y < 0.0 || crt_uv.y > 1.0) {
+        res = vec3(0.0);
+    }
+
+    float vignette = max(crt_uv.x * crt_uv.y * (1.0 - crt_uv.x) * (1.0 - crt_uv.y), 0.0);
+    res *= mix(1.0, clamp(pow(16.0 * vignette, 0.3), 0.0, 1.0), Vignette);
+
+    float scanline = clamp(0.95 + 0.05 * cos(3.14 * crt_uv.y * Scanlines), 0.0, 1.0);
+    float grille = 0.85 + 0.15 * clamp(1.5 * cos(3.14 * crt_uv.x * Scanlines * 8.0 / 3.0), 0.0, 1.0);
+    res *= scanline * grille * 1.2;
+
+    gl_FragColor = vec4(res, 1.0);
+}
+"#;
+
+/// Recreate the targets if the texture size changed.
+fn targets_for(targets: &mut Option<PingPong>, texture: Texture2D) -> &mut PingPong {
+    let (width, height) = (texture.width() as u32, texture.height() as u32);
+    if let Some(old) = targets {
+        if old.width() != width || old.height() != height {
+            old.delete();
+            *targets = None;
+        }
+    }
+    targets.get_or_insert_with(|| PingPong::new(width, height))
+}
+
+/// Separable gaussian blur, two passes.
+pub struct GaussianBlur {
+    /// Standard deviation in pixels, the kernel is up to 16 pixels in each direction
+    pub sigma: f32,
+    material: Material,
+    targets: Option<PingPong>,
+}
+
+impl GaussianBlur {
+    pub fn new(sigma: f32) -> GaussianBlur {
+        GaussianBlur {
+            sigma,
+            material: effect_material(
+                BLUR_FRAGMENT,
+                &[
+                    ("Direction", UniformType::Float2),
+                    ("Sigma", UniformType::Float1),
+                ],
+                &[],
+                false,
+            ),
+            targets: None,
+        }
+    }
+
+    /// Blurred copy of the texture.
+    /// Stays valid until the next `apply` call.
+    pub fn apply(&mut self, texture: Texture2D) -> Texture2D {
+        let targets = targets_for(&mut self.targets, texture);
+        targets.set_state(texture);
+        Self::passes(self.material, self.sigma, targets);
+
+        targets.current().texture
+    }
+
+    fn passes(material: Material, sigma: f32, targets: &mut PingPong) {
+        let texel_size = targets.texel_size();
+
+        material.set_uniform("Sigma", sigma);
+        material.set_uniform("Direction", vec2(texel_size.x, 0.));
+        targets.simulate(material, &[]);
+        material.set_uniform("Direction", vec2(0., texel_size.y));
+        targets.simulate(material, &[]);
+    }
+
+    pub fn delete(&mut self) {
+        self.material.delete();
+        if let Some(targets) = self.targets.take() {
+            targets.delete();
+        }
+    }
+}
+
+const BLUR_FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform vec2 Direction;
+uniform float Sigma;
+
+void main() {
+    float sigma = max(Sigma, 0.01);
+    float radius = min(ceil(sigma * 3.0), 16.0);
+
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+    for (int i = -16; i <= 16; i++) {
+        float x = float(i);
+        if (abs(x) > radius) {
+            continue;
+        }
+        float weight = exp(-x * x / (2.0 * sigma * sigma));
+        sum += texture2D(Texture, uv + Direction * x) * weight;
+        total += weight;
+    }
+
+    gl_FragColor = sum / total;
+}
+"#;
+
+/// Bright parts of the texture, blurred and added on top of it.
+pub struct Bloom {
+    /// Brightness, max of r, g and b, at which pixels start to glow
+    pub threshold: f32,
+    /// Multiplier of the glow
+    pub intensity: f32,
+    /// Glow blur, see [GaussianBlur::sigma]
+    pub sigma: f32,
+    threshold_material: Material,
+    blur_material: Material,
+    composite_material: Material,
+    targets: Option<PingPong>,
+}
+
+impl Default for Bloom {
+    fn default() -> Bloom {
+        Bloom::new()
+    }
+}
+
+impl Bloom {
+    pub fn new() -> Bloom {
+        Bloom {
+            threshold: 0.7,
+            intensity: 1.,
+            sigma: 4.,
+            threshold_material: effect_material(
+                BLOOM_THRESHOLD_FRAGMENT,
+                &[("Threshold", UniformType::Float1)],
+                &[],
+                false,
+            ),
+            blur_material: GaussianBlur::new(0.).material,
+            composite_material: effect_material(
+                BLOOM_COMPOSITE_FRAGMENT,
+                &[("Intensity", UniformType::Float1)],
+                &["Original"],
+                false,
+            ),
+            targets: None,
+        }
+    }
+
+    /// Texture with bloom applied.
+    /// Stays valid until the next `apply` call.
+    pub fn apply(&mut self, texture: Texture2D) -> Texture2D {
+        let targets = targets_for(&mut self.targets, texture);
+        targets.set_state(texture);
+
+        self.threshold_material
+            .set_uniform("Threshold", self.threshold);
+        targets.simulate(self.threshold_material, &[]);
+
+        GaussianBlur::passes(self.blur_material, self.sigma, targets);
+
+        self.composite_material
+            .set_uniform("Intensity", self.intensity);
+        targets
+            .simulate(self.composite_material, &[("Original", texture)])
+            .texture
+    }
+
+    pub fn delete(&mut self) {
+        self.threshold_material.delete();
+        self.blur_material.delete();
+        self.composite_material.delete();
+        if let Some(targets) = self.targets.take() {
+            targets.delete();
+        }
+    }
+}
+
+const BLOOM_THRESHOLD_FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform float Threshold;
+
+void main() {
+    vec4 texel = texture2D(Texture, uv);
+    float brightness = max(texel.r, max(texel.g, texel.b));
+
+    gl_FragColor = vec4(texel.rgb * smoothstep(Threshold, Threshold + 0.1, brightness), texel.a);
+}
+"#;
+
+const BLOOM_COMPOSITE_FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform sampler2D Original;
+uniform float Intensity;
+
+void main() {
+    vec4 original = texture2D(Original, uv);
+    vec3 glow = texture2D(Texture, uv).rgb;
+
+    gl_FragColor = vec4(original.rgb + glow * Intensity, original.a);
+}
+"#;
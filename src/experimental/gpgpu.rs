@@ -0,0 +1,158 @@
+//! "Compute" with fragment shaders.
+//!
+//! There are no compute shaders on GL2/GLES2, but a fragment shader drawn
+//! over a whole render target works just as well for things like GPU particle
+//! sims or cellular automata: each pixel of the target is one output value,
+//! and the result of the previous step is fed back as an input texture.
+//!
+//! [PingPong] keeps two render targets and swaps them after each step,
+//! so the previous state is always available to the material as "Texture" -
+//! the same sampler regular macroquad materials use for the drawn texture.
+//!
+//! ```ignore
+//! let mut life = PingPong::new(256, 256);
+//! life.set_state(initial_texture);
+//! loop {
+//!     let state = life.simulate(life_material, &[]);
+//!     draw_texture(state.texture, 0., 0., WHITE);
+//!     next_frame().await
+//! }
+//! ```
+
+use crate::{
+    camera::{pop_camera_state, push_camera_state, set_camera, Camera2D},
+    color::{Color, WHITE},
+    material::{gl_use_default_material, gl_use_material, Material},
+    math::{vec2, Vec2},
+    texture::{draw_texture_ex, render_target, DrawTextureParams, RenderTarget, Texture2D},
+    window::clear_background,
+};
+use miniquad::FilterMode;
+
+/// Two same-sized render targets, one holds the current state and
+/// the other one is drawn into on the next [PingPong::simulate] step.
+///
+/// Targets are RGBA8: miniquad has no float texture formats on GL2, so
+/// values out of 0..1 range, or needing more than 8 bits of precision,
+/// should be packed into several channels by the shader.
+/// Both targets use nearest filtering, so sampling at texel centers
+/// gives back exactly the stored values.
+pub struct PingPong {
+    targets: [RenderTarget; 2],
+    current: usize,
+    width: u32,
+    height: u32,
+}
+
+impl PingPong {
+    pub fn new(width: u32, height: u32) -> PingPong {
+        let targets = [render_target(width, height), render_target(width, height)];
+        for target in &targets {
+            target.texture.set_filter(FilterMode::Nearest);
+        }
+
+        PingPong {
+            targets,
+            current: 0,
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Size of one texel in uv space, handy as a uniform for
+    /// fetching the neighbours: `texture2D(Texture, uv + vec2(1., 0.) * TexelSize)`
+    pub fn texel_size(&self) -> Vec2 {
+        vec2(1. / self.width as f32, 1. / self.height as f32)
+    }
+
+    /// Target with the result of the last step.
+    pub fn current(&self) -> RenderTarget {
+        self.targets[self.current]
+    }
+
+    /// Replace the current state with the given texture, stretched over the whole target.
+    /// Nothing of the old state is kept, even under the transparent texels.
+    pub fn set_state(&mut self, texture: Texture2D) {
+        self.draw(self.targets[self.current], None, texture, &[]);
+    }
+
+    /// One simulation step: draw `material` over the whole next target,
+    /// with the current state bound as "Texture", and make the result current.
+    ///
+    /// `inputs` are set as the material textures, by name, before drawing.
+    /// Textures are set on the material itself, not per step, so
+    /// running the same material several times a frame with different
+    /// inputs will give all the steps the last ones.
+    ///
+    /// The target is cleared before drawing, but a material with blending
+    /// enabled still mixes its output with that transparent black.
+    pub fn simulate(&mut self, material: Material, inputs: &[(&str, Texture2D)]) -> RenderTarget {
+        let next = 1 - self.current;
+        self.draw(
+            self.targets[next],
+            Some(material),
+            self.targets[self.current].texture,
+            inputs,
+        );
+        self.current = next;
+
+        self.current()
+    }
+
+    fn draw(
+        &self,
+        target: RenderTarget,
+        material: Option<Material>,
+        texture: Texture2D,
+        inputs: &[(&str, Texture2D)],
+    ) {
+        let (width, height) = (self.width as f32, self.height as f32);
+
+        push_camera_state();
+        // y is not flipped: texel (x, y) of the target is drawn from
+        // the uv of texel (x, y) of the state texture
+        set_camera(&Camera2D {
+            zoom: vec2(2. / width, 2. / height),
+            target: vec2(width / 2., height / 2.),
+            render_target: Some(target),
+            ..Default::default()
+        });
+        // the default material blends, a transparent state drawn over
+        // the old one would keep some of it
+        clear_background(Color::new(0., 0., 0., 0.));
+
+        if let Some(material) = material {
+            for (name, input) in inputs {
+                material.set_texture(name, *input);
+            }
+            gl_use_material(material);
+        }
+        draw_texture_ex(
+            texture,
+            0.,
+            0.,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(width, height)),
+                ..Default::default()
+            },
+        );
+        gl_use_default_material();
+
+        pop_camera_state();
+    }
+
+    pub fn delete(&self) {
+        for target in &self.targets {
+            target.delete();
+        }
+    }
+}
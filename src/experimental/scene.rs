@@ -1,9 +1,17 @@
 use std::{any::Any, marker::PhantomData, ops::Drop};
 
 use crate::camera::Camera2D;
+use crate::math::{spatial::SpatialHash, Rect};
 
 pub use macroquad_macro::CapabilityTrait;
 
+mod serialize;
+
+pub use serialize::{
+    load_scene_json, load_scene_ron, register_node_type, save_scene_json, save_scene_ron,
+    SceneError,
+};
+
 #[rustfmt::skip]
 pub trait Node {
     fn ready(_node: RefMut<Self>) where Self: Sized {}
@@ -30,7 +38,7 @@ impl<T: Node + 'static> NodeAny for T {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 struct Id {
     id: usize,
     generation: u64,
@@ -43,7 +51,7 @@ pub struct Handle<T: 'static> {
 
 unsafe impl<T: 'static> Send for Handle<T> {}
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct HandleUntyped(Id);
 
 impl<T: 'static> std::fmt::Debug for Handle<T> {
@@ -147,6 +155,35 @@ impl<T: 'static> RefMut<T> {
         unsafe { (*self.capabilities).push(Box::new(x)) };
     }
 
+    /// Make the node show up in [Query::as_trait] as `D`, usually a trait object.
+    /// ```ignore
+    /// node.provides_trait::<dyn Damageable>(|enemy| enemy);
+    /// ```
+    pub fn provides_trait<D: ?Sized + 'static>(
+        &mut self,
+        view: impl Fn(&mut T) -> &mut D + 'static,
+    ) {
+        let view = move |data: *mut ()| view(unsafe { &mut *(data as *mut T) }) as *mut D;
+
+        unsafe { (*self.capabilities).push(Box::new(TraitView::<D>(Box::new(view)))) };
+    }
+
+    /// Tags are names for groups of nodes, see [Query::tag].
+    pub fn add_tag(&mut self, tag: &str) {
+        let tags = node_tags(self.handle.id.unwrap());
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) {
+        node_tags(self.handle.id.unwrap()).retain(|t| t != tag);
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        node_tags(self.handle.id.unwrap()).iter().any(|t| t == tag)
+    }
+
     pub fn delete(self) {
         assert!(self.handle.id.is_some());
 
@@ -207,6 +244,10 @@ impl<'a> RefMutAny<'a> {
         std::mem::forget(self);
     }
 
+    pub fn has_tag(&self, tag: &str) -> bool {
+        node_tags(self.handle.0).iter().any(|t| t == tag)
+    }
+
     pub fn to_typed<T>(self) -> RefMut<T> {
         let res = RefMut {
             data: self.data as *mut T,
@@ -259,6 +300,7 @@ struct Cell {
     data: *mut (),
     vtable: *mut (),
     capabilities: Vec<Box<dyn Any>>,
+    tags: Vec<String>,
     ready: *const fn(RefMut<()>),
     update: *const fn(RefMut<()>),
     fixed_update: *const fn(RefMut<()>),
@@ -285,6 +327,7 @@ impl Cell {
             data,
             vtable,
             capabilities: vec![],
+            tags: vec![],
             used,
             permanent: false,
             ready: unsafe {
@@ -336,6 +379,7 @@ impl Cell {
         self.permanent = false;
 
         self.capabilities.clear();
+        self.tags.clear();
 
         std::mem::forget(data);
     }
@@ -355,6 +399,9 @@ struct Scene {
 
     any_map: std::collections::HashMap<std::any::TypeId, Vec<(HandleUntyped, *mut u8)>>,
     free_nodes: Vec<Cell>,
+    /// [delete_node]d nodes, deleted at the end of the update
+    pending_deletes: Vec<Id>,
+    node_types: Vec<serialize::NodeType>,
 }
 
 impl Scene {
@@ -371,11 +418,14 @@ impl Scene {
             current_time: crate::time::get_time(),
             in_fixed_update: false,
             any_map: std::collections::HashMap::new(),
+            pending_deletes: vec![],
+            node_types: vec![],
         }
     }
 
     pub fn clear(&mut self) {
         self.any_map.clear();
+        self.pending_deletes.clear();
 
         for cell in &mut self.nodes {
             if let Some(Cell {
@@ -397,6 +447,13 @@ impl Scene {
         }
     }
 
+    fn cell_mut(&mut self, id: Id) -> Option<&mut Cell> {
+        self.nodes
+            .get_mut(id.id)?
+            .as_mut()
+            .filter(|cell| cell.id == id)
+    }
+
     pub fn get_any(&mut self, handle: HandleUntyped) -> Option<RefMutAny> {
         let handle = handle.0;
         let cell = self.nodes.get_mut(handle.id)?;
@@ -518,7 +575,7 @@ impl Scene {
         }
 
         self.current_time = new_time;
-        self.acc += frame_time;
+        self.acc += frame_time * crate::time::effective_time_scale() as f64;
 
         for node in &mut self.iter() {
             let cell = self.nodes[node.handle.0.id].as_mut().unwrap();
@@ -554,6 +611,15 @@ impl Scene {
             }
         }
 
+        // nodes borrowed by someone for longer than a frame wait for the next update
+        for id in std::mem::take(&mut self.pending_deletes) {
+            match self.cell_mut(id) {
+                Some(cell) if unsafe { *cell.used } => self.pending_deletes.push(id),
+                Some(_) => self.delete(id),
+                None => {}
+            }
+        }
+
         for id in self.dense_ongoing.drain(0..) {
             match id {
                 Ok(id) => {
@@ -587,7 +653,8 @@ impl Iterator for MagicVecIterator {
         let cell = &mut nodes[ix.id];
         self.n += 1;
 
-        if cell.is_none() {
+        // deleted, maybe with the cell taken by a newer node
+        if cell.as_ref().filter(|cell| cell.id == ix).is_none() {
             return self.next();
         }
         let cell = cell.as_mut().unwrap();
@@ -689,6 +756,162 @@ pub fn find_nodes_by_type<T: Any>() -> impl Iterator<Item = RefMut<T>> {
         .map(|node| node.to_typed())
 }
 
+/// Delete the node at the end of the scene update, safe to use while
+/// iterating or with the node borrowed. Does nothing for deleted nodes.
+pub fn delete_node(handle: HandleUntyped) {
+    unsafe { get_scene() }.pending_deletes.push(handle.0);
+}
+
+fn node_tags(id: Id) -> &'static mut Vec<String> {
+    &mut unsafe { get_scene() }
+        .cell_mut(id)
+        .expect("The node was deleted")
+        .tags
+}
+
+/// Erased [RefMut::provides_trait] view
+struct TraitView<D: ?Sized>(Box<dyn Fn(*mut ()) -> *mut D>);
+
+/// Node borrowed as `D`, see [Query::as_trait].
+pub struct RefMutAs<D: ?Sized + 'static> {
+    node: RefMutAny<'static>,
+    data: *mut D,
+}
+
+impl<D: ?Sized + 'static> RefMutAs<D> {
+    pub fn handle(&self) -> HandleUntyped {
+        self.node.handle
+    }
+}
+
+impl<D: ?Sized + 'static> std::ops::Deref for RefMutAs<D> {
+    type Target = D;
+
+    fn deref(&self) -> &D {
+        unsafe { &*self.data }
+    }
+}
+
+impl<D: ?Sized + 'static> std::ops::DerefMut for RefMutAs<D> {
+    fn deref_mut(&mut self) -> &mut D {
+        unsafe { &mut *self.data }
+    }
+}
+
+/// Nodes with some tags and capabilities.
+///
+/// ```ignore
+/// for mut enemy in scene::query().tag("enemy").without_tag("boss").of_type::<Enemy>() {
+///     enemy.hp -= 1;
+/// }
+/// for mut target in scene::query().tag("in_blast").as_trait::<dyn Damageable>() {
+///     target.damage(10.);
+/// }
+/// ```
+///
+/// Nodes added while iterating show up in the next queries, use
+/// [delete_node] to delete the nodes on the way.
+#[derive(Default)]
+pub struct Query {
+    tags: Vec<String>,
+    without_tags: Vec<String>,
+    capabilities: Vec<std::any::TypeId>,
+}
+
+pub fn query() -> Query {
+    Query::default()
+}
+
+impl Query {
+    /// Only the nodes tagged `tag`.
+    pub fn tag(mut self, tag: &str) -> Query {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// None of the nodes tagged `tag`.
+    pub fn without_tag(mut self, tag: &str) -> Query {
+        self.without_tags.push(tag.to_string());
+        self
+    }
+
+    /// Only the nodes that [RefMut::provides] a `C`.
+    pub fn with<C: Any>(mut self) -> Query {
+        self.capabilities.push(std::any::TypeId::of::<C>());
+        self
+    }
+
+    fn matches(&self, node: &RefMutAny) -> bool {
+        let tags = node_tags(node.handle.0);
+        let capabilities = unsafe { &*node.capabilities };
+
+        self.tags.iter().all(|tag| tags.contains(tag))
+            && !self.without_tags.iter().any(|tag| tags.contains(tag))
+            && self.capabilities.iter().all(|type_id| {
+                capabilities
+                    .iter()
+                    .any(|capability| (**capability).type_id() == *type_id)
+            })
+    }
+
+    /// Matching nodes, borrowed ones are skipped.
+    pub fn iter(self) -> impl Iterator<Item = RefMutAny<'static>> {
+        all_nodes().filter(move |node| self.matches(node))
+    }
+
+    /// Handles of the matching nodes, nothing stays borrowed.
+    pub fn handles(self) -> Vec<HandleUntyped> {
+        self.iter().map(|node| node.handle).collect()
+    }
+
+    /// Matching nodes of type `T`.
+    pub fn of_type<T: Any>(self) -> impl Iterator<Item = RefMut<T>> {
+        self.iter()
+            .filter(|node| node.is::<T>())
+            .map(|node| node.to_typed())
+    }
+
+    /// Matching nodes that [RefMut::provides_trait] `D`.
+    pub fn as_trait<D: ?Sized + 'static>(self) -> impl Iterator<Item = RefMutAs<D>> {
+        self.iter().filter_map(|node| {
+            let view = unsafe { &*node.capabilities }
+                .iter()
+                .find_map(|capability| capability.downcast_ref::<TraitView<D>>())?;
+            let data = (view.0)(node.data);
+
+            Some(RefMutAs { node, data })
+        })
+    }
+
+    /// [SpatialHash] of the matching nodes, placed by the bounds `bounds`
+    /// returns for them. The nodes it returns `None` for are left out.
+    ///
+    /// ```ignore
+    /// let index = scene::query().tag("enemy").spatial_index(64., |node| {
+    ///     node.downcast_ref::<Enemy>().map(|enemy| enemy.rect)
+    /// });
+    /// for handle in index.query_radius(explosion, 100.) {
+    ///     scene::delete_node(handle);
+    /// }
+    /// ```
+    ///
+    /// The index does not follow the nodes, build it again once they moved,
+    /// usually once a frame.
+    pub fn spatial_index(
+        self,
+        cell_size: f32,
+        bounds: impl Fn(&RefMutAny) -> Option<Rect>,
+    ) -> SpatialHash<HandleUntyped> {
+        let mut index = SpatialHash::new(cell_size);
+        for node in self.iter() {
+            if let Some(rect) = bounds(&node) {
+                index.insert(node.handle, rect);
+            }
+        }
+        index
+    }
+}
+
 const CONST_FPS: f64 = 1.0 / 60.;
 
 pub(crate) fn in_fixed_update() -> bool {
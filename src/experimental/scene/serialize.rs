@@ -0,0 +1,316 @@
+//! Saving the scene nodes to RON or JSON and loading them back, for the
+//! levels made in an editor and the saved games.
+//!
+//! ```ignore
+//! #[derive(SerRon, DeRon, SerJson, DeJson)]
+//! struct Crate {
+//!     pos: (f32, f32),
+//!     // handles are different every run, they are not saved
+//!     #[nserde(skip)]
+//!     player: Option<Handle<Player>>,
+//! }
+//! impl Node for Crate {}
+//!
+//! scene::register_node_type::<Crate>("crate");
+//!
+//! let level = load_string("level.ron").await?;
+//! scene::load_scene_ron(&level)?;
+//! ```
+//!
+//! Files are lists of `(type: "crate", node: (pos: (1.0, 2.0)))` in RON and
+//! `{"type": "crate", "node": {"pos": [1.0, 2.0]}}` in JSON, `type` first.
+
+use super::{get_scene, HandleUntyped, Node};
+
+use nanoserde::{
+    DeJson, DeJsonErr, DeJsonState, DeJsonTok, DeRon, DeRonErr, DeRonState, DeRonTok, SerJson,
+    SerJsonState, SerRon, SerRonState,
+};
+use std::any::{Any, TypeId};
+use std::str::Chars;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Ron(DeRonErr),
+    Json(DeJsonErr),
+}
+
+impl std::error::Error for SceneError {}
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SceneError::Ron(err) => write!(f, "{}", err),
+            SceneError::Json(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+type DeRonFn = fn(&mut DeRonState, &mut Chars) -> Result<Box<dyn Any>, DeRonErr>;
+type DeJsonFn = fn(&mut DeJsonState, &mut Chars) -> Result<Box<dyn Any>, DeJsonErr>;
+
+/// A [register_node_type]d type
+pub(super) struct NodeType {
+    name: String,
+    type_id: TypeId,
+    ser_ron: fn(&dyn Any, usize, &mut SerRonState),
+    ser_json: fn(&dyn Any, usize, &mut SerJsonState),
+    de_ron: DeRonFn,
+    de_json: DeJsonFn,
+    add: fn(Box<dyn Any>) -> HandleUntyped,
+}
+
+impl NodeType {
+    fn new<T>(name: &str) -> NodeType
+    where
+        T: Node + SerRon + DeRon + SerJson + DeJson + 'static,
+    {
+        NodeType {
+            name: name.to_string(),
+            type_id: TypeId::of::<T>(),
+            ser_ron: |node, d, s| node.downcast_ref::<T>().unwrap().ser_ron(d, s),
+            ser_json: |node, d, s| node.downcast_ref::<T>().unwrap().ser_json(d, s),
+            de_ron: |s, i| Ok(Box::new(T::de_ron(s, i)?)),
+            de_json: |s, i| Ok(Box::new(T::de_json(s, i)?)),
+            add: |node| super::add_node(*node.downcast::<T>().unwrap()).untyped(),
+        }
+    }
+}
+
+/// Have the nodes of type `T` saved with the scene, under `name`.
+///
+/// The name is what the files refer to the type by, it should not change
+/// when the type is renamed.
+pub fn register_node_type<T>(name: &str)
+where
+    T: Node + SerRon + DeRon + SerJson + DeJson + 'static,
+{
+    let node_types = &mut unsafe { get_scene() }.node_types;
+
+    node_types.retain(|node_type| node_type.type_id != TypeId::of::<T>());
+    assert!(
+        node_types.iter().all(|node_type| node_type.name != name),
+        "Node type name {:?} is already taken",
+        name
+    );
+    node_types.push(NodeType::new::<T>(name));
+}
+
+/// All the nodes of the registered types, in RON.
+///
+/// Nodes borrowed at the moment are missing, call it outside of the node
+/// updates.
+pub fn save_scene_ron() -> String {
+    let scene = unsafe { get_scene() };
+    let nodes: Vec<_> = scene.iter().collect();
+
+    to_ron(&saved_nodes(&scene.node_types, &nodes))
+}
+
+/// [save_scene_ron] in JSON.
+pub fn save_scene_json() -> String {
+    let scene = unsafe { get_scene() };
+    let nodes: Vec<_> = scene.iter().collect();
+
+    to_json(&saved_nodes(&scene.node_types, &nodes))
+}
+
+/// Add the nodes saved with [save_scene_ron] to the scene, returns their
+/// handles in the file order.
+///
+/// Nothing is added if the file fails to parse. The nodes already in the
+/// scene stay, [super::clear] the scene before loading a level.
+pub fn load_scene_ron(data: &str) -> Result<Vec<HandleUntyped>, SceneError> {
+    let nodes = from_ron(&unsafe { get_scene() }.node_types, data).map_err(SceneError::Ron)?;
+
+    Ok(nodes.into_iter().map(|(add, node)| add(node)).collect())
+}
+
+/// [load_scene_ron] for [save_scene_json] files.
+pub fn load_scene_json(data: &str) -> Result<Vec<HandleUntyped>, SceneError> {
+    let nodes = from_json(&unsafe { get_scene() }.node_types, data).map_err(SceneError::Json)?;
+
+    Ok(nodes.into_iter().map(|(add, node)| add(node)).collect())
+}
+
+fn saved_nodes<'a>(
+    node_types: &'a [NodeType],
+    nodes: &'a [super::RefMutAny],
+) -> Vec<(&'a NodeType, &'a dyn Any)> {
+    nodes
+        .iter()
+        .filter_map(|node| {
+            let node: &dyn Any = &**node;
+            let node_type = node_types
+                .iter()
+                .find(|node_type| node_type.type_id == node.type_id())?;
+            Some((node_type, node))
+        })
+        .collect()
+}
+
+type LoadedNode = (fn(Box<dyn Any>) -> HandleUntyped, Box<dyn Any>);
+
+fn to_ron(nodes: &[(&NodeType, &dyn Any)]) -> String {
+    let mut s = SerRonState { out: String::new() };
+
+    s.out.push_str("[\n");
+    for (node_type, node) in nodes {
+        s.indent(1);
+        s.st_pre();
+        s.field(2, "type");
+        node_type.name.ser_ron(2, &mut s);
+        s.conl();
+        s.field(2, "node");
+        (node_type.ser_ron)(*node, 2, &mut s);
+        s.conl();
+        s.st_post(1);
+        s.conl();
+    }
+    s.out.push(']');
+
+    s.out
+}
+
+fn to_json(nodes: &[(&NodeType, &dyn Any)]) -> String {
+    let mut s = SerJsonState { out: String::new() };
+
+    s.out.push('[');
+    for (n, (node_type, node)) in nodes.iter().enumerate() {
+        if n != 0 {
+            s.conl();
+        }
+        s.st_pre();
+        s.field(1, "type");
+        node_type.name.ser_json(1, &mut s);
+        s.conl();
+        s.field(1, "node");
+        (node_type.ser_json)(*node, 1, &mut s);
+        s.st_post(0);
+    }
+    s.out.push(']');
+
+    s.out
+}
+
+fn from_ron(node_types: &[NodeType], data: &str) -> Result<Vec<LoadedNode>, DeRonErr> {
+    fn field(s: &mut DeRonState, i: &mut Chars, name: &str) -> Result<(), DeRonErr> {
+        if s.tok != DeRonTok::Ident || s.identbuf != name {
+            return Err(s.err_nf(name));
+        }
+        s.next_colon(i)
+    }
+
+    let mut s = DeRonState::default();
+    let i = &mut data.chars();
+    s.next(i);
+    s.next_tok(i)?;
+
+    let mut nodes = vec![];
+    s.block_open(i)?;
+    while s.tok != DeRonTok::BlockClose {
+        s.paren_open(i)?;
+        field(&mut s, i, "type")?;
+        let name = String::de_ron(&mut s, i)?;
+        s.eat_comma_paren(i)?;
+        let node_type = find_type(node_types, &name).map_err(|msg| DeRonErr {
+            msg,
+            line: s.line,
+            col: s.col,
+        })?;
+        field(&mut s, i, "node")?;
+        nodes.push((node_type.add, (node_type.de_ron)(&mut s, i)?));
+        s.eat_comma_paren(i)?;
+        s.paren_close(i)?;
+        s.eat_comma_block(i)?;
+    }
+    s.block_close(i)?;
+
+    Ok(nodes)
+}
+
+fn from_json(node_types: &[NodeType], data: &str) -> Result<Vec<LoadedNode>, DeJsonErr> {
+    fn field(s: &mut DeJsonState, i: &mut Chars, name: &str) -> Result<(), DeJsonErr> {
+        if s.tok != DeJsonTok::Str || s.strbuf != name {
+            return Err(s.err_nf(name));
+        }
+        s.next_colon(i)
+    }
+
+    let mut s = DeJsonState::default();
+    let i = &mut data.chars();
+    s.next(i);
+    s.next_tok(i)?;
+
+    let mut nodes = vec![];
+    s.block_open(i)?;
+    while s.tok != DeJsonTok::BlockClose {
+        s.curly_open(i)?;
+        field(&mut s, i, "type")?;
+        let name = String::de_json(&mut s, i)?;
+        s.eat_comma_curly(i)?;
+        let node_type = find_type(node_types, &name).map_err(|msg| DeJsonErr {
+            msg,
+            line: s.line,
+            col: s.col,
+        })?;
+        field(&mut s, i, "node")?;
+        nodes.push((node_type.add, (node_type.de_json)(&mut s, i)?));
+        s.eat_comma_curly(i)?;
+        s.curly_close(i)?;
+        s.eat_comma_block(i)?;
+    }
+    s.block_close(i)?;
+
+    Ok(nodes)
+}
+
+fn find_type<'a>(node_types: &'a [NodeType], name: &str) -> Result<&'a NodeType, String> {
+    node_types
+        .iter()
+        .find(|node_type| node_type.name == name)
+        .ok_or_else(|| format!("Unknown node type {:?}, see register_node_type", name))
+}
+
+#[test]
+fn scene_round_trip() {
+    #[derive(SerRon, DeRon, SerJson, DeJson, Debug, PartialEq)]
+    struct Crate {
+        pos: (f32, f32),
+        label: String,
+    }
+    impl Node for Crate {}
+
+    let node_types = [NodeType::new::<Crate>("crate")];
+    let crates = [
+        Crate {
+            pos: (1., 2.),
+            label: "first".to_string(),
+        },
+        Crate {
+            pos: (-3., 0.5),
+            label: "second".to_string(),
+        },
+    ];
+    let saved: Vec<(&NodeType, &dyn Any)> = crates
+        .iter()
+        .map(|node| (&node_types[0], node as &dyn Any))
+        .collect();
+
+    let ron = to_ron(&saved);
+    let json = to_json(&saved);
+    let loaded = [
+        from_ron(&node_types, &ron).unwrap(),
+        from_json(&node_types, &json).unwrap(),
+    ];
+    for nodes in &loaded {
+        let nodes: Vec<&Crate> = nodes
+            .iter()
+            .map(|(_, node)| node.downcast_ref().unwrap())
+            .collect();
+        assert_eq!(nodes, crates.iter().collect::<Vec<_>>());
+    }
+
+    let err = from_ron(&node_types, r#"[(type: "barrel", node: ())]"#).err();
+    assert!(err.unwrap().msg.contains("barrel"));
+    assert!(from_json(&node_types, "[]").unwrap().is_empty());
+}
@@ -0,0 +1,266 @@
+//! Timers on the game clock, for the cooldowns, the spawners and the
+//! delayed actions, without counting the frame times by hand.
+//!
+//! ```ignore
+//! let mut cooldown = Timer::new(0.5);
+//! let mut spawner = Timer::repeating(2.);
+//! loop {
+//!     if is_key_down(KeyCode::Space) && cooldown.finished() {
+//!         shoot();
+//!         cooldown.restart();
+//!     }
+//!     for _ in 0..spawner.ticks() {
+//!         spawn_enemy();
+//!     }
+//!     next_frame().await;
+//! }
+//! ```
+//!
+//! Timers go by [get_game_time], slowed down by [crate::time::set_time_scale]
+//! and stopped by [crate::time::pause_time], unless made [Timer::unscaled].
+//! [after] and [every] call a function instead, from a coroutine.
+
+use crate::experimental::coroutines::{start_coroutine, Coroutine};
+use crate::time::{get_game_time, get_time};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Clock {
+    Game,
+    Real,
+}
+
+impl Clock {
+    fn now(self) -> f64 {
+        match self {
+            Clock::Game => get_game_time(),
+            Clock::Real => get_time(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timer {
+    duration: f64,
+    repeating: bool,
+    clock: Clock,
+    /// Clock time of the start, moved forward by the pauses
+    start: f64,
+    /// Intervals already counted by [Timer::ticks]
+    counted: u64,
+    paused_at: Option<f64>,
+}
+
+impl Timer {
+    /// Timer finishing once, `duration` seconds from now.
+    pub fn new(duration: f32) -> Timer {
+        Timer::start(duration, false, Clock::Game)
+    }
+
+    /// Timer finishing every `interval` seconds from now on.
+    pub fn repeating(interval: f32) -> Timer {
+        assert!(interval > 0., "Repeating timer needs a positive interval");
+
+        Timer::start(interval, true, Clock::Game)
+    }
+
+    fn start(duration: f32, repeating: bool, clock: Clock) -> Timer {
+        Timer {
+            duration: duration.max(0.) as f64,
+            repeating,
+            clock,
+            start: clock.now(),
+            counted: 0,
+            paused_at: None,
+        }
+    }
+
+    /// Go by the real time instead of the game time, ignoring the time scale
+    /// and the pause, for the menus and the UI. Restarts the timer.
+    pub fn unscaled(self) -> Timer {
+        Timer::start(self.duration as f32, self.repeating, Clock::Real)
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration as f32
+    }
+
+    /// Change the duration, keeping the time already passed.
+    pub fn set_duration(&mut self, duration: f32) {
+        assert!(!self.repeating || duration > 0.);
+
+        self.duration = duration.max(0.) as f64;
+    }
+
+    pub fn is_repeating(&self) -> bool {
+        self.repeating
+    }
+
+    fn elapsed_at(&self, now: f64) -> f64 {
+        (self.paused_at.unwrap_or(now) - self.start).max(0.)
+    }
+
+    /// Intervals passed since the start, 0 or 1 for the one-shot timers
+    fn passed_at(&self, now: f64) -> u64 {
+        let elapsed = self.elapsed_at(now);
+
+        if self.repeating {
+            (elapsed / self.duration) as u64
+        } else {
+            (elapsed >= self.duration) as u64
+        }
+    }
+
+    /// Seconds since the start, or since the last interval of a repeating
+    /// timer. Stays at the duration once a one-shot timer is finished.
+    pub fn elapsed(&self) -> f32 {
+        let elapsed = self.elapsed_at(self.clock.now());
+
+        if self.repeating {
+            (elapsed % self.duration) as f32
+        } else {
+            elapsed.min(self.duration) as f32
+        }
+    }
+
+    /// Seconds left to the end, or to the next interval.
+    pub fn remaining(&self) -> f32 {
+        self.duration as f32 - self.elapsed()
+    }
+
+    /// Part of the duration passed, from 0 to 1, to draw cooldowns with.
+    pub fn progress(&self) -> f32 {
+        if self.duration == 0. {
+            return 1.;
+        }
+        self.elapsed() / self.duration as f32
+    }
+
+    /// A one-shot timer is finished once its duration passed and stays
+    /// finished until [Timer::restart].
+    ///
+    /// A repeating timer is finished when at least one interval passed
+    /// since the last check, use [Timer::ticks] to know how many.
+    pub fn finished(&mut self) -> bool {
+        if self.repeating {
+            self.ticks() > 0
+        } else {
+            self.passed_at(self.clock.now()) == 1
+        }
+    }
+
+    /// Intervals passed since the last call, more than one when the frame
+    /// was longer than the interval. A one-shot timer ticks once.
+    pub fn ticks(&mut self) -> u32 {
+        self.ticks_at(self.clock.now())
+    }
+
+    fn ticks_at(&mut self, now: f64) -> u32 {
+        let passed = self.passed_at(now);
+        let ticks = passed.saturating_sub(self.counted);
+
+        self.counted = passed;
+        ticks as u32
+    }
+
+    /// Start over from now, keeping the timer paused if it is.
+    pub fn restart(&mut self) {
+        let now = self.clock.now();
+
+        self.start = now;
+        self.counted = 0;
+        self.paused_at = self.paused_at.map(|_| now);
+    }
+
+    pub fn pause(&mut self) {
+        self.set_paused_at(true, self.clock.now());
+    }
+
+    pub fn resume(&mut self) {
+        self.set_paused_at(false, self.clock.now());
+    }
+
+    fn set_paused_at(&mut self, paused: bool, now: f64) {
+        match (self.paused_at, paused) {
+            (None, true) => self.paused_at = Some(now),
+            (Some(paused_at), false) => {
+                self.start += now - paused_at;
+                self.paused_at = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Wait in a coroutine until [Timer::finished].
+    pub async fn wait(&mut self) {
+        while !self.finished() {
+            crate::window::next_frame().await;
+        }
+    }
+}
+
+/// Call `f` once, `seconds` of game time from now.
+///
+/// Stop the returned coroutine to cancel the call.
+pub fn after(seconds: f32, f: impl FnOnce() + Send + 'static) -> Coroutine {
+    let mut timer = Timer::new(seconds);
+
+    start_coroutine(async move {
+        timer.wait().await;
+        f();
+    })
+}
+
+/// Call `f` every `interval` seconds of game time, until the returned
+/// coroutine is stopped.
+///
+/// ```ignore
+/// let spawner = every(2., || spawn_enemy());
+/// // the boss fight
+/// spawner.stop();
+/// ```
+pub fn every(interval: f32, mut f: impl FnMut() + Send + 'static) -> Coroutine {
+    let mut timer = Timer::repeating(interval);
+
+    start_coroutine(async move {
+        loop {
+            for _ in 0..timer.ticks() {
+                f();
+            }
+            crate::window::next_frame().await;
+        }
+    })
+}
+
+#[test]
+fn timer_ticks_and_pause() {
+    let timer = |duration, repeating| Timer {
+        duration,
+        repeating,
+        clock: Clock::Game,
+        start: 0.,
+        counted: 0,
+        paused_at: None,
+    };
+
+    let mut cooldown = timer(0.5, false);
+    assert_eq!(cooldown.passed_at(0.4), 0);
+    assert_eq!(cooldown.ticks_at(0.6), 1);
+    assert_eq!(cooldown.ticks_at(0.7), 0);
+    assert_eq!(cooldown.passed_at(10.), 1);
+
+    let mut spawner = timer(1., true);
+    assert_eq!(spawner.ticks_at(0.5), 0);
+    assert_eq!(spawner.ticks_at(1.5), 1);
+    // a long frame
+    assert_eq!(spawner.ticks_at(4.2), 3);
+
+    spawner.set_paused_at(true, 4.5);
+    assert_eq!(spawner.ticks_at(9.), 0);
+    spawner.set_paused_at(false, 9.);
+    assert_eq!(spawner.elapsed_at(9.), 4.5);
+    assert_eq!(spawner.ticks_at(9.6), 1);
+}
@@ -0,0 +1,572 @@
+//! Values changing over time: positions sliding in, colors fading, cameras
+//! zooming..
+//!
+//! A [Tween] is a start value followed by segments, each moving to a new
+//! value in some time with some easing, and pauses. Update it by hand or
+//! [Tween::start] it to have it updated by macroquad every frame.
+//!
+//! ```ignore
+//! let alpha = Tween::new(0.)
+//!     .delay(0.5)
+//!     .to(1., 0.3)
+//!     .ease(easing::cubic_out)
+//!     .delay(2.)
+//!     .to(0., 0.3)
+//!     .start();
+//!
+//! loop {
+//!     draw_text("Level 1", 20., 40., 30., Color::new(1., 1., 1., alpha.value()));
+//!     next_frame().await;
+//! }
+//! ```
+//!
+//! Tweens follow the game time: [crate::time::set_time_scale] and
+//! [crate::time::pause_time] slow down or stop them.
+
+use crate::{
+    color::Color,
+    math::{Mat4, Quat, Vec2, Vec3, Vec4},
+};
+
+use std::sync::{Arc, Mutex, Weak};
+
+/// Easing functions, mapping the linear progress 0..1 to the interpolation
+/// factor. All of them start at 0 and end at 1, `back` and `elastic` go
+/// outside of 0..1 in between.
+///
+/// See <https://easings.net> for the graphs.
+pub mod easing {
+    use std::f32::consts::PI;
+
+    pub fn linear(t: f32) -> f32 {
+        t
+    }
+
+    pub fn quad_in(t: f32) -> f32 {
+        t * t
+    }
+
+    pub fn quad_out(t: f32) -> f32 {
+        1. - quad_in(1. - t)
+    }
+
+    pub fn quad_in_out(t: f32) -> f32 {
+        in_out(t, quad_in)
+    }
+
+    pub fn cubic_in(t: f32) -> f32 {
+        t * t * t
+    }
+
+    pub fn cubic_out(t: f32) -> f32 {
+        1. - cubic_in(1. - t)
+    }
+
+    pub fn cubic_in_out(t: f32) -> f32 {
+        in_out(t, cubic_in)
+    }
+
+    pub fn quart_in(t: f32) -> f32 {
+        t * t * t * t
+    }
+
+    pub fn quart_out(t: f32) -> f32 {
+        1. - quart_in(1. - t)
+    }
+
+    pub fn quart_in_out(t: f32) -> f32 {
+        in_out(t, quart_in)
+    }
+
+    pub fn quint_in(t: f32) -> f32 {
+        t * t * t * t * t
+    }
+
+    pub fn quint_out(t: f32) -> f32 {
+        1. - quint_in(1. - t)
+    }
+
+    pub fn quint_in_out(t: f32) -> f32 {
+        in_out(t, quint_in)
+    }
+
+    pub fn sine_in(t: f32) -> f32 {
+        1. - (t * PI / 2.).cos()
+    }
+
+    pub fn sine_out(t: f32) -> f32 {
+        (t * PI / 2.).sin()
+    }
+
+    pub fn sine_in_out(t: f32) -> f32 {
+        (1. - (t * PI).cos()) / 2.
+    }
+
+    pub fn expo_in(t: f32) -> f32 {
+        if t <= 0. {
+            0.
+        } else {
+            2f32.powf(10. * t - 10.)
+        }
+    }
+
+    pub fn expo_out(t: f32) -> f32 {
+        1. - expo_in(1. - t)
+    }
+
+    pub fn expo_in_out(t: f32) -> f32 {
+        in_out(t, expo_in)
+    }
+
+    pub fn circ_in(t: f32) -> f32 {
+        1. - (1. - t * t).max(0.).sqrt()
+    }
+
+    pub fn circ_out(t: f32) -> f32 {
+        1. - circ_in(1. - t)
+    }
+
+    pub fn circ_in_out(t: f32) -> f32 {
+        in_out(t, circ_in)
+    }
+
+    /// Goes back a bit before moving forward.
+    pub fn back_in(t: f32) -> f32 {
+        const OVERSHOOT: f32 = 1.70158;
+
+        t * t * ((OVERSHOOT + 1.) * t - OVERSHOOT)
+    }
+
+    /// Overshoots the target and comes back.
+    pub fn back_out(t: f32) -> f32 {
+        1. - back_in(1. - t)
+    }
+
+    pub fn back_in_out(t: f32) -> f32 {
+        in_out(t, back_in)
+    }
+
+    pub fn elastic_in(t: f32) -> f32 {
+        1. - elastic_out(1. - t)
+    }
+
+    /// Springs around the target.
+    pub fn elastic_out(t: f32) -> f32 {
+        if t <= 0. {
+            0.
+        } else if t >= 1. {
+            1.
+        } else {
+            2f32.powf(-10. * t) * ((t * 10. - 0.75) * (2. * PI / 3.)).sin() + 1.
+        }
+    }
+
+    pub fn elastic_in_out(t: f32) -> f32 {
+        in_out(t, elastic_in)
+    }
+
+    pub fn bounce_in(t: f32) -> f32 {
+        1. - bounce_out(1. - t)
+    }
+
+    /// Bounces on the target like a dropped ball.
+    pub fn bounce_out(t: f32) -> f32 {
+        const N: f32 = 7.5625;
+        const D: f32 = 2.75;
+
+        if t < 1. / D {
+            N * t * t
+        } else if t < 2. / D {
+            let t = t - 1.5 / D;
+            N * t * t + 0.75
+        } else if t < 2.5 / D {
+            let t = t - 2.25 / D;
+            N * t * t + 0.9375
+        } else {
+            let t = t - 2.625 / D;
+            N * t * t + 0.984375
+        }
+    }
+
+    pub fn bounce_in_out(t: f32) -> f32 {
+        in_out(t, bounce_in)
+    }
+
+    /// `ease_in` for the first half and mirrored for the second
+    fn in_out(t: f32, ease_in: fn(f32) -> f32) -> f32 {
+        if t < 0.5 {
+            ease_in(t * 2.) / 2.
+        } else {
+            1. - ease_in((1. - t) * 2.) / 2.
+        }
+    }
+}
+
+/// Values a [Tween] can animate.
+///
+/// Tuples of tweenable values are tweenable, `(Vec3, Quat, Vec3)` works for
+/// translation, rotation and scale.
+pub trait Tweenable: Copy + Send + 'static {
+    /// Value in between `self` (t = 0) and `other` (t = 1), `t` may go a bit
+    /// outside of 0..1 with the overshooting easings.
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn lerp(self, other: f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Tweenable for Vec2 {
+    fn lerp(self, other: Vec2, t: f32) -> Vec2 {
+        Vec2::lerp(self, other, t)
+    }
+}
+
+impl Tweenable for Vec3 {
+    fn lerp(self, other: Vec3, t: f32) -> Vec3 {
+        Vec3::lerp(self, other, t)
+    }
+}
+
+impl Tweenable for Vec4 {
+    fn lerp(self, other: Vec4, t: f32) -> Vec4 {
+        Vec4::lerp(self, other, t)
+    }
+}
+
+impl Tweenable for Color {
+    fn lerp(self, other: Color, t: f32) -> Color {
+        Color::new(
+            self.r.lerp(other.r, t),
+            self.g.lerp(other.g, t),
+            self.b.lerp(other.b, t),
+            self.a.lerp(other.a, t),
+        )
+    }
+}
+
+/// Along the shortest arc.
+impl Tweenable for Quat {
+    fn lerp(self, other: Quat, t: f32) -> Quat {
+        self.slerp(other, t)
+    }
+}
+
+/// Scale, rotation and translation are tweened separately, so rotating
+/// transforms do not shrink on the way.
+impl Tweenable for Mat4 {
+    fn lerp(self, other: Mat4, t: f32) -> Mat4 {
+        let a = self.to_scale_rotation_translation();
+        let b = other.to_scale_rotation_translation();
+        let (scale, rotation, translation) = a.lerp(b, t);
+
+        Mat4::from_scale_rotation_translation(scale, rotation, translation)
+    }
+}
+
+impl<A: Tweenable, B: Tweenable> Tweenable for (A, B) {
+    fn lerp(self, other: (A, B), t: f32) -> (A, B) {
+        (self.0.lerp(other.0, t), self.1.lerp(other.1, t))
+    }
+}
+
+impl<A: Tweenable, B: Tweenable, C: Tweenable> Tweenable for (A, B, C) {
+    fn lerp(self, other: (A, B, C), t: f32) -> (A, B, C) {
+        (
+            self.0.lerp(other.0, t),
+            self.1.lerp(other.1, t),
+            self.2.lerp(other.2, t),
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Segment<T> {
+    to: T,
+    duration: f32,
+    easing: fn(f32) -> f32,
+}
+
+/// Start value and a sequence of moves and pauses, see the [module docs](self).
+#[derive(Clone)]
+pub struct Tween<T: Tweenable> {
+    from: T,
+    segments: Vec<Segment<T>>,
+    looping: bool,
+    elapsed: f32,
+}
+
+impl<T: Tweenable> Tween<T> {
+    pub fn new(from: T) -> Tween<T> {
+        Tween {
+            from,
+            segments: vec![],
+            looping: false,
+            elapsed: 0.,
+        }
+    }
+
+    /// Move to `target` in `duration` seconds, linearly unless followed by
+    /// [Tween::ease].
+    pub fn to(mut self, target: T, duration: f32) -> Tween<T> {
+        self.segments.push(Segment {
+            to: target,
+            duration: duration.max(0.),
+            easing: easing::linear,
+        });
+        self
+    }
+
+    /// Easing of the last [Tween::to].
+    pub fn ease(mut self, easing: fn(f32) -> f32) -> Tween<T> {
+        if let Some(segment) = self.segments.last_mut() {
+            segment.easing = easing;
+        }
+        self
+    }
+
+    /// Keep the current value for `seconds`, before everything else when
+    /// used first.
+    pub fn delay(self, seconds: f32) -> Tween<T> {
+        let target = self.target();
+        self.to(target, seconds)
+    }
+
+    /// Start over from the beginning after the end, forever.
+    pub fn looping(self) -> Tween<T> {
+        Tween {
+            looping: true,
+            ..self
+        }
+    }
+
+    /// Seconds from the start to the end, without the looping.
+    pub fn duration(&self) -> f32 {
+        self.segments.iter().map(|segment| segment.duration).sum()
+    }
+
+    /// Final value.
+    pub fn target(&self) -> T {
+        self.segments.last().map_or(self.from, |segment| segment.to)
+    }
+
+    /// Advance by `delta` seconds.
+    pub fn update(&mut self, delta: f32) {
+        self.elapsed += delta;
+
+        let duration = self.duration();
+        if self.looping && duration > 0. {
+            self.elapsed %= duration;
+        } else {
+            self.elapsed = self.elapsed.min(duration);
+        }
+    }
+
+    pub fn value(&self) -> T {
+        let mut from = self.from;
+        let mut time = self.elapsed;
+
+        for segment in &self.segments {
+            if time < segment.duration {
+                return from.lerp(segment.to, (segment.easing)(time / segment.duration));
+            }
+            time -= segment.duration;
+            from = segment.to;
+        }
+
+        from
+    }
+
+    /// Never true for looping tweens.
+    pub fn is_done(&self) -> bool {
+        !self.looping && self.elapsed >= self.duration()
+    }
+
+    /// Back to the start value.
+    pub fn restart(&mut self) {
+        self.elapsed = 0.;
+    }
+
+    /// Have the tween updated every frame.
+    pub fn start(self) -> TweenHandle<T> {
+        let tween = Arc::new(Mutex::new(Running {
+            tween: self,
+            paused: false,
+        }));
+        let updater: Arc<Mutex<dyn Update>> = tween.clone();
+        crate::get_context()
+            .tweens
+            .tweens
+            .push(Arc::downgrade(&updater));
+
+        TweenHandle { tween }
+    }
+}
+
+/// Move from `from` to `to` in `duration` seconds with `easing`, updating
+/// every frame.
+pub fn tween<T: Tweenable>(
+    from: T,
+    to: T,
+    duration: f32,
+    easing: fn(f32) -> f32,
+) -> TweenHandle<T> {
+    Tween::new(from).to(to, duration).ease(easing).start()
+}
+
+struct Running<T: Tweenable> {
+    tween: Tween<T>,
+    paused: bool,
+}
+
+trait Update: Send {
+    fn update(&mut self, delta: f32);
+}
+
+impl<T: Tweenable> Update for Running<T> {
+    fn update(&mut self, delta: f32) {
+        if !self.paused {
+            self.tween.update(delta);
+        }
+    }
+}
+
+/// A started [Tween], stops when the last handle is dropped.
+///
+/// Handles can be moved into coroutines, to wait for the tween there.
+#[derive(Clone)]
+pub struct TweenHandle<T: Tweenable> {
+    tween: Arc<Mutex<Running<T>>>,
+}
+
+impl<T: Tweenable> TweenHandle<T> {
+    pub fn value(&self) -> T {
+        self.tween.lock().unwrap().tween.value()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.tween.lock().unwrap().tween.is_done()
+    }
+
+    pub fn pause(&self) {
+        self.tween.lock().unwrap().paused = true;
+    }
+
+    pub fn resume(&self) {
+        self.tween.lock().unwrap().paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.tween.lock().unwrap().paused
+    }
+
+    pub fn restart(&self) {
+        self.tween.lock().unwrap().tween.restart();
+    }
+
+    /// Wait for the tween to end, forever for the looping ones.
+    /// ```ignore
+    /// let slide = tween(vec2(-100., 50.), vec2(20., 50.), 0.5, easing::back_out);
+    /// slide.finished().await;
+    /// show_menu().await;
+    /// ```
+    pub async fn finished(&self) {
+        while !self.is_done() {
+            crate::window::next_frame().await;
+        }
+    }
+}
+
+type WeakTween = Weak<Mutex<dyn Update>>;
+
+/// Started tweens, updated at the beginning of every frame.
+#[derive(Default)]
+pub(crate) struct TweensContext {
+    tweens: Vec<WeakTween>,
+}
+
+impl TweensContext {
+    pub(crate) fn update(&mut self, delta: f32) {
+        self.tweens.retain(|tween| match tween.upgrade() {
+            Some(tween) => {
+                // done tweens stay until the last handle drops, they may be
+                // restarted
+                tween.lock().unwrap().update(delta);
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+#[test]
+fn tween_sequence() {
+    let mut tween = Tween::new(0.)
+        .delay(1.)
+        .to(10., 2.)
+        .to(0., 1.)
+        .ease(easing::quad_in);
+    assert_eq!(tween.duration(), 4.);
+
+    let mut at = |time: f32| {
+        tween.restart();
+        tween.update(time);
+        tween.value()
+    };
+    assert_eq!(at(0.5), 0.);
+    assert_eq!(at(2.), 5.);
+    assert_eq!(at(3.), 10.);
+    assert_eq!(at(3.5), 7.5);
+    assert_eq!(at(10.), 0.);
+    assert!(tween.is_done());
+
+    let mut looping = Tween::new(0.).to(1., 1.).looping();
+    looping.update(2.25);
+    assert_eq!(looping.value(), 0.25);
+    assert!(!looping.is_done());
+}
+
+#[test]
+fn easing_ends() {
+    use easing::*;
+
+    let easings: &[fn(f32) -> f32] = &[
+        linear,
+        quad_in,
+        quad_out,
+        quad_in_out,
+        cubic_in,
+        cubic_out,
+        cubic_in_out,
+        quart_in,
+        quart_out,
+        quart_in_out,
+        quint_in,
+        quint_out,
+        quint_in_out,
+        sine_in,
+        sine_out,
+        sine_in_out,
+        expo_in,
+        expo_out,
+        expo_in_out,
+        circ_in,
+        circ_out,
+        circ_in_out,
+        back_in,
+        back_out,
+        back_in_out,
+        elastic_in,
+        elastic_out,
+        elastic_in_out,
+        bounce_in,
+        bounce_out,
+        bounce_in_out,
+    ];
+    for easing in easings {
+        assert!(easing(0.).abs() < 0.001);
+        assert!((easing(1.) - 1.).abs() < 0.001);
+    }
+}
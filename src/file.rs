@@ -2,6 +2,41 @@
 
 use crate::exec;
 
+mod compression;
+mod dialog;
+#[cfg(any(feature = "http", target_arch = "wasm32"))]
+mod http;
+mod vfs;
+mod watcher;
+
+pub use dialog::{message_box, open_file_dialog, save_file_dialog, FileFilter, PickedFile};
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub(crate) use http::is_url;
+#[cfg(any(feature = "http", target_arch = "wasm32"))]
+pub use http::{fetch, fetch_with_timeout, HttpError, DEFAULT_TIMEOUT};
+pub use vfs::{
+    list_files, load_file_manifest, mount, set_file_manifest, unmount_all, Archive, Directory,
+    EmbeddedAssets, ZipArchive, ZipError,
+};
+
+/// Embed a directory into the binary, for single file builds and web builds
+/// without an assets folder next to the page.
+///
+/// The path is relative to the crate root, the files are mounted relative to
+/// the directory, the way `set_pc_assets_folder` would read them:
+/// ```ignore
+/// mount(include_assets!("assets"), 0);
+/// // assets/textures/player.png, from the binary
+/// let texture = load_texture("textures/player.png").await.unwrap();
+/// ```
+/// Changed files are embedded again on the next build, new files only once
+/// the code calling `include_assets!` is rebuilt.
+pub use macroquad_macro::include_assets;
+pub use watcher::watch_file;
+
+pub(crate) use vfs::Vfs;
+pub(crate) use watcher::AssetWatcher;
+
 #[derive(Debug)]
 pub struct FileError {
     pub kind: miniquad::fs::Error,
@@ -25,7 +60,43 @@ impl FileError {
 
 /// Load file from the path and block until its loaded
 /// Will use filesystem on PC and do http request on web
+///
+/// The archives added with [mount] are read first.
+///
+/// With the "http" feature, urls starting with `http://` or `https://` are
+/// downloaded on PC as well, see [fetch].
+///
+/// # Compressed files
+///
+/// Gzip files are unpacked, as well as zstd files with the "zstd" feature,
+/// whatever their extension: any file starting with the gzip or zstd magic
+/// bytes is. Use [load_raw_file] to get the bytes as they are, like for
+/// `.gz` files the game hands over somewhere else.
 pub async fn load_file(path: &str) -> Result<Vec<u8>, FileError> {
+    let data = load_raw_file(path).await?;
+
+    decompress(data).map_err(|err| FileError::new(miniquad::fs::Error::IOError(err), path))
+}
+
+pub(crate) fn decompress(data: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    compression::decompress(data)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+}
+
+/// [load_file] without unpacking the compressed files.
+pub async fn load_raw_file(path: &str) -> Result<Vec<u8>, FileError> {
+    if let Some(bytes) = crate::get_context().vfs.read(path) {
+        return Ok(bytes);
+    }
+
+    #[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+    if http::is_url(path) {
+        return fetch(path).await.map_err(|err| {
+            crate::logging::warn!("{}: {}", path, err);
+            FileError::new(miniquad::fs::Error::DownloadFailed, path)
+        });
+    }
+
     fn load_file_inner(path: &str) -> exec::FileLoadingFuture {
         use std::sync::{Arc, Mutex};
 
@@ -48,14 +119,17 @@ pub async fn load_file(path: &str) -> Result<Vec<u8>, FileError> {
     #[cfg(target_os = "ios")]
     let _ = std::env::set_current_dir(std::env::current_exe().unwrap().parent().unwrap());
 
+    load_file_inner(&assets_path(path)).await
+}
+
+/// Path the file will actually be loaded from, with `set_pc_assets_folder` applied.
+pub(crate) fn assets_path(path: &str) -> String {
     #[cfg(not(target_os = "android"))]
-    let path = if let Some(ref pc_assets) = crate::get_context().pc_assets_folder {
-        format!("{}/{}", pc_assets, path)
-    } else {
-        path.to_string()
-    };
+    if let Some(ref pc_assets) = crate::get_context().pc_assets_folder {
+        return format!("{}/{}", pc_assets, path);
+    }
 
-    load_file_inner(&path).await
+    path.to_string()
 }
 
 /// Load string from the path and block until its loaded.
@@ -67,6 +141,109 @@ pub async fn load_string(path: &str) -> Result<String, FileError> {
     Ok(String::from_utf8_lossy(&data).to_string())
 }
 
+/// Where [store_file] writes `path`: relative to the working directory on PC,
+/// in the app storage on mobile. Paths going out of it are rejected.
+#[cfg(not(target_arch = "wasm32"))]
+fn writable_path(path: &str) -> Result<std::path::PathBuf, FileError> {
+    use std::path::{Component, Path};
+
+    let relative = Path::new(path);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        let err = std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Only relative paths inside the app folder can be written",
+        );
+        return Err(FileError::new(miniquad::fs::Error::IOError(err), path));
+    }
+
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    let relative = crate::storage::storage_dir()
+        .ok_or_else(|| {
+            let err = std::io::Error::new(std::io::ErrorKind::NotFound, "No app storage");
+            FileError::new(miniquad::fs::Error::IOError(err), path)
+        })?
+        .join(relative);
+
+    Ok(relative.to_path_buf())
+}
+
+/// Write `data` to the file at `path`, replacing it.
+///
+/// Paths are relative to the working directory on PC, and to the app storage
+/// on Android and iOS. On web the file is downloaded by the browser, under
+/// the file name of `path` (with `js/file_dialog.js` loaded).
+/// ```ignore
+/// store_file("levels/custom.ron", level.serialize_ron().as_bytes()).unwrap();
+/// append_file("log.txt", b"level saved\n").unwrap();
+/// ```
+pub fn store_file(path: &str, data: &[u8]) -> Result<(), FileError> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        dialog::web::save(name, data);
+        Ok(())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let io_error = |err| FileError::new(miniquad::fs::Error::IOError(err), path);
+        let file = writable_path(path)?;
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        std::fs::write(file, data).map_err(io_error)
+    }
+}
+
+/// Add `data` at the end of the file at `path`, creating it if needed, for logs.
+///
+/// Not supported on web, browsers only download whole files.
+pub fn append_file(path: &str, data: &[u8]) -> Result<(), FileError> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = data;
+        let err = std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Files can not be appended to on web",
+        );
+        Err(FileError::new(miniquad::fs::Error::IOError(err), path))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::io::Write;
+
+        let io_error = |err| FileError::new(miniquad::fs::Error::IOError(err), path);
+        let file = writable_path(path)?;
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)
+            .and_then(|mut file| file.write_all(data))
+            .map_err(io_error)
+    }
+}
+
+/// Delete the file at `path` written with [store_file] or [append_file].
+///
+/// Does nothing on web, the downloads belong to the user.
+pub fn delete_file(path: &str) -> Result<(), FileError> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = path;
+        Ok(())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::remove_file(writable_path(path)?)
+            .map_err(|err| FileError::new(miniquad::fs::Error::IOError(err), path))
+    }
+}
+
 /// There are super common project layout like this:
 /// ```skip
 ///    .
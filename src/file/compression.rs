@@ -0,0 +1,85 @@
+//! Gzip and zstd compressed files, unpacked by `load_file`.
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b, 8];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Decompress `data` if it starts like a gzip or zstd stream, returns it as is otherwise.
+///
+/// Zstd needs the "zstd" feature, without it zstd files fail to load.
+pub(crate) fn decompress(data: Vec<u8>) -> Result<Vec<u8>, String> {
+    if data.starts_with(GZIP_MAGIC) {
+        gunzip(&data)
+    } else if data.starts_with(ZSTD_MAGIC) {
+        unzstd(&data)
+    } else {
+        Ok(data)
+    }
+}
+
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, String> {
+    const FHCRC: u8 = 2;
+    const FEXTRA: u8 = 4;
+    const FNAME: u8 = 8;
+    const FCOMMENT: u8 = 16;
+
+    let truncated = || "Truncated gzip file".to_string();
+    let flags = *data.get(3).ok_or_else(truncated)?;
+    let mut offset = 10;
+    if flags & FEXTRA != 0 {
+        let len = data.get(offset..offset + 2).ok_or_else(truncated)?;
+        offset += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+    // zero terminated strings
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let len = data
+                .get(offset..)
+                .and_then(|rest| rest.iter().position(|byte| *byte == 0))
+                .ok_or_else(truncated)?;
+            offset += len + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        offset += 2;
+    }
+
+    let deflated = data
+        .get(offset..data.len().saturating_sub(8))
+        .ok_or_else(truncated)?;
+    miniz_oxide::inflate::decompress_to_vec(deflated)
+        .map_err(|err| format!("Corrupted gzip file: {:?}", err.status))
+}
+
+#[cfg(feature = "zstd")]
+fn unzstd(data: &[u8]) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+
+    let mut decoder = ruzstd::StreamingDecoder::new(data)
+        .map_err(|err| format!("Corrupted zstd file: {}", err))?;
+    let mut decompressed = vec![];
+    decoder
+        .read_to_end(&mut decompressed)
+        .map_err(|err| format!("Corrupted zstd file: {}", err))?;
+
+    Ok(decompressed)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn unzstd(_data: &[u8]) -> Result<Vec<u8>, String> {
+    Err("zstd compressed file, enable the \"zstd\" feature to load it".to_string())
+}
+
+#[test]
+fn decompress_gzip() {
+    let text = b"level data level data level data";
+    let mut gzip = vec![0x1f, 0x8b, 8, 8, 0, 0, 0, 0, 0, 3];
+    gzip.extend(b"level.txt\0");
+    gzip.extend(miniz_oxide::deflate::compress_to_vec(text, 6));
+    // crc32 and size, not checked
+    gzip.extend([0; 4]);
+    gzip.extend((text.len() as u32).to_le_bytes());
+
+    assert_eq!(decompress(gzip).unwrap(), text);
+    assert_eq!(decompress(text.to_vec()).unwrap(), text);
+    assert!(decompress(vec![0x1f, 0x8b, 8]).is_err());
+}
@@ -0,0 +1,517 @@
+//! Native open and save file dialogs, and message boxes.
+//!
+//! The dialog does not block the frame, the future is pending until
+//! the user picked a file.
+
+use crate::file::FileError;
+
+/// File types a dialog lets to pick,
+/// like `FileFilter { name: "Images", extensions: &["png", "jpg"] }`.
+#[derive(Debug, Clone, Copy)]
+pub struct FileFilter<'a> {
+    pub name: &'a str,
+    pub extensions: &'a [&'a str],
+}
+
+/// File picked with [open_file_dialog].
+#[derive(Debug, Clone)]
+pub struct PickedFile {
+    /// File name, without the directories
+    pub name: String,
+    /// Full path, None on web where the page can't see the file system
+    pub path: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Ask the user for a file to open and read it.
+///
+/// Uses zenity or kdialog on linux, AppleScript on macos, Windows Forms
+/// on windows and a file input on web, which needs `js/file_dialog.js`
+/// loaded after `mq_js_bundle.js`.
+/// Returns `Ok(None)` when the dialog was cancelled.
+///
+/// ```ignore
+/// let images = FileFilter { name: "Images", extensions: &["png"] };
+/// if let Some(file) = open_file_dialog(&[images]).await? {
+///     let texture = Texture2D::from_file_with_format(&file.data, None);
+/// }
+/// ```
+pub async fn open_file_dialog(filters: &[FileFilter<'_>]) -> Result<Option<PickedFile>, FileError> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        web::open(filters).await
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = match native::pick(filters, None).await? {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let data = std::fs::read(&path)
+            .map_err(|err| FileError::new(miniquad::fs::Error::IOError(err), &path))?;
+        let name = std::path::Path::new(&path)
+            .file_name()
+            .map_or_else(|| path.clone(), |name| name.to_string_lossy().into_owned());
+
+        Ok(Some(PickedFile {
+            name,
+            path: Some(path),
+            data,
+        }))
+    }
+}
+
+/// Ask the user where to save `data` and write it there.
+///
+/// Returns the path, or `Ok(None)` when the dialog was cancelled.
+/// On web the data is downloaded as `default_name`, which is returned.
+pub async fn save_file_dialog(
+    default_name: &str,
+    filters: &[FileFilter<'_>],
+    data: &[u8],
+) -> Result<Option<String>, FileError> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = filters;
+        web::save(default_name, data);
+        Ok(Some(default_name.to_string()))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = match native::pick(filters, Some(default_name)).await? {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        std::fs::write(&path, data)
+            .map_err(|err| FileError::new(miniquad::fs::Error::IOError(err), &path))?;
+
+        Ok(Some(path))
+    }
+}
+
+/// Show a native message box with up to 3 `buttons`, returns the index of
+/// the pressed one, or `Ok(None)` when the box was closed.
+///
+/// Uses zenity or kdialog on linux, AppleScript on macos and Windows Forms on
+/// windows. Windows has no custom labels: the buttons are OK, OK/Cancel or
+/// Yes/No/Cancel, in this order. Not supported on web and mobile.
+///
+/// ```ignore
+/// let quit = message_box("Quit", "Unsaved progress will be lost", &["Quit", "Stay"]).await?;
+/// if quit == Some(0) {
+///     return;
+/// }
+/// ```
+pub async fn message_box(
+    title: &str,
+    text: &str,
+    buttons: &[&str],
+) -> Result<Option<usize>, FileError> {
+    assert!(
+        (1..=3).contains(&buttons.len()),
+        "Message box needs 1 to 3 buttons"
+    );
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (title, text);
+        let err = std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "message boxes are not supported on web",
+        );
+        Err(FileError::new(
+            miniquad::fs::Error::IOError(err),
+            "message box",
+        ))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        native::message_box(title, text, buttons).await
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use super::FileFilter;
+    use crate::file::FileError;
+
+    use std::process::{Command, Output};
+
+    /// Path chosen in an open dialog, or a save dialog with the `save` default name.
+    pub(super) async fn pick(
+        filters: &[FileFilter<'_>],
+        save: Option<&str>,
+    ) -> Result<Option<String>, FileError> {
+        let commands = commands(filters, save);
+
+        spawn(move || {
+            run(commands, |_, output| {
+                // non zero exit code for cancelled dialogs
+                let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                Some(path).filter(|path| output.status.success() && !path.is_empty())
+            })
+        })
+        .await
+    }
+
+    pub(super) async fn message_box(
+        title: &str,
+        text: &str,
+        buttons: &[&str],
+    ) -> Result<Option<usize>, FileError> {
+        let commands = message_box_commands(title, text, buttons);
+        let buttons = buttons
+            .iter()
+            .map(|button| button.to_string())
+            .collect::<Vec<_>>();
+
+        spawn(move || {
+            run(commands, |program, output| {
+                pressed(program, output, &buttons)
+            })
+        })
+        .await
+    }
+
+    /// Run `f` on a thread, for the dialogs not to block the frame.
+    async fn spawn<T: Send + 'static>(
+        f: impl FnOnce() -> Result<T, (String, std::io::Error)> + Send + 'static,
+    ) -> Result<T, FileError> {
+        crate::exec::ThreadFuture::spawn(f)
+            .await
+            .unwrap_or_else(|| {
+                let err = std::io::Error::new(
+                    std::io::ErrorKind::Interrupted,
+                    "the dialog thread panicked",
+                );
+                Err(("file dialog".to_string(), err))
+            })
+            .map_err(|(program, err)| FileError::new(miniquad::fs::Error::IOError(err), &program))
+    }
+
+    /// Run the first of the dialog programs installed, `parse` reads its output.
+    fn run<T>(
+        commands: Vec<Command>,
+        parse: impl Fn(&str, &Output) -> T,
+    ) -> Result<T, (String, std::io::Error)> {
+        let mut not_found = None;
+
+        for mut command in commands {
+            let program = command.get_program().to_string_lossy().into_owned();
+            match command.output() {
+                Ok(output) => return Ok(parse(&program, &output)),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    not_found = Some((program, err));
+                }
+                Err(err) => return Err((program, err)),
+            }
+        }
+
+        Err(not_found.unwrap_or_else(|| {
+            (
+                "file dialog".to_string(),
+                std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "file dialogs are not supported on this platform",
+                ),
+            )
+        }))
+    }
+
+    #[cfg(target_os = "windows")]
+    fn commands(filters: &[FileFilter<'_>], save: Option<&str>) -> Vec<Command> {
+        let filter = filters
+            .iter()
+            .map(|filter| {
+                let patterns = filter
+                    .extensions
+                    .iter()
+                    .map(|extension| format!("*.{}", extension))
+                    .collect::<Vec<_>>()
+                    .join(";");
+                format!("{} ({})|{}", filter.name, patterns, patterns)
+            })
+            .chain(std::iter::once("All files (*.*)|*.*".to_string()))
+            .collect::<Vec<_>>()
+            .join("|");
+        let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+
+        let (dialog, file_name) = match save {
+            Some(name) => ("SaveFileDialog", format!("$d.FileName = {};", quote(name))),
+            None => ("OpenFileDialog", String::new()),
+        };
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $d = New-Object System.Windows.Forms.{}; $d.Filter = {}; {} \
+             if ($d.ShowDialog() -eq 'OK') {{ $d.FileName }}",
+            dialog,
+            quote(&filter),
+            file_name
+        );
+
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command", script.as_str()]);
+        vec![command]
+    }
+
+    #[cfg(target_os = "macos")]
+    fn commands(filters: &[FileFilter<'_>], save: Option<&str>) -> Vec<Command> {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let script = match save {
+            Some(name) => format!(
+                "POSIX path of (choose file name default name {})",
+                quote(name)
+            ),
+            None if filters.is_empty() => "POSIX path of (choose file)".to_string(),
+            None => {
+                let types = filters
+                    .iter()
+                    .flat_map(|filter| filter.extensions.iter())
+                    .map(|extension| quote(extension))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("POSIX path of (choose file of type {{{}}})", types)
+            }
+        };
+
+        let mut command = Command::new("osascript");
+        command.args(["-e", script.as_str()]);
+        vec![command]
+    }
+
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    fn commands(_filters: &[FileFilter<'_>], _save: Option<&str>) -> Vec<Command> {
+        vec![]
+    }
+
+    #[cfg(not(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "android",
+        target_os = "ios"
+    )))]
+    fn commands(filters: &[FileFilter<'_>], save: Option<&str>) -> Vec<Command> {
+        let patterns = |filter: &FileFilter| {
+            filter
+                .extensions
+                .iter()
+                .map(|extension| format!("*.{}", extension))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut zenity = Command::new("zenity");
+        zenity.arg("--file-selection");
+        if let Some(name) = save {
+            zenity.args(["--save", "--confirm-overwrite"]);
+            zenity.arg(format!("--filename={}", name));
+        }
+        for filter in filters {
+            zenity.arg(format!(
+                "--file-filter={} | {}",
+                filter.name,
+                patterns(filter)
+            ));
+        }
+
+        let kdialog_filter = filters
+            .iter()
+            .map(|filter| format!("{}|{}", patterns(filter), filter.name))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut kdialog = Command::new("kdialog");
+        match save {
+            Some(name) => kdialog.args(["--getsavefilename", name]),
+            None => kdialog.args(["--getopenfilename", "."]),
+        };
+        if !filters.is_empty() {
+            kdialog.arg(kdialog_filter);
+        }
+
+        vec![zenity, kdialog]
+    }
+
+    /// Index of the button pressed in the message box shown by `program`.
+    fn pressed(program: &str, output: &Output, buttons: &[String]) -> Option<usize> {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = stdout.trim();
+        let position = |labels: &[&str], label: &str| labels.iter().position(|l| *l == label);
+
+        match program {
+            "powershell" => match buttons.len() {
+                1 => position(&["OK"], stdout),
+                2 => position(&["OK", "Cancel"], stdout),
+                _ => position(&["Yes", "No", "Cancel"], stdout),
+            },
+            "osascript" => {
+                let label = stdout.strip_prefix("button returned:")?;
+                buttons.iter().position(|button| button == label)
+            }
+            // the extra button prints its label
+            "zenity" if !stdout.is_empty() => buttons.iter().position(|button| button == stdout),
+            // closing a zenity question is the same as its second button
+            _ => output
+                .status
+                .code()
+                .map(|code| code as usize)
+                .filter(|code| *code < buttons.len()),
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn message_box_commands(title: &str, text: &str, buttons: &[&str]) -> Vec<Command> {
+        let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+        let kind = match buttons.len() {
+            1 => "OK",
+            2 => "OKCancel",
+            _ => "YesNoCancel",
+        };
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             [System.Windows.Forms.MessageBox]::Show({}, {}, '{}')",
+            quote(text),
+            quote(title),
+            kind
+        );
+
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command", script.as_str()]);
+        vec![command]
+    }
+
+    #[cfg(target_os = "macos")]
+    fn message_box_commands(title: &str, text: &str, buttons: &[&str]) -> Vec<Command> {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let labels = buttons
+            .iter()
+            .map(|button| quote(button))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let script = format!(
+            "display dialog {} with title {} buttons {{{}}} default button {}",
+            quote(text),
+            quote(title),
+            labels,
+            buttons.len()
+        );
+
+        let mut command = Command::new("osascript");
+        command.args(["-e", script.as_str()]);
+        vec![command]
+    }
+
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    fn message_box_commands(_title: &str, _text: &str, _buttons: &[&str]) -> Vec<Command> {
+        vec![]
+    }
+
+    #[cfg(not(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "android",
+        target_os = "ios"
+    )))]
+    fn message_box_commands(title: &str, text: &str, buttons: &[&str]) -> Vec<Command> {
+        let mut zenity = Command::new("zenity");
+        zenity.args([
+            if buttons.len() == 1 {
+                "--info"
+            } else {
+                "--question"
+            },
+            "--no-markup",
+        ]);
+        zenity.arg(format!("--title={}", title));
+        zenity.arg(format!("--text={}", text));
+        zenity.arg(format!("--ok-label={}", buttons[0]));
+        if let Some(button) = buttons.get(1) {
+            zenity.arg(format!("--cancel-label={}", button));
+        }
+        if let Some(button) = buttons.get(2) {
+            zenity.arg(format!("--extra-button={}", button));
+        }
+
+        let mut kdialog = Command::new("kdialog");
+        kdialog.args(["--title", title]);
+        match buttons {
+            [ok] => kdialog.args(["--msgbox", text, "--ok-label", ok]),
+            [yes, no] => kdialog.args(["--yesno", text, "--yes-label", yes, "--no-label", no]),
+            [yes, no, cancel, ..] => kdialog.args([
+                "--yesnocancel",
+                text,
+                "--yes-label",
+                yes,
+                "--no-label",
+                no,
+                "--cancel-label",
+                cancel,
+            ]),
+            [] => &mut kdialog,
+        };
+
+        vec![zenity, kdialog]
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(super) mod web {
+    use super::{FileFilter, PickedFile};
+    use crate::file::FileError;
+
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    extern "C" {
+        fn macroquad_open_file_dialog(accept: *const u8, accept_len: usize);
+        /// -2 while the dialog is open, -1 if it was cancelled, the file size otherwise
+        fn macroquad_file_dialog_status() -> i32;
+        fn macroquad_file_dialog_name_len() -> usize;
+        fn macroquad_file_dialog_read(name: *mut u8, data: *mut u8);
+        fn macroquad_save_file(name: *const u8, name_len: usize, data: *const u8, data_len: usize);
+    }
+
+    struct DialogFuture;
+
+    impl Future for DialogFuture {
+        type Output = Option<PickedFile>;
+
+        fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<Self::Output> {
+            let status = unsafe { macroquad_file_dialog_status() };
+            if status == -2 {
+                return Poll::Pending;
+            }
+            if status < 0 {
+                return Poll::Ready(None);
+            }
+
+            let mut name = vec![0; unsafe { macroquad_file_dialog_name_len() }];
+            let mut data = vec![0; status as usize];
+            unsafe { macroquad_file_dialog_read(name.as_mut_ptr(), data.as_mut_ptr()) };
+
+            Poll::Ready(Some(PickedFile {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                path: None,
+                data,
+            }))
+        }
+    }
+
+    pub(super) async fn open(filters: &[FileFilter<'_>]) -> Result<Option<PickedFile>, FileError> {
+        let accept = filters
+            .iter()
+            .flat_map(|filter| filter.extensions.iter())
+            .map(|extension| format!(".{}", extension))
+            .collect::<Vec<_>>()
+            .join(",");
+        unsafe { macroquad_open_file_dialog(accept.as_ptr(), accept.len()) };
+
+        Ok(DialogFuture.await)
+    }
+
+    pub(in crate::file) fn save(name: &str, data: &[u8]) {
+        unsafe { macroquad_save_file(name.as_ptr(), name.len(), data.as_ptr(), data.len()) };
+    }
+}
@@ -0,0 +1,126 @@
+//! HTTP(S) downloads, with the browser on web and with ureq natively
+//! behind the "http" feature.
+
+#[derive(Debug)]
+pub enum HttpError {
+    /// No response in time, see [fetch_with_timeout]
+    Timeout,
+    /// The server answered with a status other than 2xx
+    Status(u16),
+    /// Bad url, DNS, connection or TLS failure
+    Transport(String),
+}
+
+impl std::error::Error for HttpError {}
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HttpError::Timeout => write!(f, "Request timed out"),
+            HttpError::Status(status) => write!(f, "Server answered with status {}", status),
+            HttpError::Transport(err) => write!(f, "Request failed: {}", err),
+        }
+    }
+}
+
+/// Seconds [fetch] waits for a response.
+pub const DEFAULT_TIMEOUT: f32 = 30.;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Download `url` with a GET request.
+///
+/// `load_file` does the same for the paths starting with `http://` or `https://`,
+/// use `fetch` to tell the failures apart.
+/// ```ignore
+/// match fetch("https://example.com/scores.json").await {
+///     Ok(scores) => show_scores(&scores),
+///     Err(HttpError::Timeout) => show_offline(),
+///     Err(err) => error!("{}", err),
+/// }
+/// ```
+pub async fn fetch(url: &str) -> Result<Vec<u8>, HttpError> {
+    fetch_with_timeout(url, DEFAULT_TIMEOUT).await
+}
+
+/// [fetch] giving up after `timeout` seconds.
+///
+/// On web the browser decides on the timeouts, `timeout` is ignored.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn fetch_with_timeout(url: &str, timeout: f32) -> Result<Vec<u8>, HttpError> {
+    use std::io::Read;
+
+    let url = url.to_string();
+    crate::exec::ThreadFuture::spawn(move || {
+        let response = ureq::AgentBuilder::new()
+            .timeout(std::time::Duration::from_secs_f32(timeout))
+            .build()
+            .get(&url)
+            .call();
+        match response {
+            Ok(response) => {
+                let mut bytes = vec![];
+                response
+                    .into_reader()
+                    .read_to_end(&mut bytes)
+                    .map(|_| bytes)
+                    .map_err(io_error)
+            }
+            Err(ureq::Error::Status(status, _)) => Err(HttpError::Status(status)),
+            Err(ureq::Error::Transport(transport)) => {
+                use std::error::Error;
+
+                match transport
+                    .source()
+                    .and_then(|source| source.downcast_ref::<std::io::Error>())
+                {
+                    Some(err) if is_timeout(err) => Err(HttpError::Timeout),
+                    _ => Err(HttpError::Transport(transport.to_string())),
+                }
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|| Err(HttpError::Transport("the http thread panicked".to_string())))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_timeout(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn io_error(err: std::io::Error) -> HttpError {
+    if is_timeout(&err) {
+        HttpError::Timeout
+    } else {
+        HttpError::Transport(err.to_string())
+    }
+}
+
+/// [fetch] giving up after `timeout` seconds.
+///
+/// On web the browser decides on the timeouts, `timeout` is ignored.
+#[cfg(target_arch = "wasm32")]
+pub async fn fetch_with_timeout(url: &str, _timeout: f32) -> Result<Vec<u8>, HttpError> {
+    use std::sync::{Arc, Mutex};
+
+    let contents = Arc::new(Mutex::new(None));
+    {
+        let contents = contents.clone();
+        let url = url.to_string();
+        miniquad::fs::load_file(&url.clone(), move |bytes| {
+            *contents.lock().unwrap() =
+                Some(bytes.map_err(|kind| crate::file::FileError::new(kind, &url)));
+        });
+    }
+
+    crate::exec::FileLoadingFuture { contents }
+        .await
+        .map_err(|err| HttpError::Transport(err.to_string()))
+}
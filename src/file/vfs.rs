@@ -0,0 +1,461 @@
+//! Archives and directories mounted over the assets, read by `load_file`
+//! before the loose files.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Source of asset files for [mount].
+pub trait Archive {
+    /// Contents of the file at `path`, `None` when it is not in the archive.
+    /// `path` uses `/` separators, without a leading `./`.
+    fn read(&self, path: &str) -> Option<Vec<u8>>;
+
+    /// Paths of all the files, for [list_files]. Archives that can not tell
+    /// are left out of the listings.
+    fn list(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// Loose files in a directory, for mods or local overrides on desktop.
+pub struct Directory {
+    root: PathBuf,
+}
+
+impl Directory {
+    pub fn new<P: AsRef<Path>>(root: P) -> Directory {
+        Directory {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl Archive for Directory {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.root.join(path)).ok()
+    }
+
+    fn list(&self) -> Vec<String> {
+        fn visit(dir: &Path, prefix: &str, files: &mut Vec<String>) {
+            let entries = match std::fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(_) => return,
+            };
+            for entry in entries.flatten() {
+                let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+                if entry.path().is_dir() {
+                    visit(&entry.path(), &format!("{}/", name), files);
+                } else {
+                    files.push(name);
+                }
+            }
+        }
+
+        let mut files = vec![];
+        visit(&self.root, "", &mut files);
+        files
+    }
+}
+
+/// Files embedded into the binary with [include_assets](crate::file::include_assets).
+pub struct EmbeddedAssets {
+    files: &'static [(&'static str, &'static [u8])],
+}
+
+impl EmbeddedAssets {
+    pub fn new(files: &'static [(&'static str, &'static [u8])]) -> EmbeddedAssets {
+        EmbeddedAssets { files }
+    }
+
+    /// Paths of all the embedded files.
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|(path, _)| *path)
+    }
+}
+
+impl Archive for EmbeddedAssets {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        self.files
+            .iter()
+            .find(|(file, _)| *file == path)
+            .map(|(_, data)| data.to_vec())
+    }
+    fn list(&self) -> Vec<String> {
+        self.files().map(|path| path.to_string()).collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum ZipError {
+    /// No end of central directory record, not a zip file
+    NotZip,
+    /// A record goes past the end of the data
+    Truncated,
+    UnsupportedCompression {
+        path: String,
+        method: u16,
+    },
+}
+
+impl std::error::Error for ZipError {}
+impl std::fmt::Display for ZipError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ZipError::NotZip => write!(f, "Not a zip archive"),
+            ZipError::Truncated => write!(f, "Truncated zip archive"),
+            ZipError::UnsupportedCompression { path, method } => {
+                write!(f, "Unsupported compression {} for {}", method, path)
+            }
+        }
+    }
+}
+
+struct ZipEntry {
+    /// Offset of the local file header
+    offset: usize,
+    compressed_size: usize,
+    deflated: bool,
+}
+
+/// Zip file, stored or deflated, read in memory.
+/// ```ignore
+/// let pak = load_file("data.pak").await.unwrap();
+/// mount(ZipArchive::new(pak).unwrap(), 0);
+/// // from data.pak if it has it, from the assets folder otherwise
+/// let texture = load_texture("textures/player.png").await.unwrap();
+/// ```
+pub struct ZipArchive {
+    data: Vec<u8>,
+    entries: HashMap<String, ZipEntry>,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ZipError> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or(ZipError::Truncated)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ZipError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(ZipError::Truncated)
+}
+
+impl ZipArchive {
+    pub fn new(data: Vec<u8>) -> Result<ZipArchive, ZipError> {
+        const END_SIGNATURE: u32 = 0x06054b50;
+        const ENTRY_SIGNATURE: u32 = 0x02014b50;
+        const END_SIZE: usize = 22;
+
+        // the end record is followed by a comment of up to 64k
+        let end = (0..=data.len().saturating_sub(END_SIZE))
+            .rev()
+            .take(END_SIZE + u16::MAX as usize)
+            .find(|offset| read_u32(&data, *offset).ok() == Some(END_SIGNATURE))
+            .ok_or(ZipError::NotZip)?;
+        let count = read_u16(&data, end + 10)? as usize;
+        let mut offset = read_u32(&data, end + 16)? as usize;
+
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            if read_u32(&data, offset)? != ENTRY_SIGNATURE {
+                return Err(ZipError::Truncated);
+            }
+            let method = read_u16(&data, offset + 10)?;
+            let compressed_size = read_u32(&data, offset + 20)? as usize;
+            let name_len = read_u16(&data, offset + 28)? as usize;
+            let extra_len = read_u16(&data, offset + 30)? as usize;
+            let comment_len = read_u16(&data, offset + 32)? as usize;
+            let local_offset = read_u32(&data, offset + 42)? as usize;
+            let name = data
+                .get(offset + 46..offset + 46 + name_len)
+                .ok_or(ZipError::Truncated)?;
+            let name = String::from_utf8_lossy(name).into_owned();
+            offset += 46 + name_len + extra_len + comment_len;
+
+            if name.ends_with('/') {
+                continue;
+            }
+            if method != 0 && method != 8 {
+                return Err(ZipError::UnsupportedCompression { path: name, method });
+            }
+            entries.insert(
+                name,
+                ZipEntry {
+                    offset: local_offset,
+                    compressed_size,
+                    deflated: method == 8,
+                },
+            );
+        }
+
+        Ok(ZipArchive { data, entries })
+    }
+
+    /// Paths of all the files in the archive.
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|path| path.as_str())
+    }
+}
+
+impl Archive for ZipArchive {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        let entry = self.entries.get(path)?;
+        let name_len = read_u16(&self.data, entry.offset + 26).ok()? as usize;
+        let extra_len = read_u16(&self.data, entry.offset + 28).ok()? as usize;
+        let start = entry.offset + 30 + name_len + extra_len;
+        let bytes = self.data.get(start..start + entry.compressed_size)?;
+
+        if entry.deflated {
+            miniz_oxide::inflate::decompress_to_vec(bytes).ok()
+        } else {
+            Some(bytes.to_vec())
+        }
+    }
+    fn list(&self) -> Vec<String> {
+        self.files().map(|path| path.to_string()).collect()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Vfs {
+    /// Sorted by decreasing priority
+    mounts: Vec<(i32, Box<dyn Archive>)>,
+    /// Files listed with [set_file_manifest]
+    manifest: Vec<String>,
+}
+
+impl Vfs {
+    pub(crate) fn read(&self, path: &str) -> Option<Vec<u8>> {
+        let path = normalize(path);
+        self.mounts
+            .iter()
+            .find_map(|(_, archive)| archive.read(&path))
+    }
+
+    /// Files directly in `dir` matching `pattern`, from the archives and the manifest.
+    fn list(&self, dir: &str, pattern: &str) -> Vec<String> {
+        let dir = normalize(dir);
+        let dir = dir.trim_end_matches('/');
+        self.mounts
+            .iter()
+            .flat_map(|(_, archive)| archive.list())
+            .chain(self.manifest.iter().cloned())
+            .map(|path| normalize(&path))
+            .filter(|path| {
+                let (parent, name) = match path.rfind('/') {
+                    Some(slash) => (&path[..slash], &path[slash + 1..]),
+                    None => ("", path.as_str()),
+                };
+                parent == dir && glob_match(pattern, name)
+            })
+            .collect()
+    }
+}
+
+/// `*` matches any run of characters, `?` any single one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    // position after the last `*`, and where in the name it started matching
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut path = path.as_str();
+    while let Some(stripped) = path.strip_prefix("./") {
+        path = stripped;
+    }
+    path.trim_start_matches('/').to_string()
+}
+
+/// Read the assets from `archive` before the loose files.
+///
+/// Archives with a higher `priority` are read first, to let mods and patches
+/// overlay the assets of the game. With the same priority, the last one mounted
+/// wins.
+/// ```ignore
+/// mount(ZipArchive::new(load_file("game.pak").await?)?, 0);
+/// mount(Directory::new("mods/hd_textures"), 10);
+/// ```
+pub fn mount<A: Archive + 'static>(archive: A, priority: i32) {
+    let mounts = &mut crate::get_context().vfs.mounts;
+    let index = mounts
+        .iter()
+        .position(|(other, _)| *other <= priority)
+        .unwrap_or(mounts.len());
+
+    mounts.insert(index, (priority, Box::new(archive)));
+}
+
+/// Files [list_files] knows of on the platforms where the assets folder can
+/// not be listed, web and Android.
+///
+/// Make the list at build time, like with `find . -type f > files.txt` in
+/// the assets folder, and load it with [load_file_manifest].
+pub fn set_file_manifest(files: &[&str]) {
+    crate::get_context().vfs.manifest = files.iter().map(|file| file.to_string()).collect();
+}
+
+/// [set_file_manifest] from a text file with a path per line.
+pub async fn load_file_manifest(path: &str) -> Result<(), super::FileError> {
+    let manifest = super::load_string(path).await?;
+    crate::get_context().vfs.manifest = manifest
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect();
+    Ok(())
+}
+
+/// Paths of the files directly in `dir` with a name matching `pattern`,
+/// sorted, ready for `load_file`.
+///
+/// `*` in the pattern matches any run of characters, `?` any single one.
+/// The files come from the mounted archives, the manifest from
+/// [set_file_manifest] and, on PC, the assets folder itself.
+/// ```ignore
+/// for path in list_files("levels", "*.ron") {
+///     levels.push(load_string(&path).await.unwrap());
+/// }
+/// ```
+pub fn list_files(dir: &str, pattern: &str) -> Vec<String> {
+    let mut files = crate::get_context().vfs.list(dir, pattern);
+
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    if let Ok(entries) = std::fs::read_dir(super::assets_path(dir)) {
+        let dir = normalize(dir);
+        let dir = dir.trim_end_matches('/');
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.path().is_file() && glob_match(pattern, &name) {
+                files.push(match dir {
+                    "" => name,
+                    dir => format!("{}/{}", dir, name),
+                });
+            }
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Remove all the mounted archives, the assets are read from the loose files again.
+pub fn unmount_all() {
+    crate::get_context().vfs.mounts.clear();
+}
+
+#[test]
+fn zip_archive() {
+    fn zip(files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut data = vec![];
+        let mut directory = vec![];
+        for (name, contents, deflate) in files {
+            let stored = if *deflate {
+                miniz_oxide::deflate::compress_to_vec(contents, 6)
+            } else {
+                contents.to_vec()
+            };
+            let method: u16 = if *deflate { 8 } else { 0 };
+
+            let offset = data.len() as u32;
+            data.extend(0x04034b50u32.to_le_bytes());
+            data.extend([0; 4]);
+            data.extend(method.to_le_bytes());
+            data.extend([0; 8]);
+            data.extend((stored.len() as u32).to_le_bytes());
+            data.extend((contents.len() as u32).to_le_bytes());
+            data.extend((name.len() as u16).to_le_bytes());
+            data.extend([0; 2]);
+            data.extend(name.as_bytes());
+            data.extend(&stored);
+
+            directory.extend(0x02014b50u32.to_le_bytes());
+            directory.extend([0; 6]);
+            directory.extend(method.to_le_bytes());
+            directory.extend([0; 8]);
+            directory.extend((stored.len() as u32).to_le_bytes());
+            directory.extend((contents.len() as u32).to_le_bytes());
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(offset.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+
+        let directory_offset = data.len() as u32;
+        let directory_size = directory.len() as u32;
+        data.extend(directory);
+        data.extend(0x06054b50u32.to_le_bytes());
+        data.extend([0; 4]);
+        data.extend((files.len() as u16).to_le_bytes());
+        data.extend((files.len() as u16).to_le_bytes());
+        data.extend(directory_size.to_le_bytes());
+        data.extend(directory_offset.to_le_bytes());
+        data.extend([0; 2]);
+        data
+    }
+
+    let archive = ZipArchive::new(zip(&[
+        ("levels/1.txt", b"stored", false),
+        ("levels/2.txt", b"deflated deflated deflated", true),
+    ]))
+    .unwrap();
+    assert_eq!(archive.read("levels/1.txt").unwrap(), b"stored");
+    assert_eq!(
+        archive.read("levels/2.txt").unwrap(),
+        b"deflated deflated deflated"
+    );
+    assert!(archive.read("levels/3.txt").is_none());
+    assert!(matches!(
+        ZipArchive::new(b"not a zip".to_vec()),
+        Err(ZipError::NotZip)
+    ));
+
+    let vfs = Vfs {
+        mounts: vec![(0, Box::new(archive))],
+        manifest: vec!["levels/4.txt".to_string(), "levels/old/1.txt".to_string()],
+    };
+    assert_eq!(vfs.read(".\\levels/1.txt").unwrap(), b"stored");
+
+    let mut levels = vfs.list("./levels/", "*.txt");
+    levels.sort();
+    assert_eq!(levels, ["levels/1.txt", "levels/2.txt", "levels/4.txt"]);
+    assert!(vfs.list("levels", "?.ron").is_empty());
+}
+
+#[test]
+fn glob() {
+    assert!(glob_match("*.ron", "level.ron"));
+    assert!(glob_match("level_??.*", "level_01.ron"));
+    assert!(glob_match("*a*b*", "xxaxxbxx"));
+    assert!(glob_match("*", ""));
+    assert!(!glob_match("*.ron", "level.png"));
+    assert!(!glob_match("level_?", "level_10"));
+}
@@ -0,0 +1,160 @@
+//! Hot-reload of the assets loaded from files, in debug builds.
+
+use crate::{audio::Sound, file::assets_path, logging::error, text::Font, texture::Texture2D};
+
+use std::time::SystemTime;
+
+enum Reload {
+    Texture(Texture2D),
+    Font(Font),
+    Sound(Sound),
+    Custom(Box<dyn FnMut(Vec<u8>)>),
+}
+
+struct WatchedFile {
+    path: String,
+    modified: SystemTime,
+    reload: Reload,
+}
+
+/// Keeps track of textures, fonts and sounds loaded from files, and replaces
+/// them when the files change.
+/// Does nothing in release builds and on web.
+#[derive(Default)]
+pub(crate) struct AssetWatcher {
+    watched: Vec<WatchedFile>,
+    last_check: f64,
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+impl AssetWatcher {
+    const CHECK_INTERVAL: f64 = 0.25;
+
+    fn watch(&mut self, path: &str, reload: Reload) {
+        if !cfg!(debug_assertions) || cfg!(target_arch = "wasm32") {
+            return;
+        }
+
+        // files from the mounted archives are not on disk
+        let path = assets_path(path);
+        if let Some(modified) = modified(&path) {
+            self.watched.push(WatchedFile {
+                path,
+                modified,
+                reload,
+            });
+        }
+    }
+
+    pub(crate) fn watch_texture(&mut self, path: &str, texture: Texture2D) {
+        self.watch(path, Reload::Texture(texture));
+    }
+
+    pub(crate) fn watch_font(&mut self, path: &str, font: Font) {
+        self.watch(path, Reload::Font(font));
+    }
+
+    pub(crate) fn watch_sound(&mut self, path: &str, sound: Sound) {
+        self.watch(path, Reload::Sound(sound));
+    }
+
+    /// Stop reloading a deleted texture.
+    pub(crate) fn forget_texture(&mut self, texture: Texture2D) {
+        self.watched.retain(
+            |watched| !matches!(watched.reload, Reload::Texture(other) if other == texture),
+        );
+    }
+
+    /// Stop reloading a deleted sound.
+    pub(crate) fn forget_sound(&mut self, sound: Sound) {
+        self.watched
+            .retain(|watched| !matches!(watched.reload, Reload::Sound(other) if other == sound));
+    }
+
+    pub(crate) fn update(&mut self) {
+        if self.watched.is_empty() {
+            return;
+        }
+
+        let now = miniquad::date::now();
+        if now - self.last_check < Self::CHECK_INTERVAL {
+            return;
+        }
+        self.last_check = now;
+
+        for watched in &mut self.watched {
+            match modified(&watched.path) {
+                Some(modified) if modified != watched.modified => watched.modified = modified,
+                _ => continue,
+            }
+            let bytes = match std::fs::read(&watched.path) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    error!("Failed to reload {}: {}", watched.path, err);
+                    continue;
+                }
+            };
+
+            match &mut watched.reload {
+                Reload::Texture(texture) => {
+                    // editors saving in two steps leave half written files
+                    let image = match crate::experimental::assets::decode_image(&bytes) {
+                        Ok(image) => image,
+                        Err(err) => {
+                            error!("Failed to reload {}: {}", watched.path, err);
+                            continue;
+                        }
+                    };
+                    if image.width() == texture.width() as usize
+                        && image.height() == texture.height() as usize
+                    {
+                        texture.update(&image);
+                    } else {
+                        error!(
+                            "Failed to reload {}: the texture size changed, restart to see it",
+                            watched.path
+                        );
+                    }
+                }
+                Reload::Font(font) => {
+                    if let Err(err) = crate::text::reload_ttf_font(*font, &bytes) {
+                        error!("Failed to reload {}: {}", watched.path, err);
+                    }
+                }
+                Reload::Sound(sound) => {
+                    let (sound, path) = (*sound, watched.path.clone());
+                    crate::experimental::coroutines::start_coroutine(async move {
+                        if let Err(err) = crate::audio::reload_sound(sound, bytes).await {
+                            error!("Failed to reload {}: {}", path, err);
+                        }
+                    });
+                }
+                Reload::Custom(f) => f(bytes),
+            }
+        }
+    }
+}
+
+/// Call `f` with the new contents of the file each time it changes on disk.
+///
+/// Textures, fonts and sounds loaded with `load_texture`, `load_ttf_font` and
+/// `load_sound` are reloaded by macroquad already, this is for the other
+/// assets, like levels or configs. Like the built-in reloading, it only works
+/// in debug builds and not on web.
+/// ```ignore
+/// let level = Rc::new(RefCell::new(Level::parse(&load_string("level.json").await?)));
+/// let reloaded = level.clone();
+/// watch_file("level.json", move |bytes| {
+///     *reloaded.borrow_mut() = Level::parse(&String::from_utf8_lossy(&bytes));
+/// });
+/// ```
+pub fn watch_file<F: FnMut(Vec<u8>) + 'static>(path: &str, f: F) {
+    crate::get_context()
+        .asset_watcher
+        .watch(path, Reload::Custom(Box::new(f)));
+}
@@ -58,6 +58,7 @@ pub mod material;
 pub mod math;
 pub mod models;
 pub mod shapes;
+pub mod storage;
 pub mod text;
 pub mod texture;
 pub mod time;
@@ -66,6 +67,9 @@ pub mod window;
 
 pub mod experimental;
 
+#[cfg(feature = "egui")]
+pub mod egui;
+
 pub mod prelude;
 
 pub mod telemetry;
@@ -129,10 +133,7 @@ pub use macroquad_macro::main;
 #[doc(hidden)]
 pub use macroquad_macro::test;
 
-/// Cross platform random generator.
-pub mod rand {
-    pub use quad_rand::*;
-}
+pub mod rand;
 
 #[cfg(not(feature = "log-rs"))]
 /// Logging macros, available with miniquad "log-impl" feature.
@@ -150,7 +151,7 @@ use crate::{
     ui::ui_context::UiContext,
 };
 
-use glam::{vec2, Mat4, Vec2};
+use glam::{vec2, Mat4, Vec2, Vec3};
 
 struct Context {
     audio_context: audio::AudioContext,
@@ -180,20 +181,53 @@ struct Context {
 
     cursor_grabbed: bool,
 
+    fullscreen: bool,
+    /// The framebuffer has an alpha channel, the window is cleared transparent
+    transparent_framebuffer: bool,
+    /// Window size to restore when leaving fullscreen
+    windowed_size: Vec2,
+    window_constraints: window::WindowConstraints,
+    window_events: Vec<window::WindowEvent>,
+    window_minimized: bool,
+    dpi_scale: f32,
+
     input_events: Vec<Vec<MiniquadInputEvent>>,
 
     gl: QuadGl,
     camera_matrix: Option<Mat4>,
+    /// View matrix and position of the active camera
+    camera_view: Option<(Mat4, Vec3)>,
+    material_watcher: material::MaterialWatcher,
+    asset_watcher: file::AssetWatcher,
 
     ui_context: UiContext,
+    #[cfg(feature = "egui")]
+    egui_context: Option<egui::EguiContext>,
     coroutines_context: experimental::coroutines::CoroutinesContext,
+    tweens: experimental::tween::TweensContext,
     fonts_storage: text::FontsStorage,
 
     pc_assets_folder: Option<String>,
+    vfs: file::Vfs,
+    /// Folder of the [storage] keys
+    storage_name: String,
+    assets: experimental::assets::AssetManager,
 
     start_time: f64,
     last_frame_time: f64,
     frame_time: f64,
+    target_frame_time: Option<f64>,
+    /// Last [time::FRAME_HISTORY] frame times, oldest first
+    frame_history: std::collections::VecDeque<f32>,
+    time_scale: f32,
+    time_paused: bool,
+    /// Scaled seconds since start, for [time::get_game_time]
+    game_time: f64,
+    /// How late `std::thread::sleep` wakes up, for [time::set_target_fps]
+    #[cfg(not(target_arch = "wasm32"))]
+    sleep_overshoot: f64,
+    /// Step of the running [time::FixedTimestep::update]
+    fixed_frame_time: Option<f32>,
 
     #[cfg(one_screenshot)]
     counter: usize,
@@ -302,24 +336,49 @@ impl Context {
 
             cursor_grabbed: false,
 
+            fullscreen: false,
+            transparent_framebuffer: false,
+            windowed_size: vec2(screen_width, screen_height),
+            window_constraints: Default::default(),
+            window_events: vec![],
+            window_minimized: false,
+            dpi_scale: ctx.dpi_scale(),
+
             input_events: Vec::new(),
 
             camera_matrix: None,
+            camera_view: None,
             gl: QuadGl::new(ctx),
+            material_watcher: material::MaterialWatcher::new(),
+            asset_watcher: file::AssetWatcher::default(),
 
             ui_context: UiContext::new(ctx, screen_width, screen_height),
+            #[cfg(feature = "egui")]
+            egui_context: None,
             fonts_storage: text::FontsStorage::new(ctx),
             texture_batcher: texture::Batcher::new(ctx),
             camera_stack: vec![],
 
             audio_context: audio::AudioContext::new(),
             coroutines_context: experimental::coroutines::CoroutinesContext::new(),
+            tweens: Default::default(),
 
             pc_assets_folder: None,
+            vfs: Default::default(),
+            storage_name: "macroquad".to_string(),
+            assets: Default::default(),
 
             start_time: miniquad::date::now(),
             last_frame_time: miniquad::date::now(),
             frame_time: 1. / 60.,
+            target_frame_time: None,
+            frame_history: Default::default(),
+            time_scale: 1.,
+            time_paused: false,
+            game_time: 0.,
+            #[cfg(not(target_arch = "wasm32"))]
+            sleep_overshoot: 0.001,
+            fixed_frame_time: None,
 
             #[cfg(one_screenshot)]
             counter: 0,
@@ -331,9 +390,30 @@ impl Context {
     fn begin_frame(&mut self) {
         telemetry::begin_gpu_query("GPU");
 
+        // the window moved to a monitor with another scale
+        let dpi_scale = get_quad_context().dpi_scale();
+        if dpi_scale != self.dpi_scale {
+            self.window_events.push(window::WindowEvent::DpiChanged {
+                old: self.dpi_scale,
+                new: dpi_scale,
+            });
+            self.dpi_scale = dpi_scale;
+        }
+
         self.ui_context.process_input();
 
-        let color = Self::DEFAULT_BG_COLOR;
+        self.material_watcher
+            .update(get_quad_context(), &mut self.gl);
+        self.asset_watcher.update();
+        self.audio_context.update();
+        self.game_time += time::get_frame_time() as f64;
+        self.tweens.update(time::get_frame_time());
+
+        let color = if self.transparent_framebuffer {
+            Color::new(0., 0., 0., 0.)
+        } else {
+            Self::DEFAULT_BG_COLOR
+        };
 
         get_quad_context().clear(Some((color.r, color.g, color.b, color.a)), None, None);
         self.gl.reset();
@@ -345,7 +425,9 @@ impl Context {
         self.perform_render_passes();
 
         self.ui_context.draw(get_quad_context(), &mut self.gl);
+        telemetry::draw_overlay();
         let screen_mat = self.pixel_perfect_projection_matrix();
+        self.gl.camera_view(Mat4::IDENTITY, Vec3::ZERO);
         self.gl.draw(get_quad_context(), screen_mat);
 
         get_quad_context().commit_frame();
@@ -362,6 +444,7 @@ impl Context {
         telemetry::end_gpu_query();
 
         self.mouse_wheel = Vec2::new(0., 0.);
+        self.window_events.clear();
         self.keys_pressed.clear();
         self.keys_released.clear();
         self.mouse_pressed.clear();
@@ -401,7 +484,9 @@ impl Context {
 
     pub(crate) fn perform_render_passes(&mut self) {
         let matrix = self.projection_matrix();
+        let (view, position) = self.camera_view.unwrap_or((Mat4::IDENTITY, Vec3::ZERO));
 
+        self.gl.camera_view(view, position);
         self.gl.draw(&mut get_quad_context(), matrix);
     }
 }
@@ -456,8 +541,16 @@ impl EventHandler for Stage {
         set_quad_context(ctx);
 
         let _z = telemetry::ZoneGuard::new("Event::resize_event");
-        get_context().screen_width = width;
-        get_context().screen_height = height;
+        let context = get_context();
+        let dpi_scale = ctx.dpi_scale();
+        context.window_events.push(window::WindowEvent::Resized {
+            old: vec2(context.screen_width, context.screen_height) / dpi_scale,
+            new: vec2(width, height) / dpi_scale,
+        });
+        context.screen_width = width;
+        context.screen_height = height;
+
+        window::apply_window_constraints();
     }
 
     fn raw_mouse_motion(&mut self, _: &mut miniquad::Context, x: f32, y: f32) {
@@ -744,8 +837,10 @@ impl EventHandler for Stage {
                 let _z = telemetry::ZoneGuard::new("Event::draw end_frame");
                 get_context().end_frame();
             }
+            time::wait_target_frame_time();
             get_context().frame_time = date::now() - get_context().last_frame_time;
             get_context().last_frame_time = date::now();
+            time::record_frame_time(get_context().frame_time as f32);
 
             #[cfg(any(target_arch = "wasm32", target_os = "linux"))]
             {
@@ -762,13 +857,19 @@ impl EventHandler for Stage {
     }
 
     fn window_restored_event(&mut self, _: &mut miniquad::Context) {
-        #[cfg(target_os = "android")]
-        get_context().audio_context.resume();
+        audio::set_focused(true);
+
+        let context = get_context();
+        context.window_minimized = false;
+        context.window_events.push(window::WindowEvent::Restored);
     }
 
     fn window_minimized_event(&mut self, _: &mut miniquad::Context) {
-        #[cfg(target_os = "android")]
-        get_context().audio_context.pause();
+        audio::set_focused(false);
+
+        let context = get_context();
+        context.window_minimized = true;
+        context.window_events.push(window::WindowEvent::Minimized);
     }
 
     fn quit_requested_event(&mut self, ctx: &mut miniquad::Context) {
@@ -798,16 +899,26 @@ impl Window {
     }
 
     pub fn from_config(config: conf::Conf, future: impl Future<Output = ()> + 'static) {
+        let fullscreen = config.fullscreen;
+        let transparent_framebuffer = config.platform.framebuffer_alpha;
+        let windowed_size = vec2(config.window_width as f32, config.window_height as f32);
+        let storage_name = config.window_title.clone();
         miniquad::start(
             conf::Conf {
                 sample_count: 4,
                 ..config
             },
-            |ctx| {
+            move |ctx| {
                 unsafe {
                     MAIN_FUTURE = Some(Box::pin(future));
                 }
                 unsafe { CONTEXT = Some(Context::new(ctx)) };
+                get_context().fullscreen = fullscreen;
+                get_context().transparent_framebuffer = transparent_framebuffer;
+                get_context().windowed_size = windowed_size;
+                if !storage_name.is_empty() {
+                    get_context().storage_name = storage_name;
+                }
                 Box::new(Stage {})
             },
         );
@@ -1,9 +1,16 @@
 //! Custom materials - shaders, uniforms.
-
+//!
+//! Shaders are GLSL, the one flavour every miniquad 0.3 backend understands:
+//! `#version 100` runs both on desktop GL2 and on WebGL1.
+//! WGSL or SPIR-V input is not supported. Cross-compilers like naga
+//! emit GLSL 300 es/330 at least, with uniform blocks, while miniquad
+//! 0.3 has neither GLES3 contexts nor uniform buffers.
+
+use crate::file::{load_string, FileError};
 use crate::prelude::Texture2D;
-use crate::quad_gl::GlPipeline;
+use crate::quad_gl::{GlPipeline, QuadGl};
 use crate::{get_context, get_quad_context};
-use miniquad::{PipelineParams, ShaderError, UniformType};
+use miniquad::{PipelineParams, ShaderError, ShaderType, UniformType};
 
 /// Material instance loaded on GPU.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -19,29 +26,112 @@ impl Material {
         get_context().gl.set_uniform(self.pipeline, name, uniform);
     }
 
+    /// Set all the custom uniforms of this material in one call.
+    ///
+    /// `uniforms` should be a `#[repr(C)]` struct with fields matching, in order
+    /// and size, the "uniforms" list used for material creation.
+    /// Uniforms are tightly packed, without any padding, so only fields made of
+    /// 4-byte components (f32, u32, Vec2, Vec3, Vec4, Mat4 etc) will fit.
+    /// If the sizes do not match the value is ignored with a warning.
+    ///
+    /// There are no uniform buffers on GL2/GLES2, so this is the same as
+    /// multiple `set_uniform` calls, just way less verbose.
+    ///
+    /// ```ignore
+    /// #[repr(C)]
+    /// struct Light {
+    ///     color: Vec4,
+    ///     position: Vec3,
+    ///     radius: f32,
+    /// }
+    /// // MaterialParams::uniforms: color: Float4, position: Float3, radius: Float1
+    /// unsafe { material.set_uniforms(&Light { .. }) };
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// All the bytes of `T` are read: it has to be `#[repr(C)]` without
+    /// padding, made only of floats, ints and glam vectors and matrices.
+    pub unsafe fn set_uniforms<T>(&self, uniforms: &T) {
+        let data =
+            std::slice::from_raw_parts(uniforms as *const T as *const u8, std::mem::size_of::<T>());
+
+        get_context()
+            .gl
+            .set_uniforms_from_bytes(self.pipeline, data);
+    }
+
     pub fn set_texture(&self, name: &str, texture: Texture2D) {
         get_context().gl.set_texture(self.pipeline, name, texture);
     }
 
+    /// Set elements of a sampler array, declared as "name[N]" in "textures" list,
+    /// starting from the first one.
+    pub fn set_texture_array(&self, name: &str, textures: &[Texture2D]) {
+        let gl = &mut get_context().gl;
+        for (i, texture) in textures.iter().enumerate() {
+            gl.set_texture(self.pipeline, &format!("{}[{}]", name, i), *texture);
+        }
+    }
+
+    /// Cheap copy of this material: same shaders and pipeline params,
+    /// but independent uniform and texture values, starting with the current ones.
+    /// No shader compilation or GPU objects involved, although each instance
+    /// takes one of the limited (32) pipeline slots, just like any other material.
+    ///
+    /// Instances of a hot-reloaded material are reloaded along with it.
+    pub fn instance(&self) -> Material {
+        let context = get_context();
+
+        let pipeline = context.gl.clone_pipeline(self.pipeline);
+        context
+            .material_watcher
+            .add_instance(self.pipeline, pipeline);
+
+        Material { pipeline }
+    }
+
     /// Delete this material. Using deleted material for either rendering
     /// or uniforms manipulation will result internal GL errors.
     pub fn delete(&mut self) {
-        get_context().gl.delete_pipeline(self.pipeline);
+        let context = get_context();
+
+        context.material_watcher.unwatch(self.pipeline);
+        context.gl.delete_pipeline(self.pipeline);
     }
 }
 
 /// Params used for material loading.
 /// It is not possible to change material params at runtime, so this
 /// struct is used only once - at "load_material".
+#[derive(Clone)]
 pub struct MaterialParams {
     /// miniquad pipeline configuration for this material.
-    /// Things like blending, culling, depth dest
+    /// Things like blending, culling, depth test and write.
+    ///
+    /// The material is drawn with exactly this state: unlike default macroquad
+    /// materials, it does not follow camera depth settings, and by default
+    /// there is no blending, no culling and no depth test.
+    /// Triangles are drawn with `primitive_type` as is, while lines, like
+    /// [crate::models::draw_line_3d], always use `PrimitiveType::Lines`.
     pub pipeline_params: PipelineParams,
 
-    /// List of custom uniforms used in this material
+    /// List of custom uniforms used in this material.
+    ///
+    /// Engine uniforms are set automatically, if declared in the shader:
+    /// - `uniform mat4 Model;`
+    /// - `uniform mat4 Projection;` - camera view-projection matrix
+    /// - `uniform mat4 _View;`, `uniform mat4 _Projection;` - view and projection parts of it
+    /// - `uniform vec3 _CameraPos;`
+    /// - `uniform vec2 _ScreenSize;` - size of the screen or render target being drawn to, in pixels
+    /// - `uniform vec4 _Time;` - (time since start, sin of it, cos of it, 0)
     pub uniforms: Vec<(String, UniformType)>,
 
-    /// List of textures used in this material
+    /// List of textures used in this material.
+    /// A sampler array, `uniform sampler2D Layers[4];`, is declared as "Layers[4]"
+    /// and set with [Material::set_texture_array].
+    /// Up to 10 textures: miniquad has 12 texture slots, and two of them are taken
+    /// by "Texture" and "_ScreenTexture".
     pub textures: Vec<String>,
 }
 
@@ -55,23 +145,490 @@ impl Default for MaterialParams {
     }
 }
 
-pub fn load_material(
-    vertex_shader: &str,
-    fragment_shader: &str,
+/// [load_material] and [load_material_from_files] with `#define`s and
+/// `#include`d sources for the shaders.
+///
+/// ```ignore
+/// let material = MaterialBuilder::new(params)
+///     .define("LIGHTS", "4")
+///     .include("light.glsl", LIGHT_GLSL)
+///     .load(VERTEX, FRAGMENT)?;
+/// ```
+#[derive(Clone, Default)]
+pub struct MaterialBuilder {
     params: MaterialParams,
-) -> Result<Material, ShaderError> {
-    let context = &mut get_context();
+    defines: Vec<(String, String)>,
+    includes: Vec<(String, String)>,
+}
+
+impl MaterialBuilder {
+    pub fn new(params: MaterialParams) -> MaterialBuilder {
+        MaterialBuilder {
+            params,
+            ..Default::default()
+        }
+    }
+
+    /// `#define NAME VALUE` line injected into both shaders, right after `#version`
+    pub fn define(mut self, name: &str, value: &str) -> MaterialBuilder {
+        self.defines.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Source for the `#include "name"` directives.
+    /// With [MaterialBuilder::load_from_files] includes missing here are loaded
+    /// from files, relative to the vertex shader directory.
+    pub fn include(mut self, name: &str, source: &str) -> MaterialBuilder {
+        self.includes.push((name.to_string(), source.to_string()));
+        self
+    }
 
-    let pipeline = context.gl.make_pipeline(
-        get_quad_context(),
-        vertex_shader,
-        fragment_shader,
+    pub fn load(
+        self,
+        vertex_shader: &str,
+        fragment_shader: &str,
+    ) -> Result<Material, MaterialError> {
+        let context = &mut get_context();
+
+        let pipeline = make_pipeline(
+            &mut context.gl,
+            get_quad_context(),
+            vertex_shader,
+            fragment_shader,
+            self,
+        )?;
+
+        Ok(Material { pipeline })
+    }
+
+    /// See [load_material_from_files].
+    pub async fn load_from_files(
+        self,
+        vertex_shader_path: &str,
+        fragment_shader_path: &str,
+    ) -> Result<Material, MaterialError> {
+        let vertex_shader = load_string(vertex_shader_path).await?;
+        let fragment_shader = load_string(fragment_shader_path).await?;
+
+        let mut builder = self.clone();
+        let mut included_files = vec![];
+        let mut pending = vec![vertex_shader.clone(), fragment_shader.clone()];
+        while let Some(source) = pending.pop() {
+            for name in shaders::includes(&source) {
+                if builder
+                    .includes
+                    .iter()
+                    .any(|(included, _)| *included == name)
+                {
+                    continue;
+                }
+                let path = include_path(vertex_shader_path, &name);
+                let content = load_string(&path).await?;
+
+                pending.push(content.clone());
+                builder.includes.push((name, content));
+                included_files.push(path);
+            }
+        }
+
+        let material = builder.load(&vertex_shader, &fragment_shader)?;
+
+        get_context().material_watcher.watch(
+            material.pipeline,
+            vertex_shader_path,
+            fragment_shader_path,
+            &included_files,
+            self,
+        );
+
+        Ok(material)
+    }
+}
+
+/// Path of the included file, relative to the shader including it.
+fn include_path(shader_path: &str, include: &str) -> String {
+    match shader_path.rfind('/') {
+        Some(ix) => format!("{}/{}", &shader_path[..ix], include),
+        None => include.to_string(),
+    }
+}
+
+/// Preprocess the sources and make a pipeline out of them,
+/// mapping compiler errors back to the original sources.
+fn make_pipeline(
+    gl: &mut QuadGl,
+    ctx: &mut miniquad::Context,
+    vertex_shader: &str,
+    fragment_shader: &str,
+    builder: MaterialBuilder,
+) -> Result<GlPipeline, MaterialError> {
+    let config = shaders::PreprocessorConfig {
+        includes: builder.includes,
+        defines: builder.defines,
+    };
+    let (vertex_shader, vertex_lines) = shaders::try_preprocess_shader(vertex_shader, &config)?;
+    let (fragment_shader, fragment_lines) =
+        shaders::try_preprocess_shader(fragment_shader, &config)?;
+    let params = builder.params;
+
+    gl.make_pipeline(
+        ctx,
+        &vertex_shader,
+        &fragment_shader,
         params.pipeline_params,
         params.uniforms,
         params.textures,
-    )?;
+    )
+    .map_err(|error| match error {
+        ShaderError::CompilationError {
+            shader_type,
+            error_message,
+        } => {
+            let lines = match shader_type {
+                ShaderType::Vertex => &vertex_lines,
+                ShaderType::Fragment => &fragment_lines,
+            };
+            MaterialError::CompilationError {
+                shader_type,
+                diagnostics: parse_shader_log(&error_message, lines),
+            }
+        }
+        error => MaterialError::ShaderError(error),
+    })
+}
+
+pub fn load_material(
+    vertex_shader: &str,
+    fragment_shader: &str,
+    params: MaterialParams,
+) -> Result<Material, MaterialError> {
+    MaterialBuilder::new(params).load(vertex_shader, fragment_shader)
+}
+
+/// One message from the shader compiler log.
+#[derive(Debug, Clone)]
+pub struct ShaderDiagnostic {
+    /// Where in the original sources the message points to.
+    /// `None` if the driver did not report a line, or it is one of the injected `#define`s.
+    pub location: Option<shaders::SourceLocation>,
+    pub message: String,
+}
+
+impl std::fmt::Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{}: {}", location, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Find the line number in one line of the driver log.
+/// Handles the most common formats: "0:12(5): error: .." (mesa),
+/// "ERROR: 0:12: .." (angle, webgl, apple) and "0(12) : error .." (nvidia).
+/// Returns the line number and the message without the location part.
+fn split_log_line(line: &str) -> Option<(usize, String)> {
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+    let mut prev = ' ';
+    for (start, c) in line.char_indices() {
+        let word_start = !prev.is_alphanumeric();
+        prev = c;
+        if !word_start || !c.is_ascii_digit() {
+            continue;
+        }
+
+        let rest = &line[start + digits(&line[start..])..];
+        let rest = match rest.strip_prefix(':').or_else(|| rest.strip_prefix('(')) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let line_len = digits(rest);
+        if line_len == 0 {
+            continue;
+        }
+        let number = rest[..line_len].parse().ok()?;
+
+        let mut rest = &rest[line_len..];
+        // optional column, "0:12(5)"
+        if let Some(column) = rest.strip_prefix('(') {
+            if let Some(after) = column[digits(column)..].strip_prefix(')') {
+                rest = after;
+            }
+        }
+        let rest = rest.trim_start_matches(&[')', ':', ' '][..]);
+
+        return Some((number, format!("{}{}", &line[..start], rest)));
+    }
+    None
+}
+
+/// Split the driver log into messages, with line numbers mapped back
+/// to the original sources with `lines` from the preprocessor.
+fn parse_shader_log(log: &str, lines: &[Option<shaders::SourceLocation>]) -> Vec<ShaderDiagnostic> {
+    log.lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || c == '\0'))
+        .filter(|line| !line.is_empty())
+        .map(|line| match split_log_line(line) {
+            Some((number, message)) => ShaderDiagnostic {
+                location: number
+                    .checked_sub(1)
+                    .and_then(|ix| lines.get(ix).cloned().flatten()),
+                message,
+            },
+            None => ShaderDiagnostic {
+                location: None,
+                message: line.to_string(),
+            },
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum MaterialError {
+    FileError(FileError),
+    /// `#include` of a file missing in [MaterialBuilder::include]
+    MissingInclude(String),
+    /// Files including each other, the last one is the first one included again
+    IncludeCycle(Vec<String>),
+    /// Shader failed to compile. Driver log is split into messages,
+    /// with line numbers pointing to the original sources, includes accounted.
+    CompilationError {
+        shader_type: ShaderType,
+        diagnostics: Vec<ShaderDiagnostic>,
+    },
+    /// Link and other errors from miniquad
+    ShaderError(ShaderError),
+}
+
+impl std::error::Error for MaterialError {}
+impl std::fmt::Display for MaterialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MaterialError::FileError(error) => write!(f, "{}", error),
+            MaterialError::MissingInclude(name) => {
+                write!(f, "Include file {} in not on \"includes\" list", name)
+            }
+            MaterialError::IncludeCycle(files) => {
+                write!(f, "Include cycle: {}", files.join(" -> "))
+            }
+            MaterialError::CompilationError {
+                shader_type,
+                diagnostics,
+            } => {
+                let shader = match shader_type {
+                    ShaderType::Vertex => "vertex",
+                    ShaderType::Fragment => "fragment",
+                };
+                write!(f, "Failed to compile {} shader:", shader)?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n    {}", diagnostic)?;
+                }
+                Ok(())
+            }
+            MaterialError::ShaderError(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<FileError> for MaterialError {
+    fn from(error: FileError) -> MaterialError {
+        MaterialError::FileError(error)
+    }
+}
 
-    Ok(Material { pipeline })
+/// Load material with shaders sources from files.
+///
+/// `#include "name"` directives not covered by [MaterialBuilder::include]
+/// are loaded with [load_string], relative to the vertex shader directory.
+///
+/// In debug builds on desktop shader files, included ones as well, are watched for changes:
+/// once a file is modified, material is recompiled and swapped in-place,
+/// keeping all the uniform and texture values.
+/// If the new version fails to compile, the error is logged and the
+/// old version keeps being used.
+pub async fn load_material_from_files(
+    vertex_shader_path: &str,
+    fragment_shader_path: &str,
+    params: MaterialParams,
+) -> Result<Material, MaterialError> {
+    MaterialBuilder::new(params)
+        .load_from_files(vertex_shader_path, fragment_shader_path)
+        .await
+}
+
+pub(crate) use watcher::MaterialWatcher;
+
+mod watcher {
+    use super::{include_path, make_pipeline, shaders, MaterialBuilder};
+    use crate::{file::assets_path, logging::error, quad_gl::QuadGl};
+
+    use crate::quad_gl::GlPipeline;
+
+    struct WatchedMaterial {
+        pipeline: GlPipeline,
+        vertex_path: String,
+        fragment_path: String,
+        /// All the files material was made of, included ones as well
+        files: Vec<(String, Option<std::time::SystemTime>)>,
+        params: MaterialBuilder,
+        /// Pipelines made with [super::Material::instance]
+        instances: Vec<GlPipeline>,
+    }
+
+    /// Keeps track of materials loaded from files and recompiles them on change.
+    /// Does nothing in release builds and on web.
+    #[derive(Default)]
+    pub struct MaterialWatcher {
+        watched: Vec<WatchedMaterial>,
+        last_check: f64,
+    }
+
+    impl WatchedMaterial {
+        /// Read all the sources from disk, picking up new includes as well.
+        /// Returns sources and params used to make the pipeline.
+        fn load(&mut self) -> Result<(String, String, MaterialBuilder), String> {
+            let read = |path: &str| {
+                std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))
+            };
+
+            let vertex = read(&self.vertex_path)?;
+            let fragment = read(&self.fragment_path)?;
+
+            let mut params = self.params.clone();
+            let mut pending = vec![vertex.clone(), fragment.clone()];
+            while let Some(source) = pending.pop() {
+                for name in shaders::includes(&source) {
+                    if params
+                        .includes
+                        .iter()
+                        .any(|(included, _)| *included == name)
+                    {
+                        continue;
+                    }
+                    let path = include_path(&self.vertex_path, &name);
+                    let content = read(&path)?;
+
+                    if !self.files.iter().any(|(file, _)| *file == path) {
+                        let time = modified(&path);
+                        self.files.push((path, time));
+                    }
+                    pending.push(content.clone());
+                    params.includes.push((name, content));
+                }
+            }
+
+            Ok((vertex, fragment, params))
+        }
+    }
+
+    fn modified(path: &str) -> Option<std::time::SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    impl MaterialWatcher {
+        const CHECK_INTERVAL: f64 = 0.25;
+
+        pub fn new() -> MaterialWatcher {
+            MaterialWatcher::default()
+        }
+
+        pub fn watch(
+            &mut self,
+            pipeline: GlPipeline,
+            vertex_path: &str,
+            fragment_path: &str,
+            included_files: &[String],
+            params: MaterialBuilder,
+        ) {
+            if !cfg!(debug_assertions) || cfg!(target_arch = "wasm32") {
+                return;
+            }
+
+            let vertex_path = assets_path(vertex_path);
+            let fragment_path = assets_path(fragment_path);
+            let files = vec![vertex_path.clone(), fragment_path.clone()]
+                .into_iter()
+                .chain(included_files.iter().map(|path| assets_path(path)))
+                .map(|path| {
+                    let time = modified(&path);
+                    (path, time)
+                })
+                .collect();
+
+            self.watched.push(WatchedMaterial {
+                pipeline,
+                vertex_path,
+                fragment_path,
+                files,
+                params,
+                instances: vec![],
+            });
+        }
+
+        pub fn add_instance(&mut self, pipeline: GlPipeline, instance: GlPipeline) {
+            if let Some(watched) = self.watched.iter_mut().find(|watched| {
+                watched.pipeline == pipeline || watched.instances.contains(&pipeline)
+            }) {
+                watched.instances.push(instance);
+            }
+        }
+
+        pub fn unwatch(&mut self, pipeline: GlPipeline) {
+            self.watched.retain(|watched| watched.pipeline != pipeline);
+            for watched in &mut self.watched {
+                watched.instances.retain(|instance| *instance != pipeline);
+            }
+        }
+
+        pub fn update(&mut self, ctx: &mut miniquad::Context, gl: &mut QuadGl) {
+            if self.watched.is_empty() {
+                return;
+            }
+
+            let now = miniquad::date::now();
+            if now - self.last_check < Self::CHECK_INTERVAL {
+                return;
+            }
+            self.last_check = now;
+
+            for watched in &mut self.watched {
+                let mut changed = false;
+                for (path, time) in &mut watched.files {
+                    let new_time = modified(path);
+                    if new_time != *time {
+                        *time = new_time;
+                        changed = true;
+                    }
+                }
+                if !changed {
+                    continue;
+                }
+
+                let (vertex, fragment, params) = match watched.load() {
+                    Ok(sources) => sources,
+                    Err(err) => {
+                        error!("Failed to reload material shaders: {}", err);
+                        continue;
+                    }
+                };
+                match make_pipeline(gl, ctx, &vertex, &fragment, params) {
+                    Ok(pipeline) => {
+                        for instance in &watched.instances {
+                            let copy = gl.clone_pipeline(pipeline);
+                            gl.replace_pipeline(*instance, copy);
+                        }
+                        gl.replace_pipeline(watched.pipeline, pipeline);
+                    }
+                    Err(err) => {
+                        error!("Failed to reload material, keeping the old one: {}", err)
+                    }
+                }
+            }
+        }
+    }
 }
 
 /// All following macroquad rendering calls will use the given material.
@@ -84,83 +641,164 @@ pub fn gl_use_default_material() {
     get_context().gl.pipeline(None);
 }
 
-#[doc(hidden)]
+/// Minimal GLSL preprocessor: `#include "file"` directives and `#define` injection.
+///
+/// Used by [load_material] with [MaterialBuilder::include] and [MaterialBuilder::define].
 pub mod shaders {
+    use super::MaterialError;
+
     type IncludeFilename = String;
     type IncludeContent = String;
 
     #[derive(Debug, Clone)]
     pub struct PreprocessorConfig {
         pub includes: Vec<(IncludeFilename, IncludeContent)>,
+        /// `#define NAME VALUE` lines to inject right after the `#version` line.
+        pub defines: Vec<(String, String)>,
     }
     impl Default for PreprocessorConfig {
         fn default() -> PreprocessorConfig {
-            PreprocessorConfig { includes: vec![] }
+            PreprocessorConfig {
+                includes: vec![],
+                defines: vec![],
+            }
         }
     }
 
     impl PreprocessorConfig {}
 
-    pub fn preprocess_shader(source: &str, config: &PreprocessorConfig) -> String {
-        let mut res = source.chars().collect::<Vec<_>>();
+    /// Where a line of the preprocessed shader came from.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SourceLocation {
+        /// Name of the included file, `None` for the shader source itself.
+        pub file: Option<String>,
+        /// 1-based line number.
+        pub line: usize,
+    }
 
-        fn find(data: &[char], n: &mut usize, target: &str) -> bool {
-            if *n >= data.len() {
-                return false;
+    impl std::fmt::Display for SourceLocation {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match &self.file {
+                Some(file) => write!(f, "{}:{}", file, self.line),
+                None => write!(f, "line {}", self.line),
             }
-            let target = target.chars().collect::<Vec<_>>();
+        }
+    }
 
-            'outer: for i in *n..data.len() {
-                for j in 0..target.len() {
-                    if data[i + j] != target[j] {
-                        *n += 1;
-                        continue 'outer;
+    fn include_name(line: &str) -> Option<&str> {
+        let line = line.trim_start().strip_prefix("#include")?;
+        let line = line.trim_start().strip_prefix('"')?;
+        Some(&line[..line.find('"')?])
+    }
+
+    /// File names from all the `#include "name"` directives in the source.
+    /// Includes of the included files are not counted.
+    pub fn includes(source: &str) -> Vec<String> {
+        source
+            .lines()
+            .filter_map(include_name)
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// `stack` - the files being included, to catch the ones including each other
+    fn expand<'a>(
+        source: &'a str,
+        file: Option<&'a str>,
+        config: &'a PreprocessorConfig,
+        stack: &mut Vec<&'a str>,
+        res: &mut Vec<(&'a str, Option<SourceLocation>)>,
+    ) -> Result<(), MaterialError> {
+        for (n, line) in source.lines().enumerate() {
+            match include_name(line) {
+                Some(filename) => {
+                    let (name, content) = config
+                        .includes
+                        .iter()
+                        .find(|(name, _)| name == filename)
+                        .ok_or_else(|| MaterialError::MissingInclude(filename.to_string()))?;
+                    if stack.contains(&name.as_str()) {
+                        let mut files = stack
+                            .iter()
+                            .map(|file| file.to_string())
+                            .collect::<Vec<_>>();
+                        files.push(name.clone());
+                        return Err(MaterialError::IncludeCycle(files));
                     }
+
+                    stack.push(name);
+                    expand(content, Some(name), config, stack, res)?;
+                    stack.pop();
                 }
-                return true;
+                None => res.push((
+                    line,
+                    Some(SourceLocation {
+                        file: file.map(|file| file.to_string()),
+                        line: n + 1,
+                    }),
+                )),
             }
-            false
         }
+        Ok(())
+    }
 
-        fn skip_character(data: &[char], n: &mut usize, target: char) {
-            while *n < data.len() && data[*n] == target {
-                *n += 1;
+    /// Same as [preprocess_shader], but returns the missing includes and
+    /// the include cycles as errors instead of panicking.
+    /// Along with the source returns the origin of each of its lines,
+    /// `None` for the injected `#define` lines.
+    pub(crate) fn try_preprocess_shader(
+        source: &str,
+        config: &PreprocessorConfig,
+    ) -> Result<(String, Vec<Option<SourceLocation>>), MaterialError> {
+        // Included files replace the whole directive line
+        // and are expanded recursively, so nested includes are resolved as well.
+        let mut lines = vec![];
+        expand(source, None, config, &mut vec![], &mut lines)?;
+
+        let defines = config
+            .defines
+            .iter()
+            .map(|(name, value)| format!("#define {} {}", name, value))
+            .collect::<Vec<_>>();
+
+        // #version should be the very first directive, defines go right after it
+        let insert_ix = lines
+            .iter()
+            .position(|(line, _)| line.trim_start().starts_with("#version"))
+            .map_or(0, |ix| ix + 1);
+
+        let mut res = String::with_capacity(source.len());
+        let mut locations = Vec::with_capacity(lines.len() + defines.len());
+        for (ix, (line, location)) in lines.into_iter().enumerate() {
+            if ix == insert_ix {
+                for define in &defines {
+                    res.push_str(define);
+                    res.push('\n');
+                    locations.push(None);
+                }
             }
+            res.push_str(line);
+            res.push('\n');
+            locations.push(location);
         }
-
-        let mut i = 0;
-        while find(&res, &mut i, "#include") {
-            let directive_start_ix = i;
-            i += "#include".len();
-            skip_character(&res, &mut i, ' ');
-            assert!(res[i] == '\"');
-            i += 1;
-            let filename_start_ix = i;
-            find(&res, &mut i, "\"");
-            let filename_end_ix = i;
-            let filename = res[filename_start_ix..filename_end_ix]
-                .iter()
-                .cloned()
-                .collect::<String>();
-
-            let include_content = config
-                .includes
-                .iter()
-                .find(|(name, _)| name == &filename)
-                .expect(&format!(
-                    "Include file {} in not on \"includes\" list",
-                    filename
-                ));
-
-            let _ = res
-                .splice(
-                    directive_start_ix..filename_end_ix + 1,
-                    include_content.1.chars(),
-                )
-                .collect::<Vec<_>>();
+        if insert_ix == locations.len() {
+            for define in &defines {
+                res.push_str(define);
+                res.push('\n');
+                locations.push(None);
+            }
+        }
+        if !source.ends_with('\n') {
+            res.pop();
         }
 
-        res.into_iter().collect()
+        Ok((res, locations))
+    }
+
+    pub fn preprocess_shader(source: &str, config: &PreprocessorConfig) -> String {
+        try_preprocess_shader(source, config)
+            .unwrap_or_else(|err| panic!("{}", err))
+            .0
     }
 
     #[test]
@@ -198,4 +836,82 @@ qwe
 
         assert_eq!(result, preprocessed);
     }
+
+    #[test]
+    fn preprocessor_nested_includes_and_defines() {
+        let config = PreprocessorConfig {
+            includes: vec![
+                ("a.glsl".to_string(), "#include \"b.glsl\"\na".to_string()),
+                ("b.glsl".to_string(), "b".to_string()),
+            ],
+            defines: vec![("LIGHTS".to_string(), "4".to_string())],
+        };
+
+        let result = preprocess_shader("#version 100\n#include \"a.glsl\"\nmain", &config);
+        assert_eq!(result, "#version 100\n#define LIGHTS 4\nb\na\nmain");
+
+        let (_, lines) =
+            try_preprocess_shader("#version 100\n#include \"a.glsl\"\nmain", &config).unwrap();
+        let location = |file: Option<&str>, line| {
+            Some(SourceLocation {
+                file: file.map(|file| file.to_string()),
+                line,
+            })
+        };
+        assert_eq!(
+            lines,
+            vec![
+                location(None, 1),
+                None,
+                location(Some("b.glsl"), 1),
+                location(Some("a.glsl"), 2),
+                location(None, 3),
+            ]
+        );
+
+        assert_eq!(includes("x\n  #include \"a.glsl\"\n"), vec!["a.glsl"]);
+        assert!(matches!(
+            try_preprocess_shader("#include \"c.glsl\"", &config),
+            Err(MaterialError::MissingInclude(name)) if name == "c.glsl"
+        ));
+
+        let config = PreprocessorConfig {
+            includes: vec![
+                ("a.glsl".to_string(), "#include \"b.glsl\"".to_string()),
+                ("b.glsl".to_string(), "#include \"a.glsl\"".to_string()),
+            ],
+            defines: vec![],
+        };
+        assert!(matches!(
+            try_preprocess_shader("#include \"a.glsl\"", &config),
+            Err(MaterialError::IncludeCycle(files)) if files == ["a.glsl", "b.glsl", "a.glsl"]
+        ));
+    }
+
+    #[test]
+    fn shader_log_mapped_to_sources() {
+        let config = PreprocessorConfig {
+            includes: vec![("light.glsl".to_string(), "a\nb".to_string())],
+            defines: vec![("LIGHTS".to_string(), "4".to_string())],
+        };
+        let (_, lines) =
+            try_preprocess_shader("#version 100\n#include \"light.glsl\"\nc", &config).unwrap();
+
+        let log = "0:4(5): error: syntax error\nERROR: 0:5: 'c' : undeclared identifier\n\
+                   0(2) : error C0000: oops\nlink failed\0";
+        let diagnostics = super::parse_shader_log(log, &lines)
+            .into_iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                "light.glsl:2: error: syntax error",
+                "line 3: ERROR: 'c' : undeclared identifier",
+                "error C0000: oops",
+                "link failed"
+            ]
+        );
+    }
 }
@@ -5,7 +5,9 @@
 pub use glam::*;
 
 mod circle;
+pub mod collision;
 mod rect;
+pub mod spatial;
 
 pub use circle::Circle;
 pub use rect::{Rect, RectOffset};
@@ -0,0 +1,513 @@
+//! 2D overlap tests, swept boxes and raycasts, enough for platformers and
+//! top-down games without a physics engine.
+//!
+//! ```ignore
+//! // move the player, sliding along the walls
+//! let (rect, hits) = move_and_slide(player.rect, player.velocity * dt, &walls);
+//! player.rect = rect;
+//! player.on_ground = hits.iter().any(|hit| hit.normal.y < 0.);
+//!
+//! // line of sight
+//! let visible = raycast_shapes(enemy, player.rect.center() - enemy, 500., &level)
+//!     .map_or(true, |(_, hit)| hit.distance > enemy.distance(player.rect.center()));
+//! ```
+
+use crate::math::{vec2, Circle, Rect, Vec2};
+
+/// Penetration the sweeps forgive, the rects placed at a contact may end up
+/// this much inside the obstacle because of the rounding.
+const TOUCH_EPSILON: f32 = 0.001;
+
+/// Rectangle rotated around its center.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OrientedRect {
+    pub center: Vec2,
+    pub half_size: Vec2,
+    /// Radians, clockwise with y pointing down
+    pub rotation: f32,
+}
+
+impl OrientedRect {
+    pub fn new(center: Vec2, size: Vec2, rotation: f32) -> OrientedRect {
+        OrientedRect {
+            center,
+            half_size: size / 2.,
+            rotation,
+        }
+    }
+
+    pub fn corners(&self) -> [Vec2; 4] {
+        let Vec2 { x, y } = self.half_size;
+        [vec2(-x, -y), vec2(x, -y), vec2(x, y), vec2(-x, y)]
+            .map(|corner| self.center + rotate(corner, self.rotation))
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let local = rotate(point - self.center, -self.rotation);
+        local.x.abs() <= self.half_size.x && local.y.abs() <= self.half_size.y
+    }
+}
+
+/// Shapes the overlap tests and the raycasts work with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Rect(Rect),
+    Circle(Circle),
+    OrientedRect(OrientedRect),
+    /// Closed polygon, may be concave
+    Polygon(Vec<Vec2>),
+}
+
+impl From<Rect> for Shape {
+    fn from(rect: Rect) -> Shape {
+        Shape::Rect(rect)
+    }
+}
+
+impl From<Circle> for Shape {
+    fn from(circle: Circle) -> Shape {
+        Shape::Circle(circle)
+    }
+}
+
+impl From<OrientedRect> for Shape {
+    fn from(rect: OrientedRect) -> Shape {
+        Shape::OrientedRect(rect)
+    }
+}
+
+impl Shape {
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Shape::Rect(rect) => rect.contains(point),
+            Shape::Circle(circle) => circle.contains(&point),
+            Shape::OrientedRect(rect) => rect.contains(point),
+            Shape::Polygon(polygon) => point_in_polygon(point, polygon),
+        }
+    }
+
+    /// Smallest rect containing the shape.
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Shape::Rect(rect) => *rect,
+            Shape::Circle(circle) => Rect::new(
+                circle.x - circle.r,
+                circle.y - circle.r,
+                circle.r * 2.,
+                circle.r * 2.,
+            ),
+            _ => {
+                let vertices = self.vertices().unwrap_or_default();
+                let min = vertices
+                    .iter()
+                    .fold(Vec2::splat(f32::INFINITY), |a, b| a.min(*b));
+                let max = vertices
+                    .iter()
+                    .fold(Vec2::splat(f32::NEG_INFINITY), |a, b| a.max(*b));
+                Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+            }
+        }
+    }
+
+    /// Does the shape overlap `other`, touching counts.
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        match (self, other) {
+            (Shape::Rect(a), Shape::Rect(b)) => a.overlaps(b),
+            (Shape::Circle(a), Shape::Circle(b)) => a.point().distance(b.point()) <= a.r + b.r,
+            (Shape::Circle(circle), Shape::Rect(rect))
+            | (Shape::Rect(rect), Shape::Circle(circle)) => circle.overlaps_rect(rect),
+            (Shape::Circle(circle), shape) | (shape, Shape::Circle(circle)) => {
+                let polygon = shape.vertices().unwrap();
+                point_in_polygon(circle.point(), &polygon)
+                    || edges(&polygon)
+                        .any(|(a, b)| segment_distance(circle.point(), a, b) <= circle.r)
+            }
+            _ => {
+                let a = self.vertices().unwrap();
+                let b = other.vertices().unwrap();
+                // an empty polygon has no area to overlap with
+                if a.is_empty() || b.is_empty() {
+                    return false;
+                }
+                point_in_polygon(a[0], &b)
+                    || point_in_polygon(b[0], &a)
+                    || edges(&a).any(|(a0, a1)| {
+                        edges(&b).any(|(b0, b1)| segment_intersection(a0, a1, b0, b1).is_some())
+                    })
+            }
+        }
+    }
+
+    /// Corners of everything but circles
+    fn vertices(&self) -> Option<Vec<Vec2>> {
+        match self {
+            Shape::Rect(rect) => Some(vec![
+                rect.point(),
+                vec2(rect.right(), rect.top()),
+                vec2(rect.right(), rect.bottom()),
+                vec2(rect.left(), rect.bottom()),
+            ]),
+            Shape::Circle(_) => None,
+            Shape::OrientedRect(rect) => Some(rect.corners().to_vec()),
+            Shape::Polygon(polygon) => Some(polygon.clone()),
+        }
+    }
+}
+
+/// Even-odd rule, so self-intersecting polygons have holes.
+pub fn point_in_polygon(point: Vec2, polygon: &[Vec2]) -> bool {
+    let mut inside = false;
+    for (a, b) in edges(polygon) {
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+
+    inside
+}
+
+/// Crossing point of segments `a0`-`a1` and `b0`-`b1`, parallel segments
+/// never cross.
+pub fn segment_intersection(a0: Vec2, a1: Vec2, b0: Vec2, b1: Vec2) -> Option<Vec2> {
+    segment_param(a0, a1 - a0, b0, b1 - b0).map(|t| a0 + (a1 - a0) * t)
+}
+
+/// Position along `a` of the crossing, both segments are crossed in 0..1
+fn segment_param(a: Vec2, a_dir: Vec2, b: Vec2, b_dir: Vec2) -> Option<f32> {
+    let denominator = a_dir.perp_dot(b_dir);
+    if denominator == 0. {
+        return None;
+    }
+
+    let t = (b - a).perp_dot(b_dir) / denominator;
+    let u = (b - a).perp_dot(a_dir) / denominator;
+    if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+fn segment_distance(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab == Vec2::ZERO {
+        0.
+    } else {
+        ((point - a).dot(ab) / ab.length_squared()).clamp(0., 1.)
+    };
+
+    point.distance(a + ab * t)
+}
+
+fn edges(polygon: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+}
+
+fn rotate(v: Vec2, angle: f32) -> Vec2 {
+    let (sin, cos) = angle.sin_cos();
+    vec2(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+/// The shortest move that gets `rect` out of `obstacle`, `None` if they
+/// do not overlap.
+pub fn penetration(rect: Rect, obstacle: Rect) -> Option<Vec2> {
+    let overlap = rect.intersect(obstacle)?;
+    let away = rect.center() - obstacle.center();
+
+    if overlap.w < overlap.h {
+        Some(vec2(overlap.w.copysign(away.x), 0.))
+    } else {
+        Some(vec2(0., overlap.h.copysign(away.y)))
+    }
+}
+
+/// Where a moving rect hits an obstacle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepHit {
+    /// Part of the move done before the contact, 0..1
+    pub time: f32,
+    /// Obstacle side that was hit, pointing out of it
+    pub normal: Vec2,
+    /// Position of the moving rect at the contact
+    pub rect: Rect,
+}
+
+/// Move `rect` by `velocity` and see if it hits `obstacle` on the way.
+///
+/// Rects already overlapping at the start are not hits, get them apart with
+/// [penetration]. Touching does not count either, so rects can slide along
+/// the walls they stand on. A rect that doesn't move hits nothing.
+pub fn sweep_rect(rect: Rect, velocity: Vec2, obstacle: Rect) -> Option<SweepHit> {
+    if velocity == Vec2::ZERO {
+        return None;
+    }
+
+    // a point moving into the obstacle grown by the rect size
+    let half = rect.size() / 2.;
+    let min = obstacle.point() - half;
+    let max = vec2(obstacle.right(), obstacle.bottom()) + half;
+
+    let (mut time, normal) = slab(rect.center(), velocity, min, max)?;
+    if time > 1. {
+        return None;
+    }
+    if time < 0. {
+        let depth = time * velocity.dot(normal);
+        if depth > TOUCH_EPSILON {
+            return None;
+        }
+        time = 0.;
+    }
+
+    Some(SweepHit {
+        time,
+        normal,
+        rect: rect.offset(velocity * time),
+    })
+}
+
+/// Move `rect` by `velocity`, sliding along the `obstacles` it hits.
+/// Returns the new position and the hits, in the order they happened.
+pub fn move_and_slide(rect: Rect, velocity: Vec2, obstacles: &[Rect]) -> (Rect, Vec<SweepHit>) {
+    let mut rect = rect;
+    let mut velocity = velocity;
+    let mut hits = vec![];
+
+    // a corner stops both axes, there is nothing left to slide after two hits
+    for _ in 0..2 {
+        let hit = obstacles
+            .iter()
+            .filter_map(|obstacle| sweep_rect(rect, velocity, *obstacle))
+            .min_by(|a, b| a.time.total_cmp(&b.time));
+
+        match hit {
+            Some(hit) => {
+                let remaining = velocity * (1. - hit.time);
+                rect = hit.rect;
+                velocity = remaining - hit.normal * remaining.dot(hit.normal);
+                hits.push(hit);
+            }
+            None => break,
+        }
+    }
+
+    if hits.len() < 2 {
+        rect = rect.offset(velocity);
+    }
+
+    (rect, hits)
+}
+
+/// Time along `direction` the point at `origin` enters the box, with the
+/// side it enters through. Negative when it starts inside.
+fn slab(origin: Vec2, direction: Vec2, min: Vec2, max: Vec2) -> Option<(f32, Vec2)> {
+    let mut enter = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = Vec2::ZERO;
+
+    for (axis, side) in [(0, Vec2::X), (1, Vec2::Y)] {
+        let (origin, direction, min, max) = (origin[axis], direction[axis], min[axis], max[axis]);
+        if direction == 0. {
+            if origin <= min || origin >= max {
+                return None;
+            }
+            continue;
+        }
+
+        let t0 = (min - origin) / direction;
+        let t1 = (max - origin) / direction;
+        let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+        if near > enter {
+            enter = near;
+            normal = side * -direction.signum();
+        }
+        exit = exit.min(far);
+    }
+
+    if enter >= exit || exit <= 0. {
+        None
+    } else {
+        Some((enter, normal))
+    }
+}
+
+/// Where a ray hits a shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub distance: f32,
+    pub point: Vec2,
+    /// Pointing out of the shape, opposite to the ray for rays starting
+    /// inside
+    pub normal: Vec2,
+}
+
+/// First hit of the ray from `origin` along `direction` with `shape`, up to
+/// `max_distance` away. Rays starting inside hit at distance 0.
+pub fn raycast(origin: Vec2, direction: Vec2, max_distance: f32, shape: &Shape) -> Option<RayHit> {
+    let direction = direction.normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return None;
+    }
+    let inside = RayHit {
+        distance: 0.,
+        point: origin,
+        normal: -direction,
+    };
+
+    let (distance, normal) = match shape {
+        Shape::Rect(rect) => {
+            let max = vec2(rect.right(), rect.bottom());
+            if origin.cmpge(rect.point()).all() && origin.cmple(max).all() {
+                return Some(inside);
+            }
+            slab(origin, direction, rect.point(), max)?
+        }
+        Shape::Circle(circle) => {
+            let to_center = circle.point() - origin;
+            let c = to_center.length_squared() - circle.r * circle.r;
+            if c <= 0. {
+                return Some(inside);
+            }
+            let b = to_center.dot(direction);
+            let discriminant = b * b - c;
+            if b < 0. || discriminant < 0. {
+                return None;
+            }
+            let distance = b - discriminant.sqrt();
+            let point = origin + direction * distance;
+            (distance, (point - circle.point()) / circle.r)
+        }
+        Shape::OrientedRect(rect) => {
+            if rect.contains(origin) {
+                return Some(inside);
+            }
+            let local = rotate(origin - rect.center, -rect.rotation);
+            let local_direction = rotate(direction, -rect.rotation);
+            let (distance, normal) = slab(local, local_direction, -rect.half_size, rect.half_size)?;
+            (distance, rotate(normal, rect.rotation))
+        }
+        Shape::Polygon(polygon) => {
+            if point_in_polygon(origin, polygon) {
+                return Some(inside);
+            }
+            edges(polygon)
+                .filter_map(|(a, b)| {
+                    let distance = ray_segment(origin, direction, a, b)?;
+                    let edge = (b - a).normalize_or_zero();
+                    let normal = vec2(edge.y, -edge.x);
+                    let normal = if normal.dot(direction) > 0. {
+                        -normal
+                    } else {
+                        normal
+                    };
+                    Some((distance, normal))
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0))?
+        }
+    };
+
+    if distance > max_distance {
+        return None;
+    }
+
+    Some(RayHit {
+        distance,
+        point: origin + direction * distance,
+        normal,
+    })
+}
+
+/// Distance along the normalized `direction` to the segment `a`-`b`
+fn ray_segment(origin: Vec2, direction: Vec2, a: Vec2, b: Vec2) -> Option<f32> {
+    let denominator = direction.perp_dot(b - a);
+    if denominator == 0. {
+        return None;
+    }
+
+    let distance = (a - origin).perp_dot(b - a) / denominator;
+    let u = (a - origin).perp_dot(direction) / denominator;
+    if distance >= 0. && (0. ..=1.).contains(&u) {
+        Some(distance)
+    } else {
+        None
+    }
+}
+
+/// Nearest hit among the `shapes`, with the index of the shape hit.
+pub fn raycast_shapes(
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+    shapes: &[Shape],
+) -> Option<(usize, RayHit)> {
+    shapes
+        .iter()
+        .enumerate()
+        .filter_map(|(n, shape)| Some((n, raycast(origin, direction, max_distance, shape)?)))
+        .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+}
+
+#[test]
+fn sweep_and_slide() {
+    let floor = Rect::new(0., 10., 100., 10.);
+    let player = Rect::new(0., 0., 4., 4.);
+
+    let hit = sweep_rect(player, vec2(0., 12.), floor).unwrap();
+    assert_eq!(hit.time, 0.5);
+    assert_eq!(hit.normal, vec2(0., -1.));
+    assert_eq!(hit.rect, Rect::new(0., 6., 4., 4.));
+
+    // standing on the floor and walking along it
+    assert_eq!(sweep_rect(hit.rect, vec2(5., 0.), floor), None);
+    assert_eq!(sweep_rect(hit.rect, vec2(0., 0.), floor), None);
+
+    let (rect, hits) = move_and_slide(player, vec2(8., 12.), &[floor]);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(rect, Rect::new(8., 6., 4., 4.));
+
+    assert_eq!(
+        penetration(Rect::new(0., 8., 4., 4.), floor),
+        Some(vec2(0., -2.))
+    );
+}
+
+#[test]
+fn shapes_and_rays() {
+    let triangle = Shape::Polygon(vec![vec2(0., 0.), vec2(10., 0.), vec2(0., 10.)]);
+    assert!(triangle.contains(vec2(2., 2.)));
+    assert!(!triangle.contains(vec2(8., 8.)));
+    assert!(triangle.overlaps(&Circle::new(6., 6., 1.5).into()));
+    assert!(!triangle.overlaps(&Circle::new(8., 8., 1.).into()));
+    assert!(!triangle.overlaps(&Shape::Polygon(vec![])));
+
+    let diamond: Shape =
+        OrientedRect::new(vec2(20., 0.), vec2(4., 4.), std::f32::consts::FRAC_PI_4).into();
+    assert!(!diamond.overlaps(&Rect::new(16., -4., 1.5, 1.5).into()));
+    assert!(diamond.overlaps(&Rect::new(21., -1., 5., 2.).into()));
+
+    assert_eq!(
+        segment_intersection(vec2(0., 0.), vec2(2., 2.), vec2(0., 2.), vec2(2., 0.)),
+        Some(vec2(1., 1.))
+    );
+
+    let shapes = [
+        diamond,
+        Shape::Rect(Rect::new(10., -1., 2., 2.)),
+        Circle::new(30., 0., 1.).into(),
+    ];
+    let (n, hit) = raycast_shapes(vec2(0., 0.), vec2(1., 0.), 100., &shapes).unwrap();
+    assert_eq!(n, 1);
+    assert_eq!(hit.point, vec2(10., 0.));
+    assert_eq!(hit.normal, vec2(-1., 0.));
+
+    let hit = raycast(vec2(40., 0.), vec2(-1., 0.), 100., &shapes[2]).unwrap();
+    assert_eq!(hit.distance, 9.);
+    assert_eq!(hit.normal, vec2(1., 0.));
+    assert_eq!(raycast(vec2(0., 5.), vec2(1., 0.), 100., &shapes[2]), None);
+}
@@ -0,0 +1,249 @@
+//! Spatial hash, for the "what is near this point" and "what is in this
+//! rect" questions of the collisions and the culling, without checking
+//! every object.
+//!
+//! ```ignore
+//! let mut index = SpatialHash::new(64.);
+//! for (n, enemy) in enemies.iter().enumerate() {
+//!     index.insert(n, enemy.rect);
+//! }
+//!
+//! for n in index.query_radius(explosion.center, explosion.radius) {
+//!     enemies[n].hp -= 10;
+//! }
+//! let visible = index.query_rect(camera_rect);
+//! ```
+//!
+//! For the scene nodes, [crate::experimental::scene::Query::spatial_index]
+//! builds one keyed by the node handles.
+
+use crate::math::{vec2, Rect, Vec2};
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+type Cell = (i32, i32);
+
+struct Entry {
+    rect: Rect,
+    /// First and last cells the rect is in
+    cells: (Cell, Cell),
+}
+
+/// Objects keyed by `K`, in a grid of square cells.
+///
+/// The cells are best about the size of the objects, or a bit bigger: a
+/// lot of small cells make the big objects slow to insert, and big cells
+/// make the queries check more objects.
+pub struct SpatialHash<K> {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<K>>,
+    entries: HashMap<K, Entry>,
+}
+
+impl<K: Copy + Eq + Hash> SpatialHash<K> {
+    pub fn new(cell_size: f32) -> SpatialHash<K> {
+        assert!(cell_size > 0., "Spatial hash needs a positive cell size");
+
+        SpatialHash {
+            cell_size,
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn cell(&self, point: Vec2) -> Cell {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cell_range(&self, rect: Rect) -> (Cell, Cell) {
+        (
+            self.cell(rect.point()),
+            self.cell(vec2(rect.right(), rect.bottom())),
+        )
+    }
+
+    /// Add `key` with the bounds `rect`, or move it there if it is already
+    /// in.
+    pub fn insert(&mut self, key: K, rect: Rect) {
+        let cells = self.cell_range(rect);
+
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.rect = rect;
+            if entry.cells == cells {
+                return;
+            }
+            let old = std::mem::replace(&mut entry.cells, cells);
+            self.unlink(key, old);
+        } else {
+            self.entries.insert(key, Entry { rect, cells });
+        }
+
+        let ((x0, y0), (x1, y1)) = cells;
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                self.cells.entry((x, y)).or_default().push(key);
+            }
+        }
+    }
+
+    /// [SpatialHash::insert] for the objects as small as a point.
+    pub fn insert_point(&mut self, key: K, point: Vec2) {
+        self.insert(key, Rect::new(point.x, point.y, 0., 0.));
+    }
+
+    /// Take `key` out, returns its bounds if it was in.
+    pub fn remove(&mut self, key: K) -> Option<Rect> {
+        let entry = self.entries.remove(&key)?;
+        self.unlink(key, entry.cells);
+
+        Some(entry.rect)
+    }
+
+    fn unlink(&mut self, key: K, ((x0, y0), (x1, y1)): (Cell, Cell)) {
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                if let Some(keys) = self.cells.get_mut(&(x, y)) {
+                    keys.retain(|other| *other != key);
+                    if keys.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, key: K) -> bool {
+        self.entries.contains_key(&key)
+    }
+
+    /// Bounds `key` was inserted with.
+    pub fn bounds(&self, key: K) -> Option<Rect> {
+        self.entries.get(&key).map(|entry| entry.rect)
+    }
+
+    /// Keys with the bounds overlapping `rect`, edges included, each once.
+    pub fn query_rect(&self, rect: Rect) -> Vec<K> {
+        let ((qx0, qy0), (qx1, qy1)) = self.cell_range(rect);
+        let mut found = vec![];
+
+        // big queries go through the objects instead of the empty cells
+        let cell_count = (qx1 as i64 - qx0 as i64 + 1) * (qy1 as i64 - qy0 as i64 + 1);
+        if cell_count > self.entries.len() as i64 {
+            for (key, entry) in &self.entries {
+                if entry.rect.overlaps(&rect) {
+                    found.push(*key);
+                }
+            }
+            return found;
+        }
+
+        for x in qx0..=qx1 {
+            for y in qy0..=qy1 {
+                for key in self.cells.get(&(x, y)).into_iter().flatten() {
+                    let entry = &self.entries[key];
+                    let ((x0, y0), _) = entry.cells;
+
+                    // the objects in a few cells are found in the first
+                    // one both them and the query are in
+                    if (x, y) == (x0.max(qx0), y0.max(qy0)) && entry.rect.overlaps(&rect) {
+                        found.push(*key);
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Keys with the bounds containing `point`, edges included.
+    pub fn query_point(&self, point: Vec2) -> Vec<K> {
+        self.query_rect(Rect::new(point.x, point.y, 0., 0.))
+    }
+
+    /// Keys with the bounds within `radius` of `center`.
+    pub fn query_radius(&self, center: Vec2, radius: f32) -> Vec<K> {
+        let around = Rect::new(
+            center.x - radius,
+            center.y - radius,
+            radius * 2.,
+            radius * 2.,
+        );
+        let mut found = self.query_rect(around);
+
+        found.retain(|key| distance_to(self.entries[key].rect, center) <= radius);
+        found
+    }
+
+    /// Key with the bounds closest to `point`, up to `max_distance` away.
+    pub fn nearest(&self, point: Vec2, max_distance: f32) -> Option<K> {
+        self.query_radius(point, max_distance)
+            .into_iter()
+            .map(|key| (key, distance_to(self.entries[&key].rect, point)))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(key, _)| key)
+    }
+
+    /// All the keys with their bounds.
+    pub fn iter(&self) -> impl Iterator<Item = (K, Rect)> + '_ {
+        self.entries.iter().map(|(key, entry)| (*key, entry.rect))
+    }
+}
+
+/// Distance from `point` to the closest point of `rect`, 0 inside.
+fn distance_to(rect: Rect, point: Vec2) -> f32 {
+    let closest = point.clamp(rect.point(), vec2(rect.right(), rect.bottom()));
+
+    closest.distance(point)
+}
+
+#[test]
+fn spatial_hash_queries() {
+    let mut index = SpatialHash::new(10.);
+    index.insert(1, Rect::new(0., 0., 5., 5.));
+    // spans 3x3 cells
+    index.insert(2, Rect::new(5., 5., 20., 20.));
+    index.insert_point(3, vec2(-15., 2.));
+    for n in 10..40 {
+        index.insert_point(n, vec2(500. + n as f32, 500.));
+    }
+
+    let mut found = index.query_rect(Rect::new(-1., -1., 30., 30.));
+    found.sort_unstable();
+    assert_eq!(found, [1, 2]);
+    assert_eq!(index.query_point(vec2(24., 24.)), [2]);
+    assert_eq!(index.query_radius(vec2(-14., 3.), 2.), [3]);
+    assert_eq!(index.nearest(vec2(-4., 0.), 20.), Some(1));
+    // a query larger than the objects
+    assert_eq!(
+        index.query_rect(Rect::new(-1000., -1000., 990., 2000.)),
+        [3]
+    );
+
+    index.insert(2, Rect::new(100., 100., 1., 1.));
+    assert!(index.query_point(vec2(24., 24.)).is_empty());
+    assert_eq!(index.query_point(vec2(100.5, 100.5)), [2]);
+    assert_eq!(index.remove(1), Some(Rect::new(0., 0., 5., 5.)));
+    assert_eq!(index.remove(1), None);
+    assert_eq!(index.len(), 32);
+    assert!(index.cells.values().all(|keys| !keys.is_empty()));
+}
@@ -3,7 +3,11 @@
 use crate::{color::Color, get_context};
 
 use crate::{quad_gl::DrawMode, texture::Texture2D};
-use glam::{vec2, vec3, Vec2, Vec3};
+use glam::{vec2, vec3, Mat4, Vec2, Vec3};
+
+mod hierarchy;
+
+pub use hierarchy::{NodeId, TransformHierarchy};
 
 #[derive(Clone, Debug, Copy)]
 pub struct Vertex {
@@ -28,6 +32,197 @@ pub struct Mesh {
     pub texture: Option<Texture2D>,
 }
 
+/// Error returned by [Mesh::from_bytes] when the data is not a valid serialized mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshFormatError {
+    /// Data does not start with the mesh magic number.
+    InvalidMagic,
+    /// Mesh was serialized with a format version this macroquad can't read.
+    UnsupportedVersion(u16),
+    /// Data ended before all the vertices and indices were read.
+    UnexpectedEof,
+}
+
+impl std::error::Error for MeshFormatError {}
+impl std::fmt::Display for MeshFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MeshFormatError::InvalidMagic => write!(f, "Not a macroquad mesh"),
+            MeshFormatError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported mesh format version {}", version)
+            }
+            MeshFormatError::UnexpectedEof => write!(f, "Unexpected end of mesh data"),
+        }
+    }
+}
+
+impl Mesh {
+    const MAGIC: &'static [u8; 4] = b"MQMS";
+    const FORMAT_VERSION: u16 = 1;
+    // position: 3 x f32, uv: 2 x f32, color: 4 x u8
+    const VERTEX_SIZE: usize = 3 * 4 + 2 * 4 + 4;
+
+    /// Serialize vertices and indices into a compact little-endian binary format.
+    ///
+    /// Meant for preprocessing heavy assets offline and loading them with
+    /// [Mesh::from_bytes] at runtime. Texture is not serialized.
+    ///
+    /// Layout: `"MQMS"`, version (u16), vertices count (u32), indices count (u32),
+    /// then vertices (position, uv as f32, color as u8 rgba) and indices (u16).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        assert!(
+            self.vertices.len() <= u32::MAX as usize && self.indices.len() <= u32::MAX as usize,
+            "Mesh is too big to serialize"
+        );
+
+        let mut bytes = Vec::with_capacity(
+            14 + self.vertices.len() * Self::VERTEX_SIZE + self.indices.len() * 2,
+        );
+
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&Self::FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.vertices.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.indices.len() as u32).to_le_bytes());
+
+        for vertex in &self.vertices {
+            for x in vertex
+                .position
+                .to_array()
+                .iter()
+                .chain(&vertex.uv.to_array())
+            {
+                bytes.extend_from_slice(&x.to_le_bytes());
+            }
+            let color: [u8; 4] = vertex.color.into();
+            bytes.extend_from_slice(&color);
+        }
+        for index in &self.indices {
+            bytes.extend_from_slice(&index.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// Deserialize a mesh produced by [Mesh::to_bytes].
+    ///
+    /// The resulting mesh has no texture.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Mesh, MeshFormatError> {
+        use std::convert::TryInto;
+
+        struct Reader<'a>(&'a [u8]);
+
+        impl<'a> Reader<'a> {
+            fn take<const N: usize>(&mut self) -> Result<[u8; N], MeshFormatError> {
+                if self.0.len() < N {
+                    return Err(MeshFormatError::UnexpectedEof);
+                }
+                let (head, tail) = self.0.split_at(N);
+                self.0 = tail;
+                Ok(head.try_into().unwrap())
+            }
+
+            fn f32(&mut self) -> Result<f32, MeshFormatError> {
+                Ok(f32::from_le_bytes(self.take()?))
+            }
+        }
+
+        let mut reader = Reader(bytes);
+
+        if &reader
+            .take::<4>()
+            .map_err(|_| MeshFormatError::InvalidMagic)?
+            != Self::MAGIC
+        {
+            return Err(MeshFormatError::InvalidMagic);
+        }
+        let version = u16::from_le_bytes(reader.take()?);
+        if version != Self::FORMAT_VERSION {
+            return Err(MeshFormatError::UnsupportedVersion(version));
+        }
+        let vertices_count = u32::from_le_bytes(reader.take()?) as usize;
+        let indices_count = u32::from_le_bytes(reader.take()?) as usize;
+
+        // the counts are read from the file, the size may not even fit usize on 32 bits
+        let size = vertices_count
+            .checked_mul(Self::VERTEX_SIZE)
+            .and_then(|size| size.checked_add(indices_count.checked_mul(2)?))
+            .ok_or(MeshFormatError::UnexpectedEof)?;
+        if reader.0.len() < size {
+            return Err(MeshFormatError::UnexpectedEof);
+        }
+
+        let mut vertices = Vec::with_capacity(vertices_count);
+        for _ in 0..vertices_count {
+            let position = vec3(reader.f32()?, reader.f32()?, reader.f32()?);
+            let uv = vec2(reader.f32()?, reader.f32()?);
+            let color: [u8; 4] = reader.take()?;
+
+            vertices.push(Vertex {
+                position,
+                uv,
+                color: color.into(),
+            });
+        }
+
+        let mut indices = Vec::with_capacity(indices_count);
+        for _ in 0..indices_count {
+            indices.push(u16::from_le_bytes(reader.take()?));
+        }
+
+        Ok(Mesh {
+            vertices,
+            indices,
+            texture: None,
+        })
+    }
+}
+
+#[test]
+fn mesh_bytes_roundtrip() {
+    use crate::color::colors::*;
+
+    let mesh = Mesh {
+        vertices: vec![
+            Vertex {
+                position: vec3(0., 1., 2.),
+                uv: vec2(0., 1.),
+                color: RED,
+            },
+            Vertex {
+                position: vec3(-1., 0.5, 3.),
+                uv: vec2(1., 0.),
+                color: WHITE,
+            },
+        ],
+        indices: vec![0, 1, 0],
+        texture: None,
+    };
+    let bytes = mesh.to_bytes();
+    let decoded = Mesh::from_bytes(&bytes).unwrap();
+
+    assert_eq!(decoded.indices, mesh.indices);
+    assert_eq!(decoded.vertices[1].position, mesh.vertices[1].position);
+    assert_eq!(
+        Into::<[u8; 4]>::into(decoded.vertices[0].color),
+        Into::<[u8; 4]>::into(RED)
+    );
+
+    assert_eq!(
+        Mesh::from_bytes(&bytes[..bytes.len() - 1]).err(),
+        Some(MeshFormatError::UnexpectedEof)
+    );
+    let mut huge = bytes[..14].to_vec();
+    huge[6..10].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        Mesh::from_bytes(&huge).err(),
+        Some(MeshFormatError::UnexpectedEof)
+    );
+    assert_eq!(
+        Mesh::from_bytes(b"OBJ!").err(),
+        Some(MeshFormatError::InvalidMagic)
+    );
+}
+
 pub fn draw_mesh(mesh: &Mesh) {
     let context = get_context();
 
@@ -36,6 +231,16 @@ pub fn draw_mesh(mesh: &Mesh) {
     context.gl.geometry(&mesh.vertices[..], &mesh.indices[..]);
 }
 
+/// Draw a mesh with given model transform applied on top of the current one.
+/// Useful with [TransformHierarchy::world] for articulated objects.
+pub fn draw_mesh_with_transform(mesh: &Mesh, transform: Mat4) {
+    let context = get_context();
+
+    context.gl.push_model_matrix(transform);
+    draw_mesh(mesh);
+    get_context().gl.pop_model_matrix();
+}
+
 fn draw_quad(vertices: [(Vec3, Vec2, Color); 4]) {
     let context = get_context();
     let indices = [0, 1, 2, 0, 2, 3];
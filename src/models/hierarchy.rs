@@ -0,0 +1,239 @@
+use glam::{Mat4, Quat, Vec3};
+
+/// Handle to a node inside a [TransformHierarchy].
+///
+/// The slots of the removed nodes are reused, the generation tells a
+/// handle to the removed node from the handle to the new one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: usize,
+}
+
+struct Slot {
+    generation: usize,
+    node: Option<Node>,
+}
+
+struct Node {
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    local: Mat4,
+    world: Mat4,
+    dirty: bool,
+}
+
+/// Lightweight parent/child transform graph.
+///
+/// Each node has a local transform, relative to its parent.
+/// World transforms are calculated lazily: changing a node's local
+/// transform marks the node and all its descendants dirty and
+/// world matrices get recomputed on the next [TransformHierarchy::world] call.
+///
+/// ```no_run
+/// # use macroquad::prelude::*;
+/// # let arm_mesh: Mesh = unimplemented!();
+/// let mut hierarchy = TransformHierarchy::new();
+/// let body = hierarchy.add_node(None, Mat4::from_translation(vec3(0., 1., 0.)));
+/// let arm = hierarchy.add_node(Some(body), Mat4::from_translation(vec3(0.5, 0., 0.)));
+///
+/// hierarchy.set_local(body, Mat4::from_rotation_y(get_time() as f32));
+/// draw_mesh_with_transform(&arm_mesh, hierarchy.world(arm));
+/// ```
+#[derive(Default)]
+pub struct TransformHierarchy {
+    nodes: Vec<Slot>,
+    free: Vec<usize>,
+}
+
+impl TransformHierarchy {
+    pub fn new() -> TransformHierarchy {
+        TransformHierarchy::default()
+    }
+
+    /// Add a new node with given local transform.
+    /// With `parent` being `None` the node is a root and local transform is the world transform.
+    pub fn add_node(&mut self, parent: Option<NodeId>, local: Mat4) -> NodeId {
+        let node = Node {
+            parent,
+            children: vec![],
+            local,
+            world: local,
+            dirty: true,
+        };
+        let index = if let Some(index) = self.free.pop() {
+            self.nodes[index].node = Some(node);
+            index
+        } else {
+            self.nodes.push(Slot {
+                generation: 0,
+                node: Some(node),
+            });
+            self.nodes.len() - 1
+        };
+        let id = NodeId {
+            index,
+            generation: self.nodes[index].generation,
+        };
+
+        if let Some(parent) = parent {
+            self.node_mut(parent).children.push(id);
+        }
+        id
+    }
+
+    /// Remove the node together with all its descendants.
+    pub fn remove_node(&mut self, id: NodeId) {
+        if let Some(parent) = self.node(id).parent {
+            self.node_mut(parent).children.retain(|child| *child != id);
+        }
+
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let slot = &mut self.nodes[id.index];
+            let node = slot.node.take().unwrap();
+            slot.generation += 1;
+            stack.extend(node.children);
+            self.free.push(id.index);
+        }
+    }
+
+    /// Re-attach the node to a different parent, or make it a root with `None`.
+    /// Local transform is kept as is, so the world transform will change.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let mut ancestor = parent;
+        while let Some(ancestor_id) = ancestor {
+            assert!(ancestor_id != id, "Node can't be a parent of itself");
+            ancestor = self.node(ancestor_id).parent;
+        }
+
+        if let Some(old_parent) = self.node(id).parent {
+            self.node_mut(old_parent)
+                .children
+                .retain(|child| *child != id);
+        }
+        if let Some(parent) = parent {
+            self.node_mut(parent).children.push(id);
+        }
+        self.node_mut(id).parent = parent;
+        self.mark_dirty(id);
+    }
+
+    /// `false` once the node was removed, even if its slot was reused.
+    pub fn contains(&self, id: NodeId) -> bool {
+        match self.nodes.get(id.index) {
+            Some(slot) => slot.generation == id.generation && slot.node.is_some(),
+            None => false,
+        }
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.node(id).children
+    }
+
+    pub fn local(&self, id: NodeId) -> Mat4 {
+        self.node(id).local
+    }
+
+    pub fn set_local(&mut self, id: NodeId, local: Mat4) {
+        self.node_mut(id).local = local;
+        self.mark_dirty(id);
+    }
+
+    /// Set local transform from translation, rotation and scale.
+    pub fn set_local_trs(&mut self, id: NodeId, translation: Vec3, rotation: Quat, scale: Vec3) {
+        self.set_local(
+            id,
+            Mat4::from_scale_rotation_translation(scale, rotation, translation),
+        );
+    }
+
+    /// World transform of the node, all the parent transforms applied.
+    pub fn world(&mut self, id: NodeId) -> Mat4 {
+        let node = self.node(id);
+        if !node.dirty {
+            return node.world;
+        }
+
+        let (parent, local) = (node.parent, node.local);
+        let world = match parent {
+            Some(parent) => self.world(parent) * local,
+            None => local,
+        };
+
+        let node = self.node_mut(id);
+        node.world = world;
+        node.dirty = false;
+        world
+    }
+
+    /// Recompute all the dirty world transforms at once.
+    pub fn update(&mut self) {
+        for index in 0..self.nodes.len() {
+            let slot = &self.nodes[index];
+            if slot.node.is_some() {
+                let generation = slot.generation;
+                self.world(NodeId { index, generation });
+            }
+        }
+    }
+
+    fn mark_dirty(&mut self, id: NodeId) {
+        // world() cleans nodes only up the parent chain, so
+        // an already dirty node always has all its descendants dirty
+        let node = self.node_mut(id);
+        if node.dirty {
+            return;
+        }
+        node.dirty = true;
+        for child in node.children.clone() {
+            self.mark_dirty(child);
+        }
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        let slot = &self.nodes[id.index];
+        match &slot.node {
+            Some(node) if slot.generation == id.generation => node,
+            _ => panic!("Node {:?} was removed", id),
+        }
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        let slot = &mut self.nodes[id.index];
+        match &mut slot.node {
+            Some(node) if slot.generation == id.generation => node,
+            _ => panic!("Node {:?} was removed", id),
+        }
+    }
+}
+
+#[test]
+fn hierarchy_world_transforms() {
+    use glam::vec3;
+
+    let mut hierarchy = TransformHierarchy::new();
+    let root = hierarchy.add_node(None, Mat4::from_translation(vec3(1., 0., 0.)));
+    let child = hierarchy.add_node(Some(root), Mat4::from_translation(vec3(0., 2., 0.)));
+
+    let origin = |m: Mat4| m.transform_point3(Vec3::ZERO);
+    assert_eq!(origin(hierarchy.world(child)), vec3(1., 2., 0.));
+
+    hierarchy.set_local(root, Mat4::from_translation(vec3(5., 0., 0.)));
+    assert_eq!(origin(hierarchy.world(child)), vec3(5., 2., 0.));
+
+    hierarchy.set_parent(child, None);
+    assert_eq!(origin(hierarchy.world(child)), vec3(0., 2., 0.));
+
+    hierarchy.remove_node(root);
+    let reused = hierarchy.add_node(Some(child), Mat4::IDENTITY);
+    assert_eq!(hierarchy.children(child), &[reused]);
+    // the slot of the root is reused, its old handle stays invalid
+    assert_ne!(reused, root);
+    assert!(!hierarchy.contains(root));
+    assert!(hierarchy.contains(reused));
+}
@@ -14,14 +14,15 @@ pub use crate::window::*;
 
 pub use crate::color::{colors::*, Color};
 pub use crate::quad_gl::{DrawMode, GlPipeline, QuadGl, Vertex};
+pub use crate::rand;
 pub use glam;
-pub use miniquad::{conf::Conf, Comparison, PipelineParams, ShaderError, UniformType};
-pub use quad_rand as rand;
+pub use miniquad::{conf::Conf, Comparison, PipelineParams, ShaderError, ShaderType, UniformType};
 
 pub use crate::experimental::*;
 
 pub use crate::logging::*;
 
 pub use crate::color_u8;
+pub use crate::profile_scope;
 
 pub use image::ImageFormat;
@@ -295,6 +295,9 @@ struct GlState {
 
     render_pass: Option<RenderPass>,
     capture: bool,
+
+    view: glam::Mat4,
+    camera_position: glam::Vec3,
 }
 
 impl GlState {
@@ -313,6 +316,8 @@ struct Uniform {
 #[derive(Clone)]
 struct PipelineExt {
     pipeline: miniquad::Pipeline,
+    /// Same pipeline with `PrimitiveType::Lines`, for `DrawMode::Lines` draw calls
+    lines_pipeline: miniquad::Pipeline,
     wants_screen_texture: bool,
     uniforms: Vec<Uniform>,
     uniforms_data: Vec<u8>,
@@ -362,6 +367,25 @@ impl PipelineExt {
         transmute_uniform!(uniform_byte_size, uniform_byte_offset, 16);
         transmute_uniform!(uniform_byte_size, uniform_byte_offset, 64);
     }
+
+    /// Overwrite all the custom (non built-in) uniforms at once.
+    fn set_uniforms_from_bytes(&mut self, data: &[u8]) {
+        let builtin_uniforms = shader::uniforms().len();
+        let offset = self
+            .uniforms
+            .get(builtin_uniforms)
+            .map_or(self.uniforms_data.len(), |uniform| uniform.byte_offset);
+
+        if self.uniforms_data.len() - offset != data.len() {
+            warn!(
+                "Trying to set uniforms block sized {} bytes with a value of {} bytes",
+                self.uniforms_data.len() - offset,
+                data.len()
+            );
+            return;
+        }
+        self.uniforms_data[offset..].copy_from_slice(data);
+    }
 }
 
 struct PipelinesStorage {
@@ -462,17 +486,27 @@ impl PipelinesStorage {
         mut uniforms: Vec<(String, UniformType)>,
         textures: Vec<String>,
     ) -> GlPipeline {
-        let pipeline = Pipeline::with_params(
-            ctx,
-            &[BufferLayout::default()],
-            &[
-                VertexAttribute::new("position", VertexFormat::Float3),
-                VertexAttribute::new("texcoord", VertexFormat::Float2),
-                VertexAttribute::new("color0", VertexFormat::Byte4),
-            ],
-            shader,
-            params,
-        );
+        let attributes = [
+            VertexAttribute::new("position", VertexFormat::Float3),
+            VertexAttribute::new("texcoord", VertexFormat::Float2),
+            VertexAttribute::new("color0", VertexFormat::Byte4),
+        ];
+        let pipeline =
+            Pipeline::with_params(ctx, &[BufferLayout::default()], &attributes, shader, params);
+        let lines_pipeline = if params.primitive_type == PrimitiveType::Lines {
+            pipeline
+        } else {
+            Pipeline::with_params(
+                ctx,
+                &[BufferLayout::default()],
+                &attributes,
+                shader,
+                PipelineParams {
+                    primitive_type: PrimitiveType::Lines,
+                    ..params
+                },
+            )
+        };
 
         let id = self
             .pipelines
@@ -504,6 +538,7 @@ impl PipelinesStorage {
 
         self.pipelines[id] = Some(PipelineExt {
             pipeline,
+            lines_pipeline,
             wants_screen_texture,
             uniforms,
             uniforms_data: vec![0; max_offset],
@@ -515,6 +550,53 @@ impl PipelinesStorage {
         GlPipeline(id)
     }
 
+    /// Move `source` pipeline into the `target` slot, preserving uniform values and textures
+    /// with matching names, so all the `target` handles remain valid.
+    /// `source` handle is invalidated.
+    fn replace_pipeline(&mut self, target: GlPipeline, source: GlPipeline) {
+        let mut pipeline = self.pipelines[source.0].take().unwrap();
+        self.pipelines_amount -= 1;
+
+        let old = self.get_quad_pipeline_mut(target);
+
+        for uniform in &pipeline.uniforms {
+            if let Some(old_uniform) = old.uniforms.iter().find(|old_uniform| {
+                old_uniform.name == uniform.name
+                    && std::mem::discriminant(&old_uniform.uniform_type)
+                        == std::mem::discriminant(&uniform.uniform_type)
+            }) {
+                let size = uniform.uniform_type.size();
+                pipeline.uniforms_data[uniform.byte_offset..uniform.byte_offset + size]
+                    .copy_from_slice(
+                        &old.uniforms_data[old_uniform.byte_offset..old_uniform.byte_offset + size],
+                    );
+            }
+        }
+        for name in &pipeline.textures {
+            if let Some(texture) = old.textures_data.get(name) {
+                pipeline.textures_data.insert(name.clone(), *texture);
+            }
+        }
+
+        *old = pipeline;
+    }
+
+    /// Copy of the pipeline in a new slot, sharing the GPU pipeline
+    /// but with its own uniforms and textures.
+    fn clone_pipeline(&mut self, pip: GlPipeline) -> GlPipeline {
+        let pipeline = self.get_quad_pipeline_mut(pip).clone();
+
+        let id = self
+            .pipelines
+            .iter()
+            .position(|p| p.is_none())
+            .unwrap_or_else(|| panic!("Pipelines amount exceeded"));
+        self.pipelines[id] = Some(pipeline);
+        self.pipelines_amount += 1;
+
+        GlPipeline(id)
+    }
+
     fn get(&self, draw_mode: DrawMode, depth_enabled: bool) -> GlPipeline {
         match (draw_mode, depth_enabled) {
             (DrawMode::Triangles, false) => Self::TRIANGLES_PIPELINE,
@@ -547,6 +629,22 @@ pub struct QuadGl {
     max_indices: usize,
 }
 
+/// "Name[N]" to ("Name", N)
+fn parse_texture_array(texture: &str) -> Option<(&str, usize)> {
+    let (name, count) = texture.strip_suffix(']')?.split_once('[')?;
+    Some((name, count.parse().ok()?))
+}
+
+/// Overlap of two (x, y, w, h) rects, empty if they do not overlap
+fn intersect_rects(a: (i32, i32, i32, i32), b: (i32, i32, i32, i32)) -> (i32, i32, i32, i32) {
+    let x = a.0.max(b.0);
+    let y = a.1.max(b.1);
+    let right = (a.0 + a.2).min(b.0 + b.2);
+    let top = (a.1 + a.3).min(b.1 + b.3);
+
+    (x, y, (right - x).max(0), (top - y).max(0))
+}
+
 impl QuadGl {
     pub fn new(ctx: &mut miniquad::Context) -> QuadGl {
         let white_texture = Texture::from_rgba8(ctx, 1, 1, &[255, 255, 255, 255]);
@@ -565,6 +663,8 @@ impl QuadGl {
                 snapshotter: MagicSnapshotter::new(ctx),
                 render_pass: None,
                 capture: false,
+                view: glam::Mat4::IDENTITY,
+                camera_position: glam::Vec3::ZERO,
             },
             draw_calls: Vec::with_capacity(200),
             draw_calls_bindings: Vec::with_capacity(200),
@@ -588,6 +688,16 @@ impl QuadGl {
     ) -> Result<GlPipeline, ShaderError> {
         let mut shader_meta: ShaderMeta = shader::meta();
 
+        // engine uniforms are always there, no need to declare them twice
+        let uniforms: Vec<_> = uniforms
+            .into_iter()
+            .filter(|(name, _)| {
+                !shader::uniforms()
+                    .iter()
+                    .any(|(builtin, _)| builtin == name)
+            })
+            .collect();
+
         for uniform in &uniforms {
             shader_meta
                 .uniforms
@@ -595,7 +705,26 @@ impl QuadGl {
                 .push(UniformDesc::new(&uniform.0, uniform.1));
         }
 
+        // "Name[N]" declares a sampler array, bound element by element
+        let textures: Vec<String> = textures
+            .into_iter()
+            .flat_map(|texture| match parse_texture_array(&texture) {
+                Some((name, count)) => (0..count).map(|i| format!("{}[{}]", name, i)).collect(),
+                None => vec![texture],
+            })
+            .collect();
+
+        let max_textures = miniquad::MAX_SHADERSTAGE_IMAGES - shader_meta.images.len();
+        if textures.len() > max_textures {
+            panic!(
+                "too many textures: {}, material can have at most {}, the rest of the texture slots are used by macroquad",
+                textures.len(),
+                max_textures
+            );
+        }
+
         for texture in &textures {
+            let texture = texture.split('[').next().unwrap();
             if texture == "Texture" {
                 panic!(
                     "you can't use name `Texture` for your texture. This name is reserved for the texture that will be drawn with that material"
@@ -606,8 +735,8 @@ impl QuadGl {
                     "you can't use name `_ScreenTexture` for your texture in shaders. This name is reserved for screen texture"
                 );
             }
-            shader_meta.images.push(texture.clone());
         }
+        shader_meta.images.extend(textures.iter().cloned());
 
         let shader = Shader::new(ctx, vertex_shader, fragment_shader, shader_meta)?;
         let wants_screen_texture = fragment_shader.find("_ScreenTexture").is_some();
@@ -622,14 +751,32 @@ impl QuadGl {
         ))
     }
 
+    /// Swap `target` pipeline with the freshly made `source` pipeline.
+    /// All the `target` handles stay valid and uniforms/textures with the same
+    /// names keep their values. `source` handle should not be used afterwards.
+    pub(crate) fn replace_pipeline(&mut self, target: GlPipeline, source: GlPipeline) {
+        self.state.break_batching = true;
+        self.pipelines.replace_pipeline(target, source);
+    }
+
     pub(crate) fn clear(&mut self, ctx: &mut miniquad::Context, color: Color) {
         let clear = PassAction::clear_color(color.r, color.g, color.b, color.a);
 
+        // with a viewport, clear only that part, like for split-screen
+        let (clear, viewport) = match self.state.viewport {
+            Some(viewport) => (PassAction::Nothing, Some(viewport)),
+            None => (clear, None),
+        };
+
         if let Some(current_pass) = self.state.render_pass {
             ctx.begin_pass(current_pass, clear);
         } else {
             ctx.begin_default_pass(clear);
         }
+        if let Some((x, y, w, h)) = viewport {
+            ctx.apply_scissor_rect(x, y, w, h);
+            ctx.clear(Some((color.r, color.g, color.b, color.a)), Some(1.), None);
+        }
         ctx.end_render_pass();
 
         self.clear_draw_calls();
@@ -674,6 +821,9 @@ impl QuadGl {
         let (screen_width, screen_height) = ctx.screen_size();
         let time = (miniquad::date::now() - self.start_time) as f32;
         let time = glam::vec4(time, time.sin(), time.cos(), 0.);
+        let view = self.state.view;
+        // "Projection" is actually view-projection, "_Projection" is without the view
+        let projection_only = projection * view.inverse();
 
         for (dc, bindings) in self.draw_calls[0..self.draw_calls_count]
             .iter_mut()
@@ -717,17 +867,25 @@ impl QuadGl {
                 }
             }
 
-            ctx.apply_pipeline(&pipeline.pipeline);
+            ctx.apply_pipeline(match dc.draw_mode {
+                DrawMode::Triangles => &pipeline.pipeline,
+                DrawMode::Lines => &pipeline.lines_pipeline,
+            });
             if let Some((x, y, w, h)) = dc.viewport {
                 ctx.apply_viewport(x, y, w, h);
             } else {
                 ctx.apply_viewport(0, 0, width as i32, height as i32);
             }
-            if let Some(clip) = dc.clip {
-                ctx.apply_scissor_rect(clip.0, height as i32 - (clip.1 + clip.3), clip.2, clip.3);
-            } else {
-                ctx.apply_scissor_rect(0, 0, width as i32, height as i32);
-            }
+            let viewport = dc.viewport.unwrap_or((0, 0, width as i32, height as i32));
+            let (x, y, w, h) = match dc.clip {
+                // the clip stays inside the viewport, for the split-screen
+                Some(clip) => intersect_rects(
+                    (clip.0, height as i32 - (clip.1 + clip.3), clip.2, clip.3),
+                    viewport,
+                ),
+                None => viewport,
+            };
+            ctx.apply_scissor_rect(x, y, w, h);
             ctx.apply_bindings(bindings);
 
             if let Some(ref uniforms) = dc.uniforms {
@@ -738,6 +896,10 @@ impl QuadGl {
             pipeline.set_uniform("Projection", projection);
             pipeline.set_uniform("Model", dc.model);
             pipeline.set_uniform("_Time", time);
+            pipeline.set_uniform("_ScreenSize", glam::vec2(width as f32, height as f32));
+            pipeline.set_uniform("_CameraPos", self.state.camera_position);
+            pipeline.set_uniform("_View", view);
+            pipeline.set_uniform("_Projection", projection_only);
             ctx.apply_uniforms_from_bytes(
                 pipeline.uniforms_data.as_ptr(),
                 pipeline.uniforms_data.len(),
@@ -780,6 +942,13 @@ impl QuadGl {
         self.state.render_pass = render_pass;
     }
 
+    /// Camera view matrix and position, for the next draw() call.
+    /// Only used for `_View`, `_Projection` and `_CameraPos` material uniforms.
+    pub fn camera_view(&mut self, view: glam::Mat4, position: glam::Vec3) {
+        self.state.view = view;
+        self.state.camera_position = position;
+    }
+
     pub fn depth_test(&mut self, enable: bool) {
         self.state.depth_test_enable = enable;
     }
@@ -906,6 +1075,13 @@ impl QuadGl {
         dc.texture = self.state.texture;
     }
 
+    /// Make a pipeline sharing shaders and GPU state with the given one,
+    /// starting with a copy of its uniforms and textures.
+    /// Takes one of the pipeline slots, but creates no GPU objects.
+    pub fn clone_pipeline(&mut self, pipeline: GlPipeline) -> GlPipeline {
+        self.pipelines.clone_pipeline(pipeline)
+    }
+
     pub fn delete_pipeline(&mut self, pipeline: GlPipeline) {
         self.pipelines.delete_pipeline(pipeline);
     }
@@ -918,6 +1094,14 @@ impl QuadGl {
             .set_uniform(name, uniform);
     }
 
+    pub fn set_uniforms_from_bytes(&mut self, pipeline: GlPipeline, data: &[u8]) {
+        self.state.break_batching = true;
+
+        self.pipelines
+            .get_quad_pipeline_mut(pipeline)
+            .set_uniforms_from_bytes(data);
+    }
+
     pub fn set_texture(&mut self, pipeline: GlPipeline, name: &str, texture: Texture2D) {
         let pipeline = self.pipelines.get_quad_pipeline_mut(pipeline);
         pipeline
@@ -1005,6 +1189,10 @@ mod shader {
             ("Projection", UniformType::Mat4),
             ("Model", UniformType::Mat4),
             ("_Time", UniformType::Float4),
+            ("_ScreenSize", UniformType::Float2),
+            ("_CameraPos", UniformType::Float3),
+            ("_View", UniformType::Mat4),
+            ("_Projection", UniformType::Mat4),
         ]
     }
 
@@ -1020,3 +1208,15 @@ mod shader {
         }
     }
 }
+
+#[test]
+fn scissor_inside_viewport() {
+    assert_eq!(
+        intersect_rects((50, 0, 100, 100), (0, 0, 100, 200)),
+        (50, 0, 50, 100)
+    );
+    assert_eq!(
+        intersect_rects((0, 0, 10, 10), (100, 0, 100, 100)),
+        (100, 0, 0, 10)
+    );
+}
@@ -0,0 +1,256 @@
+//! Cross platform random generator.
+//!
+//! The free functions share one global state, seeded with [srand]. For the
+//! deterministic replays and the procedural generation, [Rng] instances
+//! keep their own state, so the visual effects rolling dice every frame do
+//! not change what the gameplay gets from the same seed:
+//!
+//! ```
+//! use macroquad::rand::Rng;
+//!
+//! let seed = 42;
+//! let mut gameplay = Rng::with_stream(seed, 0);
+//! let mut particles = Rng::with_stream(seed, 1);
+//!
+//! let damage = gameplay.gen_range(5, 10);
+//! let spread = particles.normal(0., 0.3);
+//! # assert!((5..10).contains(&damage) && spread.is_finite());
+//! ```
+
+pub use quad_rand::*;
+
+use nanoserde::{DeBin, DeJson, DeRon, SerBin, SerJson, SerRon};
+
+const MULTIPLIER: u64 = 6364136223846793005;
+/// Increment of the quad_rand global generator
+const INCREMENT: u64 = 1442695040888963407;
+
+/// PCG random generator with its own state.
+///
+/// `Rng::new(seed)` gives the same numbers as the global generator after
+/// `srand(seed)`. The state is plain data, it can be saved along with the
+/// game with nanoserde and restored to continue the same sequence.
+#[derive(Clone, Debug, PartialEq, SerBin, DeBin, SerRon, DeRon, SerJson, DeJson)]
+pub struct Rng {
+    state: u64,
+    increment: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Rng {
+        Rng::new(1)
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng::with_stream(seed, 0)
+    }
+
+    /// Generator on its own `stream`. The generators with the same seed and
+    /// different streams give independent sequences.
+    pub fn with_stream(seed: u64, stream: u64) -> Rng {
+        let mut rng = Rng {
+            state: 0,
+            increment: INCREMENT ^ (stream << 1),
+        };
+        rng.rand();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.rand();
+        rng
+    }
+
+    /// New independent generator, seeded from this one.
+    pub fn split(&mut self) -> Rng {
+        let seed = self.rand_u64();
+        let stream = self.rand_u64();
+        Rng::with_stream(seed, stream)
+    }
+
+    pub fn rand(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        let rot = (state >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    pub fn rand_u64(&mut self) -> u64 {
+        self.rand() as u64 | (self.rand() as u64) << 32
+    }
+
+    /// Random number in `low..high`, same as [gen_range].
+    pub fn gen_range<T: RngRange>(&mut self, low: T, high: T) -> T {
+        T::gen_range_with_rng(self, low, high)
+    }
+
+    /// `true` with the probability `probability`, from 0 to 1.
+    pub fn gen_bool(&mut self, probability: f32) -> bool {
+        self.unit() < probability as f64
+    }
+
+    /// Number from [0, 1)
+    fn unit(&mut self) -> f64 {
+        self.rand() as f64 / (u32::MAX as f64 + 1.)
+    }
+
+    /// Normally distributed number, most of them within `mean ± std_dev`.
+    pub fn normal(&mut self, mean: f32, std_dev: f32) -> f32 {
+        // Box-Muller transform, 1 - unit() is never 0
+        let radius = (-2. * (1. - self.unit()).ln()).sqrt();
+        let angle = std::f64::consts::TAU * self.unit();
+
+        mean + std_dev * (radius * angle.cos()) as f32
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        Some(&items[self.gen_range(0, items.len())])
+    }
+
+    pub fn choose_mut<'a, T>(&mut self, items: &'a mut [T]) -> Option<&'a mut T> {
+        if items.is_empty() {
+            return None;
+        }
+        let index = self.gen_range(0, items.len());
+        Some(&mut items[index])
+    }
+
+    /// Index in `weights`, picked with the probability of its weight over
+    /// the sum of all of them. `None` if there is no positive weight.
+    pub fn weighted_index(&mut self, weights: &[f32]) -> Option<usize> {
+        let positive = |weight: &f32| weight.max(0.) as f64;
+        let total: f64 = weights.iter().map(positive).sum();
+        if total <= 0. {
+            return None;
+        }
+
+        let mut left = self.unit() * total;
+        let picked = weights.iter().position(|weight| {
+            left -= positive(weight);
+            left < 0.
+        });
+        // rounding may leave a bit of `left` after the last weight
+        picked.or_else(|| weights.iter().rposition(|weight| *weight > 0.))
+    }
+
+    /// Item of `items` picked by its weight in `weights`, see
+    /// [Rng::weighted_index].
+    pub fn choose_weighted<'a, T>(&mut self, items: &'a [T], weights: &[f32]) -> Option<&'a T> {
+        assert_eq!(items.len(), weights.len());
+
+        self.weighted_index(weights).map(|index| &items[index])
+    }
+
+    /// Fisher-Yates shuffle
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.gen_range(0, i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Types [Rng::gen_range] works with.
+pub trait RngRange {
+    fn gen_range_with_rng(rng: &mut Rng, low: Self, high: Self) -> Self;
+}
+
+macro_rules! impl_rng_range_float (($($ty:ty),*) => {
+    $(
+        impl RngRange for $ty {
+            fn gen_range_with_rng(rng: &mut Rng, low: $ty, high: $ty) -> $ty {
+                low + (high - low) * rng.unit() as $ty
+            }
+        }
+    )*
+});
+
+macro_rules! impl_rng_range_integer (($($ty:ty),*) => {
+    $(
+        impl RngRange for $ty {
+            fn gen_range_with_rng(rng: &mut Rng, low: $ty, high: $ty) -> $ty {
+                if high <= low {
+                    return low;
+                }
+                let len = (high as i128 - low as i128) as f64;
+                (low as i128 + (rng.unit() * len) as i128) as $ty
+            }
+        }
+    )*
+});
+
+/// 64 bits types need more than the 32 random bits of [Rng::unit]: a
+/// widening multiply of a random u64 by the range length
+macro_rules! impl_rng_range_wide_integer (($($ty:ty),*) => {
+    $(
+        impl RngRange for $ty {
+            fn gen_range_with_rng(rng: &mut Rng, low: $ty, high: $ty) -> $ty {
+                if high <= low {
+                    return low;
+                }
+                let len = (high as i128 - low as i128) as u128;
+                let offset = (rng.rand_u64() as u128 * len) >> 64;
+                (low as i128 + offset as i128) as $ty
+            }
+        }
+    )*
+});
+
+impl_rng_range_float!(f32, f64);
+impl_rng_range_integer!(u8, u16, u32, i8, i16, i32);
+impl_rng_range_wide_integer!(u64, usize, i64, isize);
+
+/// [Rng::normal] with the global generator.
+pub fn normal(mean: f32, std_dev: f32) -> f32 {
+    Rng::new(rand() as u64 | (rand() as u64) << 32).normal(mean, std_dev)
+}
+
+/// [Rng::weighted_index] with the global generator.
+pub fn weighted_index(weights: &[f32]) -> Option<usize> {
+    Rng::new(rand() as u64 | (rand() as u64) << 32).weighted_index(weights)
+}
+
+#[test]
+fn rng_streams_and_state() {
+    let global = RandGenerator::new();
+    global.srand(7);
+    let mut rng = Rng::new(7);
+    for _ in 0..10 {
+        assert_eq!(rng.rand(), global.rand());
+    }
+
+    let sequence = |mut rng: Rng| (0..4).map(|_| rng.rand()).collect::<Vec<_>>();
+    assert_ne!(
+        sequence(Rng::with_stream(7, 1)),
+        sequence(Rng::with_stream(7, 2))
+    );
+
+    let saved = rng.serialize_json();
+    let restored = Rng::deserialize_json(&saved).unwrap();
+    assert_eq!(sequence(restored), sequence(rng.clone()));
+
+    let weights = [0., 3., 1., -2.];
+    let mut counts = [0; 4];
+    for _ in 0..4000 {
+        counts[rng.weighted_index(&weights).unwrap()] += 1;
+    }
+    assert_eq!((counts[0], counts[3]), (0, 0));
+    assert!(counts[1] > counts[2] * 2);
+    assert_eq!(rng.weighted_index(&[0., -1.]), None);
+
+    let samples: Vec<f32> = (0..4000).map(|_| rng.normal(10., 2.)).collect();
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    assert!((mean - 10.).abs() < 0.2);
+
+    for _ in 0..100 {
+        assert!((3..6).contains(&rng.gen_range(3, 6)));
+        assert!((-1.0..1.0).contains(&rng.gen_range(-1., 1.)));
+        assert!((i64::MIN..i64::MAX).contains(&rng.gen_range(i64::MIN, i64::MAX)));
+    }
+    // all the low bits are random
+    assert!((0..100).any(|_| rng.gen_range(0, u64::MAX) % 2 == 1));
+}
@@ -0,0 +1,221 @@
+//! Small persistent key-value storage for settings and save games.
+//!
+//! Each key is a file in the per-user data folder on PC, a file in the app
+//! storage on Android and iOS, and a localStorage entry on web (`js/storage.js`
+//! has to be loaded).
+//!
+//! ```ignore
+//! #[derive(SerRon, DeRon, Default)]
+//! struct Settings {
+//!     volume: f32,
+//!     fullscreen: bool,
+//! }
+//!
+//! let mut settings: Settings = storage::load_ron("settings").unwrap_or_default();
+//! settings.volume = 0.5;
+//! storage::save_ron("settings", &settings).unwrap();
+//! ```
+
+use crate::get_context;
+
+use nanoserde::{DeRon, SerRon};
+
+/// Name of the folder the keys are stored in, the window title by default.
+///
+/// Set it before the first save or load if the title may change. Like the
+/// keys, everything but ascii letters, digits, `-`, `_` and `.` is replaced.
+pub fn set_app_name(name: &str) {
+    get_context().storage_name = name.to_string();
+}
+
+/// Keys are file names on most platforms, everything but ascii letters,
+/// digits, `-`, `_` and `.` is replaced.
+#[cfg(not(target_arch = "wasm32"))]
+fn file_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    match name.trim_start_matches('.') {
+        "" => format!("_{}", name),
+        _ => name,
+    }
+}
+
+/// File `key` is written to before replacing the key's file. The `~` is
+/// replaced in the keys, so no key has this file name.
+#[cfg(not(target_arch = "wasm32"))]
+fn temp_file_name(key: &str) -> String {
+    format!("~{}.tmp", file_name(key))
+}
+
+/// Save `data` under `key`, replacing what was there.
+///
+/// Outside the web the keys are file names, with the characters other
+/// than ascii letters, digits, `-`, `_` and `.` replaced by `_`: the keys
+/// differing only by them, like `"save slot"` and `"save_slot"`, are the
+/// same file.
+pub fn save(key: &str, data: &[u8]) -> std::io::Result<()> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        web::save(key, data)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let dir = storage_dir()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No data folder"))?;
+        std::fs::create_dir_all(&dir)?;
+
+        // never leave a half written file behind
+        let path = dir.join(file_name(key));
+        let temp = dir.join(temp_file_name(key));
+        std::fs::write(&temp, data)?;
+        std::fs::rename(temp, path)
+    }
+}
+
+/// Data saved under `key`, `None` if nothing was.
+pub fn load(key: &str) -> Option<Vec<u8>> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        web::load(key)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::read(storage_dir()?.join(file_name(key))).ok()
+    }
+}
+
+pub fn remove(key: &str) {
+    #[cfg(target_arch = "wasm32")]
+    web::remove(key);
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(dir) = storage_dir() {
+        let _ = std::fs::remove_file(dir.join(file_name(key)));
+    }
+}
+
+/// [save] `value` as RON.
+pub fn save_ron<T: SerRon>(key: &str, value: &T) -> std::io::Result<()> {
+    save(key, value.serialize_ron().as_bytes())
+}
+
+/// [load] a value saved with [save_ron], `None` if there is none or it does
+/// not parse, like after the type changed.
+pub fn load_ron<T: DeRon>(key: &str) -> Option<T> {
+    let data = load(key)?;
+    T::deserialize_ron(&String::from_utf8_lossy(&data)).ok()
+}
+
+/// Folder the keys are stored in:
+/// - `%APPDATA%\<app name>` on Windows
+/// - `~/Library/Application Support/<app name>` on macOS and iOS
+/// - `$XDG_DATA_HOME/<app name>`, `~/.local/share/<app name>` by default, on Linux
+/// - the app internal storage on Android
+#[cfg(not(target_arch = "wasm32"))]
+pub fn storage_dir() -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    let env = |name| std::env::var_os(name).map(PathBuf::from);
+    // the window title by default, which may have slashes or colons
+    let app = file_name(&get_context().storage_name);
+
+    #[cfg(target_os = "windows")]
+    return env("APPDATA").map(|dir| dir.join(app));
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    return env("HOME").map(|dir| dir.join("Library/Application Support").join(app));
+
+    // the process is named after the package, every app owns /data/data/<package>
+    #[cfg(target_os = "android")]
+    {
+        let _ = (env, app);
+        let cmdline = std::fs::read("/proc/self/cmdline").ok()?;
+        let package = cmdline.split(|byte| *byte == 0).next()?;
+        let package = String::from_utf8_lossy(package);
+        return Some(PathBuf::from(format!("/data/data/{}/files", package)));
+    }
+
+    #[cfg(not(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "android"
+    )))]
+    return env("XDG_DATA_HOME")
+        .or_else(|| env("HOME").map(|dir| dir.join(".local/share")))
+        .map(|dir| dir.join(app));
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use super::get_context;
+
+    extern "C" {
+        fn macroquad_storage_save(
+            key: *const u8,
+            key_len: usize,
+            data: *const u8,
+            data_len: usize,
+        ) -> bool;
+        /// -1 when there is nothing under the key
+        fn macroquad_storage_len(key: *const u8, key_len: usize) -> i32;
+        fn macroquad_storage_load(key: *const u8, key_len: usize, data: *mut u8);
+        fn macroquad_storage_remove(key: *const u8, key_len: usize);
+    }
+
+    /// localStorage is shared by all the pages of the site
+    fn full_key(key: &str) -> String {
+        format!("{}/{}", get_context().storage_name, key)
+    }
+
+    pub(super) fn save(key: &str, data: &[u8]) -> std::io::Result<()> {
+        let key = full_key(key);
+        let saved =
+            unsafe { macroquad_storage_save(key.as_ptr(), key.len(), data.as_ptr(), data.len()) };
+        if saved {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "localStorage is full or disabled",
+            ))
+        }
+    }
+
+    pub(super) fn load(key: &str) -> Option<Vec<u8>> {
+        let key = full_key(key);
+        let len = unsafe { macroquad_storage_len(key.as_ptr(), key.len()) };
+        if len < 0 {
+            return None;
+        }
+        let mut data = vec![0; len as usize];
+        unsafe { macroquad_storage_load(key.as_ptr(), key.len(), data.as_mut_ptr()) };
+        Some(data)
+    }
+
+    pub(super) fn remove(key: &str) {
+        let key = full_key(key);
+        unsafe { macroquad_storage_remove(key.as_ptr(), key.len()) };
+    }
+}
+
+#[test]
+fn storage_file_name() {
+    assert_eq!(file_name("settings"), "settings");
+    assert_eq!(file_name("save slot/1.ron"), "save_slot_1.ron");
+    assert_eq!(file_name(".."), "_..");
+    assert_eq!(file_name(""), "_");
+
+    // no key saves over the temp file of another one
+    let temp = temp_file_name("slot");
+    assert_ne!(file_name(&temp), temp);
+    assert_ne!(file_name("slot.tmp"), temp);
+}
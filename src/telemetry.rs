@@ -17,6 +17,8 @@ fn get_profiler() -> &'static mut Profiler {
             capture: false,
             drawcalls: vec![],
             strings: vec![],
+            overlay: false,
+            trace: None,
         })
     }
 }
@@ -64,6 +66,25 @@ impl Drop for ZoneGuard {
     }
 }
 
+/// Profile the rest of the block as a zone named `name`.
+///
+/// Zones are only recorded while the profiler is enabled, with
+/// `telemetry::enable`, the overlay or a trace.
+/// ```ignore
+/// fn update_enemies(enemies: &mut [Enemy]) {
+///     profile_scope!("ai");
+///     for enemy in enemies {
+///         enemy.think();
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope = $crate::telemetry::ZoneGuard::new($name);
+    };
+}
+
 pub fn enable() {
     get_profiler().enable_request = Some(true);
 }
@@ -115,11 +136,17 @@ pub(crate) fn reset() {
         "New frame started with unpaired begin/end zones."
     );
 
-    profiler.frame.full_frame_time = crate::time::get_frame_time();
+    profiler.frame.full_frame_time = crate::time::unscaled_frame_time();
 
     std::mem::swap(&mut profiler.prev_frame, &mut profiler.frame);
     profiler.frame = Frame::new();
 
+    if let Some(trace) = &mut profiler.trace {
+        if profiler.enabled {
+            trace.extend(profiler.prev_frame.try_clone());
+        }
+    }
+
     if let Some(enable) = profiler.enable_request.take() {
         profiler.enabled = enable;
     }
@@ -199,6 +226,9 @@ struct Profiler {
     enable_request: Option<bool>,
     drawcalls: Vec<DrawCallTelemetry>,
     strings: Vec<String>,
+    overlay: bool,
+    /// Frames recorded since [begin_trace]
+    trace: Option<Vec<Frame>>,
 }
 
 impl Profiler {
@@ -374,3 +404,167 @@ pub(crate) fn track_drawcall(
         texture,
     });
 }
+
+const OVERLAY_FRAMES: usize = 120;
+
+/// Show the frame time graph, the GPU time and the zones of the last frame
+/// over everything else. Enables the profiler while shown.
+/// ```ignore
+/// if is_key_pressed(KeyCode::F3) {
+///     telemetry::toggle_overlay();
+/// }
+/// ```
+pub fn set_overlay(shown: bool) {
+    get_profiler().overlay = shown;
+    get_profiler().enable_request = Some(shown || get_profiler().trace.is_some());
+}
+
+pub fn toggle_overlay() {
+    set_overlay(!get_profiler().overlay);
+}
+
+pub(crate) fn draw_overlay() {
+    use crate::{
+        color::Color,
+        shapes::{draw_line, draw_rectangle},
+        text::draw_text,
+    };
+
+    let profiler = get_profiler();
+    if !profiler.overlay {
+        return;
+    }
+    sample_gpu_queries();
+
+    fn zone_lines(zones: &[Zone], depth: usize, lines: &mut Vec<String>) {
+        for zone in zones {
+            lines.push(format!(
+                "{}{} {:.2}ms",
+                "  ".repeat(depth),
+                zone.name,
+                zone.duration * 1000.
+            ));
+            zone_lines(&zone.children, depth + 1, lines);
+        }
+    }
+    let mut lines = vec![];
+    let frame_time = profiler.prev_frame.full_frame_time;
+    lines.push(format!(
+        "{:.0} fps {:.2}ms",
+        1. / frame_time.max(0.0001),
+        frame_time * 1000.
+    ));
+    if let Some(gpu) = profiler.queries.get("GPU").filter(|query| query.value != 0) {
+        lines.push(format!("GPU {:.2}ms", gpu.value as f64 / 1_000_000.));
+    }
+    zone_lines(&profiler.prev_frame.zones, 0, &mut lines);
+
+    // 60px is 1/30s, with a line at 1/60s
+    let (x, y, graph_height) = (10., 10., 60.);
+    let width = OVERLAY_FRAMES as f32 * 2.;
+    let line_height = 14.;
+    let height = graph_height + 10. + lines.len() as f32 * line_height;
+    draw_rectangle(x, y, width + 10., height, Color::new(0., 0., 0., 0.7));
+
+    let bottom = y + 5. + graph_height;
+    let history = &get_context().frame_history;
+    let shown = history
+        .iter()
+        .skip(history.len().saturating_sub(OVERLAY_FRAMES));
+    for (n, time) in shown.enumerate() {
+        let bar = (time * 30. * graph_height).min(graph_height);
+        let color = if *time > 1. / 55. {
+            Color::new(1., 0.3, 0.3, 1.)
+        } else {
+            Color::new(0.3, 1., 0.3, 1.)
+        };
+        draw_rectangle(x + 5. + n as f32 * 2., bottom - bar, 2., bar, color);
+    }
+    let target = bottom - graph_height / 2.;
+    draw_line(
+        x + 5.,
+        target,
+        x + 5. + width,
+        target,
+        1.,
+        Color::new(1., 1., 1., 0.5),
+    );
+
+    for (n, line) in lines.iter().enumerate() {
+        let line_y = bottom + 5. + (n + 1) as f32 * line_height;
+        draw_text(line, x + 5., line_y, 16., Color::new(1., 1., 1., 1.));
+    }
+}
+
+/// Start recording the zones of every frame, for [end_trace].
+pub fn begin_trace() {
+    get_profiler().trace = Some(vec![]);
+    get_profiler().enable_request = Some(true);
+}
+
+/// Stop recording and return the frames since [begin_trace] in the Chrome
+/// trace format, to open in `chrome://tracing` or https://ui.perfetto.dev.
+/// ```ignore
+/// store_file("trace.json", telemetry::end_trace().as_bytes()).unwrap();
+/// ```
+pub fn end_trace() -> String {
+    let profiler = get_profiler();
+    let frames = profiler.trace.take().unwrap_or_default();
+    profiler.enable_request = Some(profiler.overlay);
+
+    trace_json(&frames)
+}
+
+fn trace_json(frames: &[Frame]) -> String {
+    fn escape(name: &str) -> String {
+        name.chars()
+            .flat_map(|c| match c {
+                '"' => vec!['\\', '"'],
+                '\\' => vec!['\\', '\\'],
+                c if c.is_control() => format!("\\u{:04x}", c as u32).chars().collect(),
+                c => vec![c],
+            })
+            .collect()
+    }
+
+    fn zone_events(zones: &[Zone], events: &mut Vec<String>) {
+        for zone in zones {
+            events.push(format!(
+                r#"{{"name":"{}","ph":"X","ts":{:.3},"dur":{:.3},"pid":0,"tid":0}}"#,
+                escape(&zone.name),
+                zone.start_time * 1_000_000.,
+                zone.duration * 1_000_000.
+            ));
+            zone_events(&zone.children, events);
+        }
+    }
+
+    let mut list = vec![];
+    for frame in frames {
+        zone_events(&frame.zones, &mut list);
+    }
+    format!(r#"{{"traceEvents":[{}]}}"#, list.join(","))
+}
+
+#[test]
+fn chrome_trace() {
+    let zone = |name: &str, start_time, duration, children| Zone {
+        name: name.to_string(),
+        start_time,
+        duration,
+        children,
+        parent: std::ptr::null_mut(),
+    };
+    let mut frame = Frame::new();
+    frame.zones.push(zone(
+        "draw",
+        1.,
+        0.002,
+        vec![zone("\"ai\"", 1.0005, 0.001, vec![])],
+    ));
+
+    assert_eq!(
+        trace_json(&[frame]),
+        r#"{"traceEvents":[{"name":"draw","ph":"X","ts":1000000.000,"dur":2000.000,"pid":0,"tid":0},{"name":"\"ai\"","ph":"X","ts":1000500.000,"dur":1000.000,"pid":0,"tid":0}]}"#
+    );
+}
@@ -261,12 +261,16 @@ impl Default for TextParams {
 }
 
 /// Load font from file with "path"
+///
+/// In debug builds the font is reloaded when the file changes.
 pub async fn load_ttf_font(path: &str) -> Result<Font, FontError> {
     let bytes = crate::file::load_file(path)
         .await
         .map_err(|_| "The Font file couldn't be loaded")?;
+    let font = load_ttf_font_from_bytes(&bytes[..])?;
+    get_context().asset_watcher.watch_font(path, font);
 
-    load_ttf_font_from_bytes(&bytes[..])
+    Ok(font)
 }
 
 /// Load font from bytes array, may be use in combination with include_bytes!
@@ -289,6 +293,19 @@ pub fn load_ttf_font_from_bytes(bytes: &[u8]) -> Result<Font, FontError> {
     Ok(font)
 }
 
+/// Replace the glyphs of `font` with a new file, for the asset hot-reload.
+pub(crate) fn reload_ttf_font(font: Font, bytes: &[u8]) -> Result<(), FontError> {
+    let atlas = Rc::new(RefCell::new(Atlas::new(
+        get_quad_context(),
+        miniquad::FilterMode::Linear,
+    )));
+
+    *get_context().fonts_storage.get_font_mut(font) = FontInternal::load_from_bytes(atlas, bytes)?;
+    font.populate_font_cache(&Font::ascii_character_list(), 15);
+
+    Ok(())
+}
+
 /// Draw text with given font_size
 pub fn draw_text(text: &str, x: f32, y: f32, font_size: f32, color: Color) {
     draw_text_ex(
@@ -210,10 +210,15 @@ pub async fn load_image(path: &str) -> Result<Image, FileError> {
 }
 
 /// Loads a [Texture2D] from a file into GPU memory.
+///
+/// In debug builds the texture is updated when the file changes, as long as
+/// its size stays the same.
 pub async fn load_texture(path: &str) -> Result<Texture2D, FileError> {
     let bytes = load_file(path).await?;
+    let texture = Texture2D::from_file_with_format(&bytes[..], None);
+    get_context().asset_watcher.watch_texture(path, texture);
 
-    Ok(Texture2D::from_file_with_format(&bytes[..], None))
+    Ok(texture)
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -631,6 +636,7 @@ impl Texture2D {
     /// Using a deleted texture could give different results on different
     /// platforms and is not recommended.
     pub fn delete(&self) {
+        get_context().asset_watcher.forget_texture(*self);
         self.raw_miniquad_texture_handle().delete()
     }
 }
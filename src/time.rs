@@ -10,16 +10,76 @@ pub fn get_fps() -> i32 {
 }
 
 /// Returns duration in seconds of the last frame drawn
+///
+/// Scaled by [set_time_scale], and 0 while the time is paused with [pause_time].
+/// Coroutine timers, animations and particles all go by it.
 pub fn get_frame_time() -> f32 {
     let context = get_context();
 
     if crate::experimental::scene::in_fixed_update() {
         crate::experimental::scene::fixed_frame_time()
+    } else if let Some(step) = context.fixed_frame_time {
+        step
     } else {
-        context.frame_time as f32
+        context.frame_time as f32 * effective_time_scale()
     }
 }
 
+/// Real duration in seconds of the last frame drawn, for what keeps going
+/// in slow motion and pause menus, like the menus themselves.
+pub fn unscaled_frame_time() -> f32 {
+    get_context().frame_time as f32
+}
+
+/// Speed of the game time, 1 by default, 0.5 for slow motion.
+///
+/// ```ignore
+/// if player_hit {
+///     set_time_scale(0.2);
+/// }
+/// ```
+pub fn set_time_scale(scale: f32) {
+    get_context().time_scale = scale.max(0.);
+}
+
+pub fn get_time_scale() -> f32 {
+    get_context().time_scale
+}
+
+/// Stop the game time, [get_frame_time] returns 0 until [resume_time].
+///
+/// The time scale is kept for when the time resumes.
+pub fn pause_time() {
+    get_context().time_paused = true;
+}
+
+pub fn resume_time() {
+    get_context().time_paused = false;
+}
+
+pub fn is_time_paused() -> bool {
+    get_context().time_paused
+}
+
+/// Time scale with the pause applied.
+pub(crate) fn effective_time_scale() -> f32 {
+    let context = get_context();
+
+    if context.time_paused {
+        0.
+    } else {
+        context.time_scale
+    }
+}
+
+/// Seconds of game time since start, the sum of the [get_frame_time]s.
+///
+/// Goes slower with [set_time_scale] and stops with [pause_time], unlike
+/// [get_time]. Updated once per frame, at the beginning of the frame.
+pub fn get_game_time() -> f64 {
+    get_context().game_time
+}
+
 /// Returns elapsed wall-clock time in seconds since start
 ///
 /// Note that as real world time progresses during computation,
@@ -32,3 +92,240 @@ pub fn get_time() -> f64 {
 
     miniquad::date::now() - context.start_time
 }
+
+/// Cap the frame rate to `fps` frames per second, `None` to remove the cap.
+///
+/// Keeps menus and simple scenes from running at thousands of frames per
+/// second when vsync is off or ignored by the driver.
+///
+/// The cap comes on top of vsync, which is only set at startup with
+/// `Conf::platform.swap_interval`. On the web the browser paces the frames
+/// and the cap is ignored.
+pub fn set_target_fps(fps: Option<u32>) {
+    let context = get_context();
+
+    context.target_frame_time = fps.filter(|fps| *fps > 0).map(|fps| 1. / fps as f64);
+}
+
+/// Block until the frame took the target frame time.
+///
+/// Sleeps in short naps while far from the frame end, and spins the rest.
+/// How late the naps wake up is measured, so the spin covers the timer
+/// resolution of the OS, about 1ms on Linux and macOS, and the frames end
+/// within a fraction of a millisecond. The measure is capped at a few ms and
+/// quickly forgotten, so one very late wake-up, like the thread being
+/// preempted, doesn't turn the next frames into a busy wait.
+pub(crate) fn wait_target_frame_time() {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(target_frame_time) = get_context().target_frame_time {
+        const NAP: f64 = 0.001;
+        const MAX_OVERSHOOT: f64 = 0.004;
+
+        let context = get_context();
+        let frame_end = context.last_frame_time + target_frame_time;
+        loop {
+            let before = miniquad::date::now();
+            if frame_end - before <= context.sleep_overshoot + NAP {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_secs_f64(NAP));
+
+            // the worst recent case, forgotten in ~50 naps
+            let overshoot = (miniquad::date::now() - before - NAP).min(MAX_OVERSHOOT);
+            context.sleep_overshoot = overshoot.max(context.sleep_overshoot * 0.95);
+        }
+        while miniquad::date::now() < frame_end {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Number of frames kept in [frame_time_history].
+pub const FRAME_HISTORY: usize = 1000;
+
+pub(crate) fn record_frame_time(frame_time: f32) {
+    let history = &mut get_context().frame_history;
+    if history.len() == FRAME_HISTORY {
+        history.pop_front();
+    }
+    history.push_back(frame_time);
+}
+
+/// Durations in seconds of the last [FRAME_HISTORY] frames, oldest first,
+/// unscaled by [set_time_scale]. Ready to draw as a graph.
+pub fn frame_time_history() -> Vec<f32> {
+    get_context().frame_history.iter().copied().collect()
+}
+
+/// Statistics of the frame times, from [frame_stats] or
+/// [FrameStats::from_frame_times].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    pub frames: usize,
+    /// In seconds
+    pub average_frame_time: f32,
+    pub max_frame_time: f32,
+    pub average_fps: f32,
+    /// Frame rate of the slowest 1% of the frames
+    pub low_1_percent_fps: f32,
+    /// Frame rate of the slowest 0.1% of the frames
+    pub low_0_1_percent_fps: f32,
+    /// Frames taking more than twice as long as the median frame
+    pub stutters: usize,
+}
+
+impl FrameStats {
+    pub fn from_frame_times(frame_times: &[f32]) -> FrameStats {
+        if frame_times.is_empty() {
+            return FrameStats::default();
+        }
+
+        // slowest first
+        let mut sorted = frame_times.to_vec();
+        sorted.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+        let fps = |times: &[f32]| times.len() as f32 / times.iter().sum::<f32>().max(f32::EPSILON);
+        let slowest = |part: f32| {
+            let count = ((sorted.len() as f32 * part).ceil() as usize).max(1);
+            fps(&sorted[..count])
+        };
+        let median = sorted[sorted.len() / 2];
+
+        FrameStats {
+            frames: sorted.len(),
+            average_frame_time: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            max_frame_time: sorted[0],
+            average_fps: fps(&sorted),
+            low_1_percent_fps: slowest(0.01),
+            low_0_1_percent_fps: slowest(0.001),
+            stutters: sorted.iter().filter(|time| **time > median * 2.).count(),
+        }
+    }
+}
+
+/// Statistics of the last [FRAME_HISTORY] frames.
+/// ```ignore
+/// let stats = frame_stats();
+/// draw_text(&format!("{:.0} fps, 1% low {:.0}", stats.average_fps, stats.low_1_percent_fps), 10., 20., 20., WHITE);
+/// ```
+pub fn frame_stats() -> FrameStats {
+    let history = &mut get_context().frame_history;
+
+    FrameStats::from_frame_times(history.make_contiguous())
+}
+
+/// Simulation running in fixed steps whatever the frame rate, for physics
+/// and deterministic game logic.
+///
+/// Each frame runs as many steps as the frame time fits, the left over time
+/// goes to the next frame. Draw the state interpolated by [FixedTimestep::alpha]
+/// between the last two steps for smooth motion.
+/// ```ignore
+/// let mut timestep = FixedTimestep::new(1. / 60.);
+/// loop {
+///     timestep.update(|dt| {
+///         previous = position;
+///         position += velocity * dt;
+///     });
+///     let drawn = previous.lerp(position, timestep.alpha());
+///     draw_circle(drawn.x, drawn.y, 10., WHITE);
+///     next_frame().await;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: f64,
+    accumulator: f64,
+    max_steps: u32,
+}
+
+impl FixedTimestep {
+    /// Steps of `step` seconds.
+    pub fn new(step: f32) -> FixedTimestep {
+        assert!(step > 0., "Fixed timestep needs a positive step");
+
+        FixedTimestep {
+            step: step as f64,
+            accumulator: 0.,
+            max_steps: 8,
+        }
+    }
+
+    /// Most steps run in one frame, 8 by default. After a long frame, like
+    /// a loading hitch, the rest of the time is dropped instead of making
+    /// the next frames even longer.
+    pub fn max_steps(self, max_steps: u32) -> FixedTimestep {
+        FixedTimestep { max_steps, ..self }
+    }
+
+    pub fn step(&self) -> f32 {
+        self.step as f32
+    }
+
+    /// Run `f` with the step duration as many times as the last frame time
+    /// fits, returns how many times it ran. The frame time is scaled by
+    /// [set_time_scale], and no step runs while the time is paused.
+    ///
+    /// [get_frame_time] gives the step duration inside `f` as well.
+    pub fn update<F: FnMut(f32)>(&mut self, mut f: F) -> u32 {
+        let frame_time = get_context().frame_time * effective_time_scale() as f64;
+        let step = self.step as f32;
+
+        self.advance(frame_time, |dt| {
+            get_context().fixed_frame_time = Some(step);
+            f(dt);
+            get_context().fixed_frame_time = None;
+        })
+    }
+
+    fn advance<F: FnMut(f32)>(&mut self, frame_time: f64, mut f: F) -> u32 {
+        self.accumulator += frame_time;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps {
+            f(self.step as f32);
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        if steps == self.max_steps {
+            self.accumulator = self.accumulator.min(self.step);
+        }
+        steps
+    }
+
+    /// Time since the last step, as a part of a step from 0 to 1, to
+    /// interpolate the drawn state with.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).min(1.) as f32
+    }
+}
+
+#[test]
+fn fixed_timestep() {
+    let mut timestep = FixedTimestep::new(0.01);
+    let mut steps = 0;
+    assert_eq!(timestep.advance(0.025, |_| steps += 1), 2);
+    assert!((timestep.alpha() - 0.5).abs() < 0.001);
+    assert_eq!(timestep.advance(0.005, |_| steps += 1), 1);
+    assert_eq!(steps, 3);
+
+    // a long frame runs at most max_steps
+    let mut timestep = FixedTimestep::new(0.01).max_steps(4);
+    assert_eq!(timestep.advance(1., |_| {}), 4);
+    assert_eq!(timestep.advance(0., |_| {}), 1);
+    assert_eq!(timestep.advance(0., |_| {}), 0);
+}
+
+#[test]
+fn frame_stats_lows() {
+    let mut frame_times = vec![0.01; 990];
+    frame_times.extend([0.05; 10]);
+    let stats = FrameStats::from_frame_times(&frame_times);
+
+    assert_eq!(stats.frames, 1000);
+    assert_eq!(stats.stutters, 10);
+    assert!((stats.max_frame_time - 0.05).abs() < 1e-6);
+    assert!((stats.low_1_percent_fps - 20.).abs() < 0.01);
+    assert!((stats.low_0_1_percent_fps - 20.).abs() < 0.01);
+    assert!((stats.average_fps - 1000. / 10.4).abs() < 0.01);
+    assert_eq!(FrameStats::from_frame_times(&[]), FrameStats::default());
+}
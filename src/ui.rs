@@ -25,11 +25,13 @@ pub mod widgets;
 pub use clipboard::ClipboardObject;
 pub use input_handler::{InputHandler, KeyCode};
 pub use render::{DrawList, Vertex};
-pub use style::{Skin, Style, StyleBuilder};
+#[cfg(not(target_arch = "wasm32"))]
+pub use style::save_skin;
+pub use style::{load_skin, Skin, SkinError, SkinWatcher, Style, StyleBuilder};
 
 pub use crate::hash;
 
-pub(crate) use render::ElementState;
+pub use render::ElementState;
 
 use std::{borrow::Cow, ops::DerefMut};
 
@@ -68,15 +70,19 @@ use crate::{
 
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
+pub(crate) mod animation;
 mod cursor;
 mod input;
 mod key_repeat;
+mod navigation;
 
 use cursor::Cursor;
 use input::Input;
 
+pub use animation::{easing, Animation};
 pub use cursor::Layout;
 use input::{InputCharacter, Key};
+pub use navigation::NavInput;
 
 pub type Id = u64;
 
@@ -122,6 +128,8 @@ pub(crate) struct Window {
     pub childs: Vec<Id>,
     pub want_close: bool,
     pub force_focus: bool,
+    /// Opacity of everything drawn in the window, for fading it in and out
+    pub alpha: f32,
 
     margin: f32,
     window_margin: RectOffset,
@@ -165,6 +173,7 @@ impl Window {
             want_close: false,
             movable,
             force_focus,
+            alpha: 1.,
         }
     }
 
@@ -234,6 +243,16 @@ pub enum Drag {
     Dropped(Vec2, Option<Id>),
 }
 
+/// Value dragged with [Ui::drag_source], until a [Ui::drop_target] takes it.
+pub(crate) struct DragPayload {
+    source: Id,
+    payload: Box<dyn std::any::Any>,
+    label: String,
+    origin: Vec2,
+    // moved far enough from the origin, a click is not a drag
+    dragging: bool,
+}
+
 struct StyleStack {
     default_skin: Skin,
     custom_skin_stack: Vec<Skin>,
@@ -256,6 +275,10 @@ pub(crate) struct TabSelector {
     counter: isize,
     wants: Option<isize>,
     to_change: Option<isize>,
+    /// A widget with focus was registered this frame
+    focus_registered: bool,
+    /// `Tab` (false) or `Shift + Tab` (true) pressed with no focused widget
+    unfocused_tab: Option<bool>,
 }
 
 impl TabSelector {
@@ -264,10 +287,19 @@ impl TabSelector {
             counter: 0,
             wants: None,
             to_change: None,
+            focus_registered: false,
+            unfocused_tab: None,
         }
     }
 
     fn new_frame(&mut self) {
+        // nothing focused yet, `Tab` starts from the first widget and `Shift + Tab` from the last
+        if let (false, Some(shift)) = (self.focus_registered, self.unfocused_tab) {
+            self.wants = Some(if shift { -1 } else { 0 });
+        }
+        self.focus_registered = false;
+        self.unfocused_tab = None;
+
         self.to_change = if self.wants == Some(-1) {
             Some(self.counter - 1)
         } else if self.wants == Some(self.counter) {
@@ -281,34 +313,42 @@ impl TabSelector {
 
     /// Returns true if this widget should gain focus, because user pressed `Tab` or `Shift + Tab`.
     pub(crate) fn register_selectable_widget(&mut self, has_focus: bool, input: &Input) -> bool {
-        if has_focus {
-            enum PressedTabKey {
-                Tab,
-                ShiftTab,
-                Other,
-            }
+        enum PressedTabKey {
+            Tab,
+            ShiftTab,
+            Other,
+        }
 
-            let key = if input
-                .input_buffer
-                .iter()
-                .any(|inp| inp.key == Key::KeyCode(KeyCode::Tab) && inp.modifier_shift)
-            {
-                PressedTabKey::ShiftTab
-            } else if input
-                .input_buffer
-                .iter()
-                .any(|inp| inp.key == Key::KeyCode(KeyCode::Tab))
-            {
-                PressedTabKey::Tab
-            } else {
-                PressedTabKey::Other
-            };
+        let key = if input
+            .input_buffer
+            .iter()
+            .any(|inp| inp.key == Key::KeyCode(KeyCode::Tab) && inp.modifier_shift)
+        {
+            PressedTabKey::ShiftTab
+        } else if input
+            .input_buffer
+            .iter()
+            .any(|inp| inp.key == Key::KeyCode(KeyCode::Tab))
+        {
+            PressedTabKey::Tab
+        } else {
+            PressedTabKey::Other
+        };
+
+        if has_focus {
+            self.focus_registered = true;
 
             match key {
                 PressedTabKey::Tab => self.wants = Some(self.counter + 1),
                 PressedTabKey::ShiftTab => self.wants = Some(self.counter - 1),
                 PressedTabKey::Other => {}
             }
+        } else {
+            match key {
+                PressedTabKey::Tab => self.unfocused_tab = Some(false),
+                PressedTabKey::ShiftTab => self.unfocused_tab = Some(true),
+                PressedTabKey::Other => {}
+            }
         }
 
         let result = if self.to_change.map(|id| id == self.counter).unwrap_or(false) {
@@ -365,7 +405,23 @@ pub struct Ui {
     key_repeat: key_repeat::KeyRepeat,
 
     tab_selector: TabSelector,
+    navigator: navigation::Navigator,
     input_focus: Option<Id>,
+    items: canvas::ItemIds,
+
+    // hovered tooltip and when the hover started
+    tooltip: Option<(u64, f32)>,
+    tooltip_hovered: bool,
+    // tooltips, drawn on top of everything, modal included
+    overlay: Painter,
+
+    scale: f32,
+
+    drag_payload: Option<DragPayload>,
+
+    // dialog drawn this frame, and the one blocking the input from the last frame
+    dialog: Option<Id>,
+    blocking_dialog: Option<Id>,
 }
 
 #[derive(Default)]
@@ -410,7 +466,10 @@ pub(crate) struct WindowContext<'a> {
     pub last_item_clicked: &'a mut bool,
     pub last_item_hovered: &'a mut bool,
     pub tab_selector: &'a mut TabSelector,
+    pub navigator: &'a mut navigation::Navigator,
+    pub items: &'a mut canvas::ItemIds,
     pub input_focus: &'a mut Option<Id>,
+    pub time: f32,
 }
 
 impl<'a> WindowContext<'a> {
@@ -518,6 +577,47 @@ impl<'a> WindowContext<'a> {
         self.input_focus
             .map_or(false, |input_focus| input_focus == id)
     }
+
+    /// Keyboard focus for widgets without text input, like buttons and checkboxes.
+    ///
+    /// The widget gets focused by `Tab` navigation, [Ui::navigate] or
+    /// [Ui::set_input_focus], returns true if it has the focus and should
+    /// react to [Input::activated].
+    pub(crate) fn register_focusable(&mut self, id: Id, rect: Rect) -> bool {
+        let has_focus = self.input_focused(id) && self.focused;
+
+        let tabbed = self
+            .tab_selector
+            .register_selectable_widget(has_focus, self.input);
+        if self.focused {
+            self.navigator.register(id, rect);
+        }
+        *self.input_focus = focusable_focus(*self.input_focus, id, tabbed, self.input.click_down());
+
+        self.input_focused(id) && self.focused
+    }
+
+    /// Outline around the widget with the keyboard focus.
+    pub(crate) fn draw_focus_highlight(&mut self, rect: Rect) {
+        self.window
+            .painter
+            .draw_rect(rect, self.style.focus_color, None);
+    }
+}
+
+/// Keyboard focus after the focusable widget `id` got its input.
+///
+/// A click takes the focus away instead of giving it: the UI sees all the
+/// keys, a clicked button would be pressed again by each `Space` or
+/// `Enter` of the game.
+fn focusable_focus(focus: Option<Id>, id: Id, tabbed: bool, click_down: bool) -> Option<Id> {
+    if tabbed {
+        Some(id)
+    } else if click_down && focus == Some(id) {
+        None
+    } else {
+        focus
+    }
 }
 
 impl InputHandler for Ui {
@@ -546,6 +646,12 @@ impl InputHandler for Ui {
             if window.was_active == false {
                 continue;
             }
+            if self
+                .blocking_dialog
+                .is_some_and(|dialog| dialog != window.id)
+            {
+                continue;
+            }
 
             if window.top_level() && window.title_rect().contains(position) && window.movable {
                 self.moving = Some((
@@ -587,6 +693,11 @@ impl InputHandler for Ui {
             }
         }
 
+        // the dialog takes all the input, apart from the popups opened from it
+        if let Some(dialog) = self.blocking_dialog {
+            self.hovered_window = dialog;
+        }
+
         match &self.modal {
             Some(modal) if modal.was_active || modal.active => {
                 if modal.full_rect().contains(position) {
@@ -624,7 +735,8 @@ impl InputHandler for Ui {
             self.input.enter = true;
         }
 
-        if ctrl && (key == KeyCode::C || key == KeyCode::X) {
+        if ctrl && (key == KeyCode::C || key == KeyCode::X) && !self.clipboard_selection.is_empty()
+        {
             self.clipboard.set(&self.clipboard_selection);
         }
 
@@ -691,6 +803,7 @@ impl Ui {
             drag_hovered_previous_frame: None,
             storage_u32: HashMap::default(),
             storage_any: AnyStorage::default(),
+            overlay: Painter::new(atlas.clone()),
             atlas,
             clipboard_selection: String::new(),
             clipboard: Box::new(ui_context::ClipboardObject),
@@ -699,7 +812,15 @@ impl Ui {
             last_item_clicked: false,
             last_item_hovered: false,
             tab_selector: TabSelector::new(),
+            navigator: navigation::Navigator::new(),
+            items: canvas::ItemIds::default(),
             input_focus: None,
+            tooltip: None,
+            tooltip_hovered: false,
+            scale: 1.,
+            drag_payload: None,
+            dialog: None,
+            blocking_dialog: None,
         }
     }
 
@@ -807,7 +928,10 @@ impl Ui {
             last_item_clicked: &mut self.last_item_clicked,
             last_item_hovered: &mut self.last_item_hovered,
             tab_selector: &mut self.tab_selector,
+            navigator: &mut self.navigator,
+            items: &mut self.items,
             input_focus: &mut self.input_focus,
+            time: self.time,
         }
     }
 
@@ -854,7 +978,10 @@ impl Ui {
             last_item_clicked: &mut self.last_item_clicked,
             last_item_hovered: &mut self.last_item_hovered,
             tab_selector: &mut self.tab_selector,
+            navigator: &mut self.navigator,
+            items: &mut self.items,
             input_focus: &mut self.input_focus,
+            time: self.time,
         }
     }
 
@@ -873,7 +1000,7 @@ impl Ui {
         let window = if self.in_modal == false {
             match self.active_window {
                 None | Some(0) => {
-                    focused = true;
+                    focused = self.blocking_dialog.is_none();
                     &mut self.root_window
                 }
                 Some(active_window) => {
@@ -901,7 +1028,10 @@ impl Ui {
             last_item_clicked: &mut self.last_item_clicked,
             last_item_hovered: &mut self.last_item_hovered,
             tab_selector: &mut self.tab_selector,
+            navigator: &mut self.navigator,
+            items: &mut self.items,
             input_focus: &mut self.input_focus,
+            time: self.time,
         }
     }
 
@@ -962,6 +1092,11 @@ impl Ui {
     }
 
     pub fn is_mouse_over(&self, mouse_position: Vec2) -> bool {
+        if self.blocking_dialog.is_some() {
+            return true;
+        }
+
+        let mouse_position = mouse_position / self.scale;
         for window in self.windows_focus_order.iter() {
             let window = &self.windows[window];
             if window.was_active == false {
@@ -1014,6 +1149,10 @@ impl Ui {
     }
 
     fn is_focused(&self, id: Id) -> bool {
+        if let Some(dialog) = self.blocking_dialog {
+            return id == dialog || self.child_window_stack.first() == Some(&dialog);
+        }
+
         if self
             .windows
             .get(&id)
@@ -1039,10 +1178,12 @@ impl Ui {
     }
 
     pub fn new_frame(&mut self, delta: f32) {
-        self.root_window.resize(crate::math::vec2(
-            crate::window::screen_width(),
-            crate::window::screen_height(),
-        ));
+        self.root_window.resize(
+            crate::math::vec2(
+                crate::window::screen_width(),
+                crate::window::screen_height(),
+            ) / self.scale,
+        );
 
         self.frame += 1;
         self.time += delta;
@@ -1052,10 +1193,39 @@ impl Ui {
 
         self.drag_hovered_previous_frame = self.drag_hovered;
         self.drag_hovered = None;
+        // the held keys and not the key events, the events follow the OS
+        // key repeat instead of the navigation one
+        let arrows = [
+            (crate::input::KeyCode::Up, NavInput::Up),
+            (crate::input::KeyCode::Down, NavInput::Down),
+            (crate::input::KeyCode::Left, NavInput::Left),
+            (crate::input::KeyCode::Right, NavInput::Right),
+        ]
+        .iter()
+        .find(|(key, _)| crate::input::is_key_down(*key))
+        .map(|(_, direction)| *direction)
+        .filter(|_| self.navigator.arrow_keys);
+        self.input_focus = self
+            .navigator
+            .new_frame(self.input_focus, arrows, self.time);
+
         self.input.reset();
         self.input.window_active = self.hovered_window == 0;
 
         self.tab_selector.new_frame();
+        self.blocking_dialog = self.dialog.take();
+        self.items.new_frame(self.input.is_mouse_down);
+
+        if !self.tooltip_hovered {
+            self.tooltip = None;
+        }
+        self.tooltip_hovered = false;
+        self.overlay.clear();
+
+        // released without a drop target under the mouse
+        if !self.input.is_mouse_down {
+            self.drag_payload = None;
+        }
 
         self.key_repeat.new_frame(self.time);
 
@@ -1083,37 +1253,69 @@ impl Ui {
     }
 
     pub fn render(&mut self, draw_list: &mut Vec<DrawList>) {
-        self.render_window(&self.root_window, Vec2::new(0., 0.), draw_list);
+        self.render_window(&self.root_window, Vec2::new(0., 0.), 1., draw_list);
 
         for window in self.windows_focus_order.iter().rev() {
             let window = &self.windows[window];
             if window.was_active {
-                self.render_window(window, Vec2::new(0., 0.), draw_list);
+                if self.dialog == Some(window.id) {
+                    self.render_dialog_dim(draw_list);
+                }
+                self.render_window(window, Vec2::new(0., 0.), 1., draw_list);
             }
         }
 
         if let Some(modal) = self.modal.as_ref() {
             if modal.was_active {
-                self.render_window(modal, Vec2::new(0., 0.), draw_list);
+                self.render_window(modal, Vec2::new(0., 0.), 1., draw_list);
             }
         }
 
         if let Some((id, DragState::Dragging(orig))) = self.dragging {
             let window = &self.windows[&id];
 
-            self.render_window(window, self.input.mouse_position - orig, draw_list);
+            self.render_window(window, self.input.mouse_position - orig, 1., draw_list);
+        }
+
+        for cmd in &self.overlay.commands {
+            crate::ui::render::render_command(draw_list, cmd.clone());
         }
     }
 
-    fn render_window(&self, window: &Window, offset: Vec2, draw_list: &mut Vec<DrawList>) {
+    /// Darken everything behind the dialog.
+    fn render_dialog_dim(&self, draw_list: &mut Vec<DrawList>) {
+        let size = self.root_window.size;
+        let source = self.atlas.borrow().get_uv_rect(0).unwrap();
+
+        crate::ui::render::render_command(
+            draw_list,
+            crate::ui::render::DrawCommand::DrawRect {
+                rect: Rect::new(0., 0., size.x, size.y),
+                source,
+                fill: Some(self.skin_stack.top().dialog_dim_color),
+                stroke: None,
+            },
+        );
+    }
+
+    fn render_window(
+        &self,
+        window: &Window,
+        offset: Vec2,
+        alpha: f32,
+        draw_list: &mut Vec<DrawList>,
+    ) {
+        let alpha = alpha * window.alpha;
         for cmd in &window.painter.commands {
-            crate::ui::render::render_command(draw_list, cmd.offset(offset));
+            let cmd = cmd.offset(offset);
+            let cmd = if alpha < 1. { cmd.fade(alpha) } else { cmd };
+            crate::ui::render::render_command(draw_list, cmd);
         }
 
         for child in &window.childs {
             let child_window = &self.windows[child];
             if window.content_rect().overlaps(&child_window.full_rect()) {
-                self.render_window(child_window, offset, draw_list);
+                self.render_window(child_window, offset, alpha, draw_list);
             }
         }
     }
@@ -1125,6 +1327,21 @@ impl Ui {
         }
     }
 
+    /// Scale of the whole UI: widgets, fonts and mouse hit-testing.
+    ///
+    /// The UI is laid out in the same logical points as `screen_width()`,
+    /// so it already follows the OS DPI scale, this scale is on top of it.
+    /// The fonts are scaled up as they are, pixel fonts look best with
+    /// integer scales.
+    pub fn set_scale(&mut self, scale: f32) {
+        assert!(scale > 0., "UI scale should be positive");
+        self.scale = scale;
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
     pub fn set_input_focus(&mut self, id: Id) {
         self.input_focus = Some(id);
     }
@@ -1133,6 +1350,47 @@ impl Ui {
         self.input_focus = None;
     }
 
+    /// Widget with the keyboard focus, set by `Tab` navigation, [Ui::navigate],
+    /// [Ui::set_input_focus] or a click on a text input.
+    pub fn input_focus(&self) -> Option<Id> {
+        self.input_focus
+    }
+
+    /// Move the keyboard focus to the closest widget in a direction, or
+    /// press or clear the focused widget, for gamepad menus.
+    ///
+    /// Call it before drawing the widgets, the focus moves at the end of the frame.
+    ///
+    /// ```ignore
+    /// if gamepad.pressed(Button::DPadDown) {
+    ///     root_ui().navigate(NavInput::Down);
+    /// }
+    /// if gamepad.pressed(Button::South) {
+    ///     root_ui().navigate(NavInput::Activate);
+    /// }
+    /// ```
+    pub fn navigate(&mut self, input: NavInput) {
+        match input {
+            NavInput::Activate => self.input.nav_activate = true,
+            NavInput::Cancel => self.input_focus = None,
+            direction => self.navigator.navigate(direction),
+        }
+    }
+
+    /// [Ui::navigate] with a stick position, called every frame.
+    /// Holding the stick repeats the move after a delay.
+    pub fn navigate_axes(&mut self, axes: Vec2) {
+        if let Some(direction) = NavInput::from_axes(axes) {
+            self.navigate(direction);
+        }
+    }
+
+    /// Arrow keys move the focus between buttons and checkboxes like [Ui::navigate].
+    /// Off by default, the arrows are often used by the game itself.
+    pub fn set_arrow_navigation(&mut self, enabled: bool) {
+        self.navigator.arrow_keys = enabled;
+    }
+
     pub fn move_window(&mut self, id: Id, position: Vec2) {
         if let Some(window) = self.windows.get_mut(&id) {
             window.set_position(position);
@@ -1209,9 +1467,10 @@ pub(crate) mod ui_context {
         pub(crate) fn process_input(&mut self) {
             use megaui::InputHandler;
 
-            let mouse_position = mouse_position();
-
             let mut ui = self.ui.borrow_mut();
+
+            let (x, y) = mouse_position();
+            let mouse_position = (x / ui.scale, y / ui.scale);
             ui.mouse_move(mouse_position);
 
             if is_mouse_button_pressed(MouseButton::Left) {
@@ -1220,9 +1479,13 @@ pub(crate) mod ui_context {
             if is_mouse_button_released(MouseButton::Left) {
                 ui.mouse_up(mouse_position);
             }
+            if is_mouse_button_pressed(MouseButton::Right) {
+                ui.input.right_click_down = true;
+            }
 
             let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
             let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+            ui.input.modifier_shift = shift;
 
             while let Some(c) = get_char_pressed_ui() {
                 if ctrl == false {
@@ -1267,7 +1530,7 @@ pub(crate) mod ui_context {
             ui.mouse_wheel(wheel_x, -wheel_y);
         }
 
-        pub(crate) fn draw(&mut self, _ctx: &mut miniquad::Context, quad_gl: &mut QuadGl) {
+        pub(crate) fn draw(&mut self, ctx: &mut miniquad::Context, quad_gl: &mut QuadGl) {
             // TODO: this belongs to new and waits for cleaning up context initialization mess
             let material = self.material.get_or_insert_with(|| {
                 let fragment_shader = FRAGMENT_SHADER.to_string();
@@ -1303,6 +1566,11 @@ pub(crate) mod ui_context {
 
             gl_use_material(*material);
 
+            // the clipping zones are in ui units, the scissor in framebuffer pixels
+            let scale = ui.scale;
+            let pixels = scale * ctx.dpi_scale();
+            quad_gl.push_model_matrix(glam::Mat4::from_scale(glam::vec3(scale, scale, 1.)));
+
             for draw_command in &ui_draw_list {
                 if let Some(texture) = draw_command.texture {
                     quad_gl.texture(Some(texture));
@@ -1310,21 +1578,25 @@ pub(crate) mod ui_context {
                     quad_gl.texture(Some(font_texture));
                 }
 
-                quad_gl.scissor(
-                    draw_command
-                        .clipping_zone
-                        .map(|rect| (rect.x as i32, rect.y as i32, rect.w as i32, rect.h as i32)),
-                );
+                quad_gl.scissor(draw_command.clipping_zone.map(|rect| {
+                    (
+                        (rect.x * pixels) as i32,
+                        (rect.y * pixels) as i32,
+                        (rect.w * pixels) as i32,
+                        (rect.h * pixels) as i32,
+                    )
+                }));
                 quad_gl.draw_mode(DrawMode::Triangles);
                 quad_gl.geometry(&draw_command.vertices, &draw_command.indices);
             }
             quad_gl.texture(None);
+            quad_gl.pop_model_matrix();
 
             gl_use_default_material();
 
             std::mem::swap(&mut ui_draw_list, &mut self.ui_draw_list);
 
-            ui.new_frame(get_frame_time());
+            ui.new_frame(unscaled_frame_time());
         }
     }
 
@@ -1377,3 +1649,27 @@ void main() {
 }
 ";
 }
+
+#[test]
+fn click_does_not_focus() {
+    let button = 1;
+    let mut space = Input::default();
+    space.input_buffer.push(InputCharacter {
+        key: Key::Char(' '),
+        modifier_shift: false,
+        modifier_ctrl: false,
+    });
+    assert!(space.activated());
+
+    // the click presses the button, the next `Space` is for the game
+    let focus = focusable_focus(None, button, false, true);
+    assert_eq!(focus, None);
+    assert!(!(focus == Some(button) && space.activated()));
+
+    // `Tab` focuses it, until the next click
+    let focus = focusable_focus(focus, button, true, false);
+    assert!(focus == Some(button) && space.activated());
+    assert_eq!(focusable_focus(focus, button, false, true), None);
+    // the text inputs handle their own focus
+    assert_eq!(focusable_focus(Some(2), button, false, true), Some(2));
+}
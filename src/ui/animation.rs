@@ -0,0 +1,199 @@
+//! Values moving smoothly to their target over time, for UI transitions
+
+use crate::{
+    color::Color,
+    math::Vec2,
+    ui::{AnyStorage, Id, Ui, WindowContext},
+};
+
+/// Easing functions for [Animation], mapping the linear progress 0..1 to the
+/// interpolation factor.
+pub mod easing {
+    pub fn linear(t: f32) -> f32 {
+        t
+    }
+
+    pub fn ease_in(t: f32) -> f32 {
+        t * t
+    }
+
+    pub fn ease_out(t: f32) -> f32 {
+        t * (2. - t)
+    }
+
+    pub fn smoothstep(t: f32) -> f32 {
+        t * t * (3. - 2. * t)
+    }
+}
+
+pub(crate) trait Lerp: Copy + PartialEq + 'static {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, other: Vec2, t: f32) -> Vec2 {
+        Vec2::lerp(self, other, t)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, other: Color, t: f32) -> Color {
+        Color::new(
+            self.r.lerp(other.r, t),
+            self.g.lerp(other.g, t),
+            self.b.lerp(other.b, t),
+            self.a.lerp(other.a, t),
+        )
+    }
+}
+
+struct AnimationState<T> {
+    from: T,
+    to: T,
+    start: f32,
+}
+
+impl<T: Lerp> AnimationState<T> {
+    fn value(&self, time: f32, duration: f32, easing: fn(f32) -> f32) -> T {
+        if duration <= 0. {
+            return self.to;
+        }
+        let t = ((time - self.start) / duration).clamp(0., 1.);
+        self.from.lerp(self.to, easing(t))
+    }
+}
+
+/// Value of the animation `id` at `time`, starting from `initial` the first time.
+/// A new `target` starts a transition from the current value.
+pub(crate) fn animate<T: Lerp>(
+    storage: &mut AnyStorage,
+    id: Id,
+    initial: T,
+    target: T,
+    time: f32,
+    duration: f32,
+    easing: fn(f32) -> f32,
+) -> T {
+    let state = storage
+        .get_or_default::<Option<AnimationState<T>>>(id)
+        .get_or_insert(AnimationState {
+            from: initial,
+            to: initial,
+            start: time,
+        });
+
+    if state.to != target {
+        *state = AnimationState {
+            from: state.value(time, duration, easing),
+            to: target,
+            start: time,
+        };
+    }
+
+    state.value(time, duration, easing)
+}
+
+/// Number moving to its target over time, for anything a widget can be drawn with:
+/// positions, sizes, alpha..
+///
+/// ```ignore
+/// let open = ui.animate(hash!("menu"), if menu_open { 1. } else { 0. }, 0.2);
+/// let width = Animation::new(hash!("bar"), health)
+///     .duration(0.5)
+///     .easing(easing::ease_out)
+///     .ui(ui);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Animation {
+    id: Id,
+    target: f32,
+    from: Option<f32>,
+    duration: f32,
+    easing: fn(f32) -> f32,
+}
+
+impl Animation {
+    pub fn new(id: Id, target: f32) -> Animation {
+        Animation {
+            id,
+            target,
+            from: None,
+            duration: 0.2,
+            easing: easing::smoothstep,
+        }
+    }
+
+    /// Seconds to reach a new target, 0.2 by default.
+    pub fn duration(self, duration: f32) -> Animation {
+        Animation { duration, ..self }
+    }
+
+    /// Smoothstep by default.
+    pub fn easing(self, easing: fn(f32) -> f32) -> Animation {
+        Animation { easing, ..self }
+    }
+
+    /// Value the first time the animation is used, by default it starts at the target.
+    pub fn from(self, from: f32) -> Animation {
+        Animation {
+            from: Some(from),
+            ..self
+        }
+    }
+
+    /// Returns the current value.
+    pub fn ui(self, ui: &mut Ui) -> f32 {
+        animate(
+            &mut ui.storage_any,
+            self.id,
+            self.from.unwrap_or(self.target),
+            self.target,
+            ui.time,
+            self.duration,
+            self.easing,
+        )
+    }
+}
+
+impl Ui {
+    /// Number moving to `target` over `duration` seconds, see [Animation].
+    pub fn animate(&mut self, id: Id, target: f32, duration: f32) -> f32 {
+        Animation::new(id, target).duration(duration).ui(self)
+    }
+}
+
+impl<'a> WindowContext<'a> {
+    /// Widget color fading to `color`, for the hover and press transitions.
+    pub(crate) fn transition_color(&mut self, id: Id, color: Color) -> Color {
+        animate(
+            self.storage_any,
+            hash!(id, "color_transition"),
+            color,
+            color,
+            self.time,
+            self.style.transition_time,
+            easing::ease_out,
+        )
+    }
+}
+
+#[test]
+fn animation_retarget() {
+    let mut storage = AnyStorage::default();
+    let mut at =
+        |target: f32, time: f32| animate(&mut storage, 1, 0., target, time, 1., easing::linear);
+
+    assert_eq!(at(0., 0.), 0.);
+    assert_eq!(at(1., 0.), 0.);
+    assert_eq!(at(1., 0.5), 0.5);
+    // back to 0 from halfway
+    assert_eq!(at(0., 0.5), 0.5);
+    assert_eq!(at(0., 1.), 0.25);
+    assert_eq!(at(0., 2.), 0.);
+}
@@ -1,13 +1,68 @@
-//! In-window drawing canvas for custom primitives like lines, rect and textures
+//! In-window drawing canvas for custom primitives like lines, rect and textures,
+//! and the layout and input plumbing to build custom widgets with.
 
 use super::Layout;
 use super::WindowContext;
 use crate::{
     color::Color,
-    math::{Rect, Vec2},
+    math::{vec2, Rect, Vec2},
     texture::Texture2D,
+    ui::{ElementState, Id, Skin, Style, UiContent},
 };
 
+/// Hovered and pressed widgets, shared by all the windows
+#[derive(Default)]
+pub(crate) struct ItemIds {
+    hot: Option<Id>,
+    hot_previous_frame: Option<Id>,
+    active: Option<Id>,
+}
+
+impl ItemIds {
+    pub(crate) fn new_frame(&mut self, mouse_down: bool) {
+        self.hot_previous_frame = self.hot.take();
+        if !mouse_down {
+            self.active = None;
+        }
+    }
+}
+
+/// What the mouse and the keyboard did to a widget, from [DrawCanvas::interact].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Interaction {
+    pub hovered: bool,
+    /// Mouse pressed on the widget this frame
+    pub pressed: bool,
+    /// Mouse still down since it pressed the widget, for dragging
+    pub held: bool,
+    /// Mouse released over the widget it pressed, or `Enter` with the keyboard focus
+    pub clicked: bool,
+    /// The widget has the keyboard focus
+    pub focused: bool,
+}
+
+/// Drawing and layout in the current window.
+///
+/// Custom widgets get their space from the window layout with [DrawCanvas::allocate],
+/// like the built-in ones, so they can be mixed with them:
+/// ```ignore
+/// fn toggle(ui: &mut Ui, id: Id, on: &mut bool) {
+///     let mut canvas = ui.canvas();
+///     let rect = canvas.allocate(vec2(40., 20.));
+///     let interaction = canvas.interact(id, rect);
+///     if interaction.clicked {
+///         *on ^= true;
+///     }
+///
+///     let skin = canvas.skin();
+///     let state = canvas.element_state(interaction);
+///     canvas.background(&skin.button_style, rect, state);
+///     canvas.label(&skin.button_style, rect, if *on { "on" } else { "off" }, state);
+///     if interaction.focused {
+///         canvas.focus_highlight(rect);
+///     }
+/// }
+/// ```
 pub struct DrawCanvas<'a> {
     pub(crate) context: WindowContext<'a>,
 }
@@ -26,6 +81,106 @@ impl<'a> DrawCanvas<'a> {
         cursor.fit(space, Layout::Vertical)
     }
 
+    /// Id of the window, to derive the widget ids from.
+    pub fn window_id(&self) -> Id {
+        self.context.window.id
+    }
+
+    /// Room left in the window, from the cursor to the bottom right corner.
+    pub fn available_rect(&self) -> Rect {
+        let cursor = &self.context.window.cursor;
+        let position = self.cursor() + vec2(cursor.ident, 0.);
+        let area = cursor.area;
+
+        Rect::new(
+            position.x,
+            position.y,
+            (area.x + area.w - cursor.margin - position.x).max(0.),
+            (area.y + area.h - cursor.margin - position.y).max(0.),
+        )
+    }
+
+    /// Next `size` rect in the window layout, below the previous widget.
+    pub fn allocate(&mut self, size: Vec2) -> Rect {
+        self.allocate_with_layout(size, Layout::Vertical)
+    }
+
+    /// [DrawCanvas::allocate] with another layout, like `Layout::Free(position)`.
+    pub fn allocate_with_layout(&mut self, size: Vec2, layout: Layout) -> Rect {
+        let position = self.context.window.cursor.fit(size, layout);
+
+        Rect::new(position.x, position.y, size.x, size.y)
+    }
+
+    /// Mouse and keyboard input for the widget `id` covering `rect`.
+    ///
+    /// The widget joins the `Tab` and arrow keys navigation, and becomes
+    /// the hot item while hovered and the active item while pressed.
+    pub fn interact(&mut self, id: Id, rect: Rect) -> Interaction {
+        let (hovered, pressed) = self.context.register_click_intention(rect);
+        let focused = self.context.register_focusable(id, rect);
+
+        let items = &mut *self.context.items;
+        if hovered {
+            items.hot = Some(id);
+        }
+        if pressed {
+            items.active = Some(id);
+        }
+        let active = items.active == Some(id);
+
+        Interaction {
+            hovered,
+            pressed,
+            held: active && self.context.input.is_mouse_down,
+            clicked: active && hovered && self.context.input.click_up()
+                || focused && self.context.input.activated(),
+            focused,
+        }
+    }
+
+    /// Widget hovered in the last frame.
+    pub fn hot_item(&self) -> Option<Id> {
+        self.context.items.hot_previous_frame
+    }
+
+    /// Widget pressed and still held, if any.
+    pub fn active_item(&self) -> Option<Id> {
+        self.context.items.active
+    }
+
+    pub fn skin(&self) -> &'a Skin {
+        self.context.style
+    }
+
+    /// The window is the one taking the input.
+    pub fn is_window_focused(&self) -> bool {
+        self.context.focused
+    }
+
+    pub fn mouse_position(&self) -> Vec2 {
+        self.context.input.mouse_position
+    }
+
+    pub fn mouse_wheel(&self) -> Vec2 {
+        self.context.input.mouse_wheel
+    }
+
+    /// State to draw the widget styles with.
+    pub fn element_state(&self, interaction: Interaction) -> ElementState {
+        ElementState {
+            focused: self.context.focused,
+            hovered: interaction.hovered,
+            clicked: interaction.held && interaction.hovered,
+            selected: false,
+        }
+    }
+
+    /// State kept by a widget from one frame to the next.
+    pub fn state<T: Default + 'static>(&mut self, id: Id) -> &mut T {
+        self.context.storage_any.get_or_default(id)
+    }
+
     pub fn rect<S, T>(&mut self, rect: Rect, stroke: S, fill: T)
     where
         S: Into<Option<Color>>,
@@ -40,9 +195,65 @@ impl<'a> DrawCanvas<'a> {
         self.context.window.painter.draw_line(start, end, color);
     }
 
+    pub fn triangle(&mut self, p0: Vec2, p1: Vec2, p2: Vec2, color: Color) {
+        self.context.window.painter.draw_triangle(p0, p1, p2, color);
+    }
+
     pub fn image(&mut self, rect: Rect, texture: Texture2D) {
         self.context.register_click_intention(rect);
 
         self.context.window.painter.draw_raw_texture(rect, texture);
     }
+
+    /// `text` with its top left corner at `position`, in the label font.
+    pub fn text(&mut self, text: &str, position: Vec2, color: Color) {
+        let style = &self.context.style.label_style;
+        let font = &mut *style.font.borrow_mut();
+        let painter = &mut self.context.window.painter;
+
+        let offset_y = painter
+            .label_size(text, None, font, style.font_size)
+            .offset_y;
+        painter.draw_label(
+            text,
+            position + vec2(0., offset_y),
+            color,
+            font,
+            style.font_size,
+        );
+    }
+
+    /// Size of `text` drawn with `style`, margins included.
+    pub fn measure(&self, style: &Style, text: &str) -> Vec2 {
+        self.context
+            .window
+            .painter
+            .content_with_margins_size(style, &UiContent::Label(text.into()))
+    }
+
+    /// Background of `style`, the way a built-in widget would draw it.
+    pub fn background(&mut self, style: &Style, rect: Rect, state: ElementState) {
+        self.context.window.painter.draw_element_background(
+            style,
+            rect.point(),
+            rect.size(),
+            state,
+        );
+    }
+
+    /// `text` centered in `rect`, with the `style` font and colors.
+    pub fn label(&mut self, style: &Style, rect: Rect, text: &str, state: ElementState) {
+        self.context.window.painter.draw_element_content(
+            style,
+            rect.point(),
+            rect.size(),
+            &UiContent::Label(text.into()),
+            state,
+        );
+    }
+
+    /// Keyboard focus outline, as built-in widgets draw it.
+    pub fn focus_highlight(&mut self, rect: Rect) {
+        self.context.draw_focus_highlight(rect);
+    }
 }
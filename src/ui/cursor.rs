@@ -50,6 +50,8 @@ pub struct Cursor {
     pub margin: f32,
     pub next_same_line: Option<f32>,
     pub max_row_y: f32,
+    /// Screen rect of the last widget placed with `fit`, for tooltips and context menus
+    pub last_fit: Rect,
 }
 
 impl Cursor {
@@ -73,6 +75,7 @@ impl Cursor {
             area,
             next_same_line: None,
             max_row_y: 0.,
+            last_fit: Rect::new(0., 0., 0., 0.),
         }
     }
 
@@ -134,8 +137,12 @@ impl Cursor {
             .inner_rect
             .combine_with(Rect::new(res.x, res.y, size.x, size.y));
 
-        res + Vec2::new(self.area.x as f32, self.area.y as f32)
+        let pos = res
+            + Vec2::new(self.area.x as f32, self.area.y as f32)
             + self.scroll.scroll
-            + Vec2::new(self.ident, 0.)
+            + Vec2::new(self.ident, 0.);
+        self.last_fit = Rect::new(pos.x, pos.y, size.x, size.y);
+
+        pos
     }
 }
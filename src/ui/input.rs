@@ -21,11 +21,15 @@ pub struct Input {
     pub(crate) is_mouse_down: bool,
     pub(crate) click_down: bool,
     pub(crate) click_up: bool,
+    pub(crate) right_click_down: bool,
     pub(crate) mouse_wheel: Vec2,
     pub(crate) input_buffer: Vec<InputCharacter>,
     pub(crate) modifier_ctrl: bool,
+    pub(crate) modifier_shift: bool,
     pub(crate) escape: bool,
     pub(crate) enter: bool,
+    /// [crate::ui::NavInput::Activate] this frame
+    pub(crate) nav_activate: bool,
     pub(crate) cursor_grabbed: bool,
     pub(crate) window_active: bool,
 }
@@ -43,12 +47,29 @@ impl Input {
         self.click_up && self.cursor_grabbed == false && self.window_active
     }
 
+    pub fn right_click_down(&self) -> bool {
+        self.right_click_down && !self.cursor_grabbed && self.window_active
+    }
+
+    /// `Enter`, `Space` or [crate::ui::NavInput::Activate], to use the widget with the keyboard focus.
+    pub(crate) fn activated(&self) -> bool {
+        self.enter
+            || self.nav_activate
+            || self
+                .input_buffer
+                .iter()
+                .any(|inp| inp.key == Key::Char(' '))
+    }
+
     pub fn reset(&mut self) {
         self.modifier_ctrl = false;
+        self.modifier_shift = false;
         self.escape = false;
         self.enter = false;
+        self.nav_activate = false;
         self.click_down = false;
         self.click_up = false;
+        self.right_click_down = false;
         self.mouse_wheel = Vec2::new(0., 0.);
         self.input_buffer = vec![];
         self.window_active = false;
@@ -0,0 +1,191 @@
+//! Directional focus navigation, for gamepads and arrow keys
+
+use crate::{
+    math::{Rect, Vec2},
+    ui::Id,
+};
+
+/// Held direction moves the focus again after this delay
+const REPEAT_DELAY: f32 = 0.4;
+/// and then every `REPEAT_RATE` seconds
+const REPEAT_RATE: f32 = 0.12;
+/// Stick deflection needed to move the focus
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// Menu input from a gamepad, or any other device, for [crate::ui::Ui::navigate].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NavInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Press the focused widget, like `Enter`
+    Activate,
+    /// Clear the focus
+    Cancel,
+}
+
+impl NavInput {
+    /// Direction of a stick or d-pad axes, with y going down.
+    pub fn from_axes(axes: Vec2) -> Option<NavInput> {
+        if axes.length() < STICK_THRESHOLD {
+            return None;
+        }
+
+        Some(if axes.x.abs() > axes.y.abs() {
+            if axes.x > 0. {
+                NavInput::Right
+            } else {
+                NavInput::Left
+            }
+        } else if axes.y > 0. {
+            NavInput::Down
+        } else {
+            NavInput::Up
+        })
+    }
+}
+
+pub(crate) struct Navigator {
+    /// Focusable widgets registered this frame
+    focusables: Vec<(Id, Rect)>,
+    direction: Option<NavInput>,
+    /// Direction held, when it started and when it last moved the focus
+    held: Option<(NavInput, f32, f32)>,
+    pub(crate) arrow_keys: bool,
+}
+
+impl Navigator {
+    pub(crate) fn new() -> Navigator {
+        Navigator {
+            focusables: vec![],
+            direction: None,
+            held: None,
+            arrow_keys: false,
+        }
+    }
+
+    pub(crate) fn register(&mut self, id: Id, rect: Rect) {
+        self.focusables.push((id, rect));
+    }
+
+    pub(crate) fn navigate(&mut self, direction: NavInput) {
+        self.direction = Some(direction);
+    }
+
+    /// Move the focus in the direction asked this frame, or by `arrows`.
+    /// Returns the new focus.
+    ///
+    /// The arrows only move a focus the UI already has, after a Tab or
+    /// [crate::ui::Ui::navigate], they do not focus the first button.
+    pub(crate) fn new_frame(
+        &mut self,
+        focus: Option<Id>,
+        arrows: Option<NavInput>,
+        time: f32,
+    ) -> Option<Id> {
+        let focusables = std::mem::take(&mut self.focusables);
+        let direction = self
+            .direction
+            .take()
+            .or_else(|| arrows.filter(|_| focus.is_some()));
+        let direction = match direction {
+            Some(direction) => direction,
+            None => {
+                self.held = None;
+                return focus;
+            }
+        };
+
+        match self.held {
+            Some((held, since, last)) if held == direction => {
+                if time - since < REPEAT_DELAY || time - last < REPEAT_RATE {
+                    return focus;
+                }
+                self.held = Some((held, since, time));
+            }
+            _ => self.held = Some((direction, time, time)),
+        }
+
+        let id = match focus {
+            Some(id) => id,
+            None => return focusables.first().map(|(id, _)| *id),
+        };
+        match focusables.iter().find(|(focusable, _)| *focusable == id) {
+            Some((_, rect)) => neighbor(id, *rect, &focusables, direction).or(focus),
+            // text inputs use the arrows for their own cursor
+            None => focus,
+        }
+    }
+}
+
+/// Closest widget in `direction` from `rect`, preferring widgets in line with it.
+fn neighbor(id: Id, rect: Rect, focusables: &[(Id, Rect)], direction: NavInput) -> Option<Id> {
+    let center = rect.center();
+
+    focusables
+        .iter()
+        .filter(|(other, _)| *other != id)
+        .filter_map(|(other, other_rect)| {
+            let offset = other_rect.center() - center;
+            let (along, across) = match direction {
+                NavInput::Up => (-offset.y, offset.x),
+                NavInput::Down => (offset.y, offset.x),
+                NavInput::Left => (-offset.x, offset.y),
+                NavInput::Right => (offset.x, offset.y),
+                NavInput::Activate | NavInput::Cancel => return None,
+            };
+
+            if along > 0. {
+                Some((along + across.abs() * 2., *other))
+            } else {
+                None
+            }
+        })
+        .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(_, other)| other)
+}
+
+#[test]
+fn navigation_neighbor() {
+    //  1 2
+    //  3
+    let focusables = [
+        (1, Rect::new(0., 0., 50., 20.)),
+        (2, Rect::new(100., 0., 50., 20.)),
+        (3, Rect::new(0., 30., 50., 20.)),
+    ];
+    let rect = focusables[0].1;
+
+    assert_eq!(neighbor(1, rect, &focusables, NavInput::Right), Some(2));
+    assert_eq!(neighbor(1, rect, &focusables, NavInput::Down), Some(3));
+    assert_eq!(neighbor(1, rect, &focusables, NavInput::Up), None);
+    assert_eq!(
+        neighbor(3, focusables[2].1, &focusables, NavInput::Up),
+        Some(1)
+    );
+
+    let mut navigator = Navigator::new();
+    navigator.focusables = focusables.to_vec();
+    assert_eq!(navigator.new_frame(None, Some(NavInput::Down), 0.), None);
+    navigator.focusables = focusables.to_vec();
+    navigator.navigate(NavInput::Down);
+    assert_eq!(navigator.new_frame(None, None, 0.), Some(1));
+    // held, waits for the repeat delay
+    navigator.focusables = focusables.to_vec();
+    assert_eq!(
+        navigator.new_frame(Some(1), Some(NavInput::Down), 0.1),
+        Some(1)
+    );
+    navigator.focusables = focusables.to_vec();
+    assert_eq!(
+        navigator.new_frame(Some(1), Some(NavInput::Down), 0.5),
+        Some(3)
+    );
+
+    assert_eq!(
+        NavInput::from_axes(Vec2::new(0.1, -0.9)),
+        Some(NavInput::Up)
+    );
+    assert_eq!(NavInput::from_axes(Vec2::new(0.2, 0.1)), None);
+}
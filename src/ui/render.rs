@@ -3,4 +3,5 @@ mod painter;
 
 pub(crate) use mesh_rasterizer::render_command;
 pub use mesh_rasterizer::{DrawList, Vertex};
-pub(crate) use painter::{DrawCommand, ElementState, Painter};
+pub use painter::ElementState;
+pub(crate) use painter::{DrawCommand, Painter};
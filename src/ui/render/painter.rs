@@ -132,6 +132,78 @@ impl DrawCommand {
         }
     }
 
+    /// Same command, with the colors made more transparent.
+    pub fn fade(self, alpha: f32) -> DrawCommand {
+        let fade = |color: Color| Color {
+            a: color.a * alpha,
+            ..color
+        };
+
+        match self {
+            DrawCommand::DrawCharacter {
+                dest,
+                source,
+                color,
+            } => DrawCommand::DrawCharacter {
+                dest,
+                source,
+                color: fade(color),
+            },
+            DrawCommand::DrawRect {
+                rect,
+                source,
+                fill,
+                stroke,
+            } => DrawCommand::DrawRect {
+                rect,
+                source,
+                fill: fill.map(fade),
+                stroke: stroke.map(fade),
+            },
+            DrawCommand::DrawSprite {
+                rect,
+                source,
+                color,
+                offsets,
+                offsets_uv,
+            } => DrawCommand::DrawSprite {
+                rect,
+                source,
+                color: fade(color),
+                offsets,
+                offsets_uv,
+            },
+            DrawCommand::DrawTriangle {
+                p0,
+                p1,
+                p2,
+                source,
+                color,
+            } => DrawCommand::DrawTriangle {
+                p0,
+                p1,
+                p2,
+                source,
+                color: fade(color),
+            },
+            DrawCommand::DrawLine {
+                start,
+                end,
+                source,
+                color,
+            } => DrawCommand::DrawLine {
+                start,
+                end,
+                source,
+                color: fade(color),
+            },
+            // raw textures are drawn with white
+            command @ DrawCommand::DrawRawTexture { .. } | command @ DrawCommand::Clip { .. } => {
+                command
+            }
+        }
+    }
+
     pub(crate) fn estimate_triangles_budget(&self) -> (usize, usize) {
         match self {
             DrawCommand::DrawCharacter { .. } => (10, 10),
@@ -209,7 +281,19 @@ impl Painter {
         element_state: ElementState,
     ) {
         let color = style.color(element_state);
+        self.draw_element_background_color(style, pos, size, element_state, color);
+    }
 
+    /// [Painter::draw_element_background] with a color other than the style one,
+    /// like a transition in between two states.
+    pub fn draw_element_background_color(
+        &mut self,
+        style: &Style,
+        pos: Vec2,
+        size: Vec2,
+        element_state: ElementState,
+        color: Color,
+    ) {
         let background_margin = style.background_margin.unwrap_or_default();
         if let Some(background) = style.background_sprite(element_state) {
             self.draw_sprite(
@@ -9,6 +9,15 @@ use crate::{
 use std::cell::RefCell;
 use std::rc::Rc;
 
+// for the nanoserde derives output
+#[allow(clippy::question_mark)]
+mod file;
+
+pub(crate) use file::parse_color;
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::save_skin;
+pub use file::{load_skin, SkinError, SkinWatcher};
+
 pub struct StyleBuilder {
     atlas: Rc<RefCell<Atlas>>,
     font: Rc<RefCell<FontInternal>>,
@@ -211,10 +220,21 @@ impl StyleBuilder {
             text_color_clicked: self.text_color_clicked,
             font_size: self.font_size,
             reverse_background_z: self.reverse_background_z,
+            files: StyleFiles::default(),
         }
     }
 }
 
+/// Paths of the font and images a style was loaded with by [load_skin],
+/// to write them back with [save_skin].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StyleFiles {
+    pub font: Option<String>,
+    pub background: Option<String>,
+    pub background_hovered: Option<String>,
+    pub background_clicked: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Style {
     pub(crate) background: Option<u64>,
@@ -242,6 +262,7 @@ pub struct Style {
     pub(crate) text_color_clicked: Color,
     pub(crate) font_size: u16,
     pub(crate) reverse_background_z: bool,
+    pub(crate) files: StyleFiles,
 }
 
 impl Style {
@@ -264,6 +285,7 @@ impl Style {
             color_selected_hovered: Color::from_rgba(255, 255, 255, 255),
             color_inactive: None,
             reverse_background_z: false,
+            files: StyleFiles::default(),
         }
     }
 
@@ -372,6 +394,14 @@ pub struct Skin {
 
     pub scroll_width: f32,
     pub scroll_multiplier: f32,
+
+    /// Outline of the widget with the keyboard focus
+    pub focus_color: Color,
+    /// Seconds for the buttons and checkboxes to fade between their hovered and
+    /// pressed colors, 0 to switch at once
+    pub transition_time: f32,
+    /// Drawn over the screen behind a [crate::ui::widgets::Dialog]
+    pub dialog_dim_color: Color,
 }
 
 impl Skin {
@@ -471,6 +501,9 @@ impl Skin {
             title_height: 14.0,
             scroll_width: 10.0,
             scroll_multiplier: 3.,
+            focus_color: Color::from_rgba(34, 34, 255, 255),
+            transition_time: 0.1,
+            dialog_dim_color: Color::from_rgba(0, 0, 0, 100),
         }
     }
 }
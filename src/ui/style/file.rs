@@ -0,0 +1,646 @@
+//! Skins described in RON files, to style the UI without recompiling.
+//!
+//! Every field is optional, missing ones keep the default skin values:
+//! ```ron
+//! (
+//!     margin: 4.0,
+//!     button: (
+//!         font: "fonts/ui.ttf",
+//!         font_size: 20,
+//!         color: "#ccccccf0",
+//!         color_hovered: "#aaaaaaf0",
+//!         background: "button.png",
+//!         // part of the image not stretched with the button
+//!         background_margin: (left: 8.0, right: 8.0, top: 8.0, bottom: 8.0),
+//!     ),
+//! )
+//! ```
+//! Font and image paths are relative to the skin file.
+
+use super::{Skin, Style};
+use crate::{
+    color::Color,
+    file::{load_file, load_string, FileError},
+    logging::error,
+    math::RectOffset,
+    text::{atlas::Atlas, FontError, FontInternal},
+    texture::Image,
+};
+
+use nanoserde::{DeRon, DeRonErr, SerRon};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+#[derive(Debug, Default, Clone, PartialEq, DeRon, SerRon)]
+struct MarginFile {
+    left: f32,
+    right: f32,
+    top: f32,
+    bottom: f32,
+}
+
+/// Colors are "#rrggbb" or "#rrggbbaa" strings.
+#[derive(Debug, Default, Clone, PartialEq, DeRon, SerRon)]
+struct StyleFile {
+    font: Option<String>,
+    font_size: Option<u16>,
+    text_color: Option<String>,
+    text_color_hovered: Option<String>,
+    text_color_clicked: Option<String>,
+    color: Option<String>,
+    color_inactive: Option<String>,
+    color_hovered: Option<String>,
+    color_clicked: Option<String>,
+    color_selected: Option<String>,
+    color_selected_hovered: Option<String>,
+    background: Option<String>,
+    background_hovered: Option<String>,
+    background_clicked: Option<String>,
+    background_margin: Option<MarginFile>,
+    margin: Option<MarginFile>,
+    reverse_background_z: Option<bool>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, DeRon, SerRon)]
+struct SkinFile {
+    label: Option<StyleFile>,
+    button: Option<StyleFile>,
+    tabbar: Option<StyleFile>,
+    combobox: Option<StyleFile>,
+    window: Option<StyleFile>,
+    editbox: Option<StyleFile>,
+    window_titlebar: Option<StyleFile>,
+    scrollbar: Option<StyleFile>,
+    scrollbar_handle: Option<StyleFile>,
+    checkbox: Option<StyleFile>,
+    group: Option<StyleFile>,
+
+    margin: Option<f32>,
+    title_height: Option<f32>,
+    scroll_width: Option<f32>,
+    scroll_multiplier: Option<f32>,
+    focus_color: Option<String>,
+    transition_time: Option<f32>,
+    dialog_dim_color: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum SkinError {
+    FileError(FileError),
+    /// Malformed skin file
+    ParseError {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// Color not written as "#rrggbb" or "#rrggbbaa"
+    InvalidColor(String),
+    ImageError {
+        path: String,
+        message: String,
+    },
+    FontError {
+        path: String,
+        error: FontError,
+    },
+}
+
+impl std::error::Error for SkinError {}
+impl std::fmt::Display for SkinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SkinError::FileError(error) => write!(f, "{}", error),
+            SkinError::ParseError {
+                message,
+                line,
+                column,
+            } => write!(f, "Skin file error at {}:{}: {}", line, column, message),
+            SkinError::InvalidColor(color) => write!(
+                f,
+                "Invalid color \"{}\", expected \"#rrggbb\" or \"#rrggbbaa\"",
+                color
+            ),
+            SkinError::ImageError { path, message } => {
+                write!(f, "Couldn't load image {}: {}", path, message)
+            }
+            SkinError::FontError { path, error } => {
+                write!(f, "Couldn't load font {}: {}", path, error)
+            }
+        }
+    }
+}
+
+impl From<FileError> for SkinError {
+    fn from(error: FileError) -> SkinError {
+        SkinError::FileError(error)
+    }
+}
+
+impl From<DeRonErr> for SkinError {
+    fn from(error: DeRonErr) -> SkinError {
+        SkinError::ParseError {
+            message: error.msg,
+            line: error.line,
+            column: error.col,
+        }
+    }
+}
+
+pub(crate) fn parse_color(color: &str) -> Result<Color, SkinError> {
+    let invalid = || SkinError::InvalidColor(color.to_string());
+
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |i: usize| -> Result<u8, SkinError> {
+        match hex.get(i * 2..i * 2 + 2) {
+            Some(channel) => u8::from_str_radix(channel, 16).map_err(|_| invalid()),
+            None => Ok(255),
+        }
+    };
+
+    Ok(Color::from_rgba(
+        channel(0)?,
+        channel(1)?,
+        channel(2)?,
+        channel(3)?,
+    ))
+}
+
+fn color_to_string(color: Color) -> String {
+    let [r, g, b, a]: [u8; 4] = color.into();
+    format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+}
+
+/// Path of a font or image, relative to the skin file using it.
+fn asset_path(skin_path: &str, path: &str) -> String {
+    match skin_path.rfind('/') {
+        Some(ix) => format!("{}/{}", &skin_path[..ix], path),
+        None => path.to_string(),
+    }
+}
+
+fn skin_styles(skin: &mut Skin) -> [&mut Style; 11] {
+    [
+        &mut skin.label_style,
+        &mut skin.button_style,
+        &mut skin.tabbar_style,
+        &mut skin.combobox_style,
+        &mut skin.window_style,
+        &mut skin.editbox_style,
+        &mut skin.window_titlebar_style,
+        &mut skin.scrollbar_style,
+        &mut skin.scrollbar_handle_style,
+        &mut skin.checkbox_style,
+        &mut skin.group_style,
+    ]
+}
+
+/// Fonts and images of a skin file, with the fonts shared between styles.
+struct Assets<'a> {
+    atlas: Rc<RefCell<Atlas>>,
+    files: &'a HashMap<String, Vec<u8>>,
+    fonts: HashMap<String, Rc<RefCell<FontInternal>>>,
+}
+
+impl Assets<'_> {
+    fn font(&mut self, path: &str) -> Result<Rc<RefCell<FontInternal>>, SkinError> {
+        if let Some(font) = self.fonts.get(path) {
+            return Ok(font.clone());
+        }
+        let font = FontInternal::load_from_bytes(self.atlas.clone(), &self.files[path]).map_err(
+            |error| SkinError::FontError {
+                path: path.to_string(),
+                error,
+            },
+        )?;
+        let font = Rc::new(RefCell::new(font));
+        self.fonts.insert(path.to_string(), font.clone());
+        Ok(font)
+    }
+
+    /// Decode the image and add it to the UI atlas.
+    fn sprite(&mut self, path: &str) -> Result<u64, SkinError> {
+        let image = image::load_from_memory(&self.files[path])
+            .map_err(|error| SkinError::ImageError {
+                path: path.to_string(),
+                message: error.to_string(),
+            })?
+            .to_rgba8();
+        let image = Image {
+            width: image.width() as u16,
+            height: image.height() as u16,
+            bytes: image.into_raw(),
+        };
+
+        let mut atlas = self.atlas.borrow_mut();
+        let id = atlas.new_unique_id();
+        atlas.cache_sprite(id, image);
+        Ok(id)
+    }
+}
+
+impl MarginFile {
+    fn new(margin: RectOffset) -> MarginFile {
+        MarginFile {
+            left: margin.left,
+            right: margin.right,
+            top: margin.top,
+            bottom: margin.bottom,
+        }
+    }
+
+    fn offset(&self) -> RectOffset {
+        RectOffset::new(self.left, self.right, self.top, self.bottom)
+    }
+}
+
+impl StyleFile {
+    fn new(style: &Style) -> StyleFile {
+        let color = |color: Color| Some(color_to_string(color));
+
+        StyleFile {
+            font: style.files.font.clone(),
+            font_size: Some(style.font_size),
+            text_color: color(style.text_color),
+            text_color_hovered: color(style.text_color_hovered),
+            text_color_clicked: color(style.text_color_clicked),
+            color: color(style.color),
+            color_inactive: style.color_inactive.map(color_to_string),
+            color_hovered: color(style.color_hovered),
+            color_clicked: color(style.color_clicked),
+            color_selected: color(style.color_selected),
+            color_selected_hovered: color(style.color_selected_hovered),
+            background: style.files.background.clone(),
+            background_hovered: style.files.background_hovered.clone(),
+            background_clicked: style.files.background_clicked.clone(),
+            background_margin: style.background_margin.map(MarginFile::new),
+            margin: style.margin.map(MarginFile::new),
+            reverse_background_z: Some(style.reverse_background_z),
+        }
+    }
+
+    fn files(&self) -> impl Iterator<Item = &String> {
+        self.font
+            .iter()
+            .chain(&self.background)
+            .chain(&self.background_hovered)
+            .chain(&self.background_clicked)
+    }
+
+    fn apply(&self, style: &mut Style, assets: &mut Assets) -> Result<(), SkinError> {
+        let colors = [
+            (&self.text_color, &mut style.text_color),
+            (&self.text_color_hovered, &mut style.text_color_hovered),
+            (&self.text_color_clicked, &mut style.text_color_clicked),
+            (&self.color, &mut style.color),
+            (&self.color_hovered, &mut style.color_hovered),
+            (&self.color_clicked, &mut style.color_clicked),
+            (&self.color_selected, &mut style.color_selected),
+            (
+                &self.color_selected_hovered,
+                &mut style.color_selected_hovered,
+            ),
+        ];
+        for (color, style_color) in colors {
+            if let Some(color) = color {
+                *style_color = parse_color(color)?;
+            }
+        }
+        if let Some(color) = &self.color_inactive {
+            style.color_inactive = Some(parse_color(color)?);
+        }
+
+        if let Some(path) = &self.font {
+            style.font = assets.font(path)?;
+            style.files.font = Some(path.clone());
+        }
+        if let Some(font_size) = self.font_size {
+            style.font_size = font_size;
+        }
+
+        let backgrounds = [
+            (
+                &self.background,
+                &mut style.background,
+                &mut style.files.background,
+            ),
+            (
+                &self.background_hovered,
+                &mut style.background_hovered,
+                &mut style.files.background_hovered,
+            ),
+            (
+                &self.background_clicked,
+                &mut style.background_clicked,
+                &mut style.files.background_clicked,
+            ),
+        ];
+        for (path, sprite, file) in backgrounds {
+            if let Some(path) = path {
+                *sprite = Some(assets.sprite(path)?);
+                *file = Some(path.clone());
+            }
+        }
+
+        if let Some(margin) = &self.background_margin {
+            style.background_margin = Some(margin.offset());
+        }
+        if let Some(margin) = &self.margin {
+            style.margin = Some(margin.offset());
+        }
+        if let Some(reverse_background_z) = self.reverse_background_z {
+            style.reverse_background_z = reverse_background_z;
+        }
+
+        Ok(())
+    }
+}
+
+impl SkinFile {
+    fn parse(data: &str) -> Result<SkinFile, SkinError> {
+        Ok(SkinFile::deserialize_ron(data)?)
+    }
+
+    fn new(skin: &Skin) -> SkinFile {
+        let mut skin = skin.clone();
+        let mut file = SkinFile {
+            margin: Some(skin.margin),
+            title_height: Some(skin.title_height),
+            scroll_width: Some(skin.scroll_width),
+            scroll_multiplier: Some(skin.scroll_multiplier),
+            focus_color: Some(color_to_string(skin.focus_color)),
+            transition_time: Some(skin.transition_time),
+            dialog_dim_color: Some(color_to_string(skin.dialog_dim_color)),
+            ..Default::default()
+        };
+        for (style, style_file) in
+            IntoIterator::into_iter(skin_styles(&mut skin)).zip(file.styles())
+        {
+            *style_file = Some(StyleFile::new(style));
+        }
+        file
+    }
+
+    fn styles(&mut self) -> [&mut Option<StyleFile>; 11] {
+        [
+            &mut self.label,
+            &mut self.button,
+            &mut self.tabbar,
+            &mut self.combobox,
+            &mut self.window,
+            &mut self.editbox,
+            &mut self.window_titlebar,
+            &mut self.scrollbar,
+            &mut self.scrollbar_handle,
+            &mut self.checkbox,
+            &mut self.group,
+        ]
+    }
+
+    /// Fonts and images used by the styles, as written in the file.
+    fn files(&mut self) -> Vec<String> {
+        let mut files: Vec<String> = vec![];
+        for style in IntoIterator::into_iter(self.styles()).flatten() {
+            for file in style.files() {
+                if !files.contains(file) {
+                    files.push(file.clone());
+                }
+            }
+        }
+        files
+    }
+
+    /// The default skin with the file applied on top.
+    fn build(mut self, files: &HashMap<String, Vec<u8>>) -> Result<Skin, SkinError> {
+        let builder = crate::ui::root_ui().style_builder();
+        let mut skin = Skin::new(builder.atlas.clone(), builder.font);
+        let mut assets = Assets {
+            atlas: builder.atlas,
+            files,
+            fonts: HashMap::new(),
+        };
+
+        for (style_file, style) in
+            IntoIterator::into_iter(self.styles()).zip(skin_styles(&mut skin))
+        {
+            if let Some(style_file) = style_file {
+                style_file.apply(style, &mut assets)?;
+            }
+        }
+
+        let values = [
+            (self.margin, &mut skin.margin),
+            (self.title_height, &mut skin.title_height),
+            (self.scroll_width, &mut skin.scroll_width),
+            (self.scroll_multiplier, &mut skin.scroll_multiplier),
+            (self.transition_time, &mut skin.transition_time),
+        ];
+        for (value, skin_value) in values {
+            if let Some(value) = value {
+                *skin_value = value;
+            }
+        }
+        if let Some(color) = &self.focus_color {
+            skin.focus_color = parse_color(color)?;
+        }
+        if let Some(color) = &self.dialog_dim_color {
+            skin.dialog_dim_color = parse_color(color)?;
+        }
+
+        Ok(skin)
+    }
+}
+
+/// Skin and the paths of all the files it was made of.
+async fn load(path: &str) -> Result<(Skin, Vec<String>), SkinError> {
+    let mut file = SkinFile::parse(&load_string(path).await?)?;
+
+    let mut files = HashMap::new();
+    for asset in file.files() {
+        let data = load_file(&asset_path(path, &asset)).await?;
+        files.insert(asset, data);
+    }
+
+    let paths = std::iter::once(path.to_string())
+        .chain(files.keys().map(|asset| asset_path(path, asset)))
+        .collect();
+    Ok((file.build(&files)?, paths))
+}
+
+/// Load a skin from a RON file, see the [module docs](self) for the format.
+///
+/// Loaded fonts and images are added to the UI atlas and stay there.
+pub async fn load_skin(path: &str) -> Result<Skin, SkinError> {
+    Ok(load(path).await?.0)
+}
+
+/// Write the skin in the [load_skin] format.
+///
+/// Fonts and images are written as the paths they were loaded with, so the
+/// skin should be saved next to the file it was loaded from. The built in
+/// font and images of the default skin are not written: loading the file
+/// will keep them.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_skin(skin: &Skin, path: &str) -> Result<(), SkinError> {
+    let path = crate::file::assets_path(path);
+    std::fs::write(&path, SkinFile::new(skin).serialize_ron())
+        .map_err(|error| FileError::new(miniquad::fs::Error::IOError(error), &path).into())
+}
+
+/// Skin loaded with [load_skin] and reloaded when any of its files change.
+/// Reloads only in debug builds and not on web.
+///
+/// ```ignore
+/// let mut skin = SkinWatcher::new("ui/skin.ron").await.unwrap();
+/// loop {
+///     skin.update();
+///     root_ui().push_skin(skin.skin());
+///     ..
+///     root_ui().pop_skin();
+///     next_frame().await;
+/// }
+/// ```
+pub struct SkinWatcher {
+    path: String,
+    skin: Skin,
+    files: Vec<(String, Option<std::time::SystemTime>)>,
+    last_check: f64,
+}
+
+fn modified(path: &str) -> Option<std::time::SystemTime> {
+    std::fs::metadata(crate::file::assets_path(path))
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+impl SkinWatcher {
+    const CHECK_INTERVAL: f64 = 0.25;
+
+    pub async fn new(path: &str) -> Result<SkinWatcher, SkinError> {
+        let (skin, files) = load(path).await?;
+
+        Ok(SkinWatcher {
+            path: path.to_string(),
+            skin,
+            files: files
+                .into_iter()
+                .map(|path| {
+                    let time = modified(&path);
+                    (path, time)
+                })
+                .collect(),
+            last_check: miniquad::date::now(),
+        })
+    }
+
+    pub fn skin(&self) -> &Skin {
+        &self.skin
+    }
+
+    /// Reload the skin if any of its files changed.
+    /// Returns true if the skin was reloaded. On errors the old skin is kept.
+    pub fn update(&mut self) -> bool {
+        if !cfg!(debug_assertions) || cfg!(target_arch = "wasm32") {
+            return false;
+        }
+
+        let now = miniquad::date::now();
+        if now - self.last_check < Self::CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = now;
+
+        let mut changed = false;
+        for (path, time) in &mut self.files {
+            let new_time = modified(path);
+            if new_time != *time {
+                *time = new_time;
+                changed = true;
+            }
+        }
+        if !changed {
+            return false;
+        }
+
+        match self.reload() {
+            Ok(files) => {
+                for path in files {
+                    if !self.files.iter().any(|(file, _)| *file == path) {
+                        let time = modified(&path);
+                        self.files.push((path, time));
+                    }
+                }
+                true
+            }
+            Err(err) => {
+                error!("Failed to reload skin {}: {}", self.path, err);
+                false
+            }
+        }
+    }
+
+    /// Read the skin synchronously, as the file watching is native only.
+    fn reload(&mut self) -> Result<Vec<String>, SkinError> {
+        let read = |path: &str| {
+            let full_path = crate::file::assets_path(path);
+            std::fs::read(&full_path)
+                .map_err(|error| FileError::new(miniquad::fs::Error::IOError(error), &full_path))
+        };
+
+        let data = read(&self.path)?;
+        let mut file = SkinFile::parse(&String::from_utf8_lossy(&data))?;
+
+        let mut files = HashMap::new();
+        for asset in file.files() {
+            let data = read(&asset_path(&self.path, &asset))?;
+            files.insert(asset, data);
+        }
+
+        let paths = files
+            .keys()
+            .map(|asset| asset_path(&self.path, asset))
+            .collect();
+        self.skin = file.build(&files)?;
+        Ok(paths)
+    }
+}
+
+#[test]
+fn skin_file_roundtrip() {
+    assert_eq!(
+        parse_color("#ff800040").unwrap(),
+        Color::from_rgba(255, 128, 0, 64)
+    );
+    assert_eq!(
+        parse_color("#ff8000").unwrap(),
+        Color::from_rgba(255, 128, 0, 255)
+    );
+    assert!(parse_color("ff8000").is_err());
+    assert!(parse_color("#ff80").is_err());
+    assert_eq!(
+        color_to_string(Color::from_rgba(255, 128, 0, 64)),
+        "#ff800040"
+    );
+
+    let file = SkinFile::parse(
+        "(
+            margin: 4.0,
+            // comments are fine
+            button: (
+                color: \"#cccccc\",
+                margin: (left: 1.0, right: 2.0, top: 3.0, bottom: 4.0),
+                background: \"button.png\",
+            ),
+        )",
+    )
+    .unwrap();
+    assert_eq!(file.margin, Some(4.));
+    assert_eq!(file.label, None);
+    let button = file.button.as_ref().unwrap();
+    assert_eq!(button.color.as_deref(), Some("#cccccc"));
+    assert_eq!(button.margin.as_ref().unwrap().offset().bottom, 4.);
+
+    assert_eq!(SkinFile::parse(&file.serialize_ron()).unwrap(), file);
+    assert_eq!(file.clone().files(), vec!["button.png".to_string()]);
+}
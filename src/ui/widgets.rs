@@ -1,30 +1,47 @@
 mod button;
 mod checkbox;
 mod combobox;
+mod dialog;
+mod dock;
 mod drag;
+mod drag_drop;
 mod editbox;
 mod group;
 mod input;
 mod label;
+mod list;
+mod plot;
 mod popup;
+mod rich_text;
 mod separator;
 mod slider;
 mod tabbar;
+mod table;
 mod texture;
+mod tooltip;
 mod tree_node;
+mod tree_view;
 mod window;
 
 pub use button::Button;
 pub use checkbox::Checkbox;
 pub use combobox::ComboBox;
+pub use dialog::{Dialog, MessageBox};
+pub use dock::{DockLayout, DockLayoutError, DockNode, DockSpace};
 pub use editbox::Editbox;
 pub use group::{Group, GroupToken};
 #[allow(deprecated)]
 pub use input::{InputField, InputText};
 pub use label::Label;
-pub use popup::Popup;
+pub use list::List;
+pub use plot::{Plot, PlotKind};
+pub use popup::{ContextMenu, Popup};
+pub use rich_text::RichText;
 pub use slider::Slider;
 pub use tabbar::Tabbar;
+pub use table::{Table, TableResponse, TableSort};
 pub use texture::Texture;
+pub use tooltip::Tooltip;
 pub use tree_node::{TreeNode, TreeNodeToken};
+pub use tree_view::{TreeDrop, TreeDropPosition, TreeResponse, TreeView};
 pub use window::{Window, WindowToken};
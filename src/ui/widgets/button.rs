@@ -1,9 +1,10 @@
 use crate::{
     math::{Rect, Vec2},
-    ui::{ElementState, Layout, Ui, UiContent},
+    ui::{ElementState, Id, Layout, Ui, UiContent},
 };
 
 pub struct Button<'a> {
+    id: Option<Id>,
     position: Option<Vec2>,
     size: Option<Vec2>,
     content: UiContent<'a>,
@@ -16,6 +17,7 @@ impl<'a> Button<'a> {
         S: Into<UiContent<'a>>,
     {
         Button {
+            id: None,
             position: None,
             size: None,
             content: content.into(),
@@ -40,6 +42,15 @@ impl<'a> Button<'a> {
         Button { selected, ..self }
     }
 
+    /// Id for [Ui::set_input_focus], by default buttons are told apart by
+    /// their order in the `Tab` navigation.
+    pub fn id(self, id: Id) -> Self {
+        Button {
+            id: Some(id),
+            ..self
+        }
+    }
+
     pub fn ui(self, ui: &mut Ui) -> bool {
         let mut context = ui.get_active_window_context();
 
@@ -57,17 +68,34 @@ impl<'a> Button<'a> {
         let rect = Rect::new(pos.x, pos.y, size.x as f32, size.y as f32);
         let (hovered, clicked) = context.register_click_intention(rect);
 
-        if !context.style.button_style.reverse_background_z {
-            context.window.painter.draw_element_background(
+        let id = self
+            .id
+            .unwrap_or_else(|| hash!(context.window.id, "button", context.tab_selector.counter));
+        let keyboard_focused = context.register_focusable(id, rect);
+        let clicked = clicked || keyboard_focused && context.input.activated();
+
+        let state = ElementState {
+            focused: context.focused,
+            hovered,
+            clicked: hovered && context.input.is_mouse_down,
+            selected: self.selected,
+        };
+        let reverse_background_z = context.style.button_style.reverse_background_z;
+        // a background drawn over the content is never selected
+        let background_state = ElementState {
+            selected: self.selected && !reverse_background_z,
+            ..state
+        };
+        let background_color = context.style.button_style.color(background_state);
+        let background_color = context.transition_color(id, background_color);
+
+        if !reverse_background_z {
+            context.window.painter.draw_element_background_color(
                 &context.style.button_style,
                 pos,
                 size,
-                ElementState {
-                    focused: context.focused,
-                    hovered,
-                    clicked: hovered && context.input.is_mouse_down,
-                    selected: self.selected,
-                },
+                background_state,
+                background_color,
             );
         }
 
@@ -76,28 +104,23 @@ impl<'a> Button<'a> {
             pos,
             size,
             &self.content,
-            ElementState {
-                focused: context.focused,
-                hovered,
-                clicked: hovered && context.input.is_mouse_down,
-                selected: self.selected,
-            },
+            state,
         );
 
-        if context.style.button_style.reverse_background_z {
-            context.window.painter.draw_element_background(
+        if reverse_background_z {
+            context.window.painter.draw_element_background_color(
                 &context.style.button_style,
                 pos,
                 size,
-                ElementState {
-                    focused: context.focused,
-                    hovered,
-                    clicked: hovered && context.input.is_mouse_down,
-                    selected: false,
-                },
+                background_state,
+                background_color,
             );
         }
 
+        if keyboard_focused {
+            context.draw_focus_highlight(rect);
+        }
+
         clicked
     }
 }
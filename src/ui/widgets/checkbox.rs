@@ -51,7 +51,7 @@ impl<'a> Checkbox<'a> {
     }
 
     pub fn ui(self, ui: &mut Ui, data: &mut bool) {
-        let context = ui.get_active_window_context();
+        let mut context = ui.get_active_window_context();
 
         let label_size = context.window.painter.content_with_margins_size(
             &context.style.label_style,
@@ -104,6 +104,7 @@ impl<'a> Checkbox<'a> {
             clicked: hovered && context.input.is_mouse_down,
             selected: *data,
         });
+        let color = context.transition_color(self.id, color);
 
         if let Some(background) = background {
             let background_margin = context
@@ -131,9 +132,20 @@ impl<'a> Checkbox<'a> {
             );
         }
 
-        if hovered && context.input.click_up() {
+        let checkbox_rect = Rect::new(
+            checkbox_pos.x,
+            checkbox_pos.y,
+            checkbox_area.x,
+            checkbox_area.y,
+        );
+        let keyboard_focused = context.register_focusable(self.id, checkbox_rect);
+
+        if hovered && context.input.click_up() || keyboard_focused && context.input.activated() {
             *data ^= true;
         }
+        if keyboard_focused {
+            context.draw_focus_highlight(checkbox_rect);
+        }
 
         let context = ui.get_active_window_context();
 
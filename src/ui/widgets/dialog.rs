@@ -0,0 +1,204 @@
+use crate::{
+    math::{vec2, Vec2},
+    ui::{
+        widgets::{Button, Window},
+        Id, Ui, UiContent,
+    },
+};
+
+/// Window in the middle of the screen taking all the input while it is
+/// drawn, with everything behind it dimmed.
+///
+/// Popups and comboboxes opened from the dialog still work.
+/// ```ignore
+/// if show_settings {
+///     Dialog::new(hash!(), vec2(300., 200.))
+///         .label("Settings")
+///         .ui(&mut root_ui(), |ui| {
+///             ui.checkbox(hash!(), "Fullscreen", &mut fullscreen);
+///             if ui.button(None, "Close") {
+///                 show_settings = false;
+///             }
+///         });
+/// }
+/// ```
+pub struct Dialog<'a> {
+    id: Id,
+    size: Vec2,
+    label: Option<&'a str>,
+}
+
+impl<'a> Dialog<'a> {
+    pub fn new(id: Id, size: Vec2) -> Dialog<'a> {
+        Dialog {
+            id,
+            size,
+            label: None,
+        }
+    }
+
+    /// Title, by default the dialog has no titlebar.
+    pub fn label(self, label: &'a str) -> Dialog<'a> {
+        Dialog {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    pub fn ui<F: FnOnce(&mut Ui)>(self, ui: &mut Ui, f: F) {
+        let position = (ui.root_window.size - self.size) / 2.;
+        let window = Window::new(self.id, position, self.size).movable(false);
+        let window = match self.label {
+            Some(label) => window.label(label),
+            None => window.titlebar(false),
+        };
+
+        ui.dialog = Some(self.id);
+        ui.focus_window(self.id);
+        window.ui(ui, f);
+    }
+}
+
+/// Dialog with a text and a row of buttons, returns the index of the button
+/// pressed.
+///
+/// `Enter` presses the first button and `Escape` the last one.
+/// ```ignore
+/// let buttons = &["Save", "Discard", "Cancel"];
+/// match MessageBox::new(hash!(), "Save the level?").buttons(buttons).ui(&mut root_ui()) {
+///     Some(0) => save(),
+///     Some(1) => quit(),
+///     Some(_) => show_quit = false,
+///     None => {}
+/// }
+/// ```
+pub struct MessageBox<'a> {
+    id: Id,
+    text: &'a str,
+    title: Option<&'a str>,
+    buttons: &'a [&'a str],
+}
+
+impl<'a> MessageBox<'a> {
+    pub fn new(id: Id, text: &'a str) -> MessageBox<'a> {
+        MessageBox {
+            id,
+            text,
+            title: None,
+            buttons: &["Ok"],
+        }
+    }
+
+    pub fn title(self, title: &'a str) -> MessageBox<'a> {
+        MessageBox {
+            title: Some(title),
+            ..self
+        }
+    }
+
+    /// A single "Ok" button by default.
+    pub fn buttons(self, buttons: &'a [&'a str]) -> MessageBox<'a> {
+        MessageBox { buttons, ..self }
+    }
+
+    pub fn ui(self, ui: &mut Ui) -> Option<usize> {
+        // not the keys that opened the dialog
+        let shown = ui.blocking_dialog == Some(self.id);
+        let context = ui.get_active_window_context();
+        let style = context.style;
+        let painter = &context.window.painter;
+
+        let lines = self
+            .text
+            .lines()
+            .map(|line| {
+                painter
+                    .content_with_margins_size(&style.label_style, &UiContent::Label(line.into()))
+            })
+            .collect::<Vec<_>>();
+        let buttons = self
+            .buttons
+            .iter()
+            .map(|button| {
+                painter.content_with_margins_size(
+                    &style.button_style,
+                    &UiContent::Label((*button).into()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let spacing = style.margin;
+        let margin = spacing * 2.;
+        let text_size = lines.iter().fold(Vec2::ZERO, |size, line| {
+            vec2(size.x.max(line.x), size.y + line.y + spacing)
+        });
+        let buttons_size = buttons.iter().fold(Vec2::ZERO, |size, button| {
+            vec2(size.x + button.x + spacing, size.y.max(button.y))
+        });
+        let title_height = if self.title.is_some() {
+            style.title_height
+        } else {
+            0.
+        };
+        let border = style.window_style.border_margin();
+        let size = vec2(
+            text_size.x.max(buttons_size.x) + margin * 2. + border.left + border.right,
+            title_height + text_size.y + buttons_size.y + margin * 3. + border.top + border.bottom,
+        );
+
+        let (enter, escape) = (context.input.enter && shown, context.input.escape && shown);
+        let mut pressed = None;
+
+        let mut dialog = Dialog::new(self.id, size);
+        if let Some(title) = self.title {
+            dialog = dialog.label(title);
+        }
+        dialog.ui(ui, |ui| {
+            for line in self.text.lines() {
+                ui.label(None, line);
+            }
+
+            // buttons in the bottom right corner
+            let mut x = size.x - border.left - border.right - margin - buttons_size.x;
+            let y = text_size.y + margin;
+            for (n, (label, button_size)) in self.buttons.iter().zip(&buttons).enumerate() {
+                if Button::new(*label).position(vec2(x, y)).ui(ui) {
+                    pressed = Some(n);
+                }
+                x += button_size.x + spacing;
+            }
+        });
+
+        if self.buttons.is_empty() {
+            return None;
+        }
+        pressed
+            .or_else(|| Some(0).filter(|_| enter))
+            .or_else(|| Some(self.buttons.len() - 1).filter(|_| escape))
+    }
+}
+
+impl Ui {
+    /// Dialog with Yes and No buttons, returns the answer once given.
+    ///
+    /// ```ignore
+    /// if quitting {
+    ///     match root_ui().confirm(hash!(), "Quit?") {
+    ///         Some(true) => break,
+    ///         Some(false) => quitting = false,
+    ///         None => {}
+    ///     }
+    /// }
+    /// ```
+    pub fn confirm(&mut self, id: Id, text: &str) -> Option<bool> {
+        MessageBox::new(id, text)
+            .buttons(&["Yes", "No"])
+            .ui(self)
+            .map(|button| button == 0)
+    }
+
+    /// Dialog with an Ok button, returns true once it is closed.
+    pub fn message_box(&mut self, id: Id, title: &str, text: &str) -> bool {
+        MessageBox::new(id, text).title(title).ui(self).is_some()
+    }
+}
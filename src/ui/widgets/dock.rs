@@ -0,0 +1,631 @@
+//! Windows docked into splits and tabs, for editor-like multi-panel workspaces.
+
+use crate::{
+    color::Color,
+    math::{vec2, Rect, Vec2},
+    ui::{
+        widgets::{Group, Window},
+        ElementState, Id, Ui, UiContent,
+    },
+};
+
+use nanoserde::{DeRon, DeRonErr, SerRon};
+
+/// Width of the handle between the children of a split
+const SPLITTER_SIZE: f32 = 4.;
+/// How far a tab should be dragged to get undocked
+const UNDOCK_DISTANCE: f32 = 10.;
+/// Edges of a node, as a fraction of its size, docking to the side instead of a tab
+const DROP_EDGE: f32 = 0.25;
+
+/// Node of the dock tree: either split in two children, or a set of tabs.
+#[derive(Debug, Clone, PartialEq, DeRon, SerRon)]
+pub struct DockNode {
+    /// Children side by side if true, one above the other otherwise
+    horizontal: bool,
+    /// Share of the first child in the split
+    ratio: f32,
+    /// Two children for a split, none for tabs
+    children: Vec<DockNode>,
+    tabs: Vec<String>,
+    selected: usize,
+}
+
+impl DockNode {
+    pub fn tabs(windows: &[&str]) -> DockNode {
+        DockNode {
+            horizontal: false,
+            ratio: 0.5,
+            children: vec![],
+            tabs: windows.iter().map(|window| window.to_string()).collect(),
+            selected: 0,
+        }
+    }
+
+    /// `left` and `right` side by side, `left` taking `ratio` of the width.
+    pub fn horizontal(ratio: f32, left: DockNode, right: DockNode) -> DockNode {
+        DockNode {
+            horizontal: true,
+            ratio,
+            children: vec![left, right],
+            ..DockNode::tabs(&[])
+        }
+    }
+
+    /// `top` above `bottom`, taking `ratio` of the height.
+    pub fn vertical(ratio: f32, top: DockNode, bottom: DockNode) -> DockNode {
+        DockNode {
+            horizontal: false,
+            ratio,
+            children: vec![top, bottom],
+            ..DockNode::tabs(&[])
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.tabs.is_empty()
+    }
+
+    fn contains(&self, window: &str) -> bool {
+        self.tabs.iter().any(|tab| tab == window)
+            || self.children.iter().any(|child| child.contains(window))
+    }
+
+    fn remove(&mut self, window: &str) {
+        self.tabs.retain(|tab| tab != window);
+        self.selected = self.selected.min(self.tabs.len().saturating_sub(1));
+        for child in &mut self.children {
+            child.remove(window);
+        }
+    }
+
+    /// Replace the splits with an empty child by the other child.
+    fn prune(&mut self) {
+        for child in &mut self.children {
+            child.prune();
+        }
+        if let Some(empty) = self.children.iter().position(DockNode::is_empty) {
+            let other = self.children.swap_remove(1 - empty);
+            *self = other;
+        }
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> &mut DockNode {
+        match path.split_first() {
+            Some((child, path)) => self.children[*child].node_mut(path),
+            None => self,
+        }
+    }
+
+    /// Rects of the tab nodes, and of the splits with their splitter handle.
+    fn layout(&self, rect: Rect, path: &mut Vec<usize>, layout: &mut NodesLayout) {
+        if self.children.len() != 2 {
+            layout.tabs.push((path.clone(), rect));
+            return;
+        }
+
+        let (first, splitter, second) = if self.horizontal {
+            let width = ((rect.w - SPLITTER_SIZE) * self.ratio).round();
+            (
+                Rect::new(rect.x, rect.y, width, rect.h),
+                Rect::new(rect.x + width, rect.y, SPLITTER_SIZE, rect.h),
+                Rect::new(
+                    rect.x + width + SPLITTER_SIZE,
+                    rect.y,
+                    rect.w - width - SPLITTER_SIZE,
+                    rect.h,
+                ),
+            )
+        } else {
+            let height = ((rect.h - SPLITTER_SIZE) * self.ratio).round();
+            (
+                Rect::new(rect.x, rect.y, rect.w, height),
+                Rect::new(rect.x, rect.y + height, rect.w, SPLITTER_SIZE),
+                Rect::new(
+                    rect.x,
+                    rect.y + height + SPLITTER_SIZE,
+                    rect.w,
+                    rect.h - height - SPLITTER_SIZE,
+                ),
+            )
+        };
+        layout.splits.push((path.clone(), rect, splitter));
+
+        for (n, rect) in [first, second].iter().enumerate() {
+            path.push(n);
+            self.children[n].layout(*rect, path, layout);
+            path.pop();
+        }
+    }
+}
+
+#[derive(Default)]
+struct NodesLayout {
+    tabs: Vec<(Vec<usize>, Rect)>,
+    /// Split rect and its splitter
+    splits: Vec<(Vec<usize>, Rect, Rect)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DropZone {
+    Tab,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl NodesLayout {
+    /// Where a window dropped at `position` would dock, with the rect it would take.
+    fn drop_target(&self, position: Vec2) -> Option<(Vec<usize>, DropZone, Rect)> {
+        let (path, rect) = self.tabs.iter().find(|(_, rect)| rect.contains(position))?;
+
+        let x = (position.x - rect.x) / rect.w;
+        let y = (position.y - rect.y) / rect.h;
+        let (edge, zone) = [
+            (x, DropZone::Left),
+            (1. - x, DropZone::Right),
+            (y, DropZone::Top),
+            (1. - y, DropZone::Bottom),
+        ]
+        .iter()
+        .copied()
+        .fold((f32::MAX, DropZone::Tab), |nearest, edge| {
+            if edge.0 < nearest.0 {
+                edge
+            } else {
+                nearest
+            }
+        });
+        let zone = if edge < DROP_EDGE {
+            zone
+        } else {
+            DropZone::Tab
+        };
+
+        let (w, h) = (rect.w / 2., rect.h / 2.);
+        let preview = match zone {
+            DropZone::Tab => *rect,
+            DropZone::Left => Rect::new(rect.x, rect.y, w, rect.h),
+            DropZone::Right => Rect::new(rect.x + w, rect.y, w, rect.h),
+            DropZone::Top => Rect::new(rect.x, rect.y, rect.w, h),
+            DropZone::Bottom => Rect::new(rect.x, rect.y + h, rect.w, h),
+        };
+        Some((path.clone(), zone, preview))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, DeRon, SerRon)]
+struct FloatingWindow {
+    name: String,
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+/// Docked and floating windows of a [DockSpace].
+///
+/// Saved with [DockLayout::to_ron] and restored with [DockLayout::from_ron],
+/// to keep the workspace between runs.
+#[derive(Debug, Clone, PartialEq, DeRon, SerRon)]
+pub struct DockLayout {
+    root: DockNode,
+    floating: Vec<FloatingWindow>,
+}
+
+#[derive(Debug)]
+pub struct DockLayoutError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::error::Error for DockLayoutError {}
+impl std::fmt::Display for DockLayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Dock layout error at {}:{}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl From<DeRonErr> for DockLayoutError {
+    fn from(error: DeRonErr) -> DockLayoutError {
+        DockLayoutError {
+            message: error.msg,
+            line: error.line,
+            column: error.col,
+        }
+    }
+}
+
+impl DockLayout {
+    pub fn new(root: DockNode) -> DockLayout {
+        DockLayout {
+            root,
+            floating: vec![],
+        }
+    }
+
+    /// Add a window floating over the docked ones.
+    pub fn floating(mut self, window: &str, rect: Rect) -> DockLayout {
+        self.float(window, rect);
+        self
+    }
+
+    pub fn contains(&self, window: &str) -> bool {
+        self.root.contains(window) || self.floating.iter().any(|w| w.name == window)
+    }
+
+    pub fn to_ron(&self) -> String {
+        self.serialize_ron()
+    }
+
+    pub fn from_ron(data: &str) -> Result<DockLayout, DockLayoutError> {
+        Ok(DockLayout::deserialize_ron(data)?)
+    }
+
+    fn float(&mut self, window: &str, rect: Rect) {
+        self.remove(window);
+        self.floating.push(FloatingWindow {
+            name: window.to_string(),
+            x: rect.x,
+            y: rect.y,
+            w: rect.w,
+            h: rect.h,
+        });
+    }
+
+    fn remove(&mut self, window: &str) {
+        self.floating.retain(|floating| floating.name != window);
+        self.root.remove(window);
+        self.root.prune();
+    }
+
+    fn dock(&mut self, window: &str, path: &[usize], zone: DropZone) {
+        self.floating.retain(|floating| floating.name != window);
+
+        let node = self.root.node_mut(path);
+        let new = DockNode::tabs(&[window]);
+        if zone == DropZone::Tab {
+            node.tabs.push(window.to_string());
+            node.selected = node.tabs.len() - 1;
+        } else {
+            let old = std::mem::replace(node, DockNode::tabs(&[]));
+            *node = match zone {
+                DropZone::Left => DockNode::horizontal(0.5, new, old),
+                DropZone::Right => DockNode::horizontal(0.5, old, new),
+                DropZone::Top => DockNode::vertical(0.5, new, old),
+                _ => DockNode::vertical(0.5, old, new),
+            };
+        }
+        self.root.prune();
+    }
+}
+
+/// Area of the UI split into docked windows.
+///
+/// Tabs dragged away from their node become floating windows, and floating
+/// windows dropped over a node are docked: as a tab in the middle of the node,
+/// or splitting it when dropped near an edge. The handles between the splits
+/// resize them.
+///
+/// ```ignore
+/// let layout = DockLayout::new(DockNode::horizontal(
+///     0.75,
+///     DockNode::tabs(&["Scene", "Game"]),
+///     DockNode::tabs(&["Inspector"]),
+/// ));
+/// let mut dock = DockSpace::new(hash!(), layout);
+/// loop {
+///     let rect = Rect::new(0., 0., screen_width(), screen_height());
+///     dock.ui(&mut root_ui(), rect, |ui, window| match window {
+///         "Scene" => ui.label(None, "scene tree"),
+///         _ => ..,
+///     });
+///     next_frame().await;
+/// }
+/// ```
+pub struct DockSpace {
+    id: Id,
+    layout: DockLayout,
+    /// Split being resized, by its path in the tree
+    resizing: Option<Vec<usize>>,
+    /// Tab pressed and maybe about to be dragged out, with the press position
+    pressed_tab: Option<(String, Vec2)>,
+    /// Floating window being moved, docked when released over a node
+    moving: Option<String>,
+    /// Move the floating windows to their layout position, after a new layout
+    reposition: bool,
+}
+
+impl DockSpace {
+    pub fn new(id: Id, layout: DockLayout) -> DockSpace {
+        DockSpace {
+            id,
+            layout,
+            resizing: None,
+            pressed_tab: None,
+            moving: None,
+            reposition: true,
+        }
+    }
+
+    pub fn layout(&self) -> &DockLayout {
+        &self.layout
+    }
+
+    pub fn set_layout(&mut self, layout: DockLayout) {
+        self.layout = layout;
+        self.reposition = true;
+    }
+
+    pub fn is_open(&self, window: &str) -> bool {
+        self.layout.contains(window)
+    }
+
+    /// Show the window as floating if it is not in the layout yet.
+    pub fn open(&mut self, window: &str) {
+        if !self.layout.contains(window) {
+            let offset = 20. * self.layout.floating.len() as f32;
+            self.layout
+                .float(window, Rect::new(50. + offset, 50. + offset, 300., 200.));
+        }
+    }
+
+    pub fn close(&mut self, window: &str) {
+        self.layout.remove(window);
+    }
+
+    fn window_id(&self, window: &str) -> Id {
+        hash!(self.id, window)
+    }
+
+    /// Draw the docked windows in `rect`, in screen coordinates, and the floating
+    /// windows over them. `f` draws the content of each visible window, by its name.
+    pub fn ui<F: FnMut(&mut Ui, &str)>(&mut self, ui: &mut Ui, rect: Rect, mut f: F) {
+        let mut layout = NodesLayout::default();
+        self.layout.root.layout(rect, &mut vec![], &mut layout);
+
+        if !ui.input.is_mouse_down {
+            self.resizing = None;
+            self.pressed_tab = None;
+        }
+
+        self.splitters(ui, &layout);
+
+        let mut undock = None;
+        for (path, rect) in &layout.tabs {
+            if let Some(window) = self.tabs_node(ui, path, *rect, &mut f) {
+                undock = Some((window, rect.size()));
+            }
+        }
+
+        if let Some((window, size)) = undock {
+            self.undock(ui, &window, size);
+        }
+
+        self.floating_windows(ui, &layout, &mut f);
+    }
+
+    fn splitters(&mut self, ui: &mut Ui, layout: &NodesLayout) {
+        let context = ui.get_active_window_context();
+        let mouse = context.input.mouse_position;
+
+        for (path, rect, splitter) in &layout.splits {
+            let hovered = context.input.window_active && splitter.contains(mouse);
+            if hovered && context.input.click_down() {
+                self.resizing = Some(path.clone());
+            }
+
+            let resizing = self.resizing.as_ref() == Some(path);
+            if resizing {
+                let node = self.layout.root.node_mut(path);
+                let ratio = if node.horizontal {
+                    (mouse.x - rect.x) / rect.w
+                } else {
+                    (mouse.y - rect.y) / rect.h
+                };
+                node.ratio = ratio.clamp(0.05, 0.95);
+            }
+
+            context.window.painter.draw_rect(
+                *splitter,
+                None,
+                context.style.scrollbar_handle_style.color(ElementState {
+                    focused: true,
+                    hovered,
+                    clicked: resizing,
+                    selected: false,
+                }),
+            );
+        }
+    }
+
+    /// Draw the tabs and the selected window of a node.
+    /// Returns the window dragged out of the node, if any.
+    fn tabs_node<F: FnMut(&mut Ui, &str)>(
+        &mut self,
+        ui: &mut Ui,
+        path: &[usize],
+        rect: Rect,
+        f: &mut F,
+    ) -> Option<String> {
+        let context = ui.get_active_window_context();
+        let mouse = context.input.mouse_position;
+        let style = &context.style.tabbar_style;
+        let tab_height = context.style.title_height + 6.;
+        let origin = context.window.cursor.area.point() + context.window.cursor.scroll.scroll;
+
+        let node = self.layout.root.node_mut(path);
+        let mut x = rect.x;
+        for (n, window) in node.tabs.iter().enumerate() {
+            let label = UiContent::Label(window.as_str().into());
+            let width = context
+                .window
+                .painter
+                .content_with_margins_size(style, &label)
+                .x
+                + 16.;
+            let tab = Rect::new(x, rect.y, width.min(rect.x + rect.w - x), tab_height);
+            x += width + 1.;
+
+            let hovered = context.input.window_active && tab.contains(mouse);
+            if hovered && context.input.click_down() {
+                node.selected = n;
+                self.pressed_tab = Some((window.clone(), mouse));
+            }
+
+            let state = ElementState {
+                focused: true,
+                hovered,
+                clicked: hovered && context.input.is_mouse_down,
+                selected: n == node.selected,
+            };
+            context
+                .window
+                .painter
+                .draw_element_background(style, tab.point(), tab.size(), state);
+            context.window.painter.draw_element_content(
+                style,
+                tab.point(),
+                tab.size(),
+                &label,
+                state,
+            );
+        }
+
+        let dragged = match &self.pressed_tab {
+            Some((window, pressed))
+                if node.tabs.contains(window) && mouse.distance(*pressed) > UNDOCK_DISTANCE =>
+            {
+                Some(window.clone())
+            }
+            _ => None,
+        };
+
+        if let Some(window) = node.tabs.get(node.selected).cloned() {
+            let position = vec2(rect.x, rect.y + tab_height) - origin;
+            Group::new(self.window_id(&window), vec2(rect.w, rect.h - tab_height))
+                .position(position)
+                .ui(ui, |ui| f(ui, &window));
+        }
+
+        dragged
+    }
+
+    /// Make a docked window floating, moving with the mouse.
+    fn undock(&mut self, ui: &mut Ui, window: &str, size: Vec2) {
+        let mouse = ui.input.mouse_position;
+        let grab = vec2(size.x.min(40.), ui.default_skin().title_height / 2.);
+        let position = mouse - grab;
+
+        self.pressed_tab = None;
+        self.layout
+            .float(window, Rect::new(position.x, position.y, size.x, size.y));
+
+        let id = self.window_id(window);
+        ui.move_window(id, position);
+        // the window is created later this frame, before the next mouse move
+        ui.moving = Some((id, grab));
+    }
+
+    fn floating_windows<F: FnMut(&mut Ui, &str)>(
+        &mut self,
+        ui: &mut Ui,
+        layout: &NodesLayout,
+        f: &mut F,
+    ) {
+        let moving = ui.moving.map(|(id, _)| id);
+        let mouse = ui.input.mouse_position;
+        let mut closed = vec![];
+        let mut dropped = None;
+
+        for floating in &mut self.layout.floating {
+            let id = hash!(self.id, floating.name.as_str());
+            if self.reposition {
+                ui.move_window(id, vec2(floating.x, floating.y));
+            }
+
+            let open = Window::new(
+                id,
+                vec2(floating.x, floating.y),
+                vec2(floating.w, floating.h),
+            )
+            .label(&floating.name)
+            .close_button(true)
+            .ui(ui, |ui| f(ui, &floating.name));
+            if !open {
+                closed.push(floating.name.clone());
+            }
+
+            let window = ui.windows.get_mut(&id).unwrap();
+            floating.x = window.position.x;
+            floating.y = window.position.y;
+
+            if moving == Some(id) {
+                self.moving = Some(floating.name.clone());
+                if let Some((_, _, preview)) = layout.drop_target(mouse) {
+                    let color = ui.skin_stack.top().group_style.color_selected;
+                    window.painter.draw_rect(
+                        preview,
+                        color,
+                        Color::new(color.r, color.g, color.b, 0.3),
+                    );
+                }
+            } else if self.moving.as_ref() == Some(&floating.name) {
+                self.moving = None;
+                if let Some((path, zone, _)) = layout.drop_target(mouse) {
+                    dropped = Some((floating.name.clone(), path, zone));
+                }
+            }
+        }
+        self.reposition = false;
+
+        for window in closed {
+            self.layout.remove(&window);
+        }
+        if let Some((window, path, zone)) = dropped {
+            self.layout.dock(&window, &path, zone);
+        }
+    }
+}
+
+#[test]
+fn dock_layout_tree() {
+    let mut layout = DockLayout::new(DockNode::horizontal(
+        0.75,
+        DockNode::tabs(&["Scene", "Game"]),
+        DockNode::tabs(&["Inspector"]),
+    ))
+    .floating("Console", Rect::new(10., 10., 100., 100.));
+
+    let mut nodes = NodesLayout::default();
+    layout
+        .root
+        .layout(Rect::new(0., 0., 404., 100.), &mut vec![], &mut nodes);
+    assert_eq!(nodes.tabs.len(), 2);
+    assert_eq!(nodes.tabs[0].1, Rect::new(0., 0., 300., 100.));
+    assert_eq!(nodes.tabs[1].1, Rect::new(304., 0., 100., 100.));
+
+    let (path, zone, preview) = nodes.drop_target(vec2(150., 95.)).unwrap();
+    assert_eq!((path.as_slice(), zone), (&[0][..], DropZone::Bottom));
+    assert_eq!(preview, Rect::new(0., 50., 300., 50.));
+    assert_eq!(nodes.drop_target(vec2(350., 50.)).unwrap().1, DropZone::Tab);
+
+    layout.dock("Console", &path, zone);
+    assert!(layout.floating.is_empty());
+    assert_eq!(layout.root.children[0].children[1].tabs, ["Console"]);
+
+    let restored = DockLayout::from_ron(&layout.to_ron()).unwrap();
+    assert_eq!(restored, layout);
+
+    // removing the last tab of a node merges the split
+    layout.remove("Inspector");
+    assert!(layout.root.contains("Console"));
+    assert_eq!(layout.root.children.len(), 2);
+    assert!(!layout.root.horizontal);
+}
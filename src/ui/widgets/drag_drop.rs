@@ -0,0 +1,107 @@
+use crate::{
+    math::Vec2,
+    ui::{DragPayload, ElementState, Id, Ui, UiContent},
+};
+
+use std::any::Any;
+
+/// Mouse distance before a press on a drag source becomes a drag
+const DRAG_DISTANCE: f32 = 5.;
+
+impl Ui {
+    /// Make the last widget draggable, carrying `payload` to a [Ui::drop_target].
+    ///
+    /// `label` follows the mouse while dragging, returns true while
+    /// this source is dragged.
+    ///
+    /// ```ignore
+    /// for (n, item) in inventory.iter().enumerate() {
+    ///     ui.label(None, &item.name);
+    ///     ui.drag_source(hash!("item", n), &item.name, n);
+    /// }
+    /// ui.label(None, "Trash");
+    /// if let Some(n) = ui.drop_target::<usize>() {
+    ///     inventory.remove(n);
+    /// }
+    /// ```
+    pub fn drag_source<T: Any>(&mut self, id: Id, label: &str, payload: T) -> bool {
+        let context = self.get_active_window_context();
+        let mouse = context.input.mouse_position;
+        let hovered = context.window.cursor.last_fit.contains(mouse)
+            && context.window.content_rect().contains(mouse);
+
+        if hovered && context.input.click_down() {
+            self.drag_payload = Some(DragPayload {
+                source: id,
+                payload: Box::new(payload),
+                label: label.to_string(),
+                origin: mouse,
+                dragging: false,
+            });
+        }
+
+        let drag = match &mut self.drag_payload {
+            Some(drag) if drag.source == id => drag,
+            _ => return false,
+        };
+        if !drag.dragging && mouse.distance(drag.origin) > DRAG_DISTANCE {
+            drag.dragging = true;
+        }
+        if !drag.dragging {
+            return false;
+        }
+
+        let skin = self.skin_stack.top();
+        let content = UiContent::Label(drag.label.as_str().into());
+        let size = self
+            .overlay
+            .content_with_margins_size(&skin.label_style, &content);
+        let state = ElementState {
+            focused: true,
+            ..Default::default()
+        };
+        let pos = mouse + Vec2::new(10., 10.);
+        self.overlay
+            .draw_element_background(&skin.window_style, pos, size, state);
+        self.overlay
+            .draw_element_content(&skin.label_style, pos, size, &content, state);
+
+        true
+    }
+
+    /// Make the last widget accept payloads of type `T` dragged from
+    /// [Ui::drag_source], returns the payload when it is dropped here.
+    ///
+    /// While a `T` is dragged the target is outlined, and filled when hovered.
+    pub fn drop_target<T: Any>(&mut self) -> Option<T> {
+        match &self.drag_payload {
+            Some(drag) if drag.dragging && drag.payload.is::<T>() => {}
+            _ => return None,
+        }
+
+        let context = self.get_active_window_context();
+        let mouse = context.input.mouse_position;
+        let rect = context.window.cursor.last_fit;
+        let hovered = context.input.window_active
+            && rect.contains(mouse)
+            && context.window.content_rect().contains(mouse);
+
+        let fill = context.style.group_style.color(ElementState {
+            focused: true,
+            hovered: true,
+            ..Default::default()
+        });
+        context.window.painter.draw_rect(
+            rect,
+            context.style.focus_color,
+            Some(fill).filter(|_| hovered),
+        );
+
+        if hovered && context.input.click_up {
+            let drag = self.drag_payload.take()?;
+            return drag.payload.downcast().ok().map(|payload| *payload);
+        }
+
+        None
+    }
+}
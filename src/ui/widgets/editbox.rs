@@ -1,8 +1,9 @@
 #[cfg(target_os = "android")]
 use crate::get_quad_context;
 use crate::{
+    color::Color,
     math::{vec2, Rect, Vec2},
-    ui::{ElementState, Id, InputCharacter, Key, KeyCode, Layout, Ui},
+    ui::{ElementState, Id, InputCharacter, Key, KeyCode, Layout, Ui, WindowContext},
 };
 
 pub struct Editbox<'a> {
@@ -13,6 +14,8 @@ pub struct Editbox<'a> {
     filter: Option<&'a dyn Fn(char) -> bool>,
     pos: Option<Vec2>,
     password: bool,
+    line_numbers: bool,
+    highlighter: Option<&'a Highlighter<'a>>,
 }
 
 mod text_editor;
@@ -21,6 +24,8 @@ use text_editor::EditboxState;
 
 const LEFT_MARGIN: f32 = 2.;
 
+type Highlighter<'a> = dyn Fn(&str, &mut [Color]) + 'a;
+
 impl<'a> Editbox<'a> {
     pub fn new(id: Id, size: Vec2) -> Editbox<'a> {
         Editbox {
@@ -31,6 +36,8 @@ impl<'a> Editbox<'a> {
             multiline: true,
             pos: None,
             password: false,
+            line_numbers: false,
+            highlighter: None,
         }
     }
 
@@ -56,7 +63,18 @@ impl<'a> Editbox<'a> {
         Editbox { password, ..self }
     }
 
-    pub fn filter<'b>(self, filter: &'b dyn Fn(char) -> bool) -> Editbox<'b> {
+    /// Line numbers in a gutter on the left, for code editors.
+    pub fn line_numbers(self, line_numbers: bool) -> Self {
+        Editbox {
+            line_numbers,
+            ..self
+        }
+    }
+
+    pub fn filter<'b>(self, filter: &'b dyn Fn(char) -> bool) -> Editbox<'b>
+    where
+        'a: 'b,
+    {
         Editbox {
             id: self.id,
             pos: self.pos,
@@ -64,10 +82,43 @@ impl<'a> Editbox<'a> {
             select_all: self.select_all,
             size: self.size,
             password: self.password,
+            line_numbers: self.line_numbers,
+            highlighter: self.highlighter,
             filter: Some(filter),
         }
     }
 
+    /// Syntax highlighting hook, called for each line with a color for
+    /// each of its characters, all set to the editbox text color.
+    /// ```ignore
+    /// let comments = |line: &str, colors: &mut [Color]| {
+    ///     if let Some(start) = line.find("//") {
+    ///         let start = line[..start].chars().count();
+    ///         colors[start..].fill(GRAY);
+    ///     }
+    /// };
+    /// Editbox::new(hash!(), vec2(400., 300.))
+    ///     .line_numbers(true)
+    ///     .highlighter(&comments)
+    ///     .ui(ui, &mut script);
+    /// ```
+    pub fn highlighter<'b>(self, highlighter: &'b dyn Fn(&str, &mut [Color])) -> Editbox<'b>
+    where
+        'a: 'b,
+    {
+        Editbox {
+            id: self.id,
+            pos: self.pos,
+            multiline: self.multiline,
+            select_all: self.select_all,
+            size: self.size,
+            password: self.password,
+            line_numbers: self.line_numbers,
+            highlighter: Some(highlighter),
+            filter: self.filter,
+        }
+    }
+
     fn apply_keyboard_input(
         &self,
         input_buffer: &mut Vec<InputCharacter>,
@@ -100,6 +151,13 @@ impl<'a> Editbox<'a> {
                         state.insert_character(text, character);
                     }
                 }
+                InputCharacter {
+                    key: Key::KeyCode(Z),
+                    modifier_ctrl: true,
+                    modifier_shift: true,
+                } => {
+                    state.redo(text);
+                }
                 InputCharacter {
                     key: Key::KeyCode(Z),
                     modifier_ctrl: true,
@@ -161,22 +219,28 @@ impl<'a> Editbox<'a> {
                 }
                 InputCharacter {
                     key: Key::KeyCode(Backspace),
+                    modifier_ctrl,
                     ..
                 } => {
-                    if state.selection.is_none() {
-                        state.delete_current_character(text);
-                    } else {
+                    if state.selection.is_some() {
                         state.delete_selected(text);
+                    } else if modifier_ctrl {
+                        state.delete_prev_word(text);
+                    } else {
+                        state.delete_current_character(text);
                     }
                 }
                 InputCharacter {
                     key: Key::KeyCode(Delete),
+                    modifier_ctrl,
                     ..
                 } => {
-                    if state.selection.is_none() {
-                        state.delete_next_character(text);
-                    } else {
+                    if state.selection.is_some() {
                         state.delete_selected(text);
+                    } else if modifier_ctrl {
+                        state.delete_next_word(text);
+                    } else {
+                        state.delete_next_character(text);
                     }
                 }
                 InputCharacter {
@@ -287,6 +351,9 @@ impl<'a> Editbox<'a> {
 
         if let Some(selected) = state.selected_text(text) {
             *context.clipboard_selection = selected.to_owned();
+        } else if *context.input_focus == Some(self.id) {
+            // nothing to copy, and not the selection of the previous editbox either
+            context.clipboard_selection.clear();
         }
         // in case the string was updated outside of editbox
         if state.cursor > text.len() as u32 {
@@ -356,8 +423,9 @@ impl<'a> Editbox<'a> {
         );
 
         let line_height = context.style.editbox_style.font_size as f32;
+        let lines_count = text.split('\n').count();
 
-        let size = vec2(150., line_height * text.split('\n').count() as f32);
+        let size = vec2(150., line_height * lines_count as f32);
 
         // TODO: this is very weird hardcoded text margin
         let pos = context.window.cursor.fit(size, Layout::Free(vec2(2., 2.)));
@@ -368,22 +436,60 @@ impl<'a> Editbox<'a> {
 
         context.window.painter.clip(context.window.content_rect());
 
+        let colors = match self.highlighter {
+            Some(highlighter) if !self.password => highlight(text, text_color, highlighter),
+            _ => vec![],
+        };
+
+        let mut left = LEFT_MARGIN;
+        if self.line_numbers {
+            let gutter = self.draw_line_numbers(&mut context, pos, lines_count, text_color);
+            left += gutter;
+        }
+
         let state = context
             .storage_any
             .get_or_default::<EditboxState>(hash!(self.id, "cursor"));
 
-        let mut x = LEFT_MARGIN;
-        let mut y = 0.;
-        let mut clicked = false;
-
-        for (n, character) in text.chars().chain(std::iter::once(' ')).enumerate() {
-            let character = if character != '\n' && self.password {
+        let font_size = context.style.editbox_style.font_size;
+        let display_character = |character: char| {
+            if character != '\n' && self.password {
                 '*'
             } else {
                 character
+            }
+        };
+
+        // long single lines scroll horizontally with the cursor
+        if !self.multiline {
+            let font = context.style.editbox_style.font.borrow();
+            let painter = &context.window.painter;
+            let advance = |character| {
+                painter.character_advance(display_character(character), &font, font_size)
             };
+            let text_width: f32 = text.chars().map(advance).sum();
+            let cursor_x: f32 = text.chars().take(state.cursor as usize).map(advance).sum();
+            let width = self.size.x - left - LEFT_MARGIN - 2.;
+
+            state.scroll_x = state.scroll_x.min(text_width - width).max(0.);
+            if cursor_x - state.scroll_x > width {
+                state.scroll_x = cursor_x - width;
+            }
+            if cursor_x < state.scroll_x {
+                state.scroll_x = cursor_x;
+            }
+        } else {
+            state.scroll_x = 0.;
+        }
+
+        let mut x = left - state.scroll_x;
+        let mut y = 0.;
+        let mut clicked = false;
+
+        for (n, character) in text.chars().chain(std::iter::once(' ')).enumerate() {
+            let character = display_character(character);
+            let color = colors.get(n).copied().unwrap_or(text_color);
 
-            let font_size = context.style.editbox_style.font_size;
             if n == state.cursor as usize && input_focused {
                 // caret
                 context.window.painter.draw_rect(
@@ -394,7 +500,6 @@ impl<'a> Editbox<'a> {
             }
 
             let mut font = context.style.editbox_style.font.borrow_mut();
-            let font_size = context.style.editbox_style.font_size;
 
             let mut advance = 1.5; // 1.5 - hack to make cursor on newlines visible
 
@@ -428,7 +533,7 @@ impl<'a> Editbox<'a> {
                     .draw_character(
                         character,
                         pos + vec2(x, y + font_size as f32 - baseline),
-                        text_color,
+                        color,
                         &mut *font,
                         font_size,
                     )
@@ -465,7 +570,20 @@ impl<'a> Editbox<'a> {
             x += advance;
             if character == '\n' && self.multiline {
                 y += line_height;
-                x = LEFT_MARGIN;
+                x = left;
+            }
+        }
+
+        // selecting with the mouse past the ends of a single line scrolls it
+        if !clicked && !self.multiline && input_focused && context.input.is_mouse_down {
+            if let text_editor::ClickState::SelectingChars { .. } = state.click_state {
+                let mouse_x = context.input.mouse_position.x;
+                if mouse_x < rect.x && state.cursor > 0 {
+                    state.click_move(text, state.cursor - 1);
+                }
+                if mouse_x > rect.x + rect.w && state.cursor < text.len() as u32 {
+                    state.click_move(text, state.cursor + 1);
+                }
             }
         }
 
@@ -483,8 +601,88 @@ impl<'a> Editbox<'a> {
     }
 }
 
+impl<'a> Editbox<'a> {
+    /// Right aligned line numbers and the gutter separator, returns the gutter width.
+    fn draw_line_numbers(
+        &self,
+        context: &mut WindowContext,
+        pos: Vec2,
+        lines_count: usize,
+        text_color: Color,
+    ) -> f32 {
+        let style = &context.style.editbox_style;
+        let font_size = style.font_size;
+        let line_height = font_size as f32;
+        let mut font = style.font.borrow_mut();
+        let painter = &mut context.window.painter;
+
+        let digits = lines_count.to_string().len();
+        let gutter =
+            painter.character_advance('0', &font, font_size) * digits as f32 + LEFT_MARGIN * 2.;
+        let color = Color {
+            a: text_color.a * 0.5,
+            ..text_color
+        };
+
+        let descent = font.descent(font_size as f32);
+        let ascent = font.ascent(font_size as f32);
+        let baseline = (ascent + descent) / 2.;
+        for line in 0..lines_count {
+            let number = (line + 1).to_string();
+            let width: f32 = number
+                .chars()
+                .map(|digit| painter.character_advance(digit, &font, font_size))
+                .sum();
+            let mut x = gutter - LEFT_MARGIN - width;
+            let y = line as f32 * line_height + font_size as f32 - baseline;
+            for digit in number.chars() {
+                x += painter
+                    .draw_character(digit, pos + vec2(x, y), color, &mut font, font_size)
+                    .unwrap_or(0.);
+            }
+        }
+
+        let height = (line_height * lines_count as f32).max(self.size.y);
+        painter.draw_line(pos + vec2(gutter, 0.), pos + vec2(gutter, height), color);
+
+        gutter
+    }
+}
+
+/// Color of each character of `text`, with `highlighter` called line by line.
+fn highlight(
+    text: &str,
+    text_color: Color,
+    highlighter: &dyn Fn(&str, &mut [Color]),
+) -> Vec<Color> {
+    let mut colors = Vec::with_capacity(text.len());
+    for line in text.split('\n') {
+        let start = colors.len();
+        colors.extend(line.chars().map(|_| text_color));
+        highlighter(line, &mut colors[start..]);
+        // the line break
+        colors.push(text_color);
+    }
+    colors
+}
+
 impl Ui {
     pub fn editbox(&mut self, id: Id, size: Vec2, text: &mut String) -> bool {
         Editbox::new(id, size).ui(self, text)
     }
 }
+
+#[test]
+fn editbox_highlight() {
+    use crate::color::{RED, WHITE};
+
+    let numbers = |line: &str, colors: &mut [Color]| {
+        for (character, color) in line.chars().zip(colors) {
+            if character.is_ascii_digit() {
+                *color = RED;
+            }
+        }
+    };
+    let colors = highlight("é1\n2", WHITE, &numbers);
+    assert_eq!(colors, vec![WHITE, RED, WHITE, RED, WHITE]);
+}
@@ -158,6 +158,8 @@ pub struct EditboxState {
     pub last_click_time: f32,
     pub last_click: u32,
     pub selection: Option<(u32, u32)>,
+    /// Horizontal scroll of a single line editbox, to keep the cursor visible
+    pub scroll_x: f32,
     undo_stack: Vec<Box<dyn Command>>,
     redo_stack: Vec<Box<dyn Command>>,
 }
@@ -305,6 +307,25 @@ impl EditboxState {
         }
     }
 
+    /// Delete from the beginning of the word to the cursor, as ctrl+backspace does.
+    pub fn delete_prev_word(&mut self, text: &mut String) {
+        if self.cursor > 0 {
+            let begin = self.cursor - self.find_word_begin(text, self.cursor - 1) - 1;
+            self.selection = Some((begin, self.cursor));
+            self.delete_selected(text);
+        }
+    }
+
+    /// Delete from the cursor to the next word, as ctrl+delete does.
+    pub fn delete_next_word(&mut self, text: &mut String) {
+        let end =
+            (self.cursor + self.find_word_end(text, self.cursor + 1) + 1).min(text.len() as u32);
+        if end > self.cursor {
+            self.selection = Some((self.cursor, end));
+            self.delete_selected(text);
+        }
+    }
+
     pub fn move_cursor_next_word(&mut self, text: &str, shift: bool) {
         let next_word = self.find_word_end(text, self.cursor + 1) + 1;
         self.move_cursor(text, next_word as i32, shift);
@@ -497,3 +518,25 @@ impl EditboxState {
         }
     }
 }
+
+#[test]
+fn delete_words_undo() {
+    let mut state = EditboxState::default();
+    let mut text = String::new();
+    state.insert_string(&mut text, "let x = foo(bar);".to_string());
+
+    state.delete_prev_word(&mut text);
+    assert_eq!(text, "let x = foo(bar)");
+    state.delete_prev_word(&mut text);
+    assert_eq!(text, "let x = foo(");
+
+    state.cursor = 0;
+    state.delete_next_word(&mut text);
+    assert_eq!(text, "x = foo(");
+
+    state.undo(&mut text);
+    state.undo(&mut text);
+    assert_eq!(text, "let x = foo(bar)");
+    state.redo(&mut text);
+    assert_eq!(text, "let x = foo(");
+}
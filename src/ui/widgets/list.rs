@@ -0,0 +1,167 @@
+use crate::{
+    math::{Rect, Vec2},
+    ui::{ElementState, Id, Layout, Ui},
+};
+
+use std::ops::Range;
+
+/// Scrollable list that lays out and draws only the visible rows,
+/// for thousands of items like log lines or inventory slots.
+///
+/// All the rows are `row_height` high. The row callback draws the row
+/// with the UI cursor at the row top.
+///
+/// ```ignore
+/// List::new(hash!(), vec2(300., 400.), log.len(), 16.)
+///     .scroll_to(log.len() - 1)
+///     .ui(&mut root_ui(), |ui, row| ui.label(None, &log[row]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct List {
+    id: Id,
+    size: Vec2,
+    count: usize,
+    row_height: f32,
+    position: Option<Vec2>,
+    scroll_to: Option<usize>,
+}
+
+impl List {
+    pub fn new(id: Id, size: Vec2, count: usize, row_height: f32) -> List {
+        List {
+            id,
+            size,
+            count,
+            row_height,
+            position: None,
+            scroll_to: None,
+        }
+    }
+
+    pub fn position(self, position: Vec2) -> List {
+        List {
+            position: Some(position),
+            ..self
+        }
+    }
+
+    /// Scroll just enough to make the row visible.
+    pub fn scroll_to(self, row: usize) -> List {
+        List {
+            scroll_to: Some(row),
+            ..self
+        }
+    }
+
+    /// Draw the visible rows, returns their indices.
+    pub fn ui<F: FnMut(&mut Ui, usize)>(self, ui: &mut Ui, mut f: F) -> Range<usize> {
+        let parent = ui.get_active_window_context();
+        let parent_rect = parent.window.content_rect();
+        parent.window.childs.push(self.id);
+
+        let pos = parent.window.cursor.fit(
+            self.size,
+            self.position.map_or(Layout::Vertical, Layout::Free),
+        );
+        let rect = Rect::new(pos.x, pos.y, self.size.x, self.size.y);
+        let parent_id = Some(parent.window.id);
+
+        let mut context = ui.begin_window(self.id, parent_id, pos, self.size, false, true);
+        context.window.painter.clip(parent_rect);
+
+        // rows start below the top margin, which is kept below the last row too
+        let cursor = &mut context.window.cursor;
+        let top = cursor.start_y;
+        let height = top * 2. + self.count as f32 * self.row_height;
+        let scroll = &mut cursor.scroll;
+        scroll.inner_rect = scroll
+            .inner_rect
+            .combine_with(Rect::new(0., 0., 1., height));
+
+        // the window scrolls by its size per wheel step, too fast for long lists
+        let wheel = context.input.mouse_wheel.y;
+        if context.focused && rect.contains(context.input.mouse_position) && wheel != 0. {
+            context.input.mouse_wheel.y = 0.;
+            scroll.scroll_to(
+                scroll.rect.y + wheel * self.row_height * context.style.scroll_multiplier,
+            );
+        }
+        if let Some(row) = self.scroll_to {
+            let y = top + row as f32 * self.row_height;
+            if y < scroll.rect.y {
+                scroll.scroll_to(y);
+            } else if y + self.row_height > scroll.rect.y + scroll.rect.h {
+                scroll.scroll_to(y + self.row_height - scroll.rect.h);
+            }
+        }
+
+        context.scroll_area();
+
+        let clip_rect = context.window.content_rect();
+        context.window.painter.clip(clip_rect);
+        context.window.painter.draw_rect(
+            rect,
+            context.style.group_style.color(ElementState {
+                focused: context.focused,
+                ..Default::default()
+            }),
+            None,
+        );
+
+        let scroll = &context.window.cursor.scroll;
+        let rows = visible_rows(
+            scroll.rect.y - top,
+            scroll.rect.h,
+            self.row_height,
+            self.count,
+        );
+
+        for row in rows.clone() {
+            let cursor = &mut ui.get_active_window_context().window.cursor;
+            cursor.x = cursor.start_x;
+            cursor.y = top + row as f32 * self.row_height;
+            cursor.max_row_y = 0.;
+
+            f(ui, row);
+        }
+
+        let context = ui.get_active_window_context();
+        context.window.painter.clip(None);
+        ui.end_window();
+
+        rows
+    }
+}
+
+/// Rows overlapping the `scroll_y..scroll_y + height` part of the list.
+fn visible_rows(scroll_y: f32, height: f32, row_height: f32, count: usize) -> Range<usize> {
+    if row_height <= 0. {
+        return 0..0;
+    }
+    let first = (scroll_y / row_height).floor().max(0.) as usize;
+    let last = ((scroll_y + height) / row_height).ceil().max(0.) as usize;
+
+    first.min(count)..last.min(count)
+}
+
+impl Ui {
+    pub fn list<F: FnMut(&mut Ui, usize)>(
+        &mut self,
+        id: Id,
+        size: Vec2,
+        count: usize,
+        row_height: f32,
+        f: F,
+    ) -> Range<usize> {
+        List::new(id, size, count, row_height).ui(self, f)
+    }
+}
+
+#[test]
+fn list_visible_rows() {
+    assert_eq!(visible_rows(0., 100., 20., 10_000), 0..5);
+    assert_eq!(visible_rows(30., 100., 20., 10_000), 1..7);
+    assert_eq!(visible_rows(190_000., 100., 20., 10_000), 9500..9505);
+    assert_eq!(visible_rows(0., 100., 20., 3), 0..3);
+    assert_eq!(visible_rows(0., 100., 20., 0), 0..0);
+}
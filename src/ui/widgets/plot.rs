@@ -0,0 +1,353 @@
+use crate::{
+    color::Color,
+    math::{vec2, Rect, Vec2},
+    ui::{ElementState, Layout, Ui},
+};
+
+/// Room on the left of the plot area for the y axis labels
+const AXIS_WIDTH: f32 = 40.;
+/// Room below the plot area for the x axis labels
+const AXIS_HEIGHT: f32 = 14.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlotKind {
+    Line,
+    Bar,
+    Scatter,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PlotData<'a> {
+    /// y values, at x = 0, 1, 2..
+    Values(&'a [f32]),
+    Points(&'a [Vec2]),
+}
+
+impl<'a> PlotData<'a> {
+    fn len(&self) -> usize {
+        match self {
+            PlotData::Values(values) => values.len(),
+            PlotData::Points(points) => points.len(),
+        }
+    }
+
+    fn point(&self, n: usize) -> Vec2 {
+        match self {
+            PlotData::Values(values) => vec2(n as f32, values[n]),
+            PlotData::Points(points) => points[n],
+        }
+    }
+
+    fn points(&self) -> impl Iterator<Item = Vec2> + '_ {
+        (0..self.len()).map(move |n| self.point(n))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PlotSeries<'a> {
+    label: &'a str,
+    data: PlotData<'a>,
+    color: Color,
+    kind: PlotKind,
+}
+
+/// Line, bar and scatter plots, with axes fitting the data and a
+/// readout of the values under the mouse.
+///
+/// ```ignore
+/// frame_times.push(get_frame_time() * 1000.);
+/// Plot::new(vec2(300., 100.))
+///     .line("frame ms", &frame_times, GREEN)
+///     .y_range(0., 33.)
+///     .ui(&mut root_ui());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Plot<'a> {
+    size: Vec2,
+    position: Option<Vec2>,
+    series: Vec<PlotSeries<'a>>,
+    x_range: Option<(f32, f32)>,
+    y_range: Option<(f32, f32)>,
+}
+
+impl<'a> Plot<'a> {
+    pub fn new(size: Vec2) -> Plot<'a> {
+        Plot {
+            size,
+            position: None,
+            series: vec![],
+            x_range: None,
+            y_range: None,
+        }
+    }
+
+    pub fn position(self, position: Vec2) -> Plot<'a> {
+        Plot {
+            position: Some(position),
+            ..self
+        }
+    }
+
+    /// Line through the values, at x = 0, 1, 2..
+    pub fn line(self, label: &'a str, values: &'a [f32], color: Color) -> Plot<'a> {
+        self.series(label, PlotData::Values(values), color, PlotKind::Line)
+    }
+
+    /// Bar for each value, at x = 0, 1, 2..
+    pub fn bars(self, label: &'a str, values: &'a [f32], color: Color) -> Plot<'a> {
+        self.series(label, PlotData::Values(values), color, PlotKind::Bar)
+    }
+
+    pub fn points(
+        self,
+        label: &'a str,
+        points: &'a [Vec2],
+        color: Color,
+        kind: PlotKind,
+    ) -> Plot<'a> {
+        self.series(label, PlotData::Points(points), color, kind)
+    }
+
+    /// By default the x axis fits the data.
+    pub fn x_range(self, min: f32, max: f32) -> Plot<'a> {
+        Plot {
+            x_range: Some((min, max)),
+            ..self
+        }
+    }
+
+    /// By default the y axis fits the data, and 0 with bars.
+    pub fn y_range(self, min: f32, max: f32) -> Plot<'a> {
+        Plot {
+            y_range: Some((min, max)),
+            ..self
+        }
+    }
+
+    fn series(mut self, label: &'a str, data: PlotData<'a>, color: Color, kind: PlotKind) -> Self {
+        self.series.push(PlotSeries {
+            label,
+            data,
+            color,
+            kind,
+        });
+        self
+    }
+
+    /// Returns the plot coordinates under the mouse, when hovered.
+    pub fn ui(self, ui: &mut Ui) -> Option<Vec2> {
+        let context = ui.get_active_window_context();
+
+        let pos = context.window.cursor.fit(
+            self.size,
+            self.position.map_or(Layout::Vertical, Layout::Free),
+        );
+        let area = Rect::new(
+            pos.x + AXIS_WIDTH,
+            pos.y + 2.,
+            self.size.x - AXIS_WIDTH - 2.,
+            self.size.y - AXIS_HEIGHT - 2.,
+        );
+
+        let bars = self
+            .series
+            .iter()
+            .any(|series| series.kind == PlotKind::Bar);
+        let (x_min, x_max) = self.x_range.unwrap_or_else(|| {
+            let range = auto_range(
+                self.series
+                    .iter()
+                    .flat_map(|s| s.data.points().map(|p| p.x)),
+            );
+            // the bars are centered on their x
+            match bars {
+                true => (range.0 - 0.5, range.1 + 0.5),
+                false => range,
+            }
+        });
+        let (y_min, y_max) = self.y_range.unwrap_or_else(|| {
+            let ys = self
+                .series
+                .iter()
+                .flat_map(|s| s.data.points().map(|p| p.y));
+            match bars {
+                true => auto_range(ys.chain(std::iter::once(0.))),
+                false => auto_range(ys),
+            }
+        });
+
+        let to_screen = |point: Vec2| {
+            vec2(
+                area.x + (point.x - x_min) / (x_max - x_min) * area.w,
+                area.bottom() - (point.y - y_min) / (y_max - y_min) * area.h,
+            )
+        };
+
+        let style = &context.style.label_style;
+        let state = ElementState {
+            focused: context.focused,
+            ..Default::default()
+        };
+        let text_color = style.text_color(state);
+        let painter = &mut context.window.painter;
+
+        painter.draw_rect(
+            area,
+            context.style.group_style.color(state),
+            context.style.editbox_style.color(state),
+        );
+
+        {
+            let font = &mut *style.font.borrow_mut();
+            let font_size = style.font_size;
+            let mut label = |painter: &mut crate::ui::render::Painter, text: &str, pos: Vec2| {
+                let offset_y = painter.label_size(text, None, font, font_size).offset_y;
+                painter.draw_label(text, pos + vec2(0., offset_y), text_color, font, font_size);
+            };
+
+            label(painter, &format_value(y_max), vec2(pos.x, area.y));
+            label(
+                painter,
+                &format_value(y_min),
+                vec2(pos.x, area.bottom() - font_size as f32),
+            );
+            label(
+                painter,
+                &format_value(x_min),
+                vec2(area.x, area.bottom() + 2.),
+            );
+            let x_max_label = format_value(x_max);
+            let x_max_width = x_max_label.len() as f32 * font_size as f32 / 2.;
+            label(
+                painter,
+                &x_max_label,
+                vec2(area.right() - x_max_width, area.bottom() + 2.),
+            );
+        }
+
+        let parent_clip = painter.clipping_zone;
+        painter.clip(area);
+        for series in &self.series {
+            match series.kind {
+                PlotKind::Line => {
+                    let points: Vec<Vec2> = series.data.points().map(to_screen).collect();
+                    for line in points.windows(2) {
+                        painter.draw_line(line[0], line[1], series.color);
+                    }
+                }
+                PlotKind::Scatter => {
+                    for point in series.data.points().map(to_screen) {
+                        painter.draw_rect(
+                            Rect::new(point.x - 1.5, point.y - 1.5, 3., 3.),
+                            None,
+                            series.color,
+                        );
+                    }
+                }
+                PlotKind::Bar => {
+                    let width = (area.w / (x_max - x_min) * 0.8).max(1.);
+                    let base = to_screen(vec2(0., 0f32.max(y_min).min(y_max))).y;
+                    for point in series.data.points().map(to_screen) {
+                        let (top, bottom) = (point.y.min(base), point.y.max(base));
+                        painter.draw_rect(
+                            Rect::new(point.x - width / 2., top, width, bottom - top),
+                            None,
+                            series.color,
+                        );
+                    }
+                }
+            }
+        }
+        painter.clipping_zone = parent_clip;
+
+        let mouse = context.input.mouse_position;
+        if !(context.input.window_active && area.contains(mouse)) {
+            return None;
+        }
+        let hovered = vec2(
+            x_min + (mouse.x - area.x) / area.w * (x_max - x_min),
+            y_min + (area.bottom() - mouse.y) / area.h * (y_max - y_min),
+        );
+
+        // readout of the point closest to the mouse x in each series
+        painter.draw_line(
+            vec2(mouse.x, area.y),
+            vec2(mouse.x, area.bottom()),
+            text_color,
+        );
+        let font = &mut *style.font.borrow_mut();
+        let font_size = style.font_size;
+        let mut y = area.y + 2.;
+        for series in &self.series {
+            let closest = (0..series.data.len())
+                .map(|n| series.data.point(n))
+                .min_by(|a, b| {
+                    (a.x - hovered.x)
+                        .abs()
+                        .partial_cmp(&(b.x - hovered.x).abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            if let Some(point) = closest {
+                let text = format!(
+                    "{}: {}, {}",
+                    series.label,
+                    format_value(point.x),
+                    format_value(point.y)
+                );
+                let offset_y = painter.label_size(&text, None, font, font_size).offset_y;
+                painter.draw_label(
+                    &text,
+                    vec2(area.x + 4., y + offset_y),
+                    series.color,
+                    font,
+                    font_size,
+                );
+                y += font_size as f32;
+            }
+        }
+
+        Some(hovered)
+    }
+}
+
+/// Min and max of the values, widened when they are all the same.
+fn auto_range(values: impl Iterator<Item = f32>) -> (f32, f32) {
+    let (min, max) = values
+        .filter(|value| value.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+
+    if min > max {
+        (0., 1.)
+    } else if min == max {
+        (min - 0.5, max + 0.5)
+    } else {
+        (min, max)
+    }
+}
+
+fn format_value(value: f32) -> String {
+    if value.fract() == 0. && value.abs() < 1e6 {
+        format!("{}", value)
+    } else if value.abs() >= 100. {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+impl Ui {
+    pub fn plot_lines(&mut self, size: Vec2, label: &str, values: &[f32], color: Color) {
+        Plot::new(size).line(label, values, color).ui(self);
+    }
+}
+
+#[test]
+fn plot_auto_range() {
+    assert_eq!(auto_range([1., -2., 5.].iter().copied()), (-2., 5.));
+    assert_eq!(auto_range([3., 3.].iter().copied()), (2.5, 3.5));
+    assert_eq!(auto_range([f32::NAN].iter().copied()), (0., 1.));
+    assert_eq!(format_value(16.), "16");
+    assert_eq!(format_value(16.666), "16.67");
+}
@@ -1,5 +1,5 @@
 use crate::{
-    math::Vec2,
+    math::{Rect, Vec2},
     ui::{Id, Ui},
 };
 
@@ -7,18 +7,38 @@ use crate::{
 pub struct Popup {
     id: Id,
     size: Vec2,
+    position: Option<Vec2>,
 }
 
 impl Popup {
     pub fn new(id: Id, size: Vec2) -> Popup {
-        Popup { id, size }
+        Popup {
+            id,
+            size,
+            position: None,
+        }
+    }
+
+    /// By default the popup is placed at the UI cursor.
+    pub fn position(self, position: Vec2) -> Popup {
+        Popup {
+            position: Some(position),
+            ..self
+        }
+    }
+
+    /// Place the popup below `rect`, or above when there is no room below,
+    /// like a dropdown below its button.
+    pub fn anchor(self, ui: &Ui, rect: Rect) -> Popup {
+        let position = anchored(rect, self.size, ui.root_window.size);
+        self.position(position)
     }
 
     pub fn ui<F: FnOnce(&mut Ui)>(self, ui: &mut Ui, f: F) {
-        let pos = {
+        let pos = self.position.unwrap_or_else(|| {
             let context = ui.get_active_window_context();
             context.window.cursor.current_position()
-        };
+        });
 
         let _context = ui.begin_modal(self.id, pos, self.size);
         f(ui);
@@ -26,8 +46,107 @@ impl Popup {
     }
 }
 
+/// Popup opened by right clicking the last widget, at the mouse position.
+///
+/// Closed by clicking an item, clicking outside or `Escape`.
+///
+/// ```ignore
+/// ui.label(None, "Entity");
+/// ContextMenu::new(hash!(), vec2(100., 44.)).ui(ui, |ui| {
+///     if ui.button(None, "Delete") {
+///         delete = true;
+///     }
+/// });
+/// ```
+pub struct ContextMenu {
+    id: Id,
+    size: Vec2,
+}
+
+impl ContextMenu {
+    pub fn new(id: Id, size: Vec2) -> ContextMenu {
+        ContextMenu { id, size }
+    }
+
+    /// Returns true while the menu is open.
+    pub fn ui<F: FnOnce(&mut Ui)>(self, ui: &mut Ui, f: F) -> bool {
+        let screen = ui.root_window.size;
+        let context = ui.get_active_window_context();
+        let mouse = context.input.mouse_position;
+        let hovered = context.window.cursor.last_fit.contains(mouse);
+        let right_click = context.input.right_click_down();
+
+        let state = context
+            .storage_any
+            .get_or_default::<Option<Vec2>>(hash!(self.id, "context_menu"));
+
+        if let Some(pos) = *state {
+            let rect = Rect::new(pos.x, pos.y, self.size.x, self.size.y);
+            if context.input.escape
+                || context.input.right_click_down
+                || (context.input.click_down && !rect.contains(mouse))
+            {
+                *state = None;
+            }
+        }
+        if hovered && right_click {
+            *state = Some(anchored(
+                Rect::new(mouse.x, mouse.y, 0., 0.),
+                self.size,
+                screen,
+            ));
+        }
+
+        let pos = match *state {
+            Some(pos) => pos,
+            None => return false,
+        };
+
+        let context = ui.begin_modal(hash!(self.id, "context_menu_window"), pos, self.size);
+        let clicked = context.input.click_up;
+        f(ui);
+        ui.end_modal();
+
+        if clicked {
+            *ui.storage_any
+                .get_or_default::<Option<Vec2>>(hash!(self.id, "context_menu")) = None;
+        }
+
+        true
+    }
+}
+
+/// Position of a `size` rect below `anchor`, or above if it does not
+/// fit below, moved horizontally to stay within the screen.
+pub(crate) fn anchored(anchor: Rect, size: Vec2, screen: Vec2) -> Vec2 {
+    let x = anchor.x.min(screen.x - size.x).max(0.);
+    let y = if anchor.bottom() + size.y <= screen.y {
+        anchor.bottom()
+    } else {
+        (anchor.top() - size.y).max(0.)
+    };
+
+    Vec2::new(x, y)
+}
+
 impl Ui {
     pub fn popup<F: FnOnce(&mut Ui)>(&mut self, id: Id, size: Vec2, f: F) {
         Popup::new(id, size).ui(self, f)
     }
+
+    pub fn context_menu<F: FnOnce(&mut Ui)>(&mut self, id: Id, size: Vec2, f: F) -> bool {
+        ContextMenu::new(id, size).ui(self, f)
+    }
+}
+
+#[test]
+fn popup_anchored() {
+    let screen = Vec2::new(800., 600.);
+    let size = Vec2::new(100., 50.);
+
+    let button = Rect::new(10., 10., 80., 20.);
+    assert_eq!(anchored(button, size, screen), Vec2::new(10., 30.));
+
+    let bottom_right = Rect::new(750., 580., 40., 20.);
+    assert_eq!(anchored(bottom_right, size, screen), Vec2::new(700., 530.));
 }
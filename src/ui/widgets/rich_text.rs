@@ -0,0 +1,389 @@
+use crate::{
+    color::Color,
+    math::{vec2, Rect, Vec2},
+    ui::{style::parse_color, ElementState, Layout, Ui},
+};
+
+/// Height of a line, relative to its font size
+const LINE_SPACING: f32 = 1.25;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    text: String,
+    bold: bool,
+    color: Option<Color>,
+    link: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Block {
+    Paragraph,
+    Heading(u8),
+    Bullet,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Line {
+    block: Block,
+    spans: Vec<Span>,
+}
+
+/// Word placed by the layout, relative to the top left of the label
+#[derive(Debug)]
+struct Piece<'a> {
+    pos: Vec2,
+    width: f32,
+    text: &'a str,
+    font_size: u16,
+    span: Option<&'a Span>,
+}
+
+/// Label with inline markup, word wrapped to its width.
+///
+/// The markup is a small subset of markdown:
+/// - `# `, `## ` and `### ` headings, `- ` bullets, an empty line between paragraphs
+/// - `**bold**`
+/// - `[label](target)` links, the target of a clicked link is returned by [RichText::ui]
+/// - `{#ff8000 colored text}`, with a `#rrggbb` or `#rrggbbaa` color
+/// - `\` to escape the next character
+///
+/// ```ignore
+/// let text = "# Patch 1.2\n- **Faster** loading\n- See the [forum](news) for more";
+/// if let Some(link) = RichText::new(text).width(300.).ui(&mut root_ui()) {
+///     open_page(&link);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RichText<'a> {
+    text: &'a str,
+    position: Option<Vec2>,
+    width: Option<f32>,
+}
+
+impl<'a> RichText<'a> {
+    pub fn new(text: &'a str) -> RichText<'a> {
+        RichText {
+            text,
+            position: None,
+            width: None,
+        }
+    }
+
+    pub fn position<P: Into<Option<Vec2>>>(self, position: P) -> Self {
+        let position = position.into();
+
+        RichText { position, ..self }
+    }
+
+    /// Width the text is wrapped to, by default the width left in the window.
+    pub fn width(self, width: f32) -> Self {
+        RichText {
+            width: Some(width),
+            ..self
+        }
+    }
+
+    /// Returns the target of the link clicked this frame.
+    pub fn ui(self, ui: &mut Ui) -> Option<String> {
+        let context = ui.get_active_window_context();
+
+        let cursor = &context.window.cursor;
+        let width = self
+            .width
+            .unwrap_or_else(|| (cursor.area.w - cursor.ident - cursor.margin * 2.).max(1.));
+
+        let mouse = context.input.mouse_position;
+        let mouse_over =
+            context.input.window_active && context.window.content_rect().contains(mouse);
+
+        let style = &context.style.label_style;
+        let font = &mut *style.font.borrow_mut();
+        let painter = &mut context.window.painter;
+
+        let lines = parse(self.text);
+        let (pieces, height) = layout(&lines, width, style.font_size, |text, font_size| {
+            painter.label_size(text, None, font, font_size).width
+        });
+
+        let pos = context.window.cursor.fit(
+            vec2(width, height),
+            self.position.map_or(Layout::Vertical, Layout::Free),
+        );
+
+        let text_color = style.text_color(ElementState {
+            focused: context.focused,
+            ..Default::default()
+        });
+        let link_color = context.style.focus_color;
+
+        let mut ascents: Vec<(u16, f32)> = vec![];
+        let mut clicked = None;
+        for piece in &pieces {
+            let ascent = match ascents.iter().find(|(size, _)| *size == piece.font_size) {
+                Some((_, ascent)) => *ascent,
+                None => {
+                    let ascent = painter
+                        .label_size("A", None, font, piece.font_size)
+                        .offset_y;
+                    ascents.push((piece.font_size, ascent));
+                    ascent
+                }
+            };
+
+            let top_left = pos + piece.pos;
+            let baseline = top_left + vec2(0., ascent);
+            let link = piece.span.and_then(|span| span.link.as_ref());
+            let color = piece
+                .span
+                .and_then(|span| span.color)
+                .unwrap_or(if link.is_some() {
+                    link_color
+                } else {
+                    text_color
+                });
+
+            painter.draw_label(piece.text, baseline, color, font, piece.font_size);
+            if piece.span.is_some_and(|span| span.bold) {
+                painter.draw_label(
+                    piece.text,
+                    baseline + vec2(1., 0.),
+                    color,
+                    font,
+                    piece.font_size,
+                );
+            }
+
+            if let Some(link) = link {
+                let underline = baseline.y + 2.;
+                painter.draw_line(
+                    vec2(top_left.x, underline),
+                    vec2(top_left.x + piece.width, underline),
+                    color,
+                );
+
+                let rect = Rect::new(
+                    top_left.x,
+                    top_left.y,
+                    piece.width,
+                    piece.font_size as f32 * LINE_SPACING,
+                );
+                if mouse_over && rect.contains(mouse) && context.input.click_up() {
+                    clicked = Some(link.clone());
+                }
+            }
+        }
+
+        clicked
+    }
+}
+
+fn parse(text: &str) -> Vec<Line> {
+    text.lines()
+        .map(|line| {
+            let line = line.trim_start();
+            let (block, rest) = if let Some(rest) = line.strip_prefix("### ") {
+                (Block::Heading(3), rest)
+            } else if let Some(rest) = line.strip_prefix("## ") {
+                (Block::Heading(2), rest)
+            } else if let Some(rest) = line.strip_prefix("# ") {
+                (Block::Heading(1), rest)
+            } else if let Some(rest) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+                (Block::Bullet, rest)
+            } else {
+                (Block::Paragraph, line)
+            };
+
+            Line {
+                block,
+                spans: parse_inline(rest),
+            }
+        })
+        .collect()
+}
+
+fn parse_inline(mut rest: &str) -> Vec<Span> {
+    let mut spans = vec![];
+    let mut text = String::new();
+    let mut bold = false;
+    let mut colors: Vec<Color> = vec![];
+
+    let flush = |spans: &mut Vec<Span>, text: &mut String, bold: bool, color: Option<Color>| {
+        if !text.is_empty() {
+            spans.push(Span {
+                text: std::mem::take(text),
+                bold,
+                color,
+                link: None,
+            });
+        }
+    };
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**") {
+            flush(&mut spans, &mut text, bold, colors.last().copied());
+            bold = !bold;
+            rest = after;
+            continue;
+        }
+
+        if let Some((color, after)) = color_tag(rest) {
+            flush(&mut spans, &mut text, bold, colors.last().copied());
+            colors.push(color);
+            rest = after;
+            continue;
+        }
+        if c == '}' && !colors.is_empty() {
+            flush(&mut spans, &mut text, bold, colors.last().copied());
+            colors.pop();
+            rest = &rest[1..];
+            continue;
+        }
+
+        if let Some((label, target, after)) = link(rest) {
+            flush(&mut spans, &mut text, bold, colors.last().copied());
+            spans.push(Span {
+                text: label.to_string(),
+                bold,
+                color: colors.last().copied(),
+                link: Some(target.to_string()),
+            });
+            rest = after;
+            continue;
+        }
+
+        rest = &rest[c.len_utf8()..];
+        if c == '\\' {
+            if let Some(escaped) = rest.chars().next() {
+                text.push(escaped);
+                rest = &rest[escaped.len_utf8()..];
+                continue;
+            }
+        }
+        text.push(c);
+    }
+    flush(&mut spans, &mut text, bold, colors.last().copied());
+
+    spans
+}
+
+/// `{#rrggbb ` at the start of `text`
+fn color_tag(text: &str) -> Option<(Color, &str)> {
+    let tag = text.strip_prefix('{')?;
+    let end = tag.find(' ')?;
+    let color = parse_color(&tag[..end]).ok()?;
+
+    Some((color, &tag[end + 1..]))
+}
+
+/// `[label](target)` at the start of `text`
+fn link(text: &str) -> Option<(&str, &str, &str)> {
+    let link = text.strip_prefix('[')?;
+    let label_end = link.find("](")?;
+    let target = &link[label_end + 2..];
+    let target_end = target.find(')')?;
+
+    Some((
+        &link[..label_end],
+        &target[..target_end],
+        &target[target_end + 1..],
+    ))
+}
+
+/// Place the words of the lines, wrapping them at `width`.
+/// Returns the words and the total height.
+fn layout<'a, F: FnMut(&str, u16) -> f32>(
+    lines: &'a [Line],
+    width: f32,
+    font_size: u16,
+    mut measure: F,
+) -> (Vec<Piece<'a>>, f32) {
+    let mut pieces = vec![];
+    let mut y = 0.;
+
+    for line in lines {
+        let font_size = match line.block {
+            Block::Heading(1) => (font_size as f32 * 1.6) as u16,
+            Block::Heading(2) => (font_size as f32 * 1.3) as u16,
+            Block::Heading(_) => (font_size as f32 * 1.1) as u16,
+            _ => font_size,
+        };
+        let line_height = font_size as f32 * LINE_SPACING;
+
+        if line.spans.is_empty() {
+            // paragraph break
+            y += line_height / 2.;
+            continue;
+        }
+
+        let indent = match line.block {
+            Block::Bullet => {
+                let bullet = "- ";
+                let indent = measure(bullet, font_size);
+                pieces.push(Piece {
+                    pos: vec2(0., y),
+                    width: indent,
+                    text: bullet,
+                    font_size,
+                    span: None,
+                });
+                indent
+            }
+            _ => 0.,
+        };
+
+        let mut x = indent;
+        for span in &line.spans {
+            for word in span.text.split_inclusive(' ') {
+                let advance = measure(word, font_size);
+                let word_width = measure(word.trim_end(), font_size);
+                if x > indent && x + word_width > width {
+                    x = indent;
+                    y += line_height;
+                }
+
+                pieces.push(Piece {
+                    pos: vec2(x, y),
+                    width: word_width,
+                    text: word,
+                    font_size,
+                    span: Some(span),
+                });
+                x += advance;
+            }
+        }
+        y += line_height;
+    }
+
+    (pieces, y)
+}
+
+impl Ui {
+    /// Label with markup, see [RichText]. Returns the target of the clicked link.
+    pub fn rich_text<P: Into<Option<Vec2>>>(&mut self, position: P, text: &str) -> Option<String> {
+        RichText::new(text).position(position).ui(self)
+    }
+}
+
+#[test]
+fn rich_text_markup() {
+    let lines = parse("# Title\nsome **bold {#ff0000 red}** and [a link](target)\n\n- item");
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0].block, Block::Heading(1));
+    assert_eq!(lines[3].block, Block::Bullet);
+    assert!(lines[2].spans.is_empty());
+
+    let spans = &lines[1].spans;
+    let texts: Vec<&str> = spans.iter().map(|span| span.text.as_str()).collect();
+    assert_eq!(texts, ["some ", "bold ", "red", " and ", "a link"]);
+    assert!(spans[2].bold);
+    assert_eq!(spans[2].color, Some(Color::from_rgba(255, 0, 0, 255)));
+    assert_eq!(spans[4].link.as_deref(), Some("target"));
+    assert_eq!(parse_inline("\\*\\*not bold")[0].text, "**not bold");
+
+    // 10 pixels per character, wraps after "some "
+    let lines = parse("some text wrapped");
+    let (pieces, height) = layout(&lines, 100., 10, |text, _| text.len() as f32 * 10.);
+    assert_eq!(pieces[1].pos, vec2(50., 0.));
+    assert_eq!(pieces[2].pos, vec2(0., 12.5));
+    assert_eq!(height, 25.);
+}
@@ -0,0 +1,299 @@
+use crate::{
+    math::{vec2, Rect, Vec2},
+    ui::{widgets::List, ElementState, Id, Layout, Ui},
+};
+
+/// Columns narrower than this can't be made by resizing
+const MIN_COLUMN_WIDTH: f32 = 20.;
+/// Width of the grab area on the right border of a header cell
+const RESIZE_HANDLE_WIDTH: f32 = 6.;
+
+/// Column and direction the table should be sorted by,
+/// changed by clicking the column headers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableSort {
+    pub column: usize,
+    pub ascending: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableResponse {
+    pub sort: Option<TableSort>,
+    /// A header was clicked this frame, the rows should be sorted again
+    pub sort_changed: bool,
+    pub selected: Option<usize>,
+    /// A row was clicked this frame
+    pub selection_changed: bool,
+}
+
+#[derive(Default)]
+struct TableState {
+    widths: Vec<f32>,
+    sort: Option<TableSort>,
+    selected: Option<usize>,
+    /// Resized column, with the mouse x and column width when the drag started
+    resizing: Option<(usize, f32, f32)>,
+}
+
+/// Table with a header row of resizable, clickable columns.
+///
+/// The table does not own the data, the cell callback is called with
+/// the displayed row and column, and sorting is done by the caller
+/// on `sort_changed`. Only the visible rows are drawn, like with [List].
+///
+/// ```ignore
+/// let response = Table::new(hash!(), vec2(400., 300.), &["Name", "Score"], scores.len())
+///     .ui(&mut root_ui(), |ui, row, column| match column {
+///         0 => ui.label(None, &scores[row].0),
+///         _ => ui.label(None, &scores[row].1.to_string()),
+///     });
+/// if response.sort_changed {
+///     let sort = response.sort.unwrap();
+///     scores.sort_by_key(|score| score.1);
+///     if !sort.ascending {
+///         scores.reverse();
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Table<'a> {
+    id: Id,
+    size: Vec2,
+    columns: &'a [&'a str],
+    widths: Option<&'a [f32]>,
+    rows: usize,
+    row_height: f32,
+    position: Option<Vec2>,
+}
+
+impl<'a> Table<'a> {
+    pub fn new(id: Id, size: Vec2, columns: &'a [&'a str], rows: usize) -> Table<'a> {
+        Table {
+            id,
+            size,
+            columns,
+            widths: None,
+            rows,
+            row_height: 22.,
+            position: None,
+        }
+    }
+
+    pub fn position(self, position: Vec2) -> Table<'a> {
+        Table {
+            position: Some(position),
+            ..self
+        }
+    }
+
+    pub fn row_height(self, row_height: f32) -> Table<'a> {
+        Table { row_height, ..self }
+    }
+
+    /// Initial column widths, by default the columns share the table width.
+    pub fn column_widths(self, widths: &'a [f32]) -> Table<'a> {
+        Table {
+            widths: Some(widths),
+            ..self
+        }
+    }
+
+    pub fn ui<F: FnMut(&mut Ui, usize, usize)>(self, ui: &mut Ui, mut f: F) -> TableResponse {
+        let context = ui.get_active_window_context();
+
+        let pos = context.window.cursor.fit(
+            self.size,
+            self.position.map_or(Layout::Vertical, Layout::Free),
+        );
+        // the rows list is placed in the area the table was given
+        let cursor = &context.window.cursor;
+        let local_pos = pos - cursor.area.point() - cursor.scroll.scroll - vec2(cursor.ident, 0.);
+
+        let state = context.storage_any.get_or_default::<TableState>(self.id);
+        if state.widths.len() != self.columns.len() {
+            let width = self.size.x / self.columns.len().max(1) as f32;
+            state.widths = (0..self.columns.len())
+                .map(|n| self.widths.and_then(|w| w.get(n).copied()).unwrap_or(width))
+                .collect();
+        }
+
+        let mut sort_changed = false;
+        let mouse = context.input.mouse_position;
+
+        // the release ending a resize doesn't sort by the header it is over
+        let resizing = state.resizing.is_some();
+        if let Some((column, mouse_x, width)) = state.resizing {
+            state.widths[column] = (width + mouse.x - mouse_x).max(MIN_COLUMN_WIDTH);
+            if !context.input.is_mouse_down {
+                state.resizing = None;
+            }
+        }
+
+        let mut x = pos.x;
+        for (n, column) in self.columns.iter().enumerate() {
+            let width = state.widths[n];
+            let rect = Rect::new(x, pos.y, width, self.row_height);
+            let handle = Rect::new(
+                x + width - RESIZE_HANDLE_WIDTH / 2.,
+                pos.y,
+                RESIZE_HANDLE_WIDTH,
+                self.row_height,
+            );
+            let hovered = context.focused && rect.contains(mouse) && !resizing;
+
+            if context.focused && handle.contains(mouse) && context.input.click_down() {
+                state.resizing = Some((n, mouse.x, width));
+            } else if hovered && context.input.click_up() {
+                state.sort = Some(next_sort(state.sort, n));
+                sort_changed = true;
+            }
+
+            let element_state = ElementState {
+                focused: context.focused,
+                hovered,
+                clicked: hovered && context.input.is_mouse_down,
+                selected: state.sort.is_some_and(|sort| sort.column == n),
+            };
+            let style = &context.style.tabbar_style;
+            context.window.painter.clip(
+                rect.intersect(context.window.content_rect())
+                    .unwrap_or_default(),
+            );
+            context.window.painter.draw_element_background(
+                style,
+                rect.point(),
+                rect.size(),
+                element_state,
+            );
+            context
+                .window
+                .painter
+                .draw_element_label(style, rect.point(), column, element_state);
+
+            if let Some(sort) = state.sort.filter(|sort| sort.column == n) {
+                let center = vec2(rect.right() - 8., rect.y + rect.h / 2.);
+                let dy = if sort.ascending { -3. } else { 3. };
+                context.window.painter.draw_triangle(
+                    center + vec2(-4., -dy),
+                    center + vec2(4., -dy),
+                    center + vec2(0., dy),
+                    style.text_color(element_state),
+                );
+            }
+
+            x += width;
+        }
+        let clip_rect = context.window.content_rect();
+        context.window.painter.clip(clip_rect);
+
+        let TableState {
+            widths,
+            sort,
+            selected,
+            ..
+        } = state;
+        let widths = widths.clone();
+        let sort = *sort;
+        let mut selected = *selected;
+        let mut selection_changed = false;
+
+        let row_height = self.row_height;
+        List::new(
+            hash!(self.id, "rows"),
+            vec2(self.size.x, self.size.y - row_height),
+            self.rows,
+            row_height,
+        )
+        .position(local_pos + vec2(0., row_height))
+        .ui(ui, |ui, row| {
+            let context = ui.get_active_window_context();
+            let clip_rect = context.window.content_rect();
+            let row_pos = context.window.cursor.current_position();
+            let row_y = context.window.cursor.y;
+            let rect = Rect::new(row_pos.x, row_pos.y, clip_rect.w, row_height);
+
+            if context.focused
+                && rect.contains(context.input.mouse_position)
+                && context.input.click_up()
+            {
+                selected = Some(row);
+                selection_changed = true;
+            }
+            if selected == Some(row) {
+                context.window.painter.clip(clip_rect);
+                context.window.painter.draw_rect(
+                    rect,
+                    None,
+                    context.style.editbox_style.color_selected,
+                );
+            }
+
+            let mut x = 0.;
+            for (column, width) in widths.iter().enumerate() {
+                let context = ui.get_active_window_context();
+                let cursor = &mut context.window.cursor;
+                cursor.x = cursor.start_x + x;
+                cursor.y = row_y;
+                cursor.max_row_y = 0.;
+                let cell = Rect::new(row_pos.x + x, row_pos.y, *width, row_height);
+                let clip_rect = context.window.content_rect();
+                context
+                    .window
+                    .painter
+                    .clip(cell.intersect(clip_rect).unwrap_or_default());
+
+                f(ui, row, column);
+                x += width;
+            }
+        });
+
+        let context = ui.get_active_window_context();
+        context
+            .storage_any
+            .get_or_default::<TableState>(self.id)
+            .selected = selected;
+
+        TableResponse {
+            sort,
+            sort_changed,
+            selected,
+            selection_changed,
+        }
+    }
+}
+
+/// Clicking the sorted column flips the order, other columns sort ascending.
+fn next_sort(sort: Option<TableSort>, column: usize) -> TableSort {
+    match sort {
+        Some(sort) if sort.column == column => TableSort {
+            column,
+            ascending: !sort.ascending,
+        },
+        _ => TableSort {
+            column,
+            ascending: true,
+        },
+    }
+}
+
+#[test]
+fn table_sort_toggle() {
+    let sort = next_sort(None, 1);
+    assert_eq!(
+        sort,
+        TableSort {
+            column: 1,
+            ascending: true
+        }
+    );
+    let sort = next_sort(Some(sort), 1);
+    assert!(!sort.ascending);
+    let sort = next_sort(Some(sort), 0);
+    assert_eq!(
+        sort,
+        TableSort {
+            column: 0,
+            ascending: true
+        }
+    );
+}
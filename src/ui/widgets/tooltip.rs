@@ -0,0 +1,77 @@
+use crate::{
+    math::{Rect, Vec2},
+    ui::{widgets::popup::anchored, ElementState, Ui, UiContent},
+};
+
+/// Text shown next to the mouse after hovering the last widget for a while.
+///
+/// ```ignore
+/// if ui.button(None, "Save") {
+///     save();
+/// }
+/// ui.tooltip("Save the level, Ctrl+S");
+/// ```
+pub struct Tooltip<'a> {
+    text: &'a str,
+    delay: f32,
+}
+
+impl<'a> Tooltip<'a> {
+    pub fn new(text: &'a str) -> Tooltip<'a> {
+        Tooltip { text, delay: 0.5 }
+    }
+
+    /// Seconds of hovering before the tooltip shows up.
+    pub fn delay(self, delay: f32) -> Tooltip<'a> {
+        Tooltip { delay, ..self }
+    }
+
+    pub fn ui(self, ui: &mut Ui) {
+        let context = ui.get_active_window_context();
+        let mouse = context.input.mouse_position;
+        let hovered = context.input.window_active
+            && context.window.cursor.last_fit.contains(mouse)
+            && context.window.content_rect().contains(mouse);
+        if !hovered {
+            return;
+        }
+
+        let content = UiContent::Label(self.text.into());
+        let size = context
+            .window
+            .painter
+            .content_with_margins_size(&context.style.label_style, &content)
+            + Vec2::new(6., 4.);
+        let key = hash!(context.window.id, self.text);
+
+        let since = match ui.tooltip {
+            Some((hovered, since)) if hovered == key => since,
+            _ => ui.time,
+        };
+        ui.tooltip = Some((key, since));
+        ui.tooltip_hovered = true;
+        if ui.time - since < self.delay {
+            return;
+        }
+
+        // follows the mouse, below and right of the pointer
+        let anchor = Rect::new(mouse.x + 12., mouse.y - 4., 0., 22.);
+        let pos = anchored(anchor, size, ui.root_window.size);
+
+        let skin = ui.skin_stack.top();
+        let state = ElementState {
+            focused: true,
+            ..Default::default()
+        };
+        ui.overlay
+            .draw_element_background(&skin.window_style, pos, size, state);
+        ui.overlay
+            .draw_element_content(&skin.label_style, pos, size, &content, state);
+    }
+}
+
+impl Ui {
+    pub fn tooltip(&mut self, text: &str) {
+        Tooltip::new(text).ui(self)
+    }
+}
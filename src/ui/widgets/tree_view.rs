@@ -0,0 +1,365 @@
+use crate::{
+    math::{vec2, Rect},
+    ui::{widgets::Editbox, ElementState, Id, Layout, Ui},
+};
+
+const ROW_HEIGHT: f32 = 14.;
+/// Second click on a node within this many seconds starts renaming it
+const DOUBLE_CLICK_TIME: f32 = 0.3;
+
+/// Where the dragged nodes should go, relative to the target node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreeDropPosition {
+    Before,
+    Into,
+    After,
+}
+
+/// Nodes dropped on another node, to be moved by the caller.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeDrop {
+    /// The dragged node, or all the selected nodes when it was selected
+    pub nodes: Vec<Id>,
+    pub target: Id,
+    pub position: TreeDropPosition,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TreeResponse {
+    pub drop: Option<TreeDrop>,
+    /// Node renamed inline, with its new label
+    pub renamed: Option<(Id, String)>,
+    pub selection_changed: bool,
+}
+
+/// Drag payload of a node, apart from the other drag and drop payloads
+struct TreeDrag(Id);
+
+#[derive(Debug)]
+struct Rename {
+    id: Id,
+    text: String,
+    started: bool,
+}
+
+/// Tree of nodes with multi-selection, drag and drop reordering and
+/// inline renaming, for scene hierarchies and the like.
+///
+/// The tree does not own the nodes: it reports drops and renames in the
+/// [TreeResponse] and the caller updates its data.
+/// Ctrl+click toggles a node in the selection, Shift+click selects a
+/// range and double click renames.
+///
+/// ```ignore
+/// let response = tree.ui(&mut root_ui(), |ui, tree| {
+///     tree.node(ui, hash!("level"), "Level", |ui, tree| {
+///         tree.leaf(ui, hash!("player"), "Player");
+///         tree.leaf(ui, hash!("camera"), "Camera");
+///     });
+/// });
+/// if let Some(drop) = response.drop {
+///     scene.move_nodes(&drop.nodes, drop.target, drop.position);
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct TreeView {
+    selected: Vec<Id>,
+    // last clicked node, start of the shift+click ranges
+    anchor: Option<Id>,
+    // nodes in the order they were drawn last frame, and this frame
+    order: Vec<Id>,
+    drawn: Vec<Id>,
+    renaming: Option<Rename>,
+    last_click: Option<(Id, f32)>,
+    // plain click on a selected node: selects only it on release, unless dragged
+    pending_select: Option<Id>,
+    response: TreeResponse,
+}
+
+impl TreeView {
+    pub fn new() -> TreeView {
+        TreeView::default()
+    }
+
+    pub fn selected(&self) -> &[Id] {
+        &self.selected
+    }
+
+    pub fn is_selected(&self, id: Id) -> bool {
+        self.selected.contains(&id)
+    }
+
+    pub fn set_selected(&mut self, selected: &[Id]) {
+        self.selected = selected.to_vec();
+        self.anchor = selected.last().copied();
+    }
+
+    /// Start editing the label of the node.
+    pub fn rename(&mut self, id: Id, label: &str) {
+        self.renaming = Some(Rename {
+            id,
+            text: label.to_string(),
+            started: false,
+        });
+    }
+
+    /// Draw the tree, the nodes are added with [TreeView::node] and [TreeView::leaf].
+    pub fn ui<F: FnOnce(&mut Ui, &mut TreeView)>(&mut self, ui: &mut Ui, f: F) -> TreeResponse {
+        self.drawn.clear();
+        f(ui, self);
+        std::mem::swap(&mut self.order, &mut self.drawn);
+
+        if !ui.input.is_mouse_down {
+            self.pending_select = None;
+        }
+
+        std::mem::take(&mut self.response)
+    }
+
+    /// Foldable node, `f` draws the children. Returns true if clicked.
+    pub fn node<F: FnOnce(&mut Ui, &mut TreeView)>(
+        &mut self,
+        ui: &mut Ui,
+        id: Id,
+        label: &str,
+        f: F,
+    ) -> bool {
+        let (opened, clicked) = self.row(ui, id, label, true);
+
+        if opened {
+            ui.get_active_window_context().window.cursor.ident += 5.;
+            f(ui, self);
+            ui.get_active_window_context().window.cursor.ident -= 5.;
+        }
+
+        clicked
+    }
+
+    /// Node without children. Returns true if clicked.
+    pub fn leaf(&mut self, ui: &mut Ui, id: Id, label: &str) -> bool {
+        self.row(ui, id, label, false).1
+    }
+
+    fn row(&mut self, ui: &mut Ui, id: Id, label: &str, foldable: bool) -> (bool, bool) {
+        self.drawn.push(id);
+        let time = ui.time;
+
+        let context = ui.get_active_window_context();
+        let cursor = &context.window.cursor;
+        let width = (cursor.area.w - cursor.ident - cursor.margin * 2.).max(1.);
+        let size = vec2(width, ROW_HEIGHT);
+        let pos = context.window.cursor.fit(size, Layout::Vertical);
+        let rect = Rect::new(pos.x, pos.y, size.x, size.y);
+
+        let mouse = context.input.mouse_position;
+        let hovered = context.focused
+            && rect.contains(mouse)
+            && context.window.content_rect().contains(mouse);
+        let clicked = hovered && context.input.click_down();
+        let on_marker = foldable && mouse.x < pos.x + 10.;
+
+        let opened = context.storage_u32.entry(id).or_insert(0);
+        if clicked && on_marker {
+            *opened ^= 1;
+        }
+        let opened = *opened == 1;
+
+        if clicked && !on_marker {
+            let ctrl = context.input.modifier_ctrl;
+            let shift = context.input.modifier_shift;
+            self.click(id, ctrl, shift);
+
+            match self.last_click {
+                Some((last, click_time))
+                    if last == id && time - click_time < DOUBLE_CLICK_TIME && !ctrl && !shift =>
+                {
+                    self.rename(id, label);
+                    self.last_click = None;
+                }
+                _ => self.last_click = Some((id, time)),
+            }
+        }
+        if hovered && context.input.click_up() && self.pending_select == Some(id) {
+            self.selected = vec![id];
+            self.response.selection_changed = true;
+        }
+
+        if self.is_selected(id) {
+            context.window.painter.draw_rect(
+                rect,
+                None,
+                context.style.editbox_style.color_selected,
+            );
+        }
+        if foldable {
+            context.window.painter.draw_element_label(
+                &context.style.label_style,
+                pos,
+                if opened { "-" } else { "+" },
+                ElementState {
+                    focused: context.focused,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let renaming = self.renaming.as_ref().is_some_and(|rename| rename.id == id);
+        if renaming {
+            self.rename_editbox(ui, rect);
+        } else {
+            let context = ui.get_active_window_context();
+            context.window.painter.draw_element_label(
+                &context.style.label_style,
+                pos + vec2(10., 0.),
+                label,
+                ElementState {
+                    focused: context.focused,
+                    ..Default::default()
+                },
+            );
+
+            if !(clicked && on_marker)
+                && ui.drag_source(hash!(id, "tree_drag"), label, TreeDrag(id))
+            {
+                self.pending_select = None;
+            }
+            self.drop_target(ui, id, rect);
+        }
+
+        (opened, clicked)
+    }
+
+    fn click(&mut self, id: Id, ctrl: bool, shift: bool) {
+        let range = self.anchor.and_then(|anchor| {
+            let a = self.order.iter().position(|node| *node == anchor)?;
+            let b = self.order.iter().position(|node| *node == id)?;
+            Some(a.min(b)..=a.max(b))
+        });
+
+        match range {
+            Some(range) if shift => {
+                self.selected = self.order[range].to_vec();
+            }
+            _ if ctrl => {
+                if let Some(n) = self.selected.iter().position(|node| *node == id) {
+                    self.selected.remove(n);
+                } else {
+                    self.selected.push(id);
+                }
+                self.anchor = Some(id);
+            }
+            _ if self.is_selected(id) => {
+                self.pending_select = Some(id);
+                self.anchor = Some(id);
+            }
+            _ => {
+                self.selected = vec![id];
+                self.anchor = Some(id);
+            }
+        }
+        self.response.selection_changed = true;
+    }
+
+    fn rename_editbox(&mut self, ui: &mut Ui, rect: Rect) {
+        let rename = self.renaming.as_mut().unwrap();
+        let editbox_id = hash!(rename.id, "rename");
+
+        let mut editbox = Editbox::new(editbox_id, vec2(rect.w - 10., rect.h))
+            .position(rect.point() + vec2(10., 0.));
+        if !rename.started {
+            ui.set_input_focus(editbox_id);
+            editbox = editbox.select_all();
+            rename.started = true;
+        }
+        editbox.ui(ui, &mut rename.text);
+
+        let context = ui.get_active_window_context();
+        if context.input.enter {
+            let rename = self.renaming.take().unwrap();
+            self.response.renamed = Some((rename.id, rename.text));
+            *context.input_focus = None;
+        } else if context.input.escape || *context.input_focus != Some(editbox_id) {
+            // cancelled, or clicked elsewhere
+            self.renaming = None;
+        }
+    }
+
+    fn drop_target(&mut self, ui: &mut Ui, id: Id, rect: Rect) {
+        let dragged = match &ui.drag_payload {
+            Some(drag) if drag.dragging => match drag.payload.downcast_ref::<TreeDrag>() {
+                Some(TreeDrag(dragged)) if *dragged != id => *dragged,
+                _ => return,
+            },
+            _ => return,
+        };
+
+        let context = ui.get_active_window_context();
+        let mouse = context.input.mouse_position;
+        if !context.input.window_active || !rect.contains(mouse) {
+            return;
+        }
+
+        let position = drop_position((mouse.y - rect.y) / rect.h);
+        let color = context.style.focus_color;
+        let painter = &mut context.window.painter;
+        match position {
+            TreeDropPosition::Before => {
+                painter.draw_line(rect.point(), vec2(rect.right(), rect.y), color)
+            }
+            TreeDropPosition::After => painter.draw_line(
+                vec2(rect.x, rect.bottom()),
+                vec2(rect.right(), rect.bottom()),
+                color,
+            ),
+            TreeDropPosition::Into => painter.draw_rect(rect, color, None),
+        }
+
+        if context.input.click_up {
+            ui.drag_payload = None;
+
+            let nodes = if self.is_selected(dragged) {
+                self.selected.clone()
+            } else {
+                vec![dragged]
+            };
+            if !nodes.contains(&id) {
+                self.response.drop = Some(TreeDrop {
+                    nodes,
+                    target: id,
+                    position,
+                });
+            }
+        }
+    }
+}
+
+/// Top quarter of the row drops before it, bottom quarter after, the rest into.
+fn drop_position(y: f32) -> TreeDropPosition {
+    if y < 0.25 {
+        TreeDropPosition::Before
+    } else if y > 0.75 {
+        TreeDropPosition::After
+    } else {
+        TreeDropPosition::Into
+    }
+}
+
+#[test]
+fn tree_view_selection() {
+    let (a, b, c, d) = (1, 2, 3, 4);
+    let mut tree = TreeView::new();
+    tree.order = vec![a, b, c, d];
+
+    tree.click(b, false, false);
+    assert_eq!(tree.selected(), &[b]);
+    tree.click(d, false, true);
+    assert_eq!(tree.selected(), &[b, c, d]);
+    tree.click(c, true, false);
+    assert_eq!(tree.selected(), &[b, d]);
+    tree.click(a, true, false);
+    assert_eq!(tree.selected(), &[b, d, a]);
+
+    tree.click(d, false, false);
+    assert_eq!(tree.pending_select, Some(d));
+    assert_eq!(drop_position(0.5), TreeDropPosition::Into);
+}
@@ -1,6 +1,9 @@
 use crate::{
     math::{vec2, Rect, Vec2},
-    ui::{ElementState, Id, Ui, UiContent, WindowContext},
+    ui::{
+        animation::{animate, easing},
+        ElementState, Id, Ui, UiContent, WindowContext,
+    },
 };
 
 #[derive(Debug, Clone)]
@@ -12,6 +15,7 @@ pub struct Window {
     movable: bool,
     titlebar: bool,
     label: Option<String>,
+    open_animation: Option<(bool, f32)>,
 }
 
 impl Window {
@@ -24,6 +28,7 @@ impl Window {
             movable: true,
             titlebar: true,
             label: None,
+            open_animation: None,
         }
     }
 
@@ -49,13 +54,54 @@ impl Window {
         Window { titlebar, ..self }
     }
 
+    /// Fade the window in when it opens, and out when `open` turns false,
+    /// over `duration` seconds.
+    ///
+    /// The window should be drawn every frame, closed or not, for the fade out to show:
+    /// ```ignore
+    /// let opened = Window::new(hash!(), vec2(100., 100.), vec2(200., 100.))
+    ///     .close_button(true)
+    ///     .animate_open(show_settings, 0.2)
+    ///     .ui(&mut root_ui(), |ui| {});
+    /// show_settings &= opened;
+    /// ```
+    pub fn animate_open(self, open: bool, duration: f32) -> Window {
+        Window {
+            open_animation: Some((open, duration)),
+            ..self
+        }
+    }
+
     pub fn ui<F: FnOnce(&mut Ui)>(self, ui: &mut Ui, f: F) -> bool {
+        if let Some((false, _)) = self.open_animation {
+            if self.open_alpha(ui) <= 0. {
+                return true;
+            }
+        }
+
         let token = self.begin(ui);
         f(ui);
         token.end(ui)
     }
 
+    /// Opacity of the opening or closing window.
+    fn open_alpha(&self, ui: &mut Ui) -> f32 {
+        match self.open_animation {
+            Some((open, duration)) => animate(
+                &mut ui.storage_any,
+                hash!(self.id, "open_animation"),
+                0.,
+                if open { 1. } else { 0. },
+                ui.time,
+                duration,
+                easing::smoothstep,
+            ),
+            None => 1.,
+        }
+    }
+
     pub fn begin(self, ui: &mut Ui) -> WindowToken {
+        let alpha = self.open_alpha(ui);
         let context = ui.begin_window(
             self.id,
             None,
@@ -72,6 +118,7 @@ impl Window {
         }
 
         let mut context = ui.get_active_window_context();
+        context.window.alpha = alpha;
 
         self.draw_window_frame(&mut context);
         if self.close_button && self.draw_close_button(&mut context) {
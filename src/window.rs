@@ -1,4 +1,9 @@
 //! Window and associated to window rendering context related functions.
+//!
+//! Macroquad runs in a single window, miniquad can not open secondary ones.
+//! A detached view like an inspector or a level preview can be drawn into a
+//! [render_target](crate::texture::render_target) and shown in a
+//! [ui](crate::ui) window instead.
 
 use crate::{
     get_context, get_quad_context,
@@ -20,6 +25,11 @@ pub fn next_frame() -> crate::exec::FrameFuture {
 /// Fill window background with solid color.
 /// Note: even when "clear_background" was not called explicitly
 /// screen will be cleared at the beginning of the frame.
+///
+/// With `Conf::platform.framebuffer_alpha`, on X11, Wayland and Android, the
+/// window is transparent where the alpha is 0, and the frame is cleared
+/// transparent by default, for overlays.
+/// The colors should be premultiplied by their alpha.
 pub fn clear_background(color: Color) {
     let context = get_context();
 
@@ -68,6 +78,14 @@ pub fn screen_height() -> f32 {
     context.screen_height / get_quad_context().dpi_scale()
 }
 
+/// Physical pixels per logical pixel.
+///
+/// Screen sizes, mouse positions, cameras, text and the ui are all in logical
+/// pixels, and follow the scale of the monitor the window is on.
+pub fn dpi_scale() -> f32 {
+    get_quad_context().dpi_scale()
+}
+
 pub fn screen_center() -> Vec2 {
     return vec2(screen_width() / 2.0, screen_height() / 2.0);
 }
@@ -85,6 +103,230 @@ pub fn request_new_screen_size(width: f32, height: f32) {
     // Because the OS might decide to give a different screen dimension, setting the context.screen_* here would be confusing.
 }
 
+/// Size limits and aspect ratio the window is kept to while resized.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct WindowConstraints {
+    pub min_size: Option<Vec2>,
+    pub max_size: Option<Vec2>,
+    pub aspect_ratio: Option<f32>,
+}
+
+impl WindowConstraints {
+    /// Closest allowed size, fitting in `size` when the aspect ratio is locked.
+    pub fn constrain(&self, size: Vec2) -> Vec2 {
+        let min = self.min_size.unwrap_or(Vec2::ZERO);
+        let max = self.max_size.unwrap_or(Vec2::splat(f32::INFINITY));
+        let mut size = size.max(min).min(max);
+
+        if let Some(ratio) = self.aspect_ratio.filter(|ratio| *ratio > 0.) {
+            if size.x > size.y * ratio {
+                size.x = size.y * ratio;
+            } else {
+                size.y = size.x / ratio;
+            }
+            // back in the limits, at the same ratio
+            let grow = (min.x / size.x).max(min.y / size.y).max(1.);
+            size *= grow;
+            let shrink = (max.x / size.x).min(max.y / size.y).min(1.);
+            size *= shrink;
+        }
+
+        size
+    }
+}
+
+/// Keep the window between `min` and `max` sizes while the user resizes it,
+/// `None` for no limit.
+///
+/// The window is resized back after the OS resized it, so it might show the
+/// forbidden size for a frame.
+pub fn set_window_size_limits(min: Option<Vec2>, max: Option<Vec2>) {
+    let context = get_context();
+    context.window_constraints.min_size = min;
+    context.window_constraints.max_size = max;
+
+    apply_window_constraints();
+}
+
+/// Lock the window width / height ratio while it is resized, `None` to unlock.
+pub fn set_window_aspect_ratio(ratio: Option<f32>) {
+    get_context().window_constraints.aspect_ratio = ratio;
+
+    apply_window_constraints();
+}
+
+/// Resize the window to the closest size allowed by the constraints.
+pub(crate) fn apply_window_constraints() {
+    let context = get_context();
+    if context.fullscreen {
+        return;
+    }
+
+    let size = vec2(screen_width(), screen_height());
+    let constrained = context.window_constraints.constrain(size);
+    if (constrained - size).abs().max_element() >= 1. {
+        request_new_screen_size(constrained.x, constrained.y);
+    }
+}
+
+/// Change of the window state, from [window_events].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowEvent {
+    /// The window got minimized, or the app went to the background on mobile
+    Minimized,
+    Restored,
+    /// Sizes in the same units as `screen_width` and `screen_height`
+    Resized {
+        old: Vec2,
+        new: Vec2,
+    },
+    /// The window moved to a monitor with another scale, see [dpi_scale]
+    DpiChanged {
+        old: f32,
+        new: f32,
+    },
+}
+
+/// Window state changes since the last frame, to pause the game, mute it or
+/// update the layout.
+///
+/// miniquad does not report the focus or the occlusion of the window.
+/// ```ignore
+/// for event in window_events() {
+///     match event {
+///         WindowEvent::Minimized => paused = true,
+///         WindowEvent::Resized { new, .. } => layout.update(new),
+///         _ => {}
+///     }
+/// }
+/// ```
+pub fn window_events() -> Vec<WindowEvent> {
+    get_context().window_events.clone()
+}
+
+pub fn is_window_minimized() -> bool {
+    get_context().window_minimized
+}
+
+/// Switch between fullscreen and windowed mode at runtime.
+///
+/// Leaving fullscreen restores the window size from before it, or the `Conf`
+/// size if the game started fullscreen.
+/// Fullscreen is borderless, at the desktop resolution: miniquad does not
+/// expose exclusive display modes.
+pub fn set_fullscreen(fullscreen: bool) {
+    let context = get_context();
+    if context.fullscreen == fullscreen {
+        return;
+    }
+
+    if fullscreen {
+        context.windowed_size = vec2(screen_width(), screen_height());
+    }
+    context.fullscreen = fullscreen;
+    get_quad_context().set_fullscreen(fullscreen);
+    if !fullscreen {
+        let size = context.windowed_size;
+        request_new_screen_size(size.x, size.y);
+    }
+}
+
+pub fn is_fullscreen() -> bool {
+    get_context().fullscreen
+}
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn macroquad_request_browser_fullscreen();
+    fn macroquad_exit_browser_fullscreen();
+    fn macroquad_is_browser_fullscreen() -> bool;
+}
+
+/// Show the canvas fullscreen with the browser Fullscreen API, needs
+/// `js/canvas.js`. [set_fullscreen] on the other platforms.
+///
+/// Browsers only allow it shortly after a click or a key press, so it should be
+/// called in the frame of the input:
+/// ```ignore
+/// if root_ui().button(None, "Fullscreen") {
+///     request_browser_fullscreen(true);
+/// }
+/// ```
+/// The screen size follows the canvas, in and out of fullscreen.
+pub fn request_browser_fullscreen(fullscreen: bool) {
+    #[cfg(target_arch = "wasm32")]
+    unsafe {
+        if fullscreen {
+            macroquad_request_browser_fullscreen();
+        } else {
+            macroquad_exit_browser_fullscreen();
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    set_fullscreen(fullscreen);
+}
+
+/// The canvas is fullscreen, the user may leave it with Escape at any time.
+pub fn is_browser_fullscreen() -> bool {
+    #[cfg(target_arch = "wasm32")]
+    unsafe {
+        macroquad_is_browser_fullscreen()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    is_fullscreen()
+}
+
+/// Window icon from images of any size, for `Conf::icon`.
+///
+/// Each of the 16, 32 and 64 pixels icons is scaled from the smallest image
+/// at least as large, or from the largest one.
+/// miniquad only sets the icon when the window is created, so the images have
+/// to be loaded synchronously in the `Conf` function:
+/// ```ignore
+/// fn window_conf() -> Conf {
+///     let icon = Image::from_file_with_format(include_bytes!("../icon.png"), None);
+///     Conf {
+///         window_title: "Game".to_owned(),
+///         icon: window_icon(&[icon]),
+///         ..Default::default()
+///     }
+/// }
+/// ```
+pub fn window_icon(images: &[crate::texture::Image]) -> Option<miniquad::conf::Icon> {
+    fn icon<const N: usize>(images: &[crate::texture::Image], size: usize) -> Option<[u8; N]> {
+        use std::convert::TryInto;
+
+        let image = images
+            .iter()
+            .filter(|image| image.width() >= size && image.height() >= size)
+            .min_by_key(|image| image.width() * image.height())
+            .or_else(|| {
+                images
+                    .iter()
+                    .max_by_key(|image| image.width() * image.height())
+            })?;
+        let rgba = image::RgbaImage::from_raw(
+            image.width as u32,
+            image.height as u32,
+            image.bytes.clone(),
+        )?;
+        let resized = image::imageops::resize(
+            &rgba,
+            size as u32,
+            size as u32,
+            image::imageops::FilterType::Triangle,
+        );
+
+        resized.into_raw().try_into().ok()
+    }
+
+    Some(miniquad::conf::Icon {
+        small: icon(images, 16)?,
+        medium: icon(images, 32)?,
+        big: icon(images, 64)?,
+    })
+}
+
 /// With `set_panic_handler` set to a handler code, macroquad will use
 /// `std::panic::catch_unwind` on user code to catch some panics.
 ///
@@ -135,3 +377,84 @@ where
 
     crate::get_context().unwind = true;
 }
+
+/// Panic handler showing the panic message and the backtrace in a native
+/// [crate::file::message_box], instead of the window silently disappearing.
+///
+/// Where there is no message box, like on web or without zenity or kdialog
+/// on linux, they are shown on screen, with buttons to copy them for a bug
+/// report and to quit.
+/// ```ignore
+/// #[macroquad::main("Game")]
+/// async fn main() {
+///     set_panic_screen();
+///     ...
+/// }
+/// ```
+pub fn set_panic_screen() {
+    use crate::ui::{hash, root_ui, widgets};
+
+    set_panic_handler(|message, backtrace| async move {
+        let report = format!("{}\n\n{}", message, backtrace);
+        if crate::file::message_box("The game crashed", &report, &["Quit"])
+            .await
+            .is_ok()
+        {
+            return;
+        }
+
+        loop {
+            clear_background(crate::color::DARKGRAY);
+
+            let mut quit = false;
+            let size = vec2(screen_width(), screen_height()) - vec2(40., 40.);
+            widgets::Window::new(hash!(), vec2(20., 20.), size)
+                .label("The game crashed")
+                .movable(false)
+                .ui(&mut root_ui(), |ui| {
+                    if ui.button(None, "Copy to clipboard") {
+                        get_quad_context().clipboard_set(&report);
+                    }
+                    ui.same_line(0.);
+                    quit = ui.button(None, "Quit");
+                    for line in report.lines() {
+                        ui.label(None, line);
+                    }
+                });
+            if quit {
+                return;
+            }
+
+            next_frame().await;
+        }
+    });
+}
+
+#[test]
+fn window_icon_sizes() {
+    use crate::{color::WHITE, texture::Image};
+
+    assert!(window_icon(&[]).is_none());
+
+    let icon = window_icon(&[Image::gen_image_color(24, 24, WHITE)]).unwrap();
+    assert_eq!(&icon.small[0..4], &[255; 4]);
+    assert_eq!(&icon.big[icon.big.len() - 4..], &[255; 4]);
+}
+
+#[test]
+fn window_constraints() {
+    let constraints = WindowConstraints {
+        min_size: Some(vec2(320., 240.)),
+        max_size: Some(vec2(1920., 1080.)),
+        aspect_ratio: None,
+    };
+    assert_eq!(constraints.constrain(vec2(100., 2000.)), vec2(320., 1080.));
+
+    let constraints = WindowConstraints {
+        aspect_ratio: Some(2.),
+        ..constraints
+    };
+    assert_eq!(constraints.constrain(vec2(1000., 300.)), vec2(600., 300.));
+    assert_eq!(constraints.constrain(vec2(100., 100.)), vec2(480., 240.));
+    assert_eq!(constraints.constrain(vec2(3000., 3000.)), vec2(1920., 960.));
+}
@@ -34,3 +34,39 @@ async fn coroutine_memory() {
 
     assert_eq!(telemetry::active_coroutines_count(), 0);
 }
+
+#[macroquad::test]
+async fn coroutine_join_and_scope() {
+    use macroquad::experimental::coroutines::{join_all, CoroutineScope};
+
+    let values = (0..3)
+        .map(|n| {
+            start_coroutine(async move {
+                for _ in 0..n {
+                    next_frame().await;
+                }
+                n * 2
+            })
+        })
+        .collect();
+    assert_eq!(join_all(values).await, vec![Some(0), Some(2), Some(4)]);
+
+    let scope = CoroutineScope::new();
+    let parent = scope.start(async move {
+        start_coroutine(async move {
+            loop {
+                next_frame().await;
+            }
+        });
+        loop {
+            next_frame().await;
+        }
+    });
+    next_frame().await;
+    next_frame().await;
+    assert_eq!(telemetry::active_coroutines_count(), 2);
+
+    scope.cancel();
+    assert!(parent.is_done());
+    assert_eq!(telemetry::active_coroutines_count(), 0);
+}